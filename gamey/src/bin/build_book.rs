@@ -0,0 +1,181 @@
+//! Builds an opening book from self-play games.
+//!
+//! Two minimax bots play `--games` games against each other. The first
+//! `--plies` moves of the winner in each game are added to the book with
+//! weight 1, so moves that win more often are picked more often.
+//!
+//! # Usage
+//!
+//! ```bash
+//! # 200 games on a size-7 board, first 4 plies, written to book.json
+//! build_book --size 7 --games 200 --plies 4 --output book.json
+//!
+//! # Add 50 more games to an existing book
+//! build_book --games 50 --seed 1000 --merge --output book.json
+//! ```
+
+use clap::Parser;
+use gamey::{
+    GameStatus, GameY, MinimaxBot, MinimaxConfig, Movement, OpeningBook, PlayerId, YBot,
+};
+use std::path::PathBuf;
+
+/// Command-line arguments for the book builder.
+#[derive(Parser, Debug)]
+#[command(about = "Builds a GameY opening book from self-play games.")]
+struct BuildBookArgs {
+    /// Size of the triangular board.
+    #[arg(short, long, default_value_t = 7)]
+    size: u32,
+
+    /// Number of self-play games.
+    #[arg(short, long, default_value_t = 100)]
+    games: u64,
+
+    /// Number of plies from the start of each game that go into the book.
+    #[arg(short, long, default_value_t = 4)]
+    plies: usize,
+
+    /// Node budget per move; keeps the build reproducible.
+    #[arg(long, default_value_t = 20_000)]
+    nodes: u64,
+
+    /// Skill level of the self-play bots. Below the maximum the bots vary
+    /// their play, which gives the book more than one line.
+    #[arg(long, default_value_t = 18)]
+    skill: u8,
+
+    /// Seed of the first game; game `i` uses `seed + i`.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Add to the book in `--output` instead of replacing it.
+    #[arg(long, default_value_t = false)]
+    merge: bool,
+
+    /// File the book is written to.
+    #[arg(short, long, default_value = "book.json")]
+    output: PathBuf,
+}
+
+fn main() {
+    let args = BuildBookArgs::parse();
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &BuildBookArgs) -> gamey::Result<()> {
+    let mut book = if args.merge && args.output.exists() {
+        OpeningBook::load(&args.output)?
+    } else {
+        OpeningBook::new()
+    };
+
+    for i in 0..args.games {
+        let config = MinimaxConfig::new(0, u64::MAX / 2)
+            .with_skill(args.skill)
+            .with_seed(args.seed.wrapping_add(i))
+            .with_node_limit(args.nodes);
+        let bot = MinimaxBot::new("self_play", config);
+        let played = self_play(&bot, args.size, args.plies)?;
+
+        if let Some(winner) = played.winner {
+            for (position, movement) in played.opening {
+                if let Movement::Placement { player, coords } = movement
+                    && player == winner
+                {
+                    book.add(&position, coords, 1);
+                }
+            }
+        }
+        println!("game {}/{}: {} positions in book", i + 1, args.games, book.len());
+    }
+
+    book.save(&args.output)?;
+    println!("Wrote {} positions to {}", book.len(), args.output.display());
+    Ok(())
+}
+
+/// Outcome of one self-play game.
+struct SelfPlayGame {
+    /// The first positions of the game, each with the move played in it.
+    opening: Vec<(GameY, Movement)>,
+    winner: Option<PlayerId>,
+}
+
+/// Plays one game to the end, recording the first `plies` positions.
+fn self_play(bot: &dyn YBot, size: u32, plies: usize) -> gamey::Result<SelfPlayGame> {
+    let mut game = GameY::new(size);
+    let mut opening = Vec::with_capacity(plies);
+
+    while let Some(player) = game.next_player() {
+        let Some(coords) = bot.choose_move(&game) else {
+            break;
+        };
+        let movement = Movement::Placement { player, coords };
+        if opening.len() < plies {
+            opening.push((game.clone(), movement.clone()));
+        }
+        game.add_move(movement)?;
+    }
+
+    let winner = match game.status() {
+        GameStatus::Finished { winner } => Some(*winner),
+        GameStatus::Ongoing { .. } => None,
+    };
+    Ok(SelfPlayGame { opening, winner })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(output: &std::path::Path, extra: &[&str]) -> BuildBookArgs {
+        let output = output.to_str().unwrap();
+        let base = ["build_book", "--size", "4", "--games", "2", "--plies", "2"];
+        let rest = ["--nodes", "200", "--output", output];
+        BuildBookArgs::parse_from(base.iter().chain(&rest).chain(extra))
+    }
+
+    /// Sum of the weights the book holds for the empty board.
+    fn first_move_weight(book: &OpeningBook) -> u64 {
+        book.moves(&GameY::new(4))
+            .iter()
+            .map(|m| m.weight as u64)
+            .sum()
+    }
+
+    #[test]
+    fn test_self_play_records_the_opening() {
+        let bot = MinimaxBot::new("self_play", MinimaxConfig::new(0, 0).with_node_limit(200));
+        let played = self_play(&bot, 4, 3).unwrap();
+        assert_eq!(played.opening.len(), 3);
+        assert!(played.winner.is_some(), "Y has no draws, so a full game has a winner");
+        assert_eq!(played.opening[0].0.board_map().len(), 0);
+    }
+
+    #[test]
+    fn test_run_writes_a_book_that_can_be_loaded() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        run(&args(file.path(), &[])).unwrap();
+
+        let book = OpeningBook::load(file.path()).unwrap();
+        assert!(!book.is_empty());
+    }
+
+    #[test]
+    fn test_merge_adds_to_the_existing_book() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        run(&args(file.path(), &[])).unwrap();
+        let once = OpeningBook::load(file.path()).unwrap();
+
+        run(&args(file.path(), &["--merge"])).unwrap();
+        let twice = OpeningBook::load(file.path()).unwrap();
+
+        // The same seeds replay the same games, so only the weights grow.
+        assert_eq!(twice.len(), once.len());
+        assert_eq!(first_move_weight(&twice), 2 * first_move_weight(&once));
+    }
+}
@@ -3,47 +3,137 @@
 //! Uses the minimax engine with tight time limits (500 ms min/max) for quick
 //! responses at the cost of shallower search depth.
 
-use crate::{Coordinates, GameY, YBot};
+use std::sync::Arc;
 
-use super::minimax::{
-    choose_move_with_minimax, choose_pie_opening_with_minimax, decide_pie_with_minimax,
+use crate::{
+    BotMetadata, CandidateMove, Coordinates, Difficulty, GameY, MinimaxBot, OpeningBook,
+    SearchOptions, SearchProgress, SearchSession, YBot,
 };
+
+use super::minimax::MinimaxConfig;
 use super::ybot::PieChoice;
 
 /// A bot that runs minimax with a 500 ms time budget on both gates.
 ///
-/// Corresponds to the `MEDIUM` difficulty level in the game service.
-pub struct FastBot;
+/// Corresponds to the `MEDIUM` difficulty level in the game service. It is a
+/// [`MinimaxBot`] with the preset [`FastBot::config`].
+///
+/// An attached [`OpeningBook`] is consulted before the search runs, unless it
+/// is disabled with [`FastBot::with_book_enabled`].
+#[derive(Debug, Clone)]
+pub struct FastBot(MinimaxBot);
+
+impl FastBot {
+    /// Creates the bot without an opening book.
+    pub fn new() -> Self {
+        Self(MinimaxBot::new("fast_bot", Self::config()))
+    }
+
+    /// The configuration the bot plays with: 500 ms per move and per Pie Rule search.
+    pub fn config() -> MinimaxConfig {
+        MinimaxConfig::new(500, 500).with_pie_time(500)
+    }
+
+    /// Attaches an opening book.
+    pub fn with_book(self, book: Arc<OpeningBook>) -> Self {
+        Self(self.0.with_book(book))
+    }
+
+    /// Enables or disables the attached opening book.
+    pub fn with_book_enabled(self, enabled: bool) -> Self {
+        Self(self.0.with_book_enabled(enabled))
+    }
+}
+
+impl Default for FastBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl YBot for FastBot {
     fn name(&self) -> &str {
-        "fast_bot"
+        self.0.name()
     }
 
     fn choose_move(&self, game: &GameY) -> Option<Coordinates> {
-        choose_move_with_minimax(game, 500, 500)
+        self.0.choose_move(game)
+    }
+
+    fn choose_move_with_options(&self, game: &GameY, options: &SearchOptions) -> Option<Coordinates> {
+        self.0.choose_move_with_options(game, options)
+    }
+
+    fn choose_move_in_session(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+        session: &mut SearchSession,
+    ) -> Option<Coordinates> {
+        self.0.choose_move_in_session(game, options, session)
+    }
+
+    fn choose_move_with_progress(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+        on_depth: &mut dyn FnMut(&SearchProgress),
+    ) -> Option<Coordinates> {
+        self.0.choose_move_with_progress(game, options, on_depth)
+    }
+
+    fn ponder(&self, game: &GameY, session: &mut SearchSession) -> Option<Coordinates> {
+        self.0.ponder(game, session)
     }
 
     fn choose_pie_opening(&self, game: &GameY) -> Option<Coordinates> {
-        choose_pie_opening_with_minimax(game, 500)
+        self.0.choose_pie_opening(game)
+    }
+
+    fn choose_pie_opening_with_options(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        self.0.choose_pie_opening_with_options(game, options)
+    }
+
+    fn analyze(
+        &self,
+        game: &GameY,
+        multipv: usize,
+        options: &SearchOptions,
+    ) -> Option<Vec<CandidateMove>> {
+        self.0.analyze(game, multipv, options)
     }
 
     fn decide_pie(&self, game: &GameY) -> PieChoice {
-        decide_pie_with_minimax(game, 500)
+        self.0.decide_pie(game)
+    }
+
+    fn decide_pie_with_options(&self, game: &GameY, options: &SearchOptions) -> PieChoice {
+        self.0.decide_pie_with_options(game, options)
+    }
+
+    fn metadata(&self) -> BotMetadata {
+        self.0
+            .metadata()
+            .with_description("Minimax search with a 500 ms budget per move")
+            .with_difficulty(Difficulty::Medium)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Coordinates, Movement, PlayerId, YBotRegistry};
+    use crate::{Coordinates, Movement, OpeningBook, PlayerId, YBotRegistry};
     use std::sync::Arc;
 
     // The bot name is used as the HTTP route parameter and as the registry key.
     // A mismatch silently breaks all PvE games at medium difficulty.
     #[test]
     fn test_fast_bot_is_findable_in_registry() {
-        let registry = YBotRegistry::new().with_bot(Arc::new(FastBot));
+        let registry = YBotRegistry::new().with_bot(Arc::new(FastBot::new()));
         assert!(
             registry.find("fast_bot").is_some(),
             "FastBot must be retrievable by its own name — name/key mismatch breaks the HTTP API"
@@ -56,7 +146,7 @@ mod tests {
     #[test]
     fn test_fast_bot_choose_move_returns_available_cell() {
         let game = GameY::new(3);
-        let coords = FastBot.choose_move(&game).expect("bot must return a move on a non-empty board");
+        let coords = FastBot::new().choose_move(&game).expect("bot must return a move on a non-empty board");
         let idx = coords.to_index(game.board_size());
         assert!(
             game.available_cells().contains(&idx),
@@ -76,7 +166,7 @@ mod tests {
         })
         .unwrap();
 
-        let chosen = FastBot.choose_move(&game).expect("bot must return a move");
+        let chosen = FastBot::new().choose_move(&game).expect("bot must return a move");
         assert_ne!(chosen, occupied, "bot must not choose the already-occupied cell");
         let idx = chosen.to_index(game.board_size());
         assert!(game.available_cells().contains(&idx));
@@ -86,7 +176,7 @@ mod tests {
     #[test]
     fn test_fast_bot_pie_opening_returns_available_cell() {
         let game = GameY::new(5);
-        let coords = FastBot
+        let coords = FastBot::new()
             .choose_pie_opening(&game)
             .expect("pie opening must return a move on a fresh board");
         let idx = coords.to_index(game.board_size());
@@ -109,7 +199,7 @@ mod tests {
         })
         .unwrap();
 
-        let choice = FastBot.decide_pie(&game);
+        let choice = FastBot::new().decide_pie(&game);
         assert_eq!(
             choice,
             PieChoice::Swap,
@@ -130,11 +220,27 @@ mod tests {
         })
         .unwrap();
 
-        let choice = FastBot.decide_pie(&game);
+        let choice = FastBot::new().decide_pie(&game);
         assert_eq!(
             choice,
             PieChoice::Swap,
             "a corner touching 2 sides already covers 2/3 of the win condition — worth swapping"
         );
     }
+
+    // A corner is a poor first move, so a search never picks it and only
+    // the book can.
+    fn corner_book() -> Arc<OpeningBook> {
+        let mut book = OpeningBook::new();
+        book.add(&GameY::new(5), Coordinates::new(4, 0, 0), 1);
+        Arc::new(book)
+    }
+
+    #[test]
+    fn test_fast_bot_plays_book_moves() {
+        let bot = FastBot::new().with_book(corner_book());
+        let game = GameY::new(5);
+        assert_eq!(bot.choose_move(&game), Some(Coordinates::new(4, 0, 0)));
+        assert!(bot.metadata().features.randomized);
+    }
 }
@@ -0,0 +1,237 @@
+//! Multi-PV analysis: the best N root moves with scores and variations.
+
+use crate::{CandidateMove, Coordinates, GameY};
+use std::cmp::Reverse;
+use std::time::Duration;
+
+use super::config::MinimaxConfig;
+use super::eval::{Evaluator, EvaluatorKind, HeuristicEvaluator, ResistanceEvaluator};
+use super::search::{SearchContext, negamax, principal_variation};
+use super::state::MinimaxState;
+use super::stats;
+use super::stop::StopToken;
+use super::tables::KillerTable;
+use super::{ABORTED, INFINITY, MAX_DEPTH, WIN_SCORE};
+
+/// One analysed root move, expressed in cell indices.
+pub(super) struct RootLine {
+    pub mv: usize,
+    pub score: i32,
+    pub pv: Vec<usize>,
+}
+
+// ============================================================================
+// Public helper
+// ============================================================================
+
+/// Analyses `game` and returns up to `multipv` candidate moves for the side to
+/// move, best first.
+///
+/// Each candidate carries its search score (from the mover's perspective) and
+/// the expected continuation starting with the candidate itself. Only the
+/// first `multipv` scores are exact; the search spends no effort ranking the
+/// remaining moves. Returns an empty list when the game is over or the board
+/// is full.
+pub fn analyze_with_minimax(
+    game: &GameY,
+    min_time_ms: u64,
+    max_time_ms: u64,
+    multipv: usize,
+) -> Vec<CandidateMove> {
+    let evaluator = HeuristicEvaluator::default();
+    analyze_impl(game, min_time_ms, max_time_ms, None, multipv, evaluator)
+}
+
+/// Like [`analyze_with_minimax`], but searches within the time budget, or
+/// the node limit, of `config` with its evaluator and weights. The skill
+/// level does not weaken an analysis.
+pub fn analyze_with_config(
+    game: &GameY,
+    config: &MinimaxConfig,
+    multipv: usize,
+) -> Vec<CandidateMove> {
+    let (min_time_ms, max_time_ms) = (config.min_time_ms, config.max_time_ms);
+    let max_nodes = config.max_nodes;
+    match config.evaluator {
+        EvaluatorKind::Heuristic => analyze_impl(
+            game,
+            min_time_ms,
+            max_time_ms,
+            max_nodes,
+            multipv,
+            HeuristicEvaluator::new(config.weights),
+        ),
+        EvaluatorKind::Resistance => analyze_impl(
+            game,
+            min_time_ms,
+            max_time_ms,
+            max_nodes,
+            multipv,
+            ResistanceEvaluator,
+        ),
+    }
+}
+
+fn analyze_impl<E: Evaluator>(
+    game: &GameY,
+    min_time_ms: u64,
+    max_time_ms: u64,
+    max_nodes: Option<u64>,
+    multipv: usize,
+    evaluator: E,
+) -> Vec<CandidateMove> {
+    let Some(bot_player) = game.next_player() else {
+        return Vec::new();
+    };
+    let mut state = MinimaxState::new(game, bot_player);
+    if state.available_cells().next().is_none() {
+        return Vec::new();
+    }
+
+    let multipv = multipv.max(1);
+    let size = game.board_size();
+    let to_coords = |idx: usize| Coordinates::from_index(idx as u32, size);
+
+    let mut ctx = SearchContext::new(state.board.len(), Duration::from_millis(max_time_ms))
+        .with_node_limit(max_nodes)
+        .with_stop_token(StopToken::current())
+        .with_evaluator(evaluator);
+    multipv_iterative_deepening(&mut state, multipv, min_time_ms, MAX_DEPTH, &mut ctx)
+        .into_iter()
+        .take(multipv)
+        .map(|line| CandidateMove {
+            coords: to_coords(line.mv),
+            score: line.score,
+            pv: line.pv.into_iter().map(to_coords).collect(),
+        })
+        .collect()
+}
+
+// ============================================================================
+// Search
+// ============================================================================
+
+/// Iterative deepening over the whole root move list, keeping the best
+/// `multipv` moves exact at every depth up to `max_depth`.
+///
+/// Returns every root move sorted by score, best first. The ordering found at
+/// one depth seeds the next one.
+pub(super) fn multipv_iterative_deepening<E: Evaluator>(
+    state: &mut MinimaxState,
+    multipv: usize,
+    min_time_ms: u64,
+    max_depth: u8,
+    ctx: &mut SearchContext<E>,
+) -> Vec<RootLine> {
+    let min_limit = Duration::from_millis(min_time_ms);
+    let empty_cells = state.available_cells().count();
+
+    let mut order: Vec<usize> = state
+        .shortest_path_deltas(state.bot_id)
+        .into_iter()
+        .map(|(idx, _)| idx)
+        .collect();
+    let mut lines: Vec<RootLine> = order
+        .iter()
+        .map(|&mv| RootLine {
+            mv,
+            score: 0,
+            pv: vec![mv],
+        })
+        .collect();
+
+    let mut completed = 0;
+    for depth in 1..=max_depth {
+        if ctx.min_gate_reached(min_limit) || depth as usize > empty_cells {
+            break;
+        }
+
+        ctx.killers = KillerTable::new(depth as usize);
+
+        let Some(scored) = search_root_lines(state, depth, &order, multipv, ctx) else {
+            break;
+        };
+
+        order = scored.iter().map(|&(mv, _)| mv).collect();
+        lines = scored
+            .into_iter()
+            .map(|(mv, score)| RootLine {
+                mv,
+                score,
+                pv: root_line_pv(state, ctx, mv, depth as usize),
+            })
+            .collect();
+        completed = depth as u32;
+
+        ctx.history.age();
+    }
+
+    stats::record(ctx.stats(completed));
+    lines
+}
+
+/// Searches every root move in `order` at `depth` and returns them sorted by
+/// score. Returns `None` if the time limit expires mid-search.
+///
+/// Moves are searched with a window whose lower bound is the `multipv`-th best
+/// score found so far, so moves that cannot enter the top list fail low
+/// cheaply.
+pub(super) fn search_root_lines<E: Evaluator>(
+    state: &mut MinimaxState,
+    depth: u8,
+    order: &[usize],
+    multipv: usize,
+    ctx: &mut SearchContext<E>,
+) -> Option<Vec<(usize, i32)>> {
+    let player = state.bot_id;
+    let opponent = state.human_id;
+    let mut scored: Vec<(usize, i32)> = Vec::with_capacity(order.len());
+    // Best `multipv` scores so far, sorted in descending order.
+    let mut top: Vec<i32> = Vec::with_capacity(multipv + 1);
+
+    for &mv in order {
+        let alpha = if top.len() >= multipv {
+            top[multipv - 1]
+        } else {
+            -INFINITY
+        };
+
+        state.make_move(mv, player);
+        let score = if state.check_win(player) {
+            WIN_SCORE
+        } else {
+            let child = negamax(state, depth - 1, -INFINITY, -alpha, opponent, ctx);
+            if child == ABORTED {
+                state.undo_move(mv);
+                return None;
+            }
+            -child
+        };
+        state.undo_move(mv);
+
+        let pos = top.partition_point(|&s| s >= score);
+        top.insert(pos, score);
+        top.truncate(multipv);
+        scored.push((mv, score));
+    }
+
+    scored.sort_by_key(|&(_, score)| Reverse(score));
+    Some(scored)
+}
+
+/// Returns the expected line after playing root move `mv`, including `mv`.
+fn root_line_pv<E: Evaluator>(
+    state: &mut MinimaxState,
+    ctx: &SearchContext<E>,
+    mv: usize,
+    max_len: usize,
+) -> Vec<usize> {
+    let player = state.bot_id;
+    state.make_move(mv, player);
+    let mut pv = vec![mv];
+    if !state.check_win(player) {
+        pv.extend(principal_variation(state, &ctx.tt, state.human_id, max_len.saturating_sub(1)));
+    }
+    state.undo_move(mv);
+    pv
+}
@@ -0,0 +1,198 @@
+//! Tunable parameters for minimax-based bots.
+
+use super::eval::{EvalWeights, EvaluatorKind};
+use crate::{BotFeatures, SearchOptions};
+use serde::{Deserialize, Serialize};
+
+/// Weakest supported skill level.
+pub const MIN_SKILL: u8 = 1;
+
+/// Strongest skill level: full-depth search with no deliberate mistakes.
+pub const MAX_SKILL: u8 = 20;
+
+/// Default for [`MinimaxConfig::solver_max_empty`].
+pub const DEFAULT_SOLVER_MAX_EMPTY: u32 = 12;
+
+/// Default for [`MinimaxConfig::tt_size_mb`].
+pub const DEFAULT_TT_SIZE_MB: usize = 48;
+
+/// Configuration for a minimax bot.
+///
+/// Built with [`MinimaxConfig::new`] and refined with the `with_*` methods:
+///
+/// ```
+/// use gamey::MinimaxConfig;
+///
+/// let config = MinimaxConfig::new(200, 500).with_skill(8).with_seed(42);
+/// assert_eq!(config.skill, 8);
+/// assert_eq!(config.seed, Some(42));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MinimaxConfig {
+    /// Iterative deepening does not start a new depth after this many ms.
+    pub min_time_ms: u64,
+    /// Hard limit: a search in progress is abandoned after this many ms.
+    pub max_time_ms: u64,
+    /// Time budget for Pie Rule decisions and openings.
+    pub pie_time_ms: u64,
+    /// Playing strength from [`MIN_SKILL`] to [`MAX_SKILL`].
+    ///
+    /// Lower levels limit the search depth, add noise to the evaluation and
+    /// sometimes play a weaker candidate move.
+    pub skill: u8,
+    /// Seed for the random choices made at reduced skill levels.
+    /// `None` draws a fresh seed for every move.
+    pub seed: Option<u64>,
+    /// Node budget per search. When set, the time gates are ignored and the
+    /// search is reproducible regardless of machine speed.
+    pub max_nodes: Option<u64>,
+    /// At full strength, positions with at most this many empty cells are
+    /// first handed to the exact solver. `0` disables the solver.
+    pub solver_max_empty: u32,
+    /// Whether an attached opening book is consulted before searching.
+    pub use_book: bool,
+    /// Whether the search skips dead, captured and dominated cells.
+    pub prune_inferior: bool,
+    /// Size of the transposition table in megabytes, rounded down to a
+    /// power-of-two number of entries.
+    pub tt_size_mb: usize,
+    /// Threads of a full-strength search. With more than one, the root moves
+    /// are split between the threads, each with its own share of
+    /// `tt_size_mb` and of `max_nodes`. Reduced skill levels, pondering,
+    /// analysis and Pie Rule decisions always search with one thread.
+    pub threads: usize,
+    /// Evaluation function used at the leaves of the search.
+    pub evaluator: EvaluatorKind,
+    /// Weights of the heuristic evaluator.
+    pub weights: EvalWeights,
+}
+
+impl MinimaxConfig {
+    /// Creates a full-strength configuration with the given time gates.
+    pub fn new(min_time_ms: u64, max_time_ms: u64) -> Self {
+        Self {
+            min_time_ms,
+            max_time_ms,
+            pie_time_ms: max_time_ms,
+            skill: MAX_SKILL,
+            seed: None,
+            max_nodes: None,
+            solver_max_empty: DEFAULT_SOLVER_MAX_EMPTY,
+            use_book: true,
+            prune_inferior: true,
+            tt_size_mb: DEFAULT_TT_SIZE_MB,
+            threads: 1,
+            evaluator: EvaluatorKind::default(),
+            weights: EvalWeights::default(),
+        }
+    }
+
+    /// Returns `true` if the configuration plays at [`MAX_SKILL`].
+    pub fn is_full_strength(&self) -> bool {
+        self.skill >= MAX_SKILL
+    }
+
+    /// Features of a bot playing with this configuration. Pondering needs a
+    /// full-strength, time-limited configuration; reduced skill levels play
+    /// at random unless the seed is fixed.
+    pub fn features(&self) -> BotFeatures {
+        BotFeatures {
+            pie_rule: true,
+            analysis: true,
+            pondering: self.is_full_strength() && self.max_nodes.is_none(),
+            sessions: true,
+            seed: true,
+            node_limit: true,
+            skill: true,
+            randomized: !self.is_full_strength() && self.seed.is_none(),
+        }
+    }
+
+    /// Sets the skill level, clamped to `MIN_SKILL..=MAX_SKILL`.
+    pub fn with_skill(mut self, skill: u8) -> Self {
+        self.skill = skill.clamp(MIN_SKILL, MAX_SKILL);
+        self
+    }
+
+    /// Fixes the seed used for the bot's random choices.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Switches to node-limited search with the given budget.
+    pub fn with_node_limit(mut self, max_nodes: u64) -> Self {
+        self.max_nodes = Some(max_nodes);
+        self
+    }
+
+    /// Sets the number of empty cells at which the solver takes over.
+    pub fn with_solver_max_empty(mut self, empty_cells: u32) -> Self {
+        self.solver_max_empty = empty_cells;
+        self
+    }
+
+    /// Enables or disables the opening book.
+    pub fn with_book_enabled(mut self, enabled: bool) -> Self {
+        self.use_book = enabled;
+        self
+    }
+
+    /// Enables or disables inferior-cell pruning.
+    pub fn with_inferior_pruning(mut self, enabled: bool) -> Self {
+        self.prune_inferior = enabled;
+        self
+    }
+
+    /// Sets the size of the transposition table in megabytes.
+    pub fn with_tt_size_mb(mut self, size_mb: usize) -> Self {
+        self.tt_size_mb = size_mb;
+        self
+    }
+
+    /// Sets the number of search threads, at least one.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Selects the evaluation function.
+    pub fn with_evaluator(mut self, evaluator: EvaluatorKind) -> Self {
+        self.evaluator = evaluator;
+        self
+    }
+
+    /// Sets the weights of the heuristic evaluator.
+    pub fn with_weights(mut self, weights: EvalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    /// Applies per-request overrides on top of this configuration.
+    pub fn with_options(mut self, options: &SearchOptions) -> Self {
+        if let Some(seed) = options.seed {
+            self.seed = Some(seed);
+        }
+        if let Some(max_nodes) = options.max_nodes {
+            self.max_nodes = Some(max_nodes);
+        }
+        if let Some(skill) = options.skill {
+            self = self.with_skill(skill);
+        }
+        self
+    }
+
+    /// Sets the time budget used for Pie Rule decisions and openings.
+    pub fn with_pie_time(mut self, pie_time_ms: u64) -> Self {
+        self.pie_time_ms = pie_time_ms;
+        self
+    }
+}
+
+impl Default for MinimaxConfig {
+    /// Full strength with a 500 ms budget, matching `fast_bot`.
+    fn default() -> Self {
+        Self::new(500, 500)
+    }
+}
@@ -1,35 +1,163 @@
-//! Heuristic evaluation function.
+//! Evaluation functions.
+//!
+//! The search is generic over an [`Evaluator`], which it calls at its leaves.
+//! [`HeuristicEvaluator`] is the default; its weights live in
+//! [`EvalWeights`] and can be loaded from JSON for tuning.
+//! [`EvaluatorKind`] selects an evaluator from a [`MinimaxConfig`], so
+//! alternatives can be compared in bot-vs-bot matches.
+//!
+//! [`MinimaxConfig`]: super::MinimaxConfig
 
 use super::BLOCKED;
 use super::LOSE_SCORE;
 use super::WIN_SCORE;
+use super::resistance::resistance_score;
 use super::state::MinimaxState;
+use crate::{GameYError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
-/// Heuristic evaluation from `player`'s perspective.
+/// Scores a position from the point of view of one player.
+///
+/// Implement this to try a new evaluation without touching the search, and
+/// run it with [`choose_move_with_evaluator`](super::choose_move_with_evaluator).
+pub trait Evaluator {
+    /// Returns the score of `state` for `player`; positive is good for
+    /// `player`. `player` is a cell value as used by [`MinimaxState::cell`].
+    fn evaluate(&self, state: &mut MinimaxState, player: u8) -> i32;
+}
+
+/// The built-in evaluators, as selected by [`MinimaxConfig::evaluator`].
+///
+/// [`MinimaxConfig::evaluator`]: super::MinimaxConfig::evaluator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluatorKind {
+    /// [`HeuristicEvaluator`] with the configured weights.
+    #[default]
+    Heuristic,
+    /// [`ResistanceEvaluator`].
+    Resistance,
+}
+
+/// Weights of [`HeuristicEvaluator`].
+///
+/// Missing fields take their default value, so a JSON file only needs the
+/// weights being tuned:
+///
+/// ```
+/// use gamey::EvalWeights;
+///
+/// let weights = EvalWeights::from_json(r#"{ "connection_cost": 200 }"#).unwrap();
+/// assert_eq!(weights.connection_cost, 200);
+/// assert_eq!(weights.adjacency, EvalWeights::default().adjacency);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvalWeights {
+    /// Score per cell of connection-cost advantage over the opponent.
+    pub connection_cost: i32,
+    /// Score per same-player adjacency.
+    pub adjacency: i32,
+    /// Bonus per stone with at least two same-player neighbours.
+    pub well_connected: i32,
+    /// Bonus per board side touched.
+    pub edge: i32,
+    /// Weight of stone centrality on an empty board; it fades to zero as the
+    /// board fills up.
+    pub centre: i32,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            connection_cost: 150,
+            adjacency: 25,
+            well_connected: 40,
+            edge: 5,
+            centre: 5,
+        }
+    }
+}
+
+impl EvalWeights {
+    /// Parses weights from a JSON string.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| GameYError::SerdeError { error: e })
+    }
+
+    /// Loads weights from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let filename = path.as_ref().display().to_string();
+        let content = std::fs::read_to_string(path).map_err(|e| GameYError::IoError {
+            message: format!("Failed to read evaluation weights: {}", filename),
+            error: e.to_string(),
+        })?;
+        Self::from_json(&content)
+    }
+}
+
+/// The default evaluator.
 ///
 /// Primary component: BFS-based connection cost difference.
 ///     opponent_cost - own_cost  (positive = good for player)
 ///
 /// Secondary component: incremental score (well-connected pieces, edge touches,
 /// centrality) as a tiebreaker.
-pub(super) fn evaluate_state(state: &mut MinimaxState, player: u8) -> i32 {
-    let opponent = state.opponent_of(player);
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeuristicEvaluator {
+    weights: EvalWeights,
+}
+
+impl HeuristicEvaluator {
+    /// Creates an evaluator with the given weights.
+    pub fn new(weights: EvalWeights) -> Self {
+        Self { weights }
+    }
+
+    /// Returns the evaluator's weights.
+    pub fn weights(&self) -> &EvalWeights {
+        &self.weights
+    }
+}
 
-    let own_cost = state.connection_cost(player);
-    let opp_cost = state.connection_cost(opponent);
+impl Evaluator for HeuristicEvaluator {
+    fn evaluate(&self, state: &mut MinimaxState, player: u8) -> i32 {
+        let opponent = state.opponent_of(player);
 
-    let bfs_score = match (own_cost == BLOCKED, opp_cost == BLOCKED) {
-        (true, true) => 0,
-        (true, false) => LOSE_SCORE / 2,
-        (false, true) => WIN_SCORE / 2,
-        (false, false) => (opp_cost as i32 - own_cost as i32) * 150,
-    };
+        let own_cost = state.connection_cost(player);
+        let opp_cost = state.connection_cost(opponent);
 
-    let p_idx = state.player_idx(player);
-    let o_idx = 1 - p_idx;
-    let game_progress = state.occupied_count as f32 / state.board.len() as f32;
-    let incr =
-        state.scores[p_idx].evaluate(game_progress) - state.scores[o_idx].evaluate(game_progress);
+        let bfs_score = match (own_cost == BLOCKED, opp_cost == BLOCKED) {
+            (true, true) => 0,
+            (true, false) => LOSE_SCORE / 2,
+            (false, true) => WIN_SCORE / 2,
+            (false, false) => (opp_cost as i32 - own_cost as i32) * self.weights.connection_cost,
+        };
 
-    bfs_score + incr
+        let p_idx = state.player_idx(player);
+        let o_idx = 1 - p_idx;
+        let game_progress = state.occupied_count as f32 / state.board.len() as f32;
+        let incr = state.scores[p_idx].evaluate(game_progress, &self.weights)
+            - state.scores[o_idx].evaluate(game_progress, &self.weights);
+
+        bfs_score + incr
+    }
+}
+
+/// Evaluator based on the electrical resistance of each player's network;
+/// see the `resistance` module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResistanceEvaluator;
+
+impl Evaluator for ResistanceEvaluator {
+    fn evaluate(&self, state: &mut MinimaxState, player: u8) -> i32 {
+        resistance_score(state, player)
+    }
+}
+
+/// Evaluates `state` for `player` with the default heuristic and weights.
+#[cfg(test)]
+pub(super) fn evaluate_state(state: &mut MinimaxState, player: u8) -> i32 {
+    HeuristicEvaluator::default().evaluate(state, player)
 }
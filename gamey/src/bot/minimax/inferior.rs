@@ -0,0 +1,54 @@
+//! Inferior-cell analysis.
+//!
+//! Some empty cells can be left out of the search because a local pattern
+//! proves that playing them is never better than some other move:
+//!
+//! - **Dead** cells: every neighbour belongs to the same player. The
+//!   neighbours of a cell form a ring (a path along a side), so they are
+//!   already connected and already touch every side the cell touches. A stone
+//!   there changes nothing for either player.
+//! - **Captured** cells: two adjacent empty cells whose other neighbours all
+//!   belong to one player. If the other player takes one of them, the owner
+//!   answers with the second and the intruding stone is dead, so the other
+//!   player need not play there.
+//! - **Dominated** cells: an empty cell whose neighbours are the mover's
+//!   stones except for one empty cell `e`. Playing `e` leaves the cell dead,
+//!   so `e` is at least as good for the mover.
+//!
+//! All three patterns only look at the neighbours of a cell, so the check is
+//! cheap enough to run at every node.
+
+use super::state::MinimaxState;
+
+/// Returns `true` if `player` can skip the empty cell `idx`.
+pub(super) fn is_inferior(state: &MinimaxState, idx: usize, player: u8) -> bool {
+    let opponent = state.opponent_of(player);
+    let mut own = 0;
+    let mut theirs = 0;
+    let mut empty = None;
+    for &nb in state.neighbors(idx) {
+        match state.cell(nb) {
+            0 if empty.is_some() => return false,
+            0 => empty = Some(nb),
+            c if c == player => own += 1,
+            _ => theirs += 1,
+        }
+    }
+
+    match empty {
+        // Dead. A cell without neighbours is the whole board and wins.
+        None => (own == 0) != (theirs == 0),
+        // Dominated by `e` for the mover.
+        Some(_) if theirs == 0 => true,
+        // Captured by the opponent together with `e`, unless the pair spans
+        // all three sides (only possible on tiny boards).
+        Some(e) if own == 0 => {
+            state.sides(idx) | state.sides(e) != 0b111
+                && state
+                    .neighbors(e)
+                    .iter()
+                    .all(|&nb| nb == idx || state.cell(nb) == opponent)
+        }
+        Some(_) => false,
+    }
+}
@@ -1,13 +1,29 @@
 //! Minimax search engine for the Game of Y.
 //!
 //! Provides the core alpha-beta search with iterative deepening, transposition
-//! tables, killer moves, history heuristic, and Pie Rule support.
+//! tables, killer moves, history heuristic, multi-PV analysis and Pie Rule
+//! support, plus an exact proof-number solver for small positions and
+//! detection of virtual connections (bridges and edge templates). A
+//! [`SearchSession`] keeps the tables between the moves of one game and can
+//! ponder on the opponent's time, and a [`StopToken`] cancels running
+//! searches.
 
+mod analysis;
+mod config;
 mod eval;
+mod inferior;
 mod pie;
+mod ponder;
+mod resistance;
 mod search;
+mod session;
+mod skill;
+mod solver;
 mod state;
+mod stats;
+mod stop;
 mod tables;
+mod virtual_conn;
 
 #[cfg(test)]
 mod tests;
@@ -17,6 +33,9 @@ pub(crate) const WIN_SCORE: i32 = 100_000;
 pub(crate) const LOSE_SCORE: i32 = -WIN_SCORE;
 pub(crate) const INFINITY: i32 = i32::MAX / 2;
 
+/// Deepest iteration the iterative-deepening loops will attempt.
+pub(crate) const MAX_DEPTH: u8 = 100;
+
 /// Sentinel returned by `negamax` when the hard time limit is exceeded.
 pub(crate) const ABORTED: i32 = i32::MIN;
 
@@ -24,5 +43,22 @@ pub(crate) const ABORTED: i32 = i32::MIN;
 pub(crate) const BLOCKED: u32 = u32::MAX;
 
 // Public API re-exports.
-pub use pie::{choose_pie_opening_with_minimax, decide_pie_with_minimax};
-pub use search::choose_move_with_minimax;
+pub use analysis::{analyze_with_config, analyze_with_minimax};
+pub use config::{
+    DEFAULT_SOLVER_MAX_EMPTY, DEFAULT_TT_SIZE_MB, MAX_SKILL, MIN_SKILL, MinimaxConfig,
+};
+pub use eval::{EvalWeights, Evaluator, EvaluatorKind, HeuristicEvaluator, ResistanceEvaluator};
+pub use pie::{
+    choose_pie_opening_with_config, choose_pie_opening_with_minimax, decide_pie_with_config,
+    decide_pie_with_minimax,
+};
+pub use search::{
+    choose_move_in_session, choose_move_with_config, choose_move_with_evaluator,
+    choose_move_with_minimax, choose_move_with_progress,
+};
+pub use session::SearchSession;
+pub use solver::{DEFAULT_SOLVER_NODES, SolveOutcome, SolveResult, solve};
+pub use state::MinimaxState;
+pub use stats::{SearchStats, collect_search_stats};
+pub use stop::{StopOnDrop, StopToken};
+pub use virtual_conn::{Bridge, EdgeTemplate, Side, VirtualConnections, virtual_connections};
@@ -1,11 +1,26 @@
 //! Pie Rule support: opening selection and keep/swap decision.
+//!
+//! These searches run straight iterative deepening and never hand off to the
+//! endgame solver: with one stone on the board there is nothing to solve, so
+//! every scenario keeps its whole share of the time budget.
+//!
+//! Below full strength they are weakened like a move search: every scenario
+//! is scored with the evaluation noise of the skill level, and with its
+//! blunder chance the bot makes a worse choice on purpose.
 
 use crate::{Coordinates, GameY, Movement, PlayerId, YEN, game};
+use rand::Rng;
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
 use smallvec::SmallVec;
+use std::time::Duration;
 
-use super::eval::evaluate_state;
-use super::search::iterative_deepening_search;
+use super::config::MinimaxConfig;
+use super::eval::{Evaluator, EvaluatorKind, HeuristicEvaluator, ResistanceEvaluator};
+use super::search::{SearchContext, iterative_deepening_search, run_iterative_deepening};
+use super::skill::{SkillProfile, skill_rng};
 use super::state::MinimaxState;
+use super::stop::StopToken;
 use crate::bot::ybot::PieChoice;
 
 // ============================================================================
@@ -39,6 +54,81 @@ fn make_swapped_game(game: &GameY) -> GameY {
     GameY::try_from(swapped_yen).expect("swapped YEN must be valid")
 }
 
+/// Budget, evaluator and skill shared by the scenario searches of one Pie
+/// Rule call.
+struct PieSearch<E> {
+    time_ms: u64,
+    max_nodes: Option<u64>,
+    evaluator: E,
+    /// Weakening below full strength, `None` at full strength.
+    skill: Option<(SkillProfile, StdRng)>,
+    stop: StopToken,
+}
+
+impl<E: Evaluator + Clone> PieSearch<E> {
+    /// A full-strength search of `time_ms`.
+    fn timed(time_ms: u64, evaluator: E) -> Self {
+        Self {
+            time_ms,
+            max_nodes: None,
+            evaluator,
+            skill: None,
+            stop: StopToken::current(),
+        }
+    }
+
+    /// A search of `config.pie_time_ms`, or `config.max_nodes`, at the skill
+    /// level and with the seed of `config`.
+    fn for_config(config: &MinimaxConfig, evaluator: E) -> Self {
+        let skill = (!config.is_full_strength())
+            .then(|| (SkillProfile::for_level(config.skill), skill_rng(config.seed)));
+        Self {
+            max_nodes: config.max_nodes,
+            skill,
+            ..Self::timed(config.pie_time_ms, evaluator)
+        }
+    }
+
+    /// Splits the budget between `searches` scenario searches, giving each
+    /// at least `min_time_ms`.
+    fn split(mut self, searches: u64, min_time_ms: u64) -> Self {
+        self.time_ms = (self.time_ms / searches).max(min_time_ms);
+        self.max_nodes = self.max_nodes.map(|nodes| (nodes / searches).max(1));
+        self
+    }
+
+    /// Searches `state` and returns the score for the side to move.
+    fn score(&mut self, state: &mut MinimaxState) -> i32 {
+        let (time_ms, max_nodes, stop) = (self.time_ms, self.max_nodes, &self.stop);
+        let Some((profile, rng)) = &mut self.skill else {
+            let evaluator = self.evaluator.clone();
+            let (_, score) =
+                iterative_deepening_search(state, time_ms, time_ms, max_nodes, stop, evaluator);
+            return score;
+        };
+        let mut ctx = SearchContext::new(state.board.len(), Duration::from_millis(time_ms))
+            .with_node_limit(max_nodes)
+            .with_stop_token(stop.clone())
+            .with_evaluator(self.evaluator.clone())
+            .with_eval_noise(profile.eval_noise, rng.random());
+        run_iterative_deepening(state, time_ms, &mut ctx).1
+    }
+
+    /// Picks the first of `ranked`, which is sorted best first, or on a
+    /// deliberate mistake one of the next candidates of the skill level.
+    fn pick<T: Copy>(&mut self, ranked: &[T]) -> T {
+        let best = ranked[0];
+        let Some((profile, rng)) = &mut self.skill else {
+            return best;
+        };
+        if !rng.random_bool(profile.blunder_chance) {
+            return best;
+        }
+        let weaker = &ranked[1..ranked.len().min(profile.candidates)];
+        weaker.choose(rng).copied().unwrap_or(best)
+    }
+}
+
 // ============================================================================
 // Pie decision (second player)
 // ============================================================================
@@ -54,41 +144,54 @@ fn make_swapped_game(game: &GameY) -> GameY {
 /// * **Swap**: the stone changes ownership, the opponent moves next, and the
 ///   bot now owns the stone.
 pub fn decide_pie_with_minimax(game: &GameY, time_ms: u64) -> PieChoice {
+    decide_pie_impl(game, PieSearch::timed(time_ms, HeuristicEvaluator::default()))
+}
+
+/// Like [`decide_pie_with_minimax`], but searches for `config.pie_time_ms`
+/// with the evaluator and weights of `config`.
+///
+/// With `config.max_nodes` set, each scenario gets half of the nodes instead
+/// of the time budget and the decision is reproducible. Below
+/// [`MAX_SKILL`](super::MAX_SKILL) the decision is weakened as described in
+/// the [module documentation](self), with random choices drawn from
+/// `config.seed`.
+pub fn decide_pie_with_config(game: &GameY, config: &MinimaxConfig) -> PieChoice {
+    match config.evaluator {
+        EvaluatorKind::Heuristic => decide_pie_impl(
+            game,
+            PieSearch::for_config(config, HeuristicEvaluator::new(config.weights)),
+        ),
+        EvaluatorKind::Resistance => {
+            decide_pie_impl(game, PieSearch::for_config(config, ResistanceEvaluator))
+        }
+    }
+}
+
+fn decide_pie_impl<E: Evaluator + Clone>(game: &GameY, search: PieSearch<E>) -> PieChoice {
     let bot_player = match game.next_player() {
         Some(p) => p,
         None => return PieChoice::Keep,
     };
-
-    let half = time_ms.max(2) / 2;
+    let mut search = search.split(2, 1);
 
     // ── Keep scenario ──
-    let keep_score = {
-        let mut state = MinimaxState::new(game, bot_player);
-        let (_, score) = iterative_deepening_search(&mut state, half, half);
-        score
-    };
+    let keep_score = search.score(&mut MinimaxState::new(game, bot_player));
 
     // ── Swap scenario ──
     let swap_score = {
         let swapped = make_swapped_game(game);
         let opponent = game::other_player(bot_player);
-        let mut state = MinimaxState::new(&swapped, opponent);
-        let (_, opp_score) = iterative_deepening_search(&mut state, half, half);
-        -opp_score
+        -search.score(&mut MinimaxState::new(&swapped, opponent))
     };
 
-    println!(
-        "Pie decision: keep_score={}, swap_score={} → {}",
-        keep_score,
-        swap_score,
-        if swap_score > keep_score { "SWAP" } else { "KEEP" }
-    );
-
-    if swap_score > keep_score {
-        PieChoice::Swap
+    let ranked = if swap_score > keep_score {
+        [PieChoice::Swap, PieChoice::Keep]
     } else {
-        PieChoice::Keep
-    }
+        [PieChoice::Keep, PieChoice::Swap]
+    };
+    let choice = search.pick(&ranked);
+    tracing::debug!(keep_score, swap_score, ?choice, "pie decision");
+    choice
 }
 
 // ============================================================================
@@ -111,25 +214,55 @@ const MAX_PIE_CANDIDATES: usize = 8;
 ///    both keep and swap scenarios. The opponent will choose whichever is
 ///    better for them; the bot picks the cell that minimises that advantage.
 pub fn choose_pie_opening_with_minimax(game: &GameY, time_ms: u64) -> Option<Coordinates> {
+    choose_pie_opening_impl(game, PieSearch::timed(time_ms, HeuristicEvaluator::default()))
+}
+
+/// Like [`choose_pie_opening_with_minimax`], but searches for
+/// `config.pie_time_ms` and scores with the evaluator and weights of
+/// `config`, in the pre-filter as well.
+///
+/// With `config.max_nodes` set, the nodes are split evenly between the
+/// candidate searches instead of the time budget. Below
+/// [`MAX_SKILL`](super::MAX_SKILL) the choice is weakened as described in the
+/// [module documentation](self), with random choices drawn from
+/// `config.seed`.
+pub fn choose_pie_opening_with_config(game: &GameY, config: &MinimaxConfig) -> Option<Coordinates> {
+    match config.evaluator {
+        EvaluatorKind::Heuristic => choose_pie_opening_impl(
+            game,
+            PieSearch::for_config(config, HeuristicEvaluator::new(config.weights)),
+        ),
+        EvaluatorKind::Resistance => {
+            choose_pie_opening_impl(game, PieSearch::for_config(config, ResistanceEvaluator))
+        }
+    }
+}
+
+fn choose_pie_opening_impl<E: Evaluator + Clone>(
+    game: &GameY,
+    search: PieSearch<E>,
+) -> Option<Coordinates> {
     let bot_player = game.next_player()?;
     let size = game.board_size();
 
     // ── Phase 1: static pre-filter ──
-    let candidates = static_prefilter(game, bot_player);
+    let candidates = static_prefilter(game, bot_player, &search.evaluator);
     if candidates.is_empty() {
         return None;
     }
 
     // ── Phase 2: deep evaluation of each candidate ──
-    // Split the time budget evenly: each candidate gets two mini-searches
-    // (keep + swap), so per-search budget = time_ms / (candidates * 2).
-    let per_search_ms = (time_ms / (candidates.len() as u64 * 2)).max(20);
-
-    let mut best_cell = candidates[0];
-    let mut best_score = i32::MIN;
+    // Split the budget evenly: each candidate gets two mini-searches
+    // (keep + swap).
+    let mut search = search.split(candidates.len() as u64 * 2, 20);
+    let mut scored: Vec<(usize, i32)> = Vec::with_capacity(candidates.len());
 
     for &cell_idx in &candidates {
+        if search.stop.is_stopped() {
+            break;
+        }
         let coords = Coordinates::from_index(cell_idx as u32, size);
+        let opponent = game::other_player(bot_player);
 
         // ── Keep scenario ──
         // Bot owns the stone, opponent moves next.
@@ -140,31 +273,26 @@ pub fn choose_pie_opening_with_minimax(game: &GameY, time_ms: u64) -> Option<Coo
             game_copy
                 .add_move(Movement::Placement {
                     player: bot_player,
-                    coords: coords.clone(),
+                    coords,
                 })
                 .ok();
-            // Opponent searches (they move next after keep)
-            let opponent = game::other_player(bot_player);
-            let mut state = MinimaxState::new(&game_copy, opponent);
-            let (_, opp_score) = iterative_deepening_search(&mut state, per_search_ms, per_search_ms);
-            -opp_score // negate: opponent's gain is bot's loss
+            // Opponent searches (they move next after keep); their gain is
+            // the bot's loss.
+            -search.score(&mut MinimaxState::new(&game_copy, opponent))
         };
 
         // ── Swap scenario ──
         // Opponent takes the stone (it becomes theirs), bot moves next.
         let swap_score = {
             let mut game_copy = game.clone();
-            let opponent = game::other_player(bot_player);
             game_copy
                 .add_move(Movement::Placement {
                     player: opponent,
-                    coords: coords.clone(),
+                    coords,
                 })
                 .ok();
             // Bot searches (they move next after swap)
-            let mut state = MinimaxState::new(&game_copy, bot_player);
-            let (_, bot_score) = iterative_deepening_search(&mut state, per_search_ms, per_search_ms);
-            bot_score
+            search.score(&mut MinimaxState::new(&game_copy, bot_player))
         };
 
         // The opponent picks whichever is worse for the bot.
@@ -172,27 +300,32 @@ pub fn choose_pie_opening_with_minimax(game: &GameY, time_ms: u64) -> Option<Coo
         // swap_score = how good swapping is for the bot
         // Opponent chooses min(keep_score, swap_score) from bot's perspective.
         let guaranteed = keep_score.min(swap_score);
-
-        println!(
-            "Pie opening candidate cell={} keep={} swap={} guaranteed={}",
-            cell_idx, keep_score, swap_score, guaranteed
+        tracing::debug!(
+            cell = cell_idx,
+            keep_score,
+            swap_score,
+            guaranteed,
+            "pie opening candidate"
         );
-
-        if guaranteed > best_score {
-            best_score = guaranteed;
-            best_cell = cell_idx;
-        }
+        scored.push((cell_idx, guaranteed));
     }
 
-    println!("Pie opening chosen: cell={} score={}", best_cell, best_score);
-    Some(Coordinates::from_index(best_cell as u32, size))
+    // Stable, so the first of equally scored candidates stays in front.
+    scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    let chosen = if scored.is_empty() {
+        candidates[0]
+    } else {
+        search.pick(&scored).0
+    };
+    tracing::debug!(cell = chosen, "pie opening chosen");
+    Some(Coordinates::from_index(chosen as u32, size))
 }
 
 /// Pre-filters cells by static evaluation and returns the most balanced ones.
 ///
 /// "Balanced" means the opponent's advantage from keep vs swap is smallest.
 /// Among equally balanced cells, stronger ones are preferred.
-fn static_prefilter(game: &GameY, bot_player: PlayerId) -> Vec<usize> {
+fn static_prefilter<E: Evaluator>(game: &GameY, bot_player: PlayerId, evaluator: &E) -> Vec<usize> {
     let mut state = MinimaxState::new(game, bot_player);
     let cells: SmallVec<[usize; 128]> = state.available_cells().collect();
     if cells.is_empty() {
@@ -207,12 +340,12 @@ fn static_prefilter(game: &GameY, bot_player: PlayerId) -> Vec<usize> {
         .map(|&cell_idx| {
             // Keep: bot owns the stone
             state.make_move(cell_idx, bot_id);
-            let keep_value = evaluate_state(&mut state, bot_id);
+            let keep_value = evaluator.evaluate(&mut state, bot_id);
             state.undo_move(cell_idx);
 
             // Swap: opponent takes the stone
             state.make_move(cell_idx, human_id);
-            let swap_value = evaluate_state(&mut state, human_id);
+            let swap_value = evaluator.evaluate(&mut state, human_id);
             state.undo_move(cell_idx);
 
             // Balance score: penalise imbalance, reward strength
@@ -222,7 +355,7 @@ fn static_prefilter(game: &GameY, bot_player: PlayerId) -> Vec<usize> {
         })
         .collect();
 
-    scored.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+    scored.sort_unstable_by_key(|&(_, score)| std::cmp::Reverse(score));
     scored
         .into_iter()
         .take(MAX_PIE_CANDIDATES)
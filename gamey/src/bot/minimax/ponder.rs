@@ -0,0 +1,105 @@
+//! Pondering: searching on the opponent's time.
+//!
+//! After the bot has moved, the opponent usually needs a while to answer. A
+//! ponder search guesses that answer and searches the resulting position in
+//! a background thread, filling the tables of the game's [`SearchSession`].
+//! When the guess was right, the real search finds most of its work done.
+
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{Coordinates, GameY, YEN};
+
+use super::config::MinimaxConfig;
+use super::search::search_in_session;
+use super::session::SearchSession;
+use super::stop::StopToken;
+
+/// A ponder search that nobody stops ends after this many times the bot's
+/// own time budget, about as long as an opponent takes to answer.
+const PONDER_TIME_FACTOR: u64 = 4;
+
+/// Anything a ponder search holds until it ends, e.g. a search slot.
+pub(super) type PonderSlot = Box<dyn Send>;
+
+/// A ponder search running in a background thread.
+pub(super) struct Ponder {
+    predicted: Coordinates,
+    /// Layout of the pondered position, used to recognise it later.
+    layout: String,
+    started: Instant,
+    stop: StopToken,
+    handle: Option<JoinHandle<SearchSession>>,
+}
+
+impl Ponder {
+    /// Starts searching `pondered`, the position after the `predicted`
+    /// reply. The thread owns `session` until the ponder is stopped, and
+    /// `slot` until the search ends. The search also stops together with the
+    /// token of the enclosing [`StopToken::scope`], e.g. a server shutting
+    /// down.
+    pub(super) fn start(
+        pondered: GameY,
+        predicted: Coordinates,
+        config: &MinimaxConfig,
+        mut session: SearchSession,
+        slot: Option<PonderSlot>,
+    ) -> Self {
+        let mut config = config.clone();
+        let limit_ms = config.max_time_ms.saturating_mul(PONDER_TIME_FACTOR);
+        config.min_time_ms = limit_ms;
+        config.max_time_ms = limit_ms;
+        // The solver keeps nothing in the session, so its work would be lost.
+        config.solver_max_empty = 0;
+        // Neither does a parallel search.
+        config.threads = 1;
+
+        let layout = YEN::from(&pondered).layout().to_string();
+        let stop = StopToken::current().child();
+        let token = stop.clone();
+        let handle = std::thread::spawn(move || {
+            let _slot = slot;
+            search_in_session(&pondered, &config, &mut session, token);
+            session
+        });
+
+        Self {
+            predicted,
+            layout,
+            started: Instant::now(),
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// The opponent's reply this ponder search assumes.
+    pub(super) fn predicted(&self) -> Coordinates {
+        self.predicted
+    }
+
+    /// Returns `true` if `game` is the position being pondered.
+    pub(super) fn is_position(&self, game: &GameY) -> bool {
+        YEN::from(game).layout() == self.layout
+    }
+
+    /// Time spent pondering so far.
+    pub(super) fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Stops the search and returns the session it filled.
+    pub(super) fn stop(mut self) -> SearchSession {
+        self.stop.stop();
+        self.handle
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for Ponder {
+    /// A dropped ponder search stops on its own; nobody waits for it.
+    fn drop(&mut self) {
+        self.stop.stop();
+    }
+}
@@ -0,0 +1,274 @@
+//! Electrical-resistance evaluation.
+//!
+//! The board is treated as a resistor network for each player: own stones
+//! are near-perfect conductors, empty cells are unit resistors and opponent
+//! stones are cut out. A Y connection is one group touching all three sides,
+//! so a player's network resistance is measured from the best single hub
+//! cell: `min over v of R(v, A) + R(v, B) + R(v, C)`, where `R(v, S)` is the
+//! effective resistance between cell `v` and side `S`.
+//!
+//! Pairwise side-to-side resistances are not used because adjacent sides
+//! meet at the corners, so a single corner stone would short-circuit a pair.
+//!
+//! Compared with the shortest-path `connection_cost`, this counts how *many*
+//! good routes a player has, not only how short the best one is.
+//!
+//! Row-major cell order keeps each network's matrix banded with a bandwidth
+//! of at most the board size, so it is solved with a banded Cholesky
+//! factorisation and only the needed entries of its inverse are computed.
+
+use super::state::MinimaxState;
+use super::{LOSE_SCORE, WIN_SCORE};
+
+const SIDES: [u8; 3] = [0b001, 0b010, 0b100];
+
+/// Resistance of a cell occupied by the player being measured.
+const OWN_RESISTANCE: f64 = 0.01;
+
+/// Resistance of an empty cell.
+const EMPTY_RESISTANCE: f64 = 1.0;
+
+/// Score per unit of `ln(R_opponent / R_own)`.
+const RESISTANCE_SCALE: f64 = 500.0;
+/// Resistance-based evaluation from `player`'s perspective.
+///
+/// Positive when `player`'s network conducts better than the opponent's.
+/// A player who can no longer join all three sides counts as lost.
+pub(super) fn resistance_score(state: &mut MinimaxState, player: u8) -> i32 {
+    let opponent = state.opponent_of(player);
+    match (
+        total_resistance(state, player),
+        total_resistance(state, opponent),
+    ) {
+        (None, None) => 0,
+        (None, Some(_)) => LOSE_SCORE / 2,
+        (Some(_), None) => WIN_SCORE / 2,
+        (Some(own), Some(opp)) => ((opp / own).ln() * RESISTANCE_SCALE) as i32,
+    }
+}
+
+/// Returns the hub resistance of `player`'s network, or `None` if no cell
+/// can reach all three sides any more.
+pub(super) fn total_resistance(state: &mut MinimaxState, player: u8) -> Option<f64> {
+    let mut buffers = std::mem::take(&mut state.resistance_buffers);
+    let total = buffers.total_resistance(state, player);
+    state.resistance_buffers = buffers;
+    total
+}
+
+/// Working memory of the resistance evaluation, kept on [`MinimaxState`] so
+/// that evaluating a node does not allocate.
+#[derive(Clone, Default)]
+pub(super) struct ResistanceBuffers {
+    /// Hub resistance of every cell, summed over the sides.
+    totals: Vec<f64>,
+    /// Cells taking part in the current network, in index order.
+    cells: Vec<usize>,
+    /// Network node of each cell, or `usize::MAX`.
+    node_of: Vec<usize>,
+    seen: Vec<bool>,
+    network: Network,
+}
+
+impl ResistanceBuffers {
+    fn total_resistance(&mut self, state: &MinimaxState, player: u8) -> Option<f64> {
+        self.totals.clear();
+        self.totals.resize(state.board.len(), 0.0);
+        for side in SIDES {
+            self.add_resistance_to_side(state, player, side);
+        }
+        self.totals
+            .iter()
+            .copied()
+            .filter(|total| total.is_finite())
+            .min_by(f64::total_cmp)
+    }
+
+    /// Adds the effective resistance between every cell and `side` for
+    /// `player` to `totals`. Cells that cannot reach the side get
+    /// `f64::INFINITY`.
+    fn add_resistance_to_side(&mut self, state: &MinimaxState, player: u8, side: u8) {
+        let opponent = state.opponent_of(player);
+        let resistance = |idx: usize| {
+            if state.board[idx] == player {
+                OWN_RESISTANCE
+            } else {
+                EMPTY_RESISTANCE
+            }
+        };
+
+        // The side is the ground node. Only cells connected to it take part;
+        // the grounded Laplacian of a connected network is positive definite.
+        self.collect_cells_connected_to_side(state, side, opponent);
+        self.node_of.clear();
+        self.node_of.resize(state.board.len(), usize::MAX);
+        for (node, &cell) in self.cells.iter().enumerate() {
+            self.node_of[cell] = node;
+        }
+
+        // Nodes follow the row-major cell order, and neighbouring cells are
+        // at most `size` indices apart, which bounds the bandwidth.
+        let network = &mut self.network;
+        network.reset(self.cells.len(), state.size as usize);
+        for (node, &cell) in self.cells.iter().enumerate() {
+            let r = resistance(cell);
+            for &nb in &state.neighbors_cache[cell] {
+                let other = self.node_of[nb];
+                if other != usize::MAX && other > node {
+                    network.connect(node, other, 2.0 / (r + resistance(nb)));
+                }
+            }
+            if state.edges_cache[cell] & side != 0 {
+                network.ground(node, 2.0 / r);
+            }
+        }
+
+        if network.factorize().is_some() {
+            for (&cell, r) in self.cells.iter().zip(network.inverse_diagonal()) {
+                self.totals[cell] += r;
+            }
+            for (&node, total) in self.node_of.iter().zip(self.totals.iter_mut()) {
+                if node == usize::MAX {
+                    *total = f64::INFINITY;
+                }
+            }
+        } else {
+            self.totals.fill(f64::INFINITY);
+        }
+    }
+
+    /// Fills `cells` with the cells reachable from `side` without crossing
+    /// `blocker`'s stones, in index order.
+    fn collect_cells_connected_to_side(&mut self, state: &MinimaxState, side: u8, blocker: u8) {
+        let n = state.board.len();
+        let (cells, seen) = (&mut self.cells, &mut self.seen);
+        seen.clear();
+        seen.resize(n, false);
+        cells.clear();
+        cells.extend(
+            (0..n).filter(|&i| state.edges_cache[i] & side != 0 && state.board[i] != blocker),
+        );
+        for &cell in cells.iter() {
+            seen[cell] = true;
+        }
+
+        let mut next = 0;
+        while next < cells.len() {
+            let cell = cells[next];
+            next += 1;
+            for &nb in &state.neighbors_cache[cell] {
+                if !seen[nb] && state.board[nb] != blocker {
+                    seen[nb] = true;
+                    cells.push(nb);
+                }
+            }
+        }
+
+        cells.clear();
+        cells.extend((0..n).filter(|&i| seen[i]));
+    }
+}
+
+/// Grounded Laplacian of a resistor network as a symmetric band matrix.
+/// Row `i` stores its entries from column `i - bandwidth` up to the
+/// diagonal, so the storage is `dim * (bandwidth + 1)` values.
+#[derive(Clone, Default)]
+struct Network {
+    dim: usize,
+    bandwidth: usize,
+    /// Lower band of the matrix; `factorize` overwrites it with `L`.
+    band: Vec<f64>,
+    /// Lower band of the inverse, filled by `inverse_diagonal`.
+    inverse: Vec<f64>,
+}
+
+impl Network {
+    fn reset(&mut self, dim: usize, bandwidth: usize) {
+        self.dim = dim;
+        self.bandwidth = bandwidth.min(dim.saturating_sub(1));
+        self.band.clear();
+        self.band.resize(dim * (self.bandwidth + 1), 0.0);
+    }
+
+    /// Position of entry `(i, j)`, `i - bandwidth <= j <= i`, in a band.
+    fn at(&self, i: usize, j: usize) -> usize {
+        i * (self.bandwidth + 1) + self.bandwidth + j - i
+    }
+
+    /// Connects nodes `a < b`, which must lie within the bandwidth.
+    fn connect(&mut self, a: usize, b: usize, conductance: f64) {
+        debug_assert!(a < b && b - a <= self.bandwidth);
+        let (aa, bb, ba) = (self.at(a, a), self.at(b, b), self.at(b, a));
+        self.band[aa] += conductance;
+        self.band[bb] += conductance;
+        self.band[ba] -= conductance;
+    }
+
+    fn ground(&mut self, a: usize, conductance: f64) {
+        let aa = self.at(a, a);
+        self.band[aa] += conductance;
+    }
+
+    /// In-place banded Cholesky factorisation; the band ends up holding `L`
+    /// with `L * L^T = matrix`, which has the same bandwidth. Fails if the
+    /// matrix is not positive definite.
+    fn factorize(&mut self) -> Option<()> {
+        let (d, w) = (self.dim, self.bandwidth);
+        for j in 0..d {
+            let mut diag = self.band[self.at(j, j)];
+            for k in j.saturating_sub(w)..j {
+                let l = self.band[self.at(j, k)];
+                diag -= l * l;
+            }
+            if diag <= 0.0 {
+                return None;
+            }
+            let diag = diag.sqrt();
+            let jj = self.at(j, j);
+            self.band[jj] = diag;
+            for i in (j + 1)..(j + w + 1).min(d) {
+                let mut value = self.band[self.at(i, j)];
+                for k in i.saturating_sub(w)..j {
+                    value -= self.band[self.at(i, k)] * self.band[self.at(j, k)];
+                }
+                let ij = self.at(i, j);
+                self.band[ij] = value / diag;
+            }
+        }
+        Some(())
+    }
+
+    /// Returns the diagonal of the inverse of the factorised matrix. Entry
+    /// `i` is the effective resistance between node `i` and ground.
+    ///
+    /// Uses Takahashi's selected inversion: `Z = (L * L^T)^-1` satisfies
+    /// `Z * L = L^-T`, which is upper triangular, so column `j` of `Z`
+    /// follows from the later columns and the entries of `Z` inside the band
+    /// of `L` are enough to reach the diagonal.
+    fn inverse_diagonal(&mut self) -> impl Iterator<Item = f64> + '_ {
+        let (d, w) = (self.dim, self.bandwidth);
+        self.inverse.clear();
+        self.inverse.resize(self.band.len(), 0.0);
+        for j in (0..d).rev() {
+            let last = (j + w).min(d - 1);
+            let pivot = 1.0 / self.band[self.at(j, j)];
+            for i in (j + 1)..=last {
+                let mut sum = 0.0;
+                for k in (j + 1)..=last {
+                    let z = if i >= k { self.at(i, k) } else { self.at(k, i) };
+                    sum += self.band[self.at(k, j)] * self.inverse[z];
+                }
+                let ij = self.at(i, j);
+                self.inverse[ij] = -sum * pivot;
+            }
+            let mut sum = 0.0;
+            for k in (j + 1)..=last {
+                let kj = self.at(k, j);
+                sum += self.band[kj] * self.inverse[kj];
+            }
+            let jj = self.at(j, j);
+            self.inverse[jj] = pivot * (pivot - sum);
+        }
+        (0..d).map(move |i| self.inverse[self.at(i, i)])
+    }
+}
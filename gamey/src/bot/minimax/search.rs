@@ -1,56 +1,99 @@
 //! Negamax search with alpha-beta pruning, PVS, and iterative deepening.
 
-use crate::{Coordinates, GameY};
+use crate::{Coordinates, GameY, SearchProgress};
 use smallvec::SmallVec;
+use std::borrow::Cow;
 use std::time::{Duration, Instant};
 
-use super::{ABORTED, INFINITY, WIN_SCORE};
-use super::eval::evaluate_state;
+use super::{ABORTED, INFINITY, MAX_DEPTH, WIN_SCORE};
+use super::config::MinimaxConfig;
+use super::eval::{Evaluator, EvaluatorKind, HeuristicEvaluator, ResistanceEvaluator};
+use super::inferior::is_inferior;
+use super::session::SearchSession;
+use super::skill::choose_weakened_move;
+use super::solver::{DEFAULT_SOLVER_NODES, SolveOutcome, solve_state};
 use super::state::MinimaxState;
+use super::stats::{self, SearchStats};
+use super::stop::StopToken;
 use super::tables::{
     ASPIRATION_DELTA, KILLER_SLOTS, HistoryTable, KillerTable, TranspositionTable, TtFlag,
+    xorshift64,
 };
 
 // ============================================================================
 // Move ordering
 // ============================================================================
 
-/// Sorts `moves` in-place with a three-tier priority:
+/// Smallest remaining depth at which `order_moves` uses the repair and bridge
+/// terms.
+pub(super) const VC_ORDER_MIN_DEPTH: u8 = 3;
+
+/// Sorts `moves` in-place with a three-tier priority, after dropping the
+/// cells `player` can skip when `ctx.prune_inferior` is set (see the
+/// `inferior` module):
 ///
 /// 1. TT move placed first.
 /// 2. Killer moves placed next in slot order.
-/// 3. Remaining moves sorted by a blended key: `history_score * 8 + neighbors`.
-pub(super) fn order_moves(
+/// 3. Remaining moves that repair an intruded bridge or edge template, then
+///    the rest; each group sorted by a blended key:
+///    `history_score * 8 + neighbors + 2 * new_bridges`.
+///
+/// The repair and bridge terms are only looked at `depth` >=
+/// [`VC_ORDER_MIN_DEPTH`]; closer to the leaves they cost more than they save.
+pub(super) fn order_moves<E>(
     moves: &mut SmallVec<[usize; 128]>,
     state: &MinimaxState,
     player: u8,
     tt_move: Option<usize>,
     killers: [Option<usize>; KILLER_SLOTS],
-    history: &HistoryTable,
+    ctx: &SearchContext<E>,
+    depth: u8,
 ) {
     let p_idx = state.player_idx(player);
+    let vc_terms = depth >= VC_ORDER_MIN_DEPTH;
+    let board = state.board.as_slice();
     let mut priority_front: SmallVec<[usize; 4]> = SmallVec::new();
-    let mut rest: SmallVec<[(usize, u64); 128]> = SmallVec::new();
+    let mut rest: SmallVec<[(usize, (bool, u64)); 128]> = SmallVec::new();
 
     for &idx in moves.iter() {
+        if ctx.prune_inferior && is_inferior(state, idx, player) {
+            continue;
+        }
         if tt_move == Some(idx) {
             priority_front.insert(0, idx);
             continue;
         }
-        if killers.iter().any(|&k| k == Some(idx)) {
+        if killers.contains(&Some(idx)) {
             priority_front.push(idx);
             continue;
         }
-        let neighbor_count = state.neighbors_cache[idx]
-            .iter()
-            .filter(|&&n| state.board[n] == player)
-            .count() as u64;
-        let hist = history.score(idx, p_idx) as u64;
-        let key = hist * 8 + neighbor_count;
-        rest.push((idx, key));
+        let mut neighbor_count = 0u64;
+        for &nb in state.neighbors_cache[idx].as_slice() {
+            if board[nb] == player {
+                neighbor_count += 1;
+            }
+        }
+        let hist = ctx.history.score(idx, p_idx) as u64;
+        // Repairing an intruded bridge or edge template goes first; forming
+        // new bridges counts like extra neighbours.
+        let (repair, new_bridges) = if vc_terms {
+            (
+                state.is_repair(idx, player),
+                state.intact_bridges(idx, player).count() as u64,
+            )
+        } else {
+            (false, 0)
+        };
+        let key = hist * 8 + neighbor_count + 2 * new_bridges;
+        rest.push((idx, (repair, key)));
+    }
+
+    // Keep every move if all of them are inferior.
+    if priority_front.is_empty() && rest.is_empty() {
+        return;
     }
 
-    rest.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+    rest.sort_unstable_by_key(|&(_, key)| std::cmp::Reverse(key));
 
     let mut out = 0;
     for &m in &priority_front {
@@ -61,6 +104,180 @@ pub(super) fn order_moves(
         moves[out] = idx;
         out += 1;
     }
+    moves.truncate(out);
+}
+
+// ============================================================================
+// Search context
+// ============================================================================
+
+/// Mutable state shared by every node of a single search: the move-ordering
+/// tables, the transposition table, the search budget and the evaluator.
+pub(super) struct SearchContext<E = HeuristicEvaluator> {
+    pub killers: KillerTable,
+    pub tt: TranspositionTable,
+    pub history: HistoryTable,
+    pub start_time: Instant,
+    pub max_limit: Duration,
+    /// Evaluation function applied at depth 0.
+    pub evaluator: E,
+    /// Nodes visited so far by `negamax`.
+    pub nodes: u64,
+    /// Transposition table lookups made by `negamax`.
+    pub tt_probes: u64,
+    /// Lookups that found a usable entry.
+    pub tt_hits: u64,
+    /// Node budget. When set, the search is node-limited: both time gates are
+    /// ignored so that results do not depend on machine speed.
+    pub max_nodes: Option<u64>,
+    /// Half-width of the pseudo-random noise added to leaf evaluations
+    /// (0 = exact evaluation). Used by reduced skill levels.
+    pub eval_noise: i32,
+    /// Seed mixed into the leaf noise so that different games see different
+    /// mistakes while a fixed seed stays reproducible.
+    pub noise_seed: u64,
+    /// Skip dead, captured and dominated cells during move generation.
+    pub prune_inferior: bool,
+    /// Root moves the search may play; `None` allows every move. Used to
+    /// split the root between the threads of a parallel search.
+    pub root_moves: Option<Vec<usize>>,
+    /// Stopped by another thread to end the search early, e.g. when the
+    /// client went away or pondering ends.
+    pub stop: StopToken,
+}
+
+impl SearchContext {
+    /// Creates a context with the default evaluator and fresh tables.
+    pub fn new(total_cells: usize, max_limit: Duration) -> Self {
+        Self::with_tables(
+            TranspositionTable::new(),
+            HistoryTable::new(total_cells),
+            max_limit,
+        )
+    }
+
+    /// Creates a context with the default evaluator that searches with the
+    /// given tables, e.g. ones kept in a [`SearchSession`].
+    pub fn with_tables(tt: TranspositionTable, history: HistoryTable, max_limit: Duration) -> Self {
+        Self {
+            killers: KillerTable::new(0),
+            tt,
+            history,
+            start_time: Instant::now(),
+            max_limit,
+            evaluator: HeuristicEvaluator::default(),
+            nodes: 0,
+            tt_probes: 0,
+            tt_hits: 0,
+            max_nodes: None,
+            eval_noise: 0,
+            noise_seed: 0,
+            prune_inferior: true,
+            root_moves: None,
+            stop: StopToken::default(),
+        }
+    }
+}
+
+impl<E: Evaluator> SearchContext<E> {
+    /// Replaces the evaluation function.
+    pub fn with_evaluator<F: Evaluator>(self, evaluator: F) -> SearchContext<F> {
+        SearchContext {
+            killers: self.killers,
+            tt: self.tt,
+            history: self.history,
+            start_time: self.start_time,
+            max_limit: self.max_limit,
+            evaluator,
+            nodes: self.nodes,
+            tt_probes: self.tt_probes,
+            tt_hits: self.tt_hits,
+            max_nodes: self.max_nodes,
+            eval_noise: self.eval_noise,
+            noise_seed: self.noise_seed,
+            prune_inferior: self.prune_inferior,
+            root_moves: self.root_moves,
+            stop: self.stop,
+        }
+    }
+
+    /// Switches to node-limited mode when `max_nodes` is `Some`.
+    pub fn with_node_limit(mut self, max_nodes: Option<u64>) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    /// Enables or disables inferior-cell pruning (on by default).
+    pub fn with_inferior_pruning(mut self, enabled: bool) -> Self {
+        self.prune_inferior = enabled;
+        self
+    }
+
+    /// Restricts the root to `moves`, which must be taken from
+    /// [`root_moves`].
+    pub fn with_root_moves(mut self, moves: Vec<usize>) -> Self {
+        self.root_moves = Some(moves);
+        self
+    }
+
+    /// Ends the search as soon as `stop` is stopped.
+    pub fn with_stop_token(mut self, stop: StopToken) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    /// Enables evaluation noise of up to `±amplitude` points.
+    pub fn with_eval_noise(mut self, amplitude: i32, seed: u64) -> Self {
+        self.eval_noise = amplitude.max(0);
+        self.noise_seed = seed;
+        self
+    }
+
+    /// Returns `true` once the stop token is stopped, or the node budget, or
+    /// in timed mode the hard time limit, has been exceeded.
+    #[inline]
+    pub fn out_of_budget(&self) -> bool {
+        if self.stop.is_stopped() {
+            return true;
+        }
+        match self.max_nodes {
+            Some(max_nodes) => self.nodes >= max_nodes,
+            None => self.start_time.elapsed() >= self.max_limit,
+        }
+    }
+
+    /// Returns `true` when iterative deepening should not start another
+    /// depth. Never true in node-limited mode.
+    #[inline]
+    pub fn min_gate_reached(&self, min_limit: Duration) -> bool {
+        self.max_nodes.is_none() && self.start_time.elapsed() >= min_limit
+    }
+
+    /// Summary of the search so far, with `depth` as the deepest completed
+    /// depth.
+    pub fn stats(&self, depth: u32) -> SearchStats {
+        SearchStats {
+            depth,
+            nodes: self.nodes,
+            tt_probes: self.tt_probes,
+            tt_hits: self.tt_hits,
+        }
+    }
+
+    /// Deterministic noise for the leaf with Zobrist key `hash`.
+    ///
+    /// Derived from the position rather than drawn from an RNG so that
+    /// transposition-table hits stay consistent with fresh evaluations.
+    #[inline]
+    pub fn leaf_noise(&self, hash: u64) -> i32 {
+        if self.eval_noise == 0 {
+            return 0;
+        }
+        let mut x = hash ^ self.noise_seed ^ 0x9E37_79B9_7F4A_7C15;
+        let r = xorshift64(&mut x);
+        let span = 2 * self.eval_noise as u64 + 1;
+        (r % span) as i32 - self.eval_noise
+    }
 }
 
 // ============================================================================
@@ -68,14 +285,16 @@ pub(super) fn order_moves(
 // ============================================================================
 
 /// Probes the transposition table and updates the alpha-beta window.
-fn apply_tt_probe(
-    tt: &TranspositionTable,
+fn apply_tt_probe<E: Evaluator>(
+    ctx: &mut SearchContext<E>,
     hash: u64,
     depth: u8,
     alpha: &mut i32,
     beta: &mut i32,
 ) -> Option<i32> {
-    let entry = tt.probe(hash, depth)?;
+    ctx.tt_probes += 1;
+    let entry = ctx.tt.probe(hash, depth)?;
+    ctx.tt_hits += 1;
     match entry.flag {
         TtFlag::Exact => return Some(entry.score),
         TtFlag::LowerBound => *alpha = (*alpha).max(entry.score),
@@ -89,66 +308,29 @@ fn apply_tt_probe(
 }
 
 /// Principal Variation Search child evaluation.
-fn pvs_child_score(
+fn pvs_child_score<E: Evaluator>(
     state: &mut MinimaxState,
     depth: u8,
     alpha: i32,
     beta: i32,
     opponent: u8,
-    searched: u32,
-    killers: &mut KillerTable,
-    tt: &mut TranspositionTable,
-    history: &mut HistoryTable,
-    start_time: Instant,
-    max_limit: Duration,
+    searched: usize,
+    ctx: &mut SearchContext<E>,
 ) -> i32 {
     if searched == 0 {
-        let child = negamax(
-            state,
-            depth - 1,
-            -beta,
-            -alpha,
-            opponent,
-            killers,
-            tt,
-            history,
-            start_time,
-            max_limit,
-        );
+        let child = negamax(state, depth - 1, -beta, -alpha, opponent, ctx);
         if child == ABORTED {
             return ABORTED;
         }
         -child
     } else {
-        let child = negamax(
-            state,
-            depth - 1,
-            -(alpha + 1),
-            -alpha,
-            opponent,
-            killers,
-            tt,
-            history,
-            start_time,
-            max_limit,
-        );
+        let child = negamax(state, depth - 1, -(alpha + 1), -alpha, opponent, ctx);
         if child == ABORTED {
             return ABORTED;
         }
         let s = -child;
         if s > alpha && s < beta {
-            let child2 = negamax(
-                state,
-                depth - 1,
-                -beta,
-                -alpha,
-                opponent,
-                killers,
-                tt,
-                history,
-                start_time,
-                max_limit,
-            );
+            let child2 = negamax(state, depth - 1, -beta, -alpha, opponent, ctx);
             if child2 == ABORTED {
                 return ABORTED;
             }
@@ -159,36 +341,6 @@ fn pvs_child_score(
     }
 }
 
-/// Updates alpha, best score/move, and killer/history tables after evaluating
-/// a move. Returns `true` when a beta cutoff is detected.
-fn update_search_state(
-    score: i32,
-    move_idx: usize,
-    depth_idx: usize,
-    p_idx: usize,
-    depth: u8,
-    beta: i32,
-    alpha: &mut i32,
-    best_score: &mut i32,
-    best_move: &mut Option<usize>,
-    killers: &mut KillerTable,
-    history: &mut HistoryTable,
-) -> bool {
-    if score > *best_score {
-        *best_score = score;
-        *best_move = Some(move_idx);
-    }
-    if score > *alpha {
-        *alpha = score;
-    }
-    if *alpha >= beta {
-        killers.store(depth_idx, move_idx);
-        history.record_cutoff(move_idx, p_idx, depth);
-        return true;
-    }
-    false
-}
-
 /// Determines the transposition-table bound flag from the search result.
 fn compute_tt_flag(best_score: i32, alpha_orig: i32, beta: i32) -> TtFlag {
     if best_score <= alpha_orig {
@@ -200,6 +352,42 @@ fn compute_tt_flag(best_score: i32, alpha_orig: i32, beta: i32) -> TtFlag {
     }
 }
 
+/// Follows the transposition table from the current position and returns the
+/// expected line of play, starting with `player` to move.
+///
+/// The line stops at `max_len` moves, at a missing or stale TT entry, or as
+/// soon as one side completes a winning connection. The board is restored
+/// before returning.
+pub(super) fn principal_variation(
+    state: &mut MinimaxState,
+    tt: &TranspositionTable,
+    player: u8,
+    max_len: usize,
+) -> Vec<usize> {
+    let mut line = Vec::new();
+    let mut to_move = player;
+
+    while line.len() < max_len {
+        let Some(mv) = tt.best_move(state.hash) else {
+            break;
+        };
+        if !state.available_mask.contains(mv) {
+            break;
+        }
+        state.make_move(mv, to_move);
+        line.push(mv);
+        if state.check_win(to_move) {
+            break;
+        }
+        to_move = state.opponent_of(to_move);
+    }
+
+    for &mv in line.iter().rev() {
+        state.undo_move(mv);
+    }
+    line
+}
+
 // ============================================================================
 // Public helper
 // ============================================================================
@@ -209,18 +397,292 @@ pub fn choose_move_with_minimax(
     game: &GameY,
     min_time_ms: u64,
     max_time_ms: u64,
+) -> Option<Coordinates> {
+    choose_move_with_config(game, &MinimaxConfig::new(min_time_ms, max_time_ms))
+}
+
+/// Runs the minimax engine with the given configuration.
+///
+/// At [`MAX_SKILL`](super::MAX_SKILL) this is a plain full-strength search;
+/// below it the search is weakened as described in [`MinimaxConfig::skill`].
+/// With `config.max_nodes` set the search is node-limited, and together with
+/// `config.seed` the same position always yields the same move.
+pub fn choose_move_with_config(game: &GameY, config: &MinimaxConfig) -> Option<Coordinates> {
+    choose_move_in_session(game, config, &mut SearchSession::new())
+}
+
+/// Like [`choose_move_with_config`], but reuses the transposition and history
+/// tables kept in `session` and leaves them there for the next move.
+///
+/// Only timed full-strength searches use the session. Node-limited searches
+/// start from fresh tables so that they stay reproducible, and reduced skill
+/// levels use fresh tables because their noisy scores must not leak into
+/// later moves.
+///
+/// A ponder search running in the session is stopped first. If `game` is the
+/// position it pondered, the minimum time is shortened by the time already
+/// spent pondering.
+pub fn choose_move_in_session(
+    game: &GameY,
+    config: &MinimaxConfig,
+    session: &mut SearchSession,
+) -> Option<Coordinates> {
+    let pondered = session.finish_pondering(game);
+    let mut config = config.clone();
+    if let Some(pondered) = pondered {
+        // The tables already hold the pondered search of this position, so
+        // reaching the same depth again is cheap.
+        config.min_time_ms = config.min_time_ms.saturating_sub(pondered.as_millis() as u64);
+    }
+    search_in_session(game, &config, session, StopToken::current())
+}
+
+/// Runs the search behind [`choose_move_in_session`] with the evaluator
+/// selected by `config.evaluator`, ending early once `stop` is stopped.
+pub(super) fn search_in_session(
+    game: &GameY,
+    config: &MinimaxConfig,
+    session: &mut SearchSession,
+    stop: StopToken,
+) -> Option<Coordinates> {
+    let mut last = None;
+    let on_depth = &mut |progress: &SearchProgress| last = Some(progress.clone());
+    let chosen = match config.evaluator {
+        EvaluatorKind::Heuristic => choose_move_impl(
+            game,
+            config,
+            HeuristicEvaluator::new(config.weights),
+            session,
+            stop,
+            on_depth,
+        ),
+        EvaluatorKind::Resistance => {
+            choose_move_impl(game, config, ResistanceEvaluator, session, stop, on_depth)
+        }
+    };
+    session.set_last_progress(last);
+    chosen
+}
+
+/// Like [`choose_move_with_config`], but calls `on_depth` after every
+/// completed iteration of the iterative deepening search.
+///
+/// Moves found without deepening (an immediate win or block, a solved
+/// position) and searches below full strength report no progress.
+pub fn choose_move_with_progress(
+    game: &GameY,
+    config: &MinimaxConfig,
+    on_depth: &mut dyn FnMut(&SearchProgress),
+) -> Option<Coordinates> {
+    let session = &mut SearchSession::new();
+    let stop = StopToken::current();
+    match config.evaluator {
+        EvaluatorKind::Heuristic => choose_move_impl(
+            game,
+            config,
+            HeuristicEvaluator::new(config.weights),
+            session,
+            stop,
+            on_depth,
+        ),
+        EvaluatorKind::Resistance => {
+            choose_move_impl(game, config, ResistanceEvaluator, session, stop, on_depth)
+        }
+    }
+}
+
+/// Like [`choose_move_with_config`], but evaluates leaves with `evaluator`
+/// instead of the one selected by `config.evaluator`.
+///
+/// Every thread of a parallel search, see [`MinimaxConfig::threads`],
+/// evaluates with its own clone of `evaluator`.
+pub fn choose_move_with_evaluator<E: Evaluator + Clone + Send>(
+    game: &GameY,
+    config: &MinimaxConfig,
+    evaluator: E,
+) -> Option<Coordinates> {
+    choose_move_impl(
+        game,
+        config,
+        evaluator,
+        &mut SearchSession::new(),
+        StopToken::current(),
+        &mut |_| {},
+    )
+}
+
+fn choose_move_impl<E: Evaluator + Clone + Send>(
+    game: &GameY,
+    config: &MinimaxConfig,
+    evaluator: E,
+    session: &mut SearchSession,
+    stop: StopToken,
+    on_depth: &mut dyn FnMut(&SearchProgress),
 ) -> Option<Coordinates> {
     let bot_player = game.next_player()?;
     let mut state = MinimaxState::new(game, bot_player);
+    state.available_cells().next()?;
 
     if let Some(coords) = greedy_search(&mut state) {
         return Some(coords);
     }
 
-    let (best_move, _) = iterative_deepening_search(&mut state, min_time_ms, max_time_ms);
+    let best_move = if config.is_full_strength() {
+        full_strength_move(&mut state, config, evaluator, session, stop, on_depth)
+    } else {
+        choose_weakened_move(&mut state, config, evaluator, stop)
+    };
     Some(Coordinates::from_index(best_move as u32, game.board_size()))
 }
 
+/// Share of `max_time_ms`, or of `max_nodes`, the solver may use before the
+/// search takes over.
+const SOLVER_SHARE_DIVISOR: u64 = 4;
+
+/// Plays at full strength: tries to solve the position outright when few
+/// empty cells remain, and otherwise runs iterative deepening.
+///
+/// The solver gets at most a quarter of `max_time_ms`, or of `max_nodes` in
+/// node-limited mode. A solve that does not find a win leaves the search its
+/// full `min_time_ms` and the rest of `max_time_ms` or `max_nodes`; the
+/// search runs with the tables kept in `session`.
+fn full_strength_move<E: Evaluator + Clone + Send>(
+    state: &mut MinimaxState,
+    config: &MinimaxConfig,
+    evaluator: E,
+    session: &mut SearchSession,
+    stop: StopToken,
+    on_depth: &mut dyn FnMut(&SearchProgress),
+) -> usize {
+    let start = Instant::now();
+    let mut search_config = Cow::Borrowed(config);
+    if state.available_cells().count() <= config.solver_max_empty as usize {
+        let (budget, deadline) = match config.max_nodes {
+            Some(max_nodes) => ((max_nodes / SOLVER_SHARE_DIVISOR).max(1), None),
+            None => (
+                DEFAULT_SOLVER_NODES,
+                Some(start + Duration::from_millis(config.max_time_ms / SOLVER_SHARE_DIVISOR)),
+            ),
+        };
+        match solve_state(state, budget, deadline, &stop) {
+            (SolveOutcome::Win, Some(mv), _) => return mv,
+            (_, _, solver_nodes) => {
+                if let Some(max_nodes) = config.max_nodes {
+                    search_config.to_mut().max_nodes =
+                        Some(max_nodes.saturating_sub(solver_nodes).max(1));
+                }
+            }
+        }
+    }
+    let config = &*search_config;
+
+    let spent_ms = start.elapsed().as_millis() as u64;
+    let max_limit = Duration::from_millis(config.max_time_ms.saturating_sub(spent_ms));
+    if config.threads > 1 {
+        return parallel_root_search(state, config, max_limit, evaluator, stop, on_depth);
+    }
+    let total_cells = state.board.len();
+    let reuse = config.max_nodes.is_none();
+    let tt_size_mb = session.tt_size_mb(config.tt_size_mb);
+    let (tt, history) = if reuse {
+        session.take_tables(total_cells, tt_size_mb)
+    } else {
+        (
+            TranspositionTable::with_size_mb(tt_size_mb),
+            HistoryTable::new(total_cells),
+        )
+    };
+    let mut ctx = SearchContext::with_tables(tt, history, max_limit)
+        .with_node_limit(config.max_nodes)
+        .with_inferior_pruning(config.prune_inferior)
+        .with_stop_token(stop)
+        .with_evaluator(evaluator);
+    let best_move =
+        run_iterative_deepening_with_progress(state, config.min_time_ms, &mut ctx, on_depth).0;
+    if reuse {
+        session.store_tables(total_cells, tt_size_mb, ctx.tt, ctx.history);
+    }
+    best_move
+}
+
+/// Searches with `config.threads` threads by splitting the root moves.
+///
+/// The root moves are dealt out in search order, so every thread gets a
+/// share of the promising ones. Each thread runs iterative deepening over
+/// its share with its own transposition and history tables; the session's
+/// tables are not used. The move played is the best one at the deepest depth
+/// that every thread completed, and only those depths are reported.
+fn parallel_root_search<E: Evaluator + Clone + Send>(
+    state: &mut MinimaxState,
+    config: &MinimaxConfig,
+    max_limit: Duration,
+    evaluator: E,
+    stop: StopToken,
+    on_depth: &mut dyn FnMut(&SearchProgress),
+) -> usize {
+    let moves = root_moves(state, config.prune_inferior);
+    let threads = config.threads.min(moves.len()).max(1);
+    let tt_size_mb = (config.tt_size_mb / threads).max(1);
+    let max_nodes = config.max_nodes.map(|nodes| (nodes / threads as u64).max(1));
+
+    let results: Vec<(Vec<SearchProgress>, SearchStats)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread| {
+                let share = moves.iter().copied().skip(thread).step_by(threads).collect();
+                let mut state = state.clone();
+                let mut ctx = SearchContext::with_tables(
+                    TranspositionTable::with_size_mb(tt_size_mb),
+                    HistoryTable::new(state.board.len()),
+                    max_limit,
+                )
+                .with_node_limit(max_nodes)
+                .with_inferior_pruning(config.prune_inferior)
+                .with_root_moves(share)
+                .with_stop_token(stop.clone())
+                .with_evaluator(evaluator.clone());
+                scope.spawn(move || {
+                    let mut completed = Vec::new();
+                    run_iterative_deepening_with_progress(
+                        &mut state,
+                        config.min_time_ms,
+                        &mut ctx,
+                        &mut |progress| completed.push(progress.clone()),
+                    );
+                    let stats = ctx.stats(completed.len() as u32);
+                    (completed, stats)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+            .collect()
+    });
+
+    // Depths are completed in order from 1, so depth `d` is entry `d - 1`.
+    let depth = results.iter().map(|(completed, _)| completed.len()).min().unwrap_or(0);
+    let mut best_move = moves[0];
+    for i in 0..depth {
+        let best = results
+            .iter()
+            .map(|(completed, _)| &completed[i])
+            .min_by_key(|progress| std::cmp::Reverse(progress.score))
+            .expect("at least one search thread");
+        best_move = best.best_move.to_index(state.size) as usize;
+        on_depth(&SearchProgress {
+            nodes: results.iter().map(|(completed, _)| completed[i].nodes).sum(),
+            ..best.clone()
+        });
+    }
+    stats::record(SearchStats {
+        depth: depth as u32,
+        nodes: results.iter().map(|(_, stats)| stats.nodes).sum(),
+        tt_probes: results.iter().map(|(_, stats)| stats.tt_probes).sum(),
+        tt_hits: results.iter().map(|(_, stats)| stats.tt_hits).sum(),
+    });
+    best_move
+}
+
 // ============================================================================
 // Search
 // ============================================================================
@@ -233,14 +695,14 @@ pub(super) fn greedy_search(state: &mut MinimaxState) -> Option<Coordinates> {
     for move_idx in moves {
         state.make_move(move_idx, state.bot_id);
         if state.check_win(state.bot_id) {
-            println!(">>> INSTANT WIN FOUND at {}", move_idx);
+            tracing::debug!(cell = move_idx, "instant win found");
             return Some(Coordinates::from_index(move_idx as u32, state.size));
         }
         state.undo_move(move_idx);
 
         state.make_move(move_idx, state.human_id);
         if state.check_win(state.human_id) {
-            println!(">>> BLOCKING IMMEDIATE THREAT at {}", move_idx);
+            tracing::debug!(cell = move_idx, "blocking immediate threat");
             return Some(Coordinates::from_index(move_idx as u32, state.size));
         }
         state.undo_move(move_idx);
@@ -249,93 +711,111 @@ pub(super) fn greedy_search(state: &mut MinimaxState) -> Option<Coordinates> {
     None
 }
 
-/// Runs iterative deepening with aspiration windows and two time gates.
-pub(super) fn iterative_deepening_search(
+/// Runs iterative deepening with aspiration windows and two time gates,
+/// evaluating leaves with `evaluator` and ending early once `stop` is
+/// stopped. With `max_nodes` set the search is node-limited instead.
+pub(super) fn iterative_deepening_search<E: Evaluator>(
     state: &mut MinimaxState,
     min_time_ms: u64,
     max_time_ms: u64,
+    max_nodes: Option<u64>,
+    stop: &StopToken,
+    evaluator: E,
+) -> (usize, i32) {
+    let mut ctx = SearchContext::new(state.board.len(), Duration::from_millis(max_time_ms))
+        .with_node_limit(max_nodes)
+        .with_stop_token(stop.clone())
+        .with_evaluator(evaluator);
+    run_iterative_deepening(state, min_time_ms, &mut ctx)
+}
+
+/// Iterative deepening driver: searches depth 1, 2, ... until the minimum
+/// time gate is passed, the budget in `ctx` runs out mid-depth, a win is
+/// found, or the search becomes exhaustive.
+pub(super) fn run_iterative_deepening<E: Evaluator>(
+    state: &mut MinimaxState,
+    min_time_ms: u64,
+    ctx: &mut SearchContext<E>,
+) -> (usize, i32) {
+    run_iterative_deepening_with_progress(state, min_time_ms, ctx, &mut |_| {})
+}
+
+/// Like [`run_iterative_deepening`], but calls `on_depth` with the best move,
+/// score and principal variation of every completed depth.
+pub(super) fn run_iterative_deepening_with_progress<E: Evaluator>(
+    state: &mut MinimaxState,
+    min_time_ms: u64,
+    ctx: &mut SearchContext<E>,
+    on_depth: &mut dyn FnMut(&SearchProgress),
 ) -> (usize, i32) {
-    let start_time = Instant::now();
     let min_limit = Duration::from_millis(min_time_ms);
-    let max_limit = Duration::from_millis(max_time_ms);
+    let empty_cells = state.available_cells().count();
 
-    let mut tt = TranspositionTable::new();
-    let mut history = HistoryTable::new(state.board.len());
     let mut best_move = state.available_cells().next().expect("no available moves");
     let mut best_score = 0i32;
     let mut prev_score: Option<i32> = None;
+    let mut completed = 0;
 
-    for depth in 1..=100u8 {
-        if start_time.elapsed() >= min_limit {
-            println!("Min time reached, stopping after depth {}", depth - 1);
+    for depth in 1..=MAX_DEPTH {
+        if ctx.min_gate_reached(min_limit) {
+            tracing::debug!(depth = depth - 1, "min time reached, stopping");
+            break;
+        }
+        if depth as usize > empty_cells {
             break;
         }
 
-        println!("Searching at depth {}...", depth);
-
-        let mut killers = KillerTable::new(depth as usize);
+        ctx.killers = KillerTable::new(depth as usize);
 
         let (move_found, score) = if let Some(ps) = prev_score {
-            aspiration_search(
-                state,
-                depth,
-                ps,
-                &mut killers,
-                &mut tt,
-                &mut history,
-                start_time,
-                max_limit,
-            )
+            aspiration_search(state, depth, ps, ctx)
         } else {
-            search_best_move(
-                state,
-                depth,
-                -INFINITY,
-                INFINITY,
-                &mut killers,
-                &mut tt,
-                &mut history,
-                start_time,
-                max_limit,
-            )
+            search_best_move(state, depth, -INFINITY, INFINITY, ctx)
         };
 
         if score == ABORTED {
-            println!(
-                "Max time exceeded mid-depth {}, keeping result from depth {}",
-                depth,
-                depth - 1
-            );
+            tracing::debug!(depth, "budget exceeded mid-depth, keeping the previous depth");
             break;
         }
 
         best_move = move_found;
         best_score = score;
         prev_score = Some(score);
+        completed = depth as u32;
 
-        println!("Depth {}: best_move={} score={}", depth, move_found, score);
+        tracing::debug!(depth, best_move = move_found, score, "depth completed");
 
-        history.age();
+        let mut pv = principal_variation(state, &ctx.tt, state.bot_id, depth as usize);
+        if pv.first() != Some(&move_found) {
+            pv = vec![move_found];
+        }
+        let to_coords = |idx: usize| Coordinates::from_index(idx as u32, state.size);
+        on_depth(&SearchProgress {
+            depth: depth as u32,
+            score,
+            best_move: to_coords(move_found),
+            pv: pv.into_iter().map(to_coords).collect(),
+            nodes: ctx.nodes,
+        });
+
+        ctx.history.age();
 
         if score >= WIN_SCORE - 100 {
-            println!("Winning move found at depth {}", depth);
+            tracing::debug!(depth, "winning move found");
             break;
         }
     }
 
+    stats::record(ctx.stats(completed));
     (best_move, best_score)
 }
 
 /// Aspiration window wrapper: tries a narrow window first, widens on fail.
-pub(super) fn aspiration_search(
+pub(super) fn aspiration_search<E: Evaluator>(
     state: &mut MinimaxState,
     depth: u8,
     prev_score: i32,
-    killers: &mut KillerTable,
-    tt: &mut TranspositionTable,
-    history: &mut HistoryTable,
-    start_time: Instant,
-    max_limit: Duration,
+    ctx: &mut SearchContext<E>,
 ) -> (usize, i32) {
     let mut delta = ASPIRATION_DELTA;
 
@@ -343,9 +823,7 @@ pub(super) fn aspiration_search(
         let alpha = (prev_score - delta).max(-INFINITY);
         let beta = (prev_score + delta).min(INFINITY);
 
-        let (mv, score) = search_best_move(
-            state, depth, alpha, beta, killers, tt, history, start_time, max_limit,
-        );
+        let (mv, score) = search_best_move(state, depth, alpha, beta, ctx);
 
         if score == ABORTED {
             return (mv, ABORTED);
@@ -357,33 +835,40 @@ pub(super) fn aspiration_search(
 
         delta *= 4;
         if delta >= INFINITY / 2 {
-            return search_best_move(
-                state, depth, -INFINITY, INFINITY, killers, tt, history, start_time, max_limit,
-            );
+            return search_best_move(state, depth, -INFINITY, INFINITY, ctx);
         }
     }
 }
 
+/// The moves searched at the root, ordered by shortest-path delta. Inferior
+/// cells are left out with `prune_inferior`, unless every move is inferior.
+pub(super) fn root_moves(state: &mut MinimaxState, prune_inferior: bool) -> Vec<usize> {
+    let ordered = state.shortest_path_deltas(state.bot_id);
+    let mut moves: Vec<usize> = ordered.into_iter().map(|(idx, _)| idx).collect();
+    if prune_inferior && !moves.iter().all(|&idx| is_inferior(state, idx, state.bot_id)) {
+        moves.retain(|&idx| !is_inferior(state, idx, state.bot_id));
+    }
+    moves
+}
+
 /// Root search: generates moves ordered by shortest-path delta, then searches
 /// each with negamax+PVS under the given `[alpha, beta]` window.
-pub(super) fn search_best_move(
+pub(super) fn search_best_move<E: Evaluator>(
     state: &mut MinimaxState,
     depth: u8,
     mut alpha: i32,
     beta: i32,
-    killers: &mut KillerTable,
-    tt: &mut TranspositionTable,
-    history: &mut HistoryTable,
-    start_time: Instant,
-    max_limit: Duration,
+    ctx: &mut SearchContext<E>,
 ) -> (usize, i32) {
-    let ordered = state.shortest_path_deltas(state.bot_id);
-    let mut moves: Vec<usize> = ordered.into_iter().map(|(idx, _)| idx).collect();
+    let mut moves = root_moves(state, ctx.prune_inferior);
+    if let Some(allowed) = &ctx.root_moves {
+        moves.retain(|idx| allowed.contains(idx));
+    }
 
-    if let Some(tt_mv) = tt.best_move(state.hash) {
-        if let Some(pos) = moves.iter().position(|&m| m == tt_mv) {
-            moves.swap(0, pos);
-        }
+    if let Some(tt_mv) = ctx.tt.best_move(state.hash)
+        && let Some(pos) = moves.iter().position(|&m| m == tt_mv)
+    {
+        moves.swap(0, pos);
     }
 
     let player = state.bot_id;
@@ -391,28 +876,23 @@ pub(super) fn search_best_move(
     let alpha_orig = alpha;
     let mut best_score = -INFINITY;
     let mut best_move = moves[0];
-    let mut searched: u32 = 0;
 
-    for &move_idx in &moves {
+    for (searched, &move_idx) in moves.iter().enumerate() {
         state.make_move(move_idx, player);
 
-        if state.check_win(player) {
+        if state.check_win_at(move_idx, player) {
             state.undo_move(move_idx);
-            tt.store(state.hash, depth, WIN_SCORE, TtFlag::Exact, Some(move_idx));
+            ctx.tt.store(state.hash, depth, WIN_SCORE, TtFlag::Exact, Some(move_idx));
             return (move_idx, WIN_SCORE);
         }
 
-        let score = pvs_child_score(
-            state, depth, alpha, beta, opponent, searched, killers, tt, history, start_time,
-            max_limit,
-        );
+        let score = pvs_child_score(state, depth, alpha, beta, opponent, searched, ctx);
         state.undo_move(move_idx);
 
         if score == ABORTED {
             return (best_move, ABORTED);
         }
 
-        searched += 1;
         if score > best_score {
             best_score = score;
             best_move = move_idx;
@@ -426,60 +906,64 @@ pub(super) fn search_best_move(
     }
 
     let flag = compute_tt_flag(best_score, alpha_orig, beta);
-    tt.store(state.hash, depth, best_score, flag, Some(best_move));
+    ctx.tt.store(state.hash, depth, best_score, flag, Some(best_move));
     (best_move, best_score)
 }
 
 /// Negamax with alpha-beta, PVS, transposition table, killer moves, and
 /// history heuristic.
-pub(super) fn negamax(
+pub(super) fn negamax<E: Evaluator>(
     state: &mut MinimaxState,
     depth: u8,
     mut alpha: i32,
     mut beta: i32,
     player: u8,
-    killers: &mut KillerTable,
-    tt: &mut TranspositionTable,
-    history: &mut HistoryTable,
-    start_time: Instant,
-    max_limit: Duration,
+    ctx: &mut SearchContext<E>,
 ) -> i32 {
-    if start_time.elapsed() >= max_limit {
+    if ctx.out_of_budget() {
         return ABORTED;
     }
+    ctx.nodes += 1;
 
     let alpha_orig = alpha;
     let position_hash = state.hash;
 
-    if let Some(score) = apply_tt_probe(&tt, position_hash, depth, &mut alpha, &mut beta) {
+    if let Some(score) = apply_tt_probe(ctx, position_hash, depth, &mut alpha, &mut beta) {
         return score;
     }
 
     if depth == 0 {
-        let score = evaluate_state(state, player);
-        tt.store(position_hash, 0, score, TtFlag::Exact, None);
+        let score = ctx.evaluator.evaluate(state, player) + ctx.leaf_noise(position_hash);
+        ctx.tt.store(position_hash, 0, score, TtFlag::Exact, None);
         return score;
     }
 
     let opponent = state.opponent_of(player);
     let depth_idx = depth as usize;
-    let killer_moves = killers.get(depth_idx);
-    let tt_move = tt.best_move(position_hash);
+    let killer_moves = ctx.killers.get(depth_idx);
+    let tt_move = ctx.tt.best_move(position_hash);
     let mut moves: SmallVec<[usize; 128]> = state.available_cells().collect();
-    order_moves(&mut moves, state, player, tt_move, killer_moves, history);
+    order_moves(
+        &mut moves,
+        state,
+        player,
+        tt_move,
+        killer_moves,
+        ctx,
+        depth,
+    );
 
     let mut best_score = -INFINITY;
     let mut best_move_found: Option<usize> = None;
-    let mut searched: u32 = 0;
     let p_idx = state.player_idx(player);
 
-    for move_idx in moves {
+    for (searched, move_idx) in moves.into_iter().enumerate() {
         state.make_move(move_idx, player);
 
-        if state.check_win(player) {
+        if state.check_win_at(move_idx, player) {
             state.undo_move(move_idx);
-            killers.store(depth_idx, move_idx);
-            tt.store(
+            ctx.killers.store(depth_idx, move_idx);
+            ctx.tt.store(
                 position_hash,
                 depth,
                 WIN_SCORE,
@@ -489,35 +973,28 @@ pub(super) fn negamax(
             return WIN_SCORE;
         }
 
-        let score = pvs_child_score(
-            state, depth, alpha, beta, opponent, searched, killers, tt, history, start_time,
-            max_limit,
-        );
+        let score = pvs_child_score(state, depth, alpha, beta, opponent, searched, ctx);
         state.undo_move(move_idx);
 
         if score == ABORTED {
             return ABORTED;
         }
 
-        searched += 1;
-        if update_search_state(
-            score,
-            move_idx,
-            depth_idx,
-            p_idx,
-            depth,
-            beta,
-            &mut alpha,
-            &mut best_score,
-            &mut best_move_found,
-            killers,
-            history,
-        ) {
+        if score > best_score {
+            best_score = score;
+            best_move_found = Some(move_idx);
+        }
+        if score > alpha {
+            alpha = score;
+        }
+        if alpha >= beta {
+            ctx.killers.store(depth_idx, move_idx);
+            ctx.history.record_cutoff(move_idx, p_idx, depth);
             break;
         }
     }
 
     let flag = compute_tt_flag(best_score, alpha_orig, beta);
-    tt.store(position_hash, depth, best_score, flag, best_move_found);
+    ctx.tt.store(position_hash, depth, best_score, flag, best_move_found);
     best_score
 }
@@ -0,0 +1,256 @@
+//! Search tables kept between the moves of one game.
+
+use std::time::Duration;
+
+use crate::{Coordinates, GameY, Movement, SearchProgress, game};
+
+use super::config::MinimaxConfig;
+use super::ponder::{Ponder, PonderSlot};
+use super::state::MinimaxState;
+use super::tables::{HistoryTable, TranspositionTable};
+
+/// Transposition and history tables reused across the moves of one game.
+///
+/// A fresh search allocates a transposition table of
+/// [`MinimaxConfig::tt_size_mb`](super::MinimaxConfig::tt_size_mb) and starts
+/// with empty move-ordering history. Passing the same session to consecutive
+/// searches of a game skips that allocation and lets each move start from the
+/// positions and cutoffs found while thinking about the previous ones.
+///
+/// A session must only be used with one bot configuration: entries scored by
+/// one evaluator are meaningless to another. The tables are allocated on the
+/// first search, so creating a session is cheap.
+///
+/// Between moves a session can also ponder, i.e. search on the opponent's
+/// time; see [`start_pondering`](Self::start_pondering).
+///
+/// ```
+/// use gamey::{GameY, MinimaxConfig, SearchSession, choose_move_in_session};
+///
+/// let config = MinimaxConfig::new(10, 50);
+/// let mut session = SearchSession::new();
+/// let game = GameY::new(7);
+///
+/// assert!(choose_move_in_session(&game, &config, &mut session).is_some());
+/// assert_eq!(session.searches(), 1);
+/// ```
+#[derive(Default)]
+pub struct SearchSession {
+    tables: Option<SessionTables>,
+    searches: u64,
+    /// Running ponder search. It owns the tables until it is stopped.
+    ponder: Option<Ponder>,
+    /// Slot handed to the next ponder search; see
+    /// [`set_ponder_slot`](Self::set_ponder_slot).
+    ponder_slot: Option<PonderSlot>,
+    /// Last completed iteration of the latest search in this session.
+    last_progress: Option<SearchProgress>,
+    /// Cap on the transposition table size; see
+    /// [`with_tt_limit_mb`](Self::with_tt_limit_mb).
+    tt_limit_mb: Option<usize>,
+}
+
+/// The reusable tables together with the parameters they were built for.
+struct SessionTables {
+    total_cells: usize,
+    tt_size_mb: usize,
+    tt: TranspositionTable,
+    history: HistoryTable,
+}
+
+impl SearchSession {
+    /// Creates an empty session.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Caps the transposition table of the searches in this session at
+    /// `size_mb` MB, whatever their configuration asks for, e.g. to bound
+    /// the memory of a server keeping many sessions.
+    pub fn with_tt_limit_mb(mut self, size_mb: usize) -> Self {
+        self.tt_limit_mb = Some(size_mb.max(1));
+        self
+    }
+
+    /// Number of searches that have stored their tables in this session.
+    pub fn searches(&self) -> u64 {
+        self.searches
+    }
+
+    /// Takes the last completed iteration of the latest search in this
+    /// session, if it reported any; see
+    /// [`choose_move_with_progress`](super::choose_move_with_progress) for
+    /// the searches that do not.
+    pub fn take_last_progress(&mut self) -> Option<SearchProgress> {
+        self.last_progress.take()
+    }
+
+    /// Returns `true` once a search has left its tables in the session.
+    pub fn has_tables(&self) -> bool {
+        self.tables.is_some()
+    }
+
+    /// Starts pondering after the bot's move in `game`, with the opponent to
+    /// move.
+    ///
+    /// The opponent's most likely reply is taken from the tables, or from the
+    /// shortest-path heuristic if the tables do not know the position. A
+    /// background thread then searches the position after that reply with
+    /// `config`, until the ponder search is stopped, the token of the
+    /// enclosing [`StopToken::scope`](super::StopToken::scope) is stopped, or
+    /// a few times `config.max_time_ms` have passed. Any earlier ponder
+    /// search is stopped first.
+    ///
+    /// Returns the predicted reply, or `None` if there is nothing to ponder:
+    /// the game is over, the reply ends it, or `config` is node-limited or
+    /// below full strength.
+    pub fn start_pondering(&mut self, game: &GameY, config: &MinimaxConfig) -> Option<Coordinates> {
+        // Dropped on every early return, so an unused slot is given back.
+        let slot = self.ponder_slot.take();
+        self.stop_pondering();
+        if !config.is_full_strength() || config.max_nodes.is_some() {
+            return None;
+        }
+
+        let opponent = game.next_player()?;
+        let predicted = self.predict_reply(game)?;
+        let mut pondered = game.clone();
+        pondered
+            .add_move(Movement::Placement {
+                player: opponent,
+                coords: predicted,
+            })
+            .ok()?;
+        pondered.next_player()?;
+
+        let session = SearchSession {
+            tables: self.tables.take(),
+            tt_limit_mb: self.tt_limit_mb,
+            ..SearchSession::default()
+        };
+        self.ponder = Some(Ponder::start(pondered, predicted, config, session, slot));
+        Some(predicted)
+    }
+
+    /// Gives the next ponder search a slot to hold while it runs, such as a
+    /// [`BackgroundPermit`](crate::BackgroundPermit) of a server limiting
+    /// concurrent searches. The slot is dropped when that search ends, or
+    /// right away if [`start_pondering`](Self::start_pondering) finds nothing
+    /// to ponder.
+    pub fn set_ponder_slot<T: Send + 'static>(&mut self, slot: T) {
+        self.ponder_slot = Some(Box::new(slot));
+    }
+
+    /// Drops a slot that no ponder search has taken, e.g. because the bot
+    /// does not ponder.
+    pub fn release_ponder_slot(&mut self) {
+        self.ponder_slot = None;
+    }
+
+    /// Returns `true` while a ponder search is running.
+    pub fn is_pondering(&self) -> bool {
+        self.ponder.is_some()
+    }
+
+    /// The reply the running ponder search assumes.
+    pub fn predicted_move(&self) -> Option<Coordinates> {
+        self.ponder.as_ref().map(Ponder::predicted)
+    }
+
+    /// Stops pondering and keeps what the ponder search found in the tables.
+    pub fn stop_pondering(&mut self) {
+        if let Some(ponder) = self.ponder.take() {
+            self.absorb(ponder.stop());
+        }
+    }
+
+    /// Stops pondering and throws the tables away, e.g. when the game ended.
+    pub fn discard_pondering(&mut self) {
+        if let Some(ponder) = self.ponder.take() {
+            ponder.stop();
+        }
+    }
+
+    /// Stops pondering before the real search of `game`. Returns the time
+    /// spent pondering if `game` is the pondered position.
+    pub(super) fn finish_pondering(&mut self, game: &GameY) -> Option<Duration> {
+        let ponder = self.ponder.take()?;
+        let hit = ponder.is_position(game).then(|| ponder.elapsed());
+        self.absorb(ponder.stop());
+        hit
+    }
+
+    /// Takes back the tables and search count of a stopped ponder search.
+    fn absorb(&mut self, pondered: SearchSession) {
+        self.tables = pondered.tables;
+        self.searches += pondered.searches;
+    }
+
+    /// Guesses the opponent's reply in `game`, seen from the bot's side so
+    /// that position keys match the ones the bot's searches stored.
+    fn predict_reply(&self, game: &GameY) -> Option<Coordinates> {
+        let opponent = game.next_player()?;
+        let mut state = MinimaxState::new(game, game::other_player(opponent));
+        let remembered = self
+            .tables
+            .as_ref()
+            .filter(|tables| tables.total_cells == state.board.len())
+            .and_then(|tables| tables.tt.best_move(state.hash))
+            .filter(|&mv| state.available_mask.contains(mv));
+        let reply = match remembered {
+            Some(mv) => mv,
+            None => state.shortest_path_deltas(state.human_id).first()?.0,
+        };
+        Some(Coordinates::from_index(reply as u32, game.board_size()))
+    }
+
+    /// The transposition table size of a search asking for `tt_size_mb`.
+    pub(super) fn tt_size_mb(&self, tt_size_mb: usize) -> usize {
+        self.tt_limit_mb.map_or(tt_size_mb, |limit| tt_size_mb.min(limit))
+    }
+
+    /// Hands the tables to a search on a board of `total_cells` cells.
+    ///
+    /// The stored tables are reused when they fit the board and the requested
+    /// size; otherwise new ones are allocated. Reused tables start a new
+    /// generation, so entries from earlier moves are replaced first.
+    pub(super) fn take_tables(
+        &mut self,
+        total_cells: usize,
+        tt_size_mb: usize,
+    ) -> (TranspositionTable, HistoryTable) {
+        match self.tables.take() {
+            Some(tables) if tables.total_cells == total_cells && tables.tt_size_mb == tt_size_mb => {
+                let mut tt = tables.tt;
+                tt.new_search();
+                (tt, tables.history)
+            }
+            _ => (
+                TranspositionTable::with_size_mb(tt_size_mb),
+                HistoryTable::new(total_cells),
+            ),
+        }
+    }
+
+    /// Records the last completed iteration of a search in this session.
+    pub(super) fn set_last_progress(&mut self, progress: Option<SearchProgress>) {
+        self.last_progress = progress;
+    }
+
+    /// Stores the tables of a finished search for the next move.
+    pub(super) fn store_tables(
+        &mut self,
+        total_cells: usize,
+        tt_size_mb: usize,
+        tt: TranspositionTable,
+        history: HistoryTable,
+    ) {
+        self.tables = Some(SessionTables {
+            total_cells,
+            tt_size_mb,
+            tt,
+            history,
+        });
+        self.searches += 1;
+    }
+}
@@ -0,0 +1,105 @@
+//! Reduced skill levels: depth limits, evaluation noise and deliberate
+//! mistakes picked from the multi-PV candidate list.
+
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+use super::analysis::{RootLine, multipv_iterative_deepening};
+use super::config::{MAX_SKILL, MIN_SKILL, MinimaxConfig};
+use super::eval::Evaluator;
+use super::search::SearchContext;
+use super::state::MinimaxState;
+use super::stop::StopToken;
+use super::tables::{HistoryTable, TranspositionTable};
+use super::{MAX_DEPTH, WIN_SCORE};
+
+/// How a skill level weakens the engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct SkillProfile {
+    /// Deepest iteration searched.
+    pub max_depth: u8,
+    /// Half-width of the noise added to every leaf evaluation.
+    pub eval_noise: i32,
+    /// Probability of playing a weaker candidate instead of the best move.
+    pub blunder_chance: f64,
+    /// Number of root candidates kept exact (and eligible as mistakes).
+    pub candidates: usize,
+}
+
+impl SkillProfile {
+    pub fn for_level(skill: u8) -> Self {
+        let skill = skill.clamp(MIN_SKILL, MAX_SKILL);
+        let weakness = (MAX_SKILL - skill) as usize;
+        Self {
+            max_depth: if weakness == 0 { MAX_DEPTH } else { 1 + skill / 3 },
+            eval_noise: weakness as i32 * 15,
+            blunder_chance: weakness as f64 / (2 * MAX_SKILL as usize) as f64,
+            candidates: 1 + weakness.div_ceil(4),
+        }
+    }
+}
+
+/// Chooses a move for a reduced skill level.
+///
+/// The search is depth-limited and noisy, and with probability growing as the
+/// skill drops the bot plays one of the weaker root candidates. The caller is
+/// expected to have played immediate wins and blocks already.
+pub(super) fn choose_weakened_move<E: Evaluator>(
+    state: &mut MinimaxState,
+    config: &MinimaxConfig,
+    evaluator: E,
+    stop: StopToken,
+) -> usize {
+    let profile = SkillProfile::for_level(config.skill);
+    let mut rng = skill_rng(config.seed);
+
+    let mut ctx = SearchContext::with_tables(
+        TranspositionTable::with_size_mb(config.tt_size_mb),
+        HistoryTable::new(state.board.len()),
+        Duration::from_millis(config.max_time_ms),
+    )
+        .with_node_limit(config.max_nodes)
+        .with_inferior_pruning(config.prune_inferior)
+        .with_stop_token(stop)
+        .with_evaluator(evaluator)
+        .with_eval_noise(profile.eval_noise, rng.random());
+    let lines = multipv_iterative_deepening(
+        state,
+        profile.candidates,
+        config.min_time_ms,
+        profile.max_depth,
+        &mut ctx,
+    );
+
+    pick_candidate(&lines, &profile, &mut rng)
+}
+
+/// The random generator of a weakened search: seeded with `seed`, or with a
+/// fresh seed if there is none.
+pub(super) fn skill_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::seed_from_u64(rand::rng().random()),
+    }
+}
+
+/// Picks the best line, or with `profile.blunder_chance` one of the other
+/// top candidates. Candidates scored as forced losses are never chosen on
+/// purpose.
+pub(super) fn pick_candidate(lines: &[RootLine], profile: &SkillProfile, rng: &mut StdRng) -> usize {
+    let best = lines[0].mv;
+    if !rng.random_bool(profile.blunder_chance) {
+        return best;
+    }
+
+    let weaker: Vec<usize> = lines
+        .iter()
+        .take(profile.candidates)
+        .skip(1)
+        .filter(|line| line.score > -(WIN_SCORE - 100))
+        .map(|line| line.mv)
+        .collect();
+    weaker.choose(rng).copied().unwrap_or(best)
+}
@@ -0,0 +1,271 @@
+//! Exact solver based on depth-first proof-number search (df-pn).
+//!
+//! Y has no draws, so every position is either a win or a loss for the side
+//! to move. On small boards and in late middlegames df-pn can prove which one
+//! it is well within a move's time budget, and returns a winning move as
+//! proof.
+
+use super::state::MinimaxState;
+use super::stop::StopToken;
+use crate::{Coordinates, GameY};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+/// Proof and disproof numbers saturate at this value, which stands for
+/// "infinite" (proven or disproven).
+const PN_INF: u32 = u32::MAX / 4;
+
+/// XORed into the position hash when the root player's opponent is to move,
+/// so that the same stones with different sides to move never share an entry.
+const SIDE_TO_MOVE_SALT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// How often (in nodes) the wall-clock deadline is checked.
+const DEADLINE_CHECK_INTERVAL: u64 = 1024;
+
+/// Node budget used by the solve API when the caller does not give one.
+pub const DEFAULT_SOLVER_NODES: u64 = 1_000_000;
+
+/// Game-theoretic value of a position for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SolveOutcome {
+    /// The side to move wins with perfect play.
+    Win,
+    /// The side to move loses against perfect play.
+    Loss,
+    /// The budget ran out before the position was solved.
+    Unknown,
+}
+
+/// Result of [`solve`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SolveResult {
+    pub outcome: SolveOutcome,
+    /// A winning move for the side to move; only set when `outcome` is `Win`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_move: Option<Coordinates>,
+    /// Number of nodes expanded by the solver.
+    pub nodes: u64,
+}
+
+/// Solves `game` for the side to move, expanding at most `max_nodes` nodes.
+///
+/// Returns [`SolveOutcome::Unknown`] if the budget is exhausted, the current
+/// [`StopToken`] is stopped, or the game is already over.
+pub fn solve(game: &GameY, max_nodes: u64) -> SolveResult {
+    let Some(player) = game.next_player() else {
+        return SolveResult {
+            outcome: SolveOutcome::Unknown,
+            proof_move: None,
+            nodes: 0,
+        };
+    };
+    let mut state = MinimaxState::new(game, player);
+    let (outcome, proof_move, nodes) =
+        solve_state(&mut state, max_nodes, None, &StopToken::current());
+    SolveResult {
+        outcome,
+        proof_move: proof_move.map(|idx| Coordinates::from_index(idx as u32, game.board_size())),
+        nodes,
+    }
+}
+
+/// Solves `state` for `state.bot_id`, within a node budget and an optional
+/// deadline, until `stop` is stopped. Returns the outcome, the proof move (as
+/// a cell index) and the number of nodes expanded.
+pub(super) fn solve_state(
+    state: &mut MinimaxState,
+    max_nodes: u64,
+    deadline: Option<Instant>,
+    stop: &StopToken,
+) -> (SolveOutcome, Option<usize>, u64) {
+    let mut solver = Solver::new(max_nodes, deadline, stop.clone());
+    let root = state.bot_id;
+    solver.mid(state, root, PN_INF, PN_INF);
+
+    let (phi, delta) = solver.lookup(solver.key(state, root));
+    let outcome = if solver.aborted {
+        SolveOutcome::Unknown
+    } else if phi == 0 {
+        SolveOutcome::Win
+    } else if delta == 0 {
+        SolveOutcome::Loss
+    } else {
+        SolveOutcome::Unknown
+    };
+    let proof_move = match outcome {
+        SolveOutcome::Win => solver.proof_move(state, root),
+        _ => None,
+    };
+    (outcome, proof_move, solver.nodes)
+}
+
+struct Solver {
+    /// Proof and disproof numbers from the point of view of the side to move.
+    tt: HashMap<u64, (u32, u32)>,
+    nodes: u64,
+    max_nodes: u64,
+    deadline: Option<Instant>,
+    stop: StopToken,
+    aborted: bool,
+}
+
+impl Solver {
+    fn new(max_nodes: u64, deadline: Option<Instant>, stop: StopToken) -> Self {
+        Self {
+            tt: HashMap::new(),
+            nodes: 0,
+            max_nodes,
+            deadline,
+            stop,
+            aborted: false,
+        }
+    }
+
+    #[inline]
+    fn key(&self, state: &MinimaxState, player: u8) -> u64 {
+        side_key(state.hash, state, player)
+    }
+
+    #[inline]
+    fn lookup(&self, key: u64) -> (u32, u32) {
+        self.tt.get(&key).copied().unwrap_or((1, 1))
+    }
+
+    fn out_of_budget(&mut self) -> bool {
+        if self.nodes >= self.max_nodes || self.stop.is_stopped() {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if self.nodes.is_multiple_of(DEADLINE_CHECK_INTERVAL) => {
+                Instant::now() >= deadline
+            }
+            _ => false,
+        }
+    }
+
+    /// Multiple iterative deepening: expands the node until its proof number
+    /// reaches `phi_th` or its disproof number reaches `delta_th`.
+    fn mid(&mut self, state: &mut MinimaxState, player: u8, phi_th: u32, delta_th: u32) {
+        if self.out_of_budget() {
+            self.aborted = true;
+            return;
+        }
+        self.nodes += 1;
+
+        let key = self.key(state, player);
+        let mut moves: SmallVec<[usize; 64]> = state.available_cells().collect();
+        if moves.is_empty() {
+            // A full board always has a winner, and it was not us.
+            self.tt.insert(key, (PN_INF, 0));
+            return;
+        }
+        if find_winning_move(state, &moves, player).is_some() {
+            self.tt.insert(key, (0, PN_INF));
+            return;
+        }
+
+        // If the opponent threatens to win on some cell we must play there;
+        // two such threats cannot both be blocked.
+        let opponent = state.opponent_of(player);
+        let threats = winning_moves(state, &moves, opponent, 2);
+        match threats.len() {
+            0 => {}
+            1 => moves = threats,
+            _ => {
+                self.tt.insert(key, (PN_INF, 0));
+                return;
+            }
+        }
+
+        loop {
+            // Children are scored from the opponent's point of view: our proof
+            // number is their smallest disproof number, and our disproof number
+            // is the sum of their proof numbers.
+            let mut phi = PN_INF;
+            let mut delta = 0u32;
+            let mut best = moves[0];
+            let mut best_child_phi = PN_INF;
+            let mut second_delta = PN_INF;
+            for &mv in &moves {
+                let (c_phi, c_delta) = self.lookup(side_key(state.child_hash(mv, player), state, opponent));
+                delta = delta.saturating_add(c_phi).min(PN_INF);
+                if c_delta < phi {
+                    second_delta = phi;
+                    phi = c_delta;
+                    best = mv;
+                    best_child_phi = c_phi;
+                } else if c_delta < second_delta {
+                    second_delta = c_delta;
+                }
+            }
+
+            if phi >= phi_th || delta >= delta_th {
+                self.tt.insert(key, (phi, delta));
+                return;
+            }
+
+            let child_phi_th = (delta_th - delta).saturating_add(best_child_phi).min(PN_INF);
+            let child_delta_th = phi_th.min(second_delta.saturating_add(1));
+            state.make_move(best, player);
+            self.mid(state, opponent, child_phi_th, child_delta_th);
+            state.undo_move(best);
+            if self.aborted {
+                return;
+            }
+        }
+    }
+
+    /// Returns a move that wins for `player` in a position proven to be won.
+    fn proof_move(&self, state: &mut MinimaxState, player: u8) -> Option<usize> {
+        let moves: SmallVec<[usize; 64]> = state.available_cells().collect();
+        if let Some(mv) = find_winning_move(state, &moves, player) {
+            return Some(mv);
+        }
+        let opponent = state.opponent_of(player);
+        moves.into_iter().find(|&mv| {
+            let (_, c_delta) = self.lookup(side_key(state.child_hash(mv, player), state, opponent));
+            c_delta == 0
+        })
+    }
+}
+
+/// Applies the side-to-move salt to a position hash.
+#[inline]
+fn side_key(hash: u64, state: &MinimaxState, player: u8) -> u64 {
+    if player == state.bot_id {
+        hash
+    } else {
+        hash ^ SIDE_TO_MOVE_SALT
+    }
+}
+
+/// Returns a move in `moves` that immediately completes a winning chain.
+fn find_winning_move(state: &mut MinimaxState, moves: &[usize], player: u8) -> Option<usize> {
+    winning_moves(state, moves, player, 1).into_iter().next()
+}
+
+/// Returns up to `limit` moves in `moves` that immediately win for `player`.
+fn winning_moves(
+    state: &mut MinimaxState,
+    moves: &[usize],
+    player: u8,
+    limit: usize,
+) -> SmallVec<[usize; 64]> {
+    let mut found = SmallVec::new();
+    for &mv in moves {
+        state.make_move(mv, player);
+        let won = state.check_win(player);
+        state.undo_move(mv);
+        if won {
+            found.push(mv);
+            if found.len() == limit {
+                break;
+            }
+        }
+    }
+    found
+}
@@ -3,9 +3,14 @@
 use crate::{Coordinates, GameY, PlayerId, game};
 use fixedbitset::FixedBitSet;
 use smallvec::SmallVec;
-use std::collections::VecDeque;
 use super::BLOCKED;
+use super::eval::EvalWeights;
+use super::resistance::ResistanceBuffers;
 use super::tables::xorshift64;
+use super::virtual_conn::{
+    BridgeSlot, CarriedBridge, CarriedTemplate, build_bridge_cache, build_carrier_cache,
+    build_template_cache, build_template_carrier_cache,
+};
 
 // ============================================================================
 // Incremental evaluation score
@@ -20,14 +25,14 @@ pub(super) struct IncrementalScore {
     pub edge_refs: [u8; 3],
     /// Sum of same-player neighbor counts across all placed pieces.
     pub connections: i32,
-    /// Cumulative +40 bonus for every piece with >= 2 same-player neighbors.
+    /// Number of pieces with >= 2 same-player neighbors.
     pub well_connected: i32,
     /// Sum of `(50 - off_center)` for all placed pieces.
     pub center_sum: i32,
 }
 
 impl IncrementalScore {
-    pub fn evaluate(&self, game_progress: f32) -> i32 {
+    pub fn evaluate(&self, game_progress: f32, weights: &EvalWeights) -> i32 {
         let edges_touched: u8 = self
             .edge_refs
             .iter()
@@ -35,11 +40,11 @@ impl IncrementalScore {
             .map(|(i, &r)| if r > 0 { 1 << i } else { 0 })
             .fold(0u8, |acc, b| acc | b);
 
-        let center_weight = (1.0 - game_progress) * 5.0;
+        let center_weight = (1.0 - game_progress) * weights.centre as f32;
 
-        self.well_connected
-            + edges_touched.count_ones() as i32 * 5
-            + self.connections * 25
+        self.well_connected * weights.well_connected
+            + edges_touched.count_ones() as i32 * weights.edge
+            + self.connections * weights.adjacency
             + (self.center_sum as f32 * center_weight) as i32
     }
 }
@@ -48,13 +53,29 @@ impl IncrementalScore {
 // State
 // ============================================================================
 
-pub(crate) struct MinimaxState {
+/// Mutable board representation used by the minimax engine.
+///
+/// Cells are addressed by their index (see [`Coordinates::to_index`]) and hold
+/// `0` when empty or the id of the player owning them. The bot the search
+/// runs for is [`bot_id`](Self::bot_id); its opponent is
+/// [`human_id`](Self::human_id). Custom [`Evaluator`](super::Evaluator)s read
+/// the position through the accessors below.
+#[derive(Clone)]
+pub struct MinimaxState {
     pub(super) board: Vec<u8>,
     pub(super) size: u32,
     pub(super) available_mask: FixedBitSet,
     pub(super) neighbors_cache: Vec<Vec<usize>>,
     pub(super) edges_cache: Vec<u8>,
     pub(super) center_cache: Vec<i32>,
+    /// Bridge partners and carriers per cell.
+    pub(super) bridge_cache: Vec<SmallVec<[BridgeSlot; 6]>>,
+    /// Edge-template carriers per cell and side (index 0=A, 1=B, 2=C).
+    pub(super) template_cache: Vec<[Option<[usize; 2]>; 3]>,
+    /// Bridges each cell is a carrier of.
+    pub(super) carrier_cache: Vec<SmallVec<[CarriedBridge; 6]>>,
+    /// Edge templates each cell is a carrier of.
+    pub(super) template_carrier_cache: Vec<SmallVec<[CarriedTemplate; 4]>>,
     pub(super) bot_id: u8,
     pub(super) human_id: u8,
     // Reusable buffers for check_win DFS.
@@ -65,8 +86,18 @@ pub(crate) struct MinimaxState {
     // Two auxiliary BFS buffers for connection_cost.
     eval_buf_a: Vec<u32>,
     eval_buf_b: Vec<u32>,
-    // Reusable BFS queue.
-    bfs_queue: VecDeque<usize>,
+    // Reusable buffers for the resistance evaluation.
+    pub(super) resistance_buffers: ResistanceBuffers,
+    // Reusable BFS layers: cells at the current distance and at the next.
+    bfs_layer: Vec<usize>,
+    bfs_next_layer: Vec<usize>,
+    // Cells on each side, and cells with the carriers of their edge template
+    // to it: the BFS sources.
+    side_cells: [Vec<usize>; 3],
+    side_templates: [Vec<(usize, [usize; 2])>; 3],
+    // Intact bridges of the stone on each cell as a bit set of its
+    // `bridge_cache` slots, maintained incrementally.
+    pub(super) bridge_masks: Vec<u8>,
     // Incremental evaluation state.
     pub(super) scores: [IncrementalScore; 2],
     pub(super) piece_neighbor_counts: Vec<u8>,
@@ -83,6 +114,20 @@ impl MinimaxState {
 
         let (neighbors_cache, edges_cache, center_cache) =
             Self::build_board_caches(game, size, total_cells);
+        let bridge_cache = build_bridge_cache(&neighbors_cache);
+        let template_cache = build_template_cache(&neighbors_cache, &edges_cache);
+        let carrier_cache = build_carrier_cache(&bridge_cache);
+        let template_carrier_cache = build_template_carrier_cache(&template_cache);
+        let side_cells = std::array::from_fn(|side| {
+            (0..total_cells)
+                .filter(|&idx| edges_cache[idx] & (1 << side) != 0)
+                .collect()
+        });
+        let side_templates = std::array::from_fn(|side| {
+            (0..total_cells)
+                .filter_map(|idx| Some((idx, template_cache[idx][side]?)))
+                .collect()
+        });
         let board = Self::build_board(game, size, total_cells);
         let available_mask = Self::build_available_mask(game, total_cells);
         let zobrist_keys = Self::build_zobrist_keys(total_cells);
@@ -94,6 +139,10 @@ impl MinimaxState {
             neighbors_cache,
             edges_cache,
             center_cache,
+            bridge_cache,
+            template_cache,
+            carrier_cache,
+            template_carrier_cache,
             bot_id: bot_player.id() as u8 + 1,
             human_id: game::other_player(bot_player).id() as u8 + 1,
             visited: vec![false; total_cells],
@@ -101,7 +150,12 @@ impl MinimaxState {
             bfs_dist: vec![0u32; total_cells],
             eval_buf_a: vec![0u32; total_cells],
             eval_buf_b: vec![0u32; total_cells],
-            bfs_queue: VecDeque::with_capacity(total_cells),
+            resistance_buffers: ResistanceBuffers::default(),
+            bfs_layer: Vec::with_capacity(total_cells),
+            bfs_next_layer: Vec::with_capacity(total_cells),
+            side_cells,
+            side_templates,
+            bridge_masks: vec![0u8; total_cells],
             scores: [IncrementalScore::default(), IncrementalScore::default()],
             piece_neighbor_counts: vec![0u8; total_cells],
             occupied_count: 0,
@@ -205,12 +259,17 @@ impl MinimaxState {
 
             score.connections += k as i32;
             if k >= 2 {
-                score.well_connected += 40;
+                score.well_connected += 1;
             }
             score.center_sum += self.center_cache[idx];
 
             self.occupied_count += 1;
             self.hash ^= self.zobrist_keys[idx][p];
+            for (j, slot) in self.bridge_cache[idx].iter().enumerate() {
+                if self.bridge_intact(slot, player) {
+                    self.bridge_masks[idx] |= 1 << j;
+                }
+            }
         }
     }
 
@@ -221,13 +280,21 @@ impl MinimaxState {
     pub(super) fn make_move(&mut self, idx: usize, player: u8) {
         let p = self.player_idx(player);
 
-        let neighbors: SmallVec<[usize; 6]> = self.neighbors_cache[idx].iter().copied().collect();
-
-        let k = neighbors
-            .iter()
-            .filter(|&&n| self.board[n] == player)
-            .count() as u8;
-        self.piece_neighbor_counts[idx] = k;
+        let neighbors = self.neighbors_cache[idx].as_slice();
+        let board = self.board.as_slice();
+        let counts = self.piece_neighbor_counts.as_mut_slice();
+        let mut k = 0u8;
+        let mut newly_connected = 0;
+        for &nb in neighbors {
+            if board[nb] == player {
+                k += 1;
+                counts[nb] += 1;
+                if counts[nb] == 2 {
+                    newly_connected += 1;
+                }
+            }
+        }
+        counts[idx] = k;
 
         {
             let eb = self.edges_cache[idx];
@@ -243,20 +310,13 @@ impl MinimaxState {
             }
             score.connections += 2 * k as i32;
             if k >= 2 {
-                score.well_connected += 40;
+                score.well_connected += 1;
             }
             score.center_sum += self.center_cache[idx];
+            score.well_connected += newly_connected;
         }
 
-        for &nb in &neighbors {
-            if self.board[nb] == player {
-                let old_k = self.piece_neighbor_counts[nb];
-                self.piece_neighbor_counts[nb] += 1;
-                if old_k == 1 {
-                    self.scores[p].well_connected += 40;
-                }
-            }
-        }
+        self.toggle_bridges(idx, player);
 
         self.board[idx] = player;
         self.available_mask.set(idx, false);
@@ -270,9 +330,19 @@ impl MinimaxState {
 
         self.hash ^= self.zobrist_keys[idx][p];
 
-        let neighbors: SmallVec<[usize; 6]> = self.neighbors_cache[idx].iter().copied().collect();
-
-        let k = self.piece_neighbor_counts[idx];
+        let neighbors = self.neighbors_cache[idx].as_slice();
+        let board = self.board.as_slice();
+        let counts = self.piece_neighbor_counts.as_mut_slice();
+        let k = counts[idx];
+        let mut lost_connected = 0;
+        for &nb in neighbors {
+            if board[nb] == player {
+                if counts[nb] == 2 {
+                    lost_connected += 1;
+                }
+                counts[nb] -= 1;
+            }
+        }
 
         {
             let eb = self.edges_cache[idx];
@@ -288,26 +358,49 @@ impl MinimaxState {
             }
             score.connections -= 2 * k as i32;
             if k >= 2 {
-                score.well_connected -= 40;
+                score.well_connected -= 1;
             }
             score.center_sum -= self.center_cache[idx];
+            score.well_connected -= lost_connected;
         }
 
-        for &nb in &neighbors {
-            if self.board[nb] == player {
-                let cur_k = self.piece_neighbor_counts[nb];
-                self.piece_neighbor_counts[nb] -= 1;
-                if cur_k == 2 {
-                    self.scores[p].well_connected -= 40;
-                }
-            }
-        }
+        self.toggle_bridges(idx, player);
 
         self.board[idx] = 0;
         self.available_mask.set(idx, true);
         self.occupied_count -= 1;
     }
 
+    /// Updates `bridge_masks` for a `player` stone placed on or removed
+    /// from `idx`; the same bits flip either way.
+    fn toggle_bridges(&mut self, idx: usize, player: u8) {
+        // The stone forms bridges with its partners and breaks the bridges
+        // it intrudes as a carrier. Neither test reads `idx` itself.
+        let Self {
+            board,
+            bridge_cache,
+            carrier_cache,
+            bridge_masks,
+            ..
+        } = self;
+        let board = board.as_slice();
+        let masks = bridge_masks.as_mut_slice();
+        for (j, slot) in bridge_cache[idx].iter().enumerate() {
+            let [c1, c2] = slot.carriers;
+            if board[slot.partner] == player && board[c1] == 0 && board[c2] == 0 {
+                masks[idx] ^= 1 << j;
+                masks[slot.partner] ^= 1 << slot.back;
+            }
+        }
+        for carried in carrier_cache[idx].iter() {
+            let [a, b] = carried.stones;
+            if board[a] != 0 && board[a] == board[b] && board[carried.other] == 0 {
+                masks[a] ^= 1 << carried.slots[0];
+                masks[b] ^= 1 << carried.slots[1];
+            }
+        }
+    }
+
     // -------------------------------------------------------------------------
     // Helpers
     // -------------------------------------------------------------------------
@@ -317,8 +410,52 @@ impl MinimaxState {
         if player == self.bot_id { 0 } else { 1 }
     }
 
+    /// Returns the board size (length of one side).
+    pub fn board_size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the content of every cell: `0` for empty, otherwise the id of
+    /// the owning player.
+    pub fn cells(&self) -> &[u8] {
+        &self.board
+    }
+
+    /// Returns the content of cell `idx`.
     #[inline]
-    pub(super) fn opponent_of(&self, player: u8) -> u8 {
+    pub fn cell(&self, idx: usize) -> u8 {
+        self.board[idx]
+    }
+
+    /// Returns the id of the player the search runs for.
+    pub fn bot_id(&self) -> u8 {
+        self.bot_id
+    }
+
+    /// Returns the id of the bot's opponent.
+    pub fn human_id(&self) -> u8 {
+        self.human_id
+    }
+
+    /// Returns the indices of the cells adjacent to `idx`.
+    pub fn neighbors(&self, idx: usize) -> &[usize] {
+        &self.neighbors_cache[idx]
+    }
+
+    /// Returns the board sides touched by `idx` as a bitmask
+    /// (`0b001` = A, `0b010` = B, `0b100` = C).
+    pub fn sides(&self, idx: usize) -> u8 {
+        self.edges_cache[idx]
+    }
+
+    /// Returns the number of occupied cells.
+    pub fn occupied_count(&self) -> usize {
+        self.occupied_count
+    }
+
+    /// Returns the other player.
+    #[inline]
+    pub fn opponent_of(&self, player: u8) -> u8 {
         if player == self.bot_id {
             self.human_id
         } else {
@@ -326,14 +463,16 @@ impl MinimaxState {
         }
     }
 
-    pub(super) fn available_cells(&self) -> impl Iterator<Item = usize> + '_ {
-        self.available_mask.ones()
+    /// Returns the position hash after `player` plays `idx`, without
+    /// touching the board.
+    #[inline]
+    pub(super) fn child_hash(&self, idx: usize, player: u8) -> u64 {
+        self.hash ^ self.zobrist_keys[idx][self.player_idx(player)]
     }
 
-    /// Returns 0 if `idx` is occupied by `player`, 1 if empty.
-    #[inline]
-    fn cell_cost(&self, idx: usize, player: u8) -> u32 {
-        if self.board[idx] == player { 0 } else { 1 }
+    /// Returns the indices of the empty cells.
+    pub fn available_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        self.available_mask.ones()
     }
 
     // -------------------------------------------------------------------------
@@ -344,16 +483,25 @@ impl MinimaxState {
         self.visited.fill(false);
 
         for idx in 0..self.board.len() {
-            if self.board[idx] == player && self.edges_cache[idx] != 0 && !self.visited[idx] {
-                if self.dfs_collect_edges(idx, player) == 0b111 {
-                    return true;
-                }
+            if self.board[idx] == player
+                && self.edges_cache[idx] != 0
+                && !self.visited[idx]
+                && self.dfs_collect_edges(idx, player) == 0b111
+            {
+                return true;
             }
         }
 
         false
     }
 
+    /// Like [`check_win`](Self::check_win) right after `player` played
+    /// `idx`: only the group through the new stone can have started to win.
+    pub(super) fn check_win_at(&mut self, idx: usize, player: u8) -> bool {
+        self.visited.fill(false);
+        self.dfs_collect_edges(idx, player) == 0b111
+    }
+
     fn dfs_collect_edges(&mut self, start: usize, player: u8) -> u8 {
         let mut edges_mask = 0u8;
 
@@ -383,36 +531,98 @@ impl MinimaxState {
     // BFS and connection cost
     // -------------------------------------------------------------------------
 
+    /// 0-1 BFS from `side_bit`: entering an own stone costs 0 and an empty
+    /// cell 1. Intact bridges between own stones and own stones holding an
+    /// edge template to the side are free links as well.
     fn bfs_from_side(&mut self, side_bit: u8, player: u8) {
         let opponent = self.opponent_of(player);
-        let n = self.board.len();
-
-        self.bfs_dist.iter_mut().for_each(|d| *d = BLOCKED);
-        self.bfs_queue.clear();
-
-        for idx in 0..n {
-            if self.edges_cache[idx] & side_bit != 0 && self.board[idx] != opponent {
-                self.bfs_dist[idx] = self.cell_cost(idx, player);
-                self.bfs_queue.push_back(idx);
+        let side = side_bit.trailing_zeros() as usize;
+        // Plain slices keep the hot loop cheap in unoptimised builds.
+        let Self {
+            board,
+            neighbors_cache,
+            bridge_cache,
+            bridge_masks,
+            bfs_dist,
+            bfs_layer,
+            bfs_next_layer,
+            side_cells,
+            side_templates,
+            ..
+        } = self;
+        let board = board.as_slice();
+        let dist = bfs_dist.as_mut_slice();
+
+        dist.fill(BLOCKED);
+        bfs_layer.clear();
+        bfs_next_layer.clear();
+
+        for &idx in &side_cells[side] {
+            let cell = board[idx];
+            if cell == player {
+                dist[idx] = 0;
+                bfs_layer.push(idx);
+            } else if cell == 0 {
+                dist[idx] = 1;
+                bfs_next_layer.push(idx);
+            }
+        }
+        for &(idx, [c1, c2]) in &side_templates[side] {
+            if board[idx] == player && board[c1] == 0 && board[c2] == 0 && dist[idx] != 0 {
+                dist[idx] = 0;
+                bfs_layer.push(idx);
             }
         }
 
-        while let Some(idx) = self.bfs_queue.pop_front() {
-            let d = self.bfs_dist[idx];
-            for &nb in &self.neighbors_cache[idx] {
-                if self.bfs_dist[nb] == BLOCKED && self.board[nb] != opponent {
-                    self.bfs_dist[nb] = d + self.cell_cost(nb, player);
-                    self.bfs_queue.push_back(nb);
+        let mut d = 0;
+        loop {
+            while let Some(idx) = bfs_layer.pop() {
+                // A cell improved after it was queued has already been
+                // expanded at its smaller distance.
+                if dist[idx] < d {
+                    continue;
+                }
+                for &nb in neighbors_cache[idx].as_slice() {
+                    let cell = board[nb];
+                    if cell == opponent {
+                        continue;
+                    }
+                    if cell == player {
+                        if d < dist[nb] {
+                            dist[nb] = d;
+                            bfs_layer.push(nb);
+                        }
+                    } else if d + 1 < dist[nb] {
+                        dist[nb] = d + 1;
+                        bfs_next_layer.push(nb);
+                    }
                 }
+                let mut bridges = bridge_masks[idx];
+                while bridges != 0 {
+                    let partner = bridge_cache[idx][bridges.trailing_zeros() as usize].partner;
+                    bridges &= bridges - 1;
+                    if d < dist[partner] {
+                        dist[partner] = d;
+                        bfs_layer.push(partner);
+                    }
+                }
+            }
+            if bfs_next_layer.is_empty() {
+                break;
             }
+            std::mem::swap(bfs_layer, bfs_next_layer);
+            d += 1;
         }
     }
 
     /// Computes the approximate Steiner tree cost to connect all three board
-    /// sides for `player`.
-    pub(super) fn connection_cost(&mut self, player: u8) -> u32 {
+    /// sides for `player`: the number of empty cells still needed, or
+    /// `u32::MAX` if the sides can no longer be connected.
+    ///
+    /// Bridges and edge templates count as already connected; see
+    /// [`bridges`](Self::bridges) and [`edge_templates`](Self::edge_templates).
+    pub fn connection_cost(&mut self, player: u8) -> u32 {
         let opponent = self.opponent_of(player);
-        let n = self.board.len();
 
         self.bfs_from_side(0b001, player);
         self.eval_buf_a.copy_from_slice(&self.bfs_dist);
@@ -422,19 +632,21 @@ impl MinimaxState {
 
         self.bfs_from_side(0b100, player);
 
+        let board = self.board.as_slice();
+        let (dist_a, dist_b) = (self.eval_buf_a.as_slice(), self.eval_buf_b.as_slice());
+        let dist_c = self.bfs_dist.as_slice();
         let mut min_cost = BLOCKED;
-        for i in 0..n {
-            if self.board[i] == opponent {
+        for i in 0..board.len() {
+            let cell = board[i];
+            if cell == opponent {
                 continue;
             }
-            let da = self.eval_buf_a[i];
-            let db = self.eval_buf_b[i];
-            let dc = self.bfs_dist[i];
-            let raw = sat_add(sat_add(da, db), dc);
-            if raw == BLOCKED {
+            let (da, db, dc) = (dist_a[i], dist_b[i], dist_c[i]);
+            if da == BLOCKED || db == BLOCKED || dc == BLOCKED {
                 continue;
             }
-            let cell_overcounting = if self.board[i] == player { 0 } else { 2 };
+            let raw = da + db + dc;
+            let cell_overcounting = if cell == player { 0 } else { 2 };
             let cost = raw - cell_overcounting;
             if cost < min_cost {
                 min_cost = cost;
@@ -453,25 +665,15 @@ impl MinimaxState {
         let mut deltas: Vec<(usize, u32)> = available
             .into_iter()
             .map(|idx| {
-                self.board[idx] = player;
+                self.make_move(idx, player);
                 let new_cost = self.connection_cost(player);
-                self.board[idx] = 0;
+                self.undo_move(idx);
 
                 (idx, baseline.saturating_sub(new_cost))
             })
             .collect();
 
-        deltas.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+        deltas.sort_unstable_by_key(|&(_, delta)| std::cmp::Reverse(delta));
         deltas
     }
 }
-
-/// Saturating addition that treats `BLOCKED` as infinity.
-#[inline]
-fn sat_add(a: u32, b: u32) -> u32 {
-    if a == BLOCKED || b == BLOCKED {
-        BLOCKED
-    } else {
-        a.saturating_add(b)
-    }
-}
@@ -0,0 +1,90 @@
+//! Statistics of finished searches, for monitoring.
+//!
+//! Like the [`StopToken`](super::StopToken), collection is scoped to the
+//! calling thread, so the public entry points keep their signatures: wrap
+//! any bot call in [`collect_search_stats`] to learn how deep and how wide
+//! the searches it ran went.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static COLLECTED: RefCell<Option<Vec<SearchStats>>> = const { RefCell::new(None) };
+}
+
+/// Summary of one iterative deepening search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Deepest depth completed.
+    pub depth: u32,
+    /// Nodes searched.
+    pub nodes: u64,
+    /// Transposition table lookups.
+    pub tt_probes: u64,
+    /// Lookups that found an entry deep enough to use.
+    pub tt_hits: u64,
+}
+
+/// Runs `f` and returns its result together with the statistics of every
+/// search it ran on the calling thread, in order.
+///
+/// ```
+/// use gamey::{GameY, MinimaxConfig, choose_move_with_config, collect_search_stats};
+///
+/// let config = MinimaxConfig::new(0, 60_000).with_node_limit(1_000);
+/// let (coords, stats) = collect_search_stats(|| choose_move_with_config(&GameY::new(7), &config));
+/// assert!(coords.is_some());
+/// assert_eq!(stats.len(), 1);
+/// ```
+pub fn collect_search_stats<R>(f: impl FnOnce() -> R) -> (R, Vec<SearchStats>) {
+    struct Restore(Option<Vec<SearchStats>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            COLLECTED.with(|collected| *collected.borrow_mut() = previous);
+        }
+    }
+
+    let previous = COLLECTED.with(|collected| collected.borrow_mut().replace(Vec::new()));
+    let restore = Restore(previous);
+    let result = f();
+    let stats = COLLECTED.with(|collected| collected.borrow_mut().take().unwrap_or_default());
+    drop(restore);
+    (result, stats)
+}
+
+/// Records `stats` if the calling thread is collecting.
+pub(super) fn record(stats: SearchStats) {
+    COLLECTED.with(|collected| {
+        if let Some(collected) = collected.borrow_mut().as_mut() {
+            collected.push(stats);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_are_only_kept_while_collecting() {
+        record(SearchStats::default());
+        let ((), stats) = collect_search_stats(|| {
+            record(SearchStats {
+                depth: 3,
+                ..SearchStats::default()
+            })
+        });
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].depth, 3);
+    }
+
+    #[test]
+    fn test_nested_collection_restores_outer_scope() {
+        let ((), outer) = collect_search_stats(|| {
+            let ((), inner) = collect_search_stats(|| record(SearchStats::default()));
+            assert_eq!(inner.len(), 1);
+            record(SearchStats::default());
+        });
+        assert_eq!(outer.len(), 1);
+    }
+}
@@ -0,0 +1,152 @@
+//! Cooperative cancellation of running searches.
+//!
+//! A [`StopToken`] is a shared flag that every search polls together with its
+//! time and node budget. Once the token is stopped, the search returns the
+//! best result found so far within a few nodes.
+//!
+//! The public entry points of the engine (`choose_move_*`, analysis, the Pie
+//! Rule helpers and [`solve`](super::solve)) keep their signatures and obey
+//! the token of the enclosing [`StopToken::scope`], so that callers such as
+//! the HTTP server can cancel any bot without the bot knowing about it.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+thread_local! {
+    static CURRENT: RefCell<Option<StopToken>> = const { RefCell::new(None) };
+}
+
+/// A cloneable flag that stops the searches watching it.
+///
+/// ```
+/// use gamey::{GameY, StopToken, choose_move_with_minimax};
+///
+/// let stop = StopToken::new();
+/// stop.stop();
+/// // The search gives up at once and still returns a legal move.
+/// let coords = stop.scope(|| choose_move_with_minimax(&GameY::new(5), 60_000, 60_000));
+/// assert!(coords.is_some());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StopToken {
+    stopped: Arc<AtomicBool>,
+    /// Token whose stop also stops this one, see [`child`](Self::child).
+    parent: Option<Arc<StopToken>>,
+}
+
+impl StopToken {
+    /// Creates a token that has not been stopped.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stops every search watching this token or one of its clones.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+
+    /// Creates a token that is stopped on its own or together with this
+    /// one, e.g. a single request's search within a server shutting down.
+    ///
+    /// ```
+    /// use gamey::StopToken;
+    ///
+    /// let server = StopToken::new();
+    /// let request = server.child();
+    /// request.stop();
+    /// assert!(!server.is_stopped());
+    ///
+    /// let request = server.child();
+    /// server.stop();
+    /// assert!(request.is_stopped());
+    /// ```
+    pub fn child(&self) -> Self {
+        Self {
+            stopped: Arc::default(),
+            parent: Some(Arc::new(self.clone())),
+        }
+    }
+
+    /// Returns `true` once [`stop`](Self::stop) has been called on this
+    /// token or on the token it is a [`child`](Self::child) of.
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+            || self.parent.as_ref().is_some_and(|parent| parent.is_stopped())
+    }
+
+    /// Returns a guard that stops this token when dropped.
+    pub fn stop_on_drop(&self) -> StopOnDrop {
+        StopOnDrop(self.clone())
+    }
+
+    /// Runs `f` with this token as the current token of the calling thread,
+    /// so that searches started inside `f` stop together with it.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        struct Restore(Option<StopToken>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                CURRENT.with(|current| *current.borrow_mut() = previous);
+            }
+        }
+
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        let _restore = Restore(previous);
+        f()
+    }
+
+    /// The token of the innermost enclosing [`scope`](Self::scope) on this
+    /// thread, or a token that is never stopped.
+    pub fn current() -> Self {
+        CURRENT.with(|current| current.borrow().clone()).unwrap_or_default()
+    }
+}
+
+/// Stops its [`StopToken`] when dropped, e.g. together with the future of an
+/// HTTP request whose client went away.
+#[derive(Debug)]
+pub struct StopOnDrop(StopToken);
+
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_the_flag() {
+        let token = StopToken::new();
+        let clone = token.clone();
+        assert!(!clone.is_stopped());
+        token.stop();
+        assert!(clone.is_stopped());
+    }
+
+    #[test]
+    fn test_stop_on_drop_stops_token() {
+        let token = StopToken::new();
+        drop(token.stop_on_drop());
+        assert!(token.is_stopped());
+    }
+
+    #[test]
+    fn test_scope_sets_and_restores_current_token() {
+        assert!(!StopToken::current().is_stopped());
+
+        let outer = StopToken::new();
+        outer.stop();
+        outer.scope(|| {
+            assert!(StopToken::current().is_stopped());
+            StopToken::new().scope(|| assert!(!StopToken::current().is_stopped()));
+            assert!(StopToken::current().is_stopped());
+        });
+
+        assert!(!StopToken::current().is_stopped());
+    }
+}
@@ -3,10 +3,7 @@
 /// Number of killer move slots per depth level.
 pub(super) const KILLER_SLOTS: usize = 2;
 
-/// Number of buckets in the transposition table. Must be a power of two.
-/// Each bucket holds two entries (depth-preferred + always-replace).
-/// Total entries = 2 * TT_BUCKETS ≈ 2M entries × ~20 bytes ≈ 40 MB.
-const TT_BUCKETS: usize = 1 << 20;
+use super::config::DEFAULT_TT_SIZE_MB;
 
 /// Default aspiration window half-width for iterative deepening.
 pub(super) const ASPIRATION_DELTA: i32 = 50;
@@ -34,6 +31,8 @@ pub(super) struct TtEntry {
     pub best_move: u32,
     pub depth: u8,
     pub flag: TtFlag,
+    /// Search that wrote the entry; see [`TranspositionTable::new_search`].
+    pub generation: u8,
 }
 
 impl TtEntry {
@@ -42,19 +41,44 @@ impl TtEntry {
     }
 }
 
+/// Each bucket holds two entries (depth-preferred + always-replace). The
+/// number of buckets is a power of two.
 pub(super) struct TranspositionTable {
     entries: Vec<TtEntry>,
     mask: usize,
+    generation: u8,
 }
 
 impl TranspositionTable {
+    /// Creates a table of [`DEFAULT_TT_SIZE_MB`] (2M entries).
     pub fn new() -> Self {
+        Self::with_size_mb(DEFAULT_TT_SIZE_MB)
+    }
+
+    /// Creates the largest table that fits in `size_mb` megabytes, with at
+    /// least one bucket.
+    pub fn with_size_mb(size_mb: usize) -> Self {
+        let bucket_bytes = 2 * std::mem::size_of::<TtEntry>();
+        let fitting = (size_mb << 20) / bucket_bytes;
+        let buckets = if fitting == 0 {
+            1
+        } else {
+            1 << fitting.ilog2()
+        };
         Self {
-            entries: vec![TtEntry::default(); TT_BUCKETS * 2],
-            mask: TT_BUCKETS - 1,
+            entries: vec![TtEntry::default(); buckets * 2],
+            mask: buckets - 1,
+            generation: 0,
         }
     }
 
+    /// Starts a new search on a reused table. Entries left by earlier
+    /// searches stay available for probing, but the depth-preferred slot no
+    /// longer protects them from being replaced.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
     #[inline]
     fn bucket_base(&self, key: u64) -> usize {
         (key as usize & self.mask) * 2
@@ -66,24 +90,21 @@ impl TranspositionTable {
         let base = self.bucket_base(key);
         let bm = best_move.map(|m| m as u32).unwrap_or(u32::MAX);
 
-        let dp = &self.entries[base];
-        if dp.is_empty() || depth >= dp.depth {
-            self.entries[base] = TtEntry {
-                key,
-                score,
-                best_move: bm,
-                depth,
-                flag,
-            };
-        }
-
-        self.entries[base + 1] = TtEntry {
+        let entry = TtEntry {
             key,
             score,
             best_move: bm,
             depth,
             flag,
+            generation: self.generation,
         };
+
+        let dp = &self.entries[base];
+        if dp.is_empty() || dp.generation != self.generation || depth >= dp.depth {
+            self.entries[base] = entry.clone();
+        }
+
+        self.entries[base + 1] = entry;
     }
 
     pub fn probe(&self, key: u64, depth: u8) -> Option<&TtEntry> {
@@ -1,14 +1,19 @@
 //! Tests for the minimax search engine.
 
-use super::eval::evaluate_state;
+use super::config::MinimaxConfig;
+use super::eval::{HeuristicEvaluator, evaluate_state};
 use super::search::{
-    aspiration_search, greedy_search, iterative_deepening_search, negamax, order_moves,
-    search_best_move,
+    SearchContext, aspiration_search, greedy_search, iterative_deepening_search, negamax,
+    VC_ORDER_MIN_DEPTH, order_moves, search_best_move,
 };
+use super::session::SearchSession;
+use super::stop::StopToken;
 use super::state::MinimaxState;
-use super::tables::{HistoryTable, KillerTable, TranspositionTable};
+use super::tables::{HistoryTable, KillerTable, TranspositionTable, TtFlag};
 use super::{ABORTED, INFINITY, LOSE_SCORE, WIN_SCORE};
-use crate::{GameY, PlayerId};
+use crate::{
+    Coordinates, GameY, Movement, PlayerId, choose_move_in_session, choose_move_with_config,
+};
 use smallvec::SmallVec;
 use std::time::{Duration, Instant};
 
@@ -20,23 +25,10 @@ fn get_valid_cells(state: &MinimaxState, count: usize) -> Vec<usize> {
     state.available_cells().take(count).collect()
 }
 
-fn make_search_context(
-    max_depth: u8,
-    total_cells: usize,
-) -> (
-    Instant,
-    Duration,
-    KillerTable,
-    TranspositionTable,
-    HistoryTable,
-) {
-    (
-        Instant::now(),
-        Duration::from_secs(60),
-        KillerTable::new(max_depth as usize),
-        TranspositionTable::new(),
-        HistoryTable::new(total_cells),
-    )
+fn make_search_context(max_depth: u8, total_cells: usize) -> SearchContext {
+    let mut ctx = SearchContext::new(total_cells, Duration::from_secs(60));
+    ctx.killers = KillerTable::new(max_depth as usize);
+    ctx
 }
 
 // ============================================================================
@@ -198,7 +190,7 @@ fn test_connection_cost_decreases_with_own_piece() {
 #[test]
 fn test_order_moves_places_tt_move_first() {
     let state = create_empty_state(4);
-    let history = HistoryTable::new(state.board.len());
+    let ctx = SearchContext::new(state.board.len(), Duration::from_secs(60));
     let mut moves: SmallVec<[usize; 128]> = state.available_cells().take(8).collect();
     let tt_mv = moves[5];
 
@@ -208,7 +200,8 @@ fn test_order_moves_places_tt_move_first() {
         state.bot_id,
         Some(tt_mv),
         [None, None],
-        &history,
+        &ctx,
+        VC_ORDER_MIN_DEPTH,
     );
 
     assert_eq!(moves[0], tt_mv);
@@ -217,7 +210,7 @@ fn test_order_moves_places_tt_move_first() {
 #[test]
 fn test_order_moves_tt_before_killer() {
     let state = create_empty_state(4);
-    let history = HistoryTable::new(state.board.len());
+    let ctx = SearchContext::new(state.board.len(), Duration::from_secs(60));
     let mut moves: SmallVec<[usize; 128]> = state.available_cells().take(10).collect();
     let tt_mv = moves[7];
     let killer = moves[3];
@@ -228,7 +221,8 @@ fn test_order_moves_tt_before_killer() {
         state.bot_id,
         Some(tt_mv),
         [Some(killer), None],
-        &history,
+        &ctx,
+        VC_ORDER_MIN_DEPTH,
     );
 
     assert_eq!(moves[0], tt_mv, "TT move must be first");
@@ -238,7 +232,7 @@ fn test_order_moves_tt_before_killer() {
 #[test]
 fn test_order_moves_preserves_all_moves() {
     let state = create_empty_state(4);
-    let history = HistoryTable::new(state.board.len());
+    let ctx = SearchContext::new(state.board.len(), Duration::from_secs(60));
     let mut moves: SmallVec<[usize; 128]> = state.available_cells().take(10).collect();
     let original: std::collections::HashSet<usize> = moves.iter().copied().collect();
 
@@ -248,7 +242,8 @@ fn test_order_moves_preserves_all_moves() {
         state.bot_id,
         None,
         [None, None],
-        &history,
+        &ctx,
+        VC_ORDER_MIN_DEPTH,
     );
 
     let after: std::collections::HashSet<usize> = moves.iter().copied().collect();
@@ -267,23 +262,13 @@ fn test_negamax_depth_zero_matches_evaluate() {
 
     let bot = state.bot_id;
     let total = state.board.len();
-    let (start, limit, mut killers, mut tt, mut hist) = make_search_context(0, total);
-    let score = negamax(
-        &mut state,
-        0,
-        -INFINITY,
-        INFINITY,
-        bot,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let mut ctx = make_search_context(0, total);
+    let score = negamax(&mut state, 0, -INFINITY, INFINITY, bot, &mut ctx);
     assert_eq!(score, evaluate_state(&mut state, bot));
 }
 
 #[test]
+#[allow(clippy::manual_range_contains)]
 fn test_negamax_score_in_valid_range() {
     let mut state = create_empty_state(3);
     let cell = state.available_cells().next().unwrap();
@@ -291,19 +276,8 @@ fn test_negamax_score_in_valid_range() {
 
     let human = state.human_id;
     let total = state.board.len();
-    let (start, limit, mut killers, mut tt, mut hist) = make_search_context(1, total);
-    let score = negamax(
-        &mut state,
-        1,
-        -INFINITY,
-        INFINITY,
-        human,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let mut ctx = make_search_context(1, total);
+    let score = negamax(&mut state, 1, -INFINITY, INFINITY, human, &mut ctx);
 
     assert!(score >= LOSE_SCORE && score <= WIN_SCORE);
 }
@@ -311,25 +285,12 @@ fn test_negamax_score_in_valid_range() {
 #[test]
 fn test_negamax_aborts_when_time_exceeded() {
     let mut state = create_empty_state(3);
-    let start = Instant::now() - Duration::from_secs(100);
-    let limit = Duration::from_millis(1);
-    let mut killers = KillerTable::new(5);
-    let mut tt = TranspositionTable::new();
-    let mut hist = HistoryTable::new(state.board.len());
+    let mut ctx = make_search_context(5, state.board.len());
+    ctx.start_time = Instant::now() - Duration::from_secs(100);
+    ctx.max_limit = Duration::from_millis(1);
     let bot = state.bot_id;
 
-    let score = negamax(
-        &mut state,
-        5,
-        -INFINITY,
-        INFINITY,
-        bot,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let score = negamax(&mut state, 5, -INFINITY, INFINITY, bot, &mut ctx);
 
     assert_eq!(score, ABORTED);
 }
@@ -338,34 +299,12 @@ fn test_negamax_aborts_when_time_exceeded() {
 fn test_negamax_with_tt_returns_same_result_twice() {
     let mut state = create_empty_state(3);
     let total = state.board.len();
-    let (start, limit, mut killers, mut tt, mut hist) = make_search_context(3, total);
+    let mut ctx = make_search_context(3, total);
     let bot = state.bot_id;
 
-    let score1 = negamax(
-        &mut state,
-        3,
-        -INFINITY,
-        INFINITY,
-        bot,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let score1 = negamax(&mut state, 3, -INFINITY, INFINITY, bot, &mut ctx);
 
-    let score2 = negamax(
-        &mut state,
-        3,
-        -INFINITY,
-        INFINITY,
-        bot,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let score2 = negamax(&mut state, 3, -INFINITY, INFINITY, bot, &mut ctx);
 
     assert_eq!(score1, score2);
 }
@@ -383,23 +322,9 @@ fn test_greedy_search_empty_board_returns_none() {
 #[test]
 fn test_search_best_move_returns_valid_index() {
     let mut state = create_empty_state(3);
-    let start = Instant::now();
-    let limit = Duration::from_secs(60);
-    let mut killers = KillerTable::new(2);
-    let mut tt = TranspositionTable::new();
-    let mut hist = HistoryTable::new(state.board.len());
+    let mut ctx = make_search_context(2, state.board.len());
 
-    let (best_move, score) = search_best_move(
-        &mut state,
-        2,
-        -INFINITY,
-        INFINITY,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let (best_move, score) = search_best_move(&mut state, 2, -INFINITY, INFINITY, &mut ctx);
 
     assert!(best_move < state.board.len());
     assert!(state.available_mask.contains(best_move));
@@ -409,7 +334,14 @@ fn test_search_best_move_returns_valid_index() {
 #[test]
 fn test_iterative_deepening_returns_valid_move() {
     let mut state = create_empty_state(3);
-    let (best_move, _score) = iterative_deepening_search(&mut state, 50, 200);
+    let (best_move, _score) = iterative_deepening_search(
+        &mut state,
+        50,
+        200,
+        None,
+        &StopToken::new(),
+        HeuristicEvaluator::default(),
+    );
     assert!(best_move < state.board.len());
 }
 
@@ -424,6 +356,7 @@ fn test_choose_move_with_minimax_returns_valid_coords() {
 }
 
 #[test]
+#[allow(clippy::assertions_on_constants)]
 fn test_constants_are_consistent() {
     assert_eq!(WIN_SCORE, 100_000);
     assert_eq!(LOSE_SCORE, -100_000);
@@ -498,38 +431,13 @@ fn test_history_records_and_ages() {
 #[test]
 fn test_aspiration_search_same_as_full_window() {
     let mut state = create_empty_state(3);
-    let start = Instant::now();
-    let limit = Duration::from_secs(60);
-    let mut killers = KillerTable::new(2);
-    let mut tt = TranspositionTable::new();
-    let mut hist = HistoryTable::new(state.board.len());
+    let mut ctx = make_search_context(2, state.board.len());
 
-    let (mv_full, score_full) = search_best_move(
-        &mut state,
-        2,
-        -INFINITY,
-        INFINITY,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let (mv_full, score_full) = search_best_move(&mut state, 2, -INFINITY, INFINITY, &mut ctx);
 
-    tt = TranspositionTable::new();
-    hist = HistoryTable::new(state.board.len());
-    killers = KillerTable::new(2);
+    ctx = make_search_context(2, state.board.len());
 
-    let (mv_asp, score_asp) = aspiration_search(
-        &mut state,
-        2,
-        score_full,
-        &mut killers,
-        &mut tt,
-        &mut hist,
-        start,
-        limit,
-    );
+    let (mv_asp, score_asp) = aspiration_search(&mut state, 2, score_full, &mut ctx);
 
     assert_eq!(
         score_full, score_asp,
@@ -537,3 +445,1222 @@ fn test_aspiration_search_same_as_full_window() {
     );
     assert_eq!(mv_full, mv_asp, "aspiration must find same best move");
 }
+
+// ============================================================================
+// Multi-PV analysis
+// ============================================================================
+
+#[test]
+fn test_search_root_lines_best_matches_search_best_move() {
+    use super::analysis::search_root_lines;
+
+    let mut state = create_empty_state(3);
+    let mut ctx = make_search_context(2, state.board.len());
+    let (_, best_score) = search_best_move(&mut state, 2, -INFINITY, INFINITY, &mut ctx);
+
+    let order: Vec<usize> = state.available_cells().collect();
+    let mut ctx = make_search_context(2, state.board.len());
+    let lines = search_root_lines(&mut state, 2, &order, 3, &mut ctx).expect("search must not abort");
+
+    assert_eq!(lines.len(), order.len(), "every root move must be scored");
+    assert_eq!(lines[0].1, best_score, "top line must carry the best score");
+    assert!(
+        lines.windows(2).all(|w| w[0].1 >= w[1].1),
+        "lines must be sorted best first"
+    );
+}
+
+#[test]
+fn test_analyze_with_minimax_returns_distinct_sorted_candidates() {
+    use super::analysis::analyze_with_minimax;
+
+    let game = GameY::new(4);
+    let candidates = analyze_with_minimax(&game, 50, 200, 3);
+
+    assert_eq!(candidates.len(), 3);
+    let unique: std::collections::HashSet<_> = candidates.iter().map(|c| c.coords).collect();
+    assert_eq!(unique.len(), 3, "candidates must be distinct moves");
+    assert!(candidates.windows(2).all(|w| w[0].score >= w[1].score));
+    for candidate in &candidates {
+        assert_eq!(candidate.pv.first(), Some(&candidate.coords), "PV must start with the candidate");
+    }
+}
+
+#[test]
+fn test_analyze_with_minimax_caps_at_available_moves() {
+    use super::analysis::analyze_with_minimax;
+
+    let game = GameY::new(2);
+    let candidates = analyze_with_minimax(&game, 50, 200, 10);
+    assert_eq!(candidates.len(), 3, "a size-2 board only has three moves");
+}
+
+#[test]
+fn test_analyze_with_minimax_ranks_winning_move_first() {
+    use super::analysis::analyze_with_minimax;
+    use crate::YEN;
+
+    // Blue to move: (0,0,2) or (0,1,1) completes the chain to side A.
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "B/BR/..R".to_string());
+    let game = GameY::try_from(yen).unwrap();
+
+    let candidates = analyze_with_minimax(&game, 50, 200, 2);
+    assert_eq!(candidates[0].score, WIN_SCORE);
+    assert_eq!(candidates[0].coords.x(), 0, "the winning move must be on side A");
+}
+
+// Without weights every leaf of an open position scores zero, so the
+// analysis must report zero scores if it uses the configured weights.
+#[test]
+fn test_analyze_with_config_uses_the_configured_weights() {
+    use super::analysis::analyze_with_config;
+    use super::eval::EvalWeights;
+
+    let mut game = GameY::new(8);
+    for (player, (x, y, z)) in [(0, (3, 2, 2)), (1, (2, 4, 1))] {
+        game.add_move(Movement::Placement {
+            player: PlayerId::new(player),
+            coords: Coordinates::new(x, y, z),
+        })
+        .unwrap();
+    }
+    let config = MinimaxConfig::new(50, 200);
+    let zero = EvalWeights {
+        connection_cost: 0,
+        adjacency: 0,
+        well_connected: 0,
+        edge: 0,
+        centre: 0,
+    };
+
+    let weighted = analyze_with_config(&game, &config, 3);
+    assert!(weighted.iter().any(|candidate| candidate.score != 0));
+    let unweighted = analyze_with_config(&game, &config.with_weights(zero), 3);
+    assert_eq!(unweighted.len(), 3);
+    assert!(unweighted.iter().all(|candidate| candidate.score == 0));
+}
+
+#[test]
+fn test_pie_with_config_runs_the_configured_evaluator() {
+    use super::eval::EvaluatorKind;
+    use super::pie::{choose_pie_opening_with_config, decide_pie_with_config};
+
+    let config = MinimaxConfig::default()
+        .with_evaluator(EvaluatorKind::Resistance)
+        .with_pie_time(100);
+    let mut game = GameY::new(5);
+    let opening = choose_pie_opening_with_config(&game, &config).expect("a fresh board has moves");
+    assert!(game.available_cells().contains(&opening.to_index(5)));
+
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: opening,
+    })
+    .unwrap();
+    // Either answer is fine; the decision must simply come back in time.
+    let start = Instant::now();
+    decide_pie_with_config(&game, &config);
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_analyze_with_minimax_full_board_returns_empty() {
+    use super::analysis::analyze_with_minimax;
+    use crate::YEN;
+
+    let yen = YEN::new(2, 0, vec!['B', 'R'], "B/RB".to_string());
+    let game = GameY::try_from(yen).unwrap();
+    assert!(analyze_with_minimax(&game, 50, 200, 3).is_empty());
+}
+
+// ============================================================================
+// Skill levels
+// ============================================================================
+
+#[test]
+fn test_skill_profile_full_strength_has_no_handicap() {
+    use super::config::MAX_SKILL;
+    use super::skill::SkillProfile;
+
+    let profile = SkillProfile::for_level(MAX_SKILL);
+    assert_eq!(profile.max_depth, super::MAX_DEPTH);
+    assert_eq!(profile.eval_noise, 0);
+    assert_eq!(profile.blunder_chance, 0.0);
+    assert_eq!(profile.candidates, 1);
+}
+
+#[test]
+fn test_skill_profile_weakens_monotonically() {
+    use super::config::{MAX_SKILL, MIN_SKILL};
+    use super::skill::SkillProfile;
+
+    for skill in MIN_SKILL..MAX_SKILL {
+        let weaker = SkillProfile::for_level(skill);
+        let stronger = SkillProfile::for_level(skill + 1);
+        assert!(weaker.max_depth <= stronger.max_depth);
+        assert!(weaker.eval_noise > stronger.eval_noise);
+        assert!(weaker.blunder_chance > stronger.blunder_chance);
+        assert!(weaker.candidates >= stronger.candidates);
+    }
+}
+
+#[test]
+fn test_leaf_noise_is_bounded_and_deterministic() {
+    let ctx = make_search_context(0, 10).with_eval_noise(30, 99);
+    let same_seed = make_search_context(0, 10).with_eval_noise(30, 99);
+    let noise: Vec<i32> = (0..200u64).map(|h| ctx.leaf_noise(h * 0x9E37_79B9)).collect();
+
+    for (h, &n) in noise.iter().enumerate() {
+        assert_eq!(n, same_seed.leaf_noise(h as u64 * 0x9E37_79B9), "noise must depend only on position and seed");
+        assert!(n.abs() <= 30);
+    }
+    assert!(noise.iter().any(|&n| n != 0), "noise must actually perturb evaluations");
+    assert_eq!(make_search_context(0, 10).leaf_noise(12345), 0, "no noise by default");
+}
+
+#[test]
+fn test_pick_candidate_never_chooses_forced_loss() {
+    use super::analysis::RootLine;
+    use super::skill::{SkillProfile, pick_candidate};
+    use rand::SeedableRng;
+
+    let lines = vec![
+        RootLine { mv: 1, score: 10, pv: vec![1] },
+        RootLine { mv: 2, score: -WIN_SCORE, pv: vec![2] },
+    ];
+    let profile = SkillProfile {
+        max_depth: 1,
+        eval_noise: 0,
+        blunder_chance: 1.0,
+        candidates: 2,
+    };
+    let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+    for _ in 0..20 {
+        assert_eq!(pick_candidate(&lines, &profile, &mut rng), 1);
+    }
+}
+
+/// The size-5 board after a centre opening, with the second player to decide.
+fn opened_for_pie() -> GameY {
+    let mut game = GameY::new(5);
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: Coordinates::new(2, 1, 1),
+    })
+    .unwrap();
+    game
+}
+
+#[test]
+fn test_weakened_pie_searches_are_reproducible_with_seed() {
+    use super::config::MIN_SKILL;
+    use super::pie::{choose_pie_opening_with_config, decide_pie_with_config};
+
+    let config = MinimaxConfig::new(0, 0)
+        .with_skill(MIN_SKILL)
+        .with_seed(7)
+        .with_node_limit(2_000);
+    let opened = opened_for_pie();
+    let decision = decide_pie_with_config(&opened, &config);
+    let opening = choose_pie_opening_with_config(&GameY::new(5), &config);
+    for _ in 0..3 {
+        assert_eq!(decide_pie_with_config(&opened, &config), decision);
+        assert_eq!(choose_pie_opening_with_config(&GameY::new(5), &config), opening);
+    }
+}
+
+#[test]
+fn test_skill_weakens_pie_decisions() {
+    use super::config::{MAX_SKILL, MIN_SKILL};
+    use super::pie::decide_pie_with_config;
+    use crate::PieChoice;
+
+    let opened = opened_for_pie();
+    let decisions = |skill: u8| -> Vec<PieChoice> {
+        (0..20)
+            .map(|seed| {
+                let config = MinimaxConfig::new(0, 0)
+                    .with_skill(skill)
+                    .with_seed(seed)
+                    .with_node_limit(2_000);
+                decide_pie_with_config(&opened, &config)
+            })
+            .collect()
+    };
+
+    // At full strength the seed changes nothing; at the weakest level the
+    // bot sometimes makes the worse choice on purpose.
+    let full = decisions(MAX_SKILL);
+    assert!(full.iter().all(|&choice| choice == full[0]));
+    let weakest = decisions(MIN_SKILL);
+    assert!(weakest.iter().any(|&choice| choice != weakest[0]));
+}
+
+// ============================================================================
+// Node-limited search
+// ============================================================================
+
+#[test]
+fn test_node_limit_stops_search() {
+    let mut state = create_empty_state(5);
+    let mut ctx = SearchContext::new(state.board.len(), Duration::from_secs(60))
+        .with_node_limit(Some(200));
+
+    let (mv, _) = super::search::run_iterative_deepening(&mut state, 0, &mut ctx);
+
+    assert!(state.available_cells().any(|c| c == mv));
+    assert!(ctx.nodes <= 200, "visited {} nodes", ctx.nodes);
+    assert!(ctx.out_of_budget());
+}
+
+#[test]
+fn test_node_limited_search_is_reproducible() {
+    use super::config::MinimaxConfig;
+    use super::search::choose_move_with_config;
+
+    let game = GameY::new(6);
+    let config = MinimaxConfig::new(0, 0).with_node_limit(2_000);
+    let first = choose_move_with_config(&game, &config);
+    for _ in 0..3 {
+        assert_eq!(choose_move_with_config(&game, &config), first);
+    }
+}
+
+#[test]
+fn test_solver_and_search_share_the_node_limit() {
+    use super::search::choose_move_with_progress;
+    use super::solver::{SolveOutcome, solve};
+
+    let game = GameY::new(6);
+    let config = MinimaxConfig::new(0, 0)
+        .with_node_limit(4_000)
+        .with_solver_max_empty(21);
+    // The solver gets a quarter of the nodes and cannot prove the opening.
+    let solved = solve(&game, 1_000);
+    assert_eq!(solved.outcome, SolveOutcome::Unknown);
+
+    let mut searched = 0;
+    choose_move_with_progress(&game, &config, &mut |progress| searched = progress.nodes);
+    assert!(searched > 0);
+    assert!(
+        solved.nodes + searched <= 4_000,
+        "solver {} + search {} nodes",
+        solved.nodes,
+        searched
+    );
+}
+
+#[test]
+fn test_seeded_weak_search_is_reproducible() {
+    use super::config::MinimaxConfig;
+    use super::search::choose_move_with_config;
+
+    let game = GameY::new(6);
+    let config = MinimaxConfig::new(0, 0)
+        .with_skill(5)
+        .with_seed(99)
+        .with_node_limit(2_000);
+    let first = choose_move_with_config(&game, &config);
+    for _ in 0..3 {
+        assert_eq!(choose_move_with_config(&game, &config), first);
+    }
+}
+
+// ============================================================================
+// Proof-number solver
+// ============================================================================
+
+#[test]
+fn test_solver_proves_first_player_win_on_small_boards() {
+    use super::solver::{SolveOutcome, solve};
+
+    for size in 1..=4 {
+        let result = solve(&GameY::new(size), 1_000_000);
+        assert_eq!(result.outcome, SolveOutcome::Win, "size {size}");
+        assert!(result.proof_move.is_some_and(|c| c.is_valid(size)));
+    }
+}
+
+#[test]
+fn test_solver_proof_move_keeps_the_win() {
+    use super::solver::{SolveOutcome, solve};
+    use crate::Movement;
+
+    let mut game = GameY::new(4);
+    let result = solve(&game, 1_000_000);
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: result.proof_move.unwrap(),
+    })
+    .unwrap();
+
+    // After the proof move every reply of the opponent loses.
+    assert_eq!(solve(&game, 1_000_000).outcome, SolveOutcome::Loss);
+}
+
+#[test]
+fn test_solver_finds_immediate_win() {
+    use super::solver::{SolveOutcome, solve};
+
+    // Blue to move; the bottom-middle cell joins both blue stones to side A.
+    let yen = crate::YEN::new(3, 0, vec!['B', 'R'], "./BB/R.R".to_string());
+    let game = GameY::try_from(yen).unwrap();
+    let result = solve(&game, 10_000);
+    assert_eq!(result.outcome, SolveOutcome::Win);
+    assert_eq!(result.proof_move, Some(crate::Coordinates::new(0, 1, 1)));
+}
+
+#[test]
+fn test_solver_reports_unknown_when_budget_exhausted() {
+    use super::solver::{SolveOutcome, solve};
+
+    let result = solve(&GameY::new(7), 50);
+    assert_eq!(result.outcome, SolveOutcome::Unknown);
+    assert!(result.proof_move.is_none());
+    assert!(result.nodes <= 50);
+}
+
+#[test]
+fn test_solver_agrees_with_exhaustive_search() {
+    use super::solver::{SolveOutcome, solve_state};
+
+    let mut state = create_empty_state(4);
+    let mut ctx = make_search_context(10, state.board.len());
+    let (_, score) = search_best_move(&mut state, 10, -INFINITY, INFINITY, &mut ctx);
+
+    let (outcome, _, _) = solve_state(&mut state, 1_000_000, None, &StopToken::new());
+    let expected = if score > 0 { SolveOutcome::Win } else { SolveOutcome::Loss };
+    assert_eq!(outcome, expected);
+}
+
+#[test]
+fn test_full_strength_bot_plays_proof_move_when_solvable() {
+    use super::config::MinimaxConfig;
+    use super::search::choose_move_with_config;
+    use super::solver::{SolveOutcome, solve};
+    use crate::Movement;
+
+    let mut game = GameY::new(4);
+    let config = MinimaxConfig::new(0, 0).with_node_limit(1_000_000);
+    let coords = choose_move_with_config(&game, &config).unwrap();
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords,
+    })
+    .unwrap();
+
+    assert_eq!(solve(&game, 1_000_000).outcome, SolveOutcome::Loss);
+}
+
+// ============================================================================
+// Resistance evaluation
+// ============================================================================
+
+#[test]
+fn test_resistance_is_symmetric_on_empty_board() {
+    use super::resistance::{resistance_score, total_resistance};
+
+    let mut state = create_empty_state(5);
+    let (bot, human) = (state.bot_id, state.human_id);
+    let own = total_resistance(&mut state, bot).unwrap();
+    let opp = total_resistance(&mut state, human).unwrap();
+    assert!((own - opp).abs() < 1e-9);
+    assert_eq!(resistance_score(&mut state, bot), 0);
+}
+
+// A size-1 board is one unit resistor touching all three sides: half a cell
+// to each side gives 0.5 + 0.5 + 0.5.
+#[test]
+fn test_resistance_of_single_cell_board() {
+    use super::resistance::total_resistance;
+
+    let mut state = create_empty_state(1);
+    let bot = state.bot_id;
+    let total = total_resistance(&mut state, bot).unwrap();
+    assert!((total - 1.5).abs() < 1e-9, "got {total}");
+}
+
+#[test]
+fn test_resistance_drops_with_own_stone() {
+    use super::resistance::{resistance_score, total_resistance};
+
+    let mut state = create_empty_state(5);
+    let (bot, human) = (state.bot_id, state.human_id);
+    let baseline = total_resistance(&mut state, bot).unwrap();
+    let centre = crate::Coordinates::new(2, 1, 1).to_index(5) as usize;
+    state.make_move(centre, bot);
+
+    assert!(total_resistance(&mut state, bot).unwrap() < baseline);
+    assert!(resistance_score(&mut state, bot) > 0);
+    assert!(resistance_score(&mut state, human) < 0);
+}
+
+#[test]
+fn test_resistance_prefers_centre_over_corner() {
+    use super::resistance::resistance_score;
+
+    let mut centre_state = create_empty_state(5);
+    let centre = crate::Coordinates::new(2, 1, 1).to_index(5) as usize;
+    centre_state.make_move(centre, centre_state.bot_id);
+
+    let mut corner_state = create_empty_state(5);
+    let corner = crate::Coordinates::new(4, 0, 0).to_index(5) as usize;
+    corner_state.make_move(corner, corner_state.bot_id);
+
+    let bot = centre_state.bot_id;
+    assert!(resistance_score(&mut centre_state, bot) > resistance_score(&mut corner_state, bot));
+}
+
+#[test]
+fn test_resistance_cut_off_side_counts_as_lost() {
+    use super::resistance::{resistance_score, total_resistance};
+
+    // Human owns the whole bottom row (side A): the bot can never reach it.
+    let mut state = create_empty_state(3);
+    for cell in [3, 4, 5] {
+        state.make_move(cell, state.human_id);
+    }
+    let bot = state.bot_id;
+    assert!(total_resistance(&mut state, bot).is_none());
+    assert_eq!(resistance_score(&mut state, bot), LOSE_SCORE / 2);
+}
+
+#[test]
+fn test_resistance_evaluator_matches_resistance_score() {
+    use super::eval::{Evaluator, ResistanceEvaluator};
+    use super::resistance::resistance_score;
+
+    let mut state = create_empty_state(4);
+    let cell = get_valid_cells(&state, 1)[0];
+    state.make_move(cell, state.bot_id);
+    let bot = state.bot_id;
+
+    assert_eq!(
+        ResistanceEvaluator.evaluate(&mut state, bot),
+        resistance_score(&mut state, bot)
+    );
+}
+
+// The buffers kept on the state must not leak results between positions.
+#[test]
+fn test_resistance_reuses_buffers_across_positions() {
+    use super::resistance::total_resistance;
+
+    let mut state = create_empty_state(6);
+    let bot = state.bot_id;
+    total_resistance(&mut state, bot).unwrap();
+    let cells = get_valid_cells(&state, 3);
+    state.make_move(cells[0], bot);
+    state.make_move(cells[2], state.human_id);
+    let reused = total_resistance(&mut state, bot).unwrap();
+
+    let mut fresh = create_empty_state(6);
+    fresh.make_move(cells[0], bot);
+    fresh.make_move(cells[2], fresh.human_id);
+    let expected = total_resistance(&mut fresh, bot).unwrap();
+    assert!((reused - expected).abs() < 1e-9, "{reused} != {expected}");
+}
+
+#[test]
+fn test_resistance_bot_takes_immediate_win() {
+    use super::config::MinimaxConfig;
+    use super::eval::EvaluatorKind;
+    use super::search::choose_move_with_config;
+
+    let game = GameY::try_from(crate::YEN::new(3, 0, vec!['B', 'R'], "./BB/R.R".to_string())).unwrap();
+    let config = MinimaxConfig::new(0, 0)
+        .with_node_limit(500)
+        .with_evaluator(EvaluatorKind::Resistance);
+    assert_eq!(
+        choose_move_with_config(&game, &config),
+        Some(crate::Coordinates::new(0, 1, 1))
+    );
+}
+
+// ============================================================================
+// Pluggable evaluators
+// ============================================================================
+
+#[test]
+fn test_default_heuristic_evaluator_matches_evaluate_state() {
+    use super::eval::{Evaluator, HeuristicEvaluator};
+
+    let mut state = create_empty_state(5);
+    for (i, cell) in get_valid_cells(&state, 4).into_iter().enumerate() {
+        let player = if i % 2 == 0 { state.bot_id } else { state.human_id };
+        state.make_move(cell, player);
+    }
+    let bot = state.bot_id;
+
+    assert_eq!(
+        HeuristicEvaluator::default().evaluate(&mut state, bot),
+        evaluate_state(&mut state, bot)
+    );
+}
+
+#[test]
+fn test_heuristic_weights_change_the_score() {
+    use super::eval::{EvalWeights, Evaluator, HeuristicEvaluator};
+
+    let mut state = create_empty_state(5);
+    let centre = crate::Coordinates::new(2, 1, 1).to_index(5) as usize;
+    state.make_move(centre, state.bot_id);
+    let bot = state.bot_id;
+
+    let default_score = HeuristicEvaluator::default().evaluate(&mut state, bot);
+    let doubled = EvalWeights {
+        connection_cost: 300,
+        ..EvalWeights::default()
+    };
+    let doubled_score = HeuristicEvaluator::new(doubled).evaluate(&mut state, bot);
+    assert!(doubled_score > default_score);
+
+    let flat = EvalWeights {
+        connection_cost: 0,
+        adjacency: 0,
+        well_connected: 0,
+        edge: 0,
+        centre: 0,
+    };
+    assert_eq!(HeuristicEvaluator::new(flat).evaluate(&mut state, bot), 0);
+}
+
+#[test]
+fn test_eval_weights_load_from_file() {
+    use super::eval::EvalWeights;
+    use std::io::Write;
+
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    write!(file, r#"{{"adjacency": 30, "centre": 0}}"#).unwrap();
+    let weights = EvalWeights::load(file.path()).unwrap();
+
+    assert_eq!(weights.adjacency, 30);
+    assert_eq!(weights.centre, 0);
+    assert_eq!(weights.connection_cost, EvalWeights::default().connection_cost);
+}
+
+#[test]
+fn test_eval_weights_reject_invalid_json() {
+    use super::eval::EvalWeights;
+
+    assert!(EvalWeights::from_json(r#"{"adjacency": "many"}"#).is_err());
+}
+
+/// Prefers bot stones whose index is close to the first cell of the bottom
+/// row, whatever the position.
+#[derive(Clone, Copy)]
+struct CornerSeeker;
+
+impl super::eval::Evaluator for CornerSeeker {
+    fn evaluate(&self, state: &mut MinimaxState, player: u8) -> i32 {
+        let corner = (state.board_size() * (state.board_size() - 1) / 2) as usize;
+        let sign = if player == state.bot_id() { 1 } else { -1 };
+        sign * state
+            .cells()
+            .iter()
+            .enumerate()
+            .filter(|&(_, &owner)| owner == state.bot_id())
+            .map(|(idx, _)| -(idx.abs_diff(corner) as i32))
+            .sum::<i32>()
+    }
+}
+
+#[test]
+fn test_search_runs_with_custom_evaluator() {
+    use super::config::MinimaxConfig;
+    use super::search::choose_move_with_evaluator;
+
+    let game = GameY::new(5);
+    let config = MinimaxConfig::new(0, 0)
+        .with_node_limit(2_000)
+        .with_solver_max_empty(0);
+    let coords = choose_move_with_evaluator(&game, &config, CornerSeeker).unwrap();
+
+    assert_eq!(coords.to_index(5), 10, "the search must follow the custom evaluator");
+}
+
+// ============================================================================
+// Inferior cells
+// ============================================================================
+
+fn cell_index(size: u32, x: u32, y: u32, z: u32) -> usize {
+    crate::Coordinates::new(x, y, z).to_index(size) as usize
+}
+
+/// Size-5 board with the centre cell (2,1,1) surrounded by `owner`, except
+/// for the neighbours listed in `skip`.
+fn surrounded_centre(owner_is_bot: bool, skip: &[usize]) -> (MinimaxState, usize) {
+    let mut state = create_empty_state(5);
+    let owner = if owner_is_bot { state.bot_id } else { state.human_id };
+    let centre = cell_index(5, 2, 1, 1);
+    let ring: Vec<usize> = state.neighbors(centre).to_vec();
+    for (i, nb) in ring.into_iter().enumerate() {
+        if !skip.contains(&i) {
+            state.make_move(nb, owner);
+        }
+    }
+    (state, centre)
+}
+
+#[test]
+fn test_dead_cell_is_inferior_for_both_players() {
+    use super::inferior::is_inferior;
+
+    let (state, centre) = surrounded_centre(true, &[]);
+    assert!(is_inferior(&state, centre, state.bot_id));
+    assert!(is_inferior(&state, centre, state.human_id));
+}
+
+#[test]
+fn test_dominated_cell_is_inferior_only_for_owner() {
+    use super::inferior::is_inferior;
+
+    let (state, centre) = surrounded_centre(true, &[0]);
+    assert!(is_inferior(&state, centre, state.bot_id));
+    assert!(!is_inferior(&state, centre, state.human_id));
+}
+
+#[test]
+fn test_cell_with_two_empty_neighbours_is_not_inferior() {
+    use super::inferior::is_inferior;
+
+    let (state, centre) = surrounded_centre(true, &[0, 3]);
+    assert!(!is_inferior(&state, centre, state.bot_id));
+    assert!(!is_inferior(&state, centre, state.human_id));
+}
+
+#[test]
+fn test_captured_pair_is_inferior_for_intruder() {
+    use super::inferior::is_inferior;
+
+    // Corner (4,0,0) and its neighbour (3,1,0): the pair's other neighbours
+    // are all taken by the bot.
+    let mut state = create_empty_state(5);
+    let pair = [cell_index(5, 4, 0, 0), cell_index(5, 3, 1, 0)];
+    let mut wall: Vec<usize> = pair
+        .iter()
+        .flat_map(|&c| state.neighbors(c).to_vec())
+        .filter(|nb| !pair.contains(nb))
+        .collect();
+    wall.sort_unstable();
+    wall.dedup();
+    for nb in wall {
+        state.make_move(nb, state.bot_id);
+    }
+
+    for cell in pair {
+        assert!(is_inferior(&state, cell, state.human_id));
+    }
+}
+
+#[test]
+fn test_order_moves_drops_inferior_cells() {
+    use super::inferior::is_inferior;
+
+    let (state, centre) = surrounded_centre(true, &[]);
+    let ctx = SearchContext::new(state.board.len(), Duration::from_secs(60));
+    let mut moves: SmallVec<[usize; 128]> = state.available_cells().collect();
+    let before = moves.len();
+
+    order_moves(
+        &mut moves,
+        &state,
+        state.human_id,
+        None,
+        [None, None],
+        &ctx,
+        VC_ORDER_MIN_DEPTH,
+    );
+
+    assert!(moves.len() < before);
+    assert!(!moves.contains(&centre));
+    assert!(moves.iter().all(|&m| !is_inferior(&state, m, state.human_id)));
+}
+
+#[test]
+fn test_order_moves_keeps_inferior_cells_when_nothing_else_is_left() {
+    let (state, centre) = surrounded_centre(true, &[]);
+    let ctx = SearchContext::new(state.board.len(), Duration::from_secs(60));
+    let mut moves: SmallVec<[usize; 128]> = SmallVec::from_slice(&[centre]);
+
+    order_moves(
+        &mut moves,
+        &state,
+        state.bot_id,
+        None,
+        [None, None],
+        &ctx,
+        VC_ORDER_MIN_DEPTH,
+    );
+
+    assert_eq!(moves.as_slice(), &[centre]);
+}
+
+#[test]
+fn test_pruned_search_matches_unpruned_on_solved_positions() {
+    use super::inferior::is_inferior;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(34);
+    let mut positions_with_inferior_cells = 0;
+    let mut solved = 0;
+    let (mut pruned_nodes, mut full_nodes) = (0, 0);
+
+    while solved < 16 {
+        // A random size-5 position with 8 empty cells and no winner yet.
+        let mut state = create_empty_state(5);
+        let mut cells: Vec<usize> = state.available_cells().collect();
+        cells.shuffle(&mut rng);
+        let mut finished = false;
+        for (i, &cell) in cells.iter().take(7).enumerate() {
+            let player = if i % 2 == 0 { state.bot_id } else { state.human_id };
+            state.make_move(cell, player);
+            finished |= state.check_win(player);
+        }
+        if finished {
+            continue;
+        }
+        solved += 1;
+
+        let empty: Vec<usize> = state.available_cells().collect();
+        if empty
+            .iter()
+            .any(|&c| is_inferior(&state, c, state.bot_id) || is_inferior(&state, c, state.human_id))
+        {
+            positions_with_inferior_cells += 1;
+        }
+
+        let depth = empty.len() as u8;
+        let mut pruned_ctx = make_search_context(depth, state.board.len());
+        let mut full_ctx = make_search_context(depth, state.board.len()).with_inferior_pruning(false);
+        let (_, pruned) = search_best_move(&mut state, depth, -INFINITY, INFINITY, &mut pruned_ctx);
+        let (_, full) = search_best_move(&mut state, depth, -INFINITY, INFINITY, &mut full_ctx);
+
+        assert_eq!(pruned.abs(), WIN_SCORE, "the search must be exhaustive");
+        assert_eq!(pruned, full);
+        pruned_nodes += pruned_ctx.nodes;
+        full_nodes += full_ctx.nodes;
+    }
+
+    assert!(positions_with_inferior_cells > 0);
+    assert!(pruned_nodes < full_nodes);
+}
+
+// ============================================================================
+// Search sessions
+// ============================================================================
+
+// A one-bucket table makes every key collide in the same two slots.
+#[test]
+fn test_tt_new_search_lets_old_entries_be_replaced() {
+    let mut tt = TranspositionTable::with_size_mb(0);
+    tt.store(1, 8, 10, TtFlag::Exact, Some(3));
+    tt.store(2, 2, 20, TtFlag::Exact, Some(4));
+    assert!(tt.probe(1, 8).is_some(), "the deeper entry keeps its slot");
+
+    tt.store(3, 2, 30, TtFlag::Exact, Some(5));
+    assert!(tt.probe(1, 8).is_some(), "a shallow entry of the same search must not evict it");
+
+    tt.new_search();
+    tt.store(4, 2, 40, TtFlag::Exact, Some(6));
+    assert!(tt.probe(1, 0).is_none(), "entries of an earlier search are replaced first");
+    assert_eq!(tt.best_move(4), Some(6));
+}
+
+fn session_config() -> MinimaxConfig {
+    MinimaxConfig::new(20, 100).with_tt_size_mb(1)
+}
+
+#[test]
+fn test_session_keeps_tables_between_moves() {
+    let mut game = GameY::new(5);
+    let mut session = SearchSession::new();
+
+    let first = choose_move_in_session(&game, &session_config(), &mut session).unwrap();
+    assert_eq!(session.searches(), 1);
+
+    // The stored table still knows the best move of the searched position.
+    let state = MinimaxState::new(&game, PlayerId::new(0));
+    let (tt, history) = session.take_tables(state.board.len(), 1);
+    assert_eq!(tt.best_move(state.hash), Some(first.to_index(5) as usize));
+    session.store_tables(state.board.len(), 1, tt, history);
+
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: first,
+    })
+    .unwrap();
+    assert!(choose_move_in_session(&game, &session_config(), &mut session).is_some());
+    assert_eq!(session.searches(), 3);
+}
+
+#[test]
+fn test_session_tables_are_rebuilt_for_another_board() {
+    let mut session = SearchSession::new();
+    choose_move_in_session(&GameY::new(5), &session_config(), &mut session).unwrap();
+
+    let mut game = GameY::new(6);
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: Coordinates::new(3, 1, 1),
+    })
+    .unwrap();
+    let state = MinimaxState::new(&game, PlayerId::new(1));
+    let (tt, _) = session.take_tables(state.board.len(), 1);
+    assert_eq!(tt.best_move(state.hash), None);
+    assert!(!session.has_tables());
+}
+
+#[test]
+fn test_session_caps_the_table_size() {
+    let game = GameY::new(5);
+    let mut session = SearchSession::new().with_tt_limit_mb(1);
+    assert_eq!(session.tt_size_mb(48), 1);
+
+    let config = MinimaxConfig::new(10, 20).with_tt_size_mb(48);
+    let first = choose_move_in_session(&game, &config, &mut session).unwrap();
+    // The tables were stored at the capped size, so they are reused.
+    let state = MinimaxState::new(&game, PlayerId::new(0));
+    let (tt, _) = session.take_tables(state.board.len(), 1);
+    assert_eq!(tt.best_move(state.hash), Some(first.to_index(5) as usize));
+}
+
+#[test]
+fn test_session_keeps_the_last_iteration_of_its_search() {
+    let mut session = SearchSession::new();
+    let game = GameY::new(5);
+    let coords = choose_move_in_session(&game, &session_config(), &mut session).unwrap();
+
+    let last = session.take_last_progress().expect("the search completes a depth");
+    assert_eq!(last.best_move, coords);
+    assert_eq!(last.pv.first(), Some(&coords));
+    assert_eq!(session.take_last_progress(), None);
+}
+
+// Node-limited searches must stay reproducible, so they never see entries
+// from earlier moves.
+#[test]
+fn test_node_limited_search_does_not_use_session() {
+    let mut session = SearchSession::new();
+    let config = session_config().with_node_limit(500);
+    let game = GameY::new(5);
+
+    let with_session = choose_move_in_session(&game, &config, &mut session);
+    assert_eq!(with_session, choose_move_with_config(&game, &config));
+    assert_eq!(session.searches(), 0);
+}
+
+// ============================================================================
+// Pondering
+// ============================================================================
+
+/// Plays the bot's first move on a size-5 board with `session` and returns
+/// the game with the opponent to move.
+fn game_after_bot_move(session: &mut SearchSession) -> GameY {
+    let mut game = GameY::new(5);
+    let mv = choose_move_in_session(&game, &session_config(), session).unwrap();
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: mv,
+    })
+    .unwrap();
+    game
+}
+
+fn play(game: &GameY, player: u32, coords: Coordinates) -> GameY {
+    let mut next = game.clone();
+    next.add_move(Movement::Placement {
+        player: PlayerId::new(player),
+        coords,
+    })
+    .unwrap();
+    next
+}
+
+#[test]
+fn test_pondering_predicts_available_reply() {
+    let mut session = SearchSession::new();
+    let game = game_after_bot_move(&mut session);
+
+    let predicted = session.start_pondering(&game, &session_config()).unwrap();
+    assert!(session.is_pondering());
+    assert_eq!(session.predicted_move(), Some(predicted));
+    assert!(game.available_cells().contains(&predicted.to_index(5)));
+
+    std::thread::sleep(Duration::from_millis(20));
+    session.stop_pondering();
+    assert!(!session.is_pondering());
+    assert!(session.has_tables(), "stopping keeps the pondered tables");
+    assert_eq!(session.searches(), 2);
+}
+
+#[test]
+fn test_ponder_hit_is_recognised() {
+    let mut session = SearchSession::new();
+    let game = game_after_bot_move(&mut session);
+    let predicted = session.start_pondering(&game, &session_config()).unwrap();
+
+    let hit = play(&game, 1, predicted);
+    assert!(session.finish_pondering(&hit).is_some());
+    assert!(!session.is_pondering());
+
+    session.start_pondering(&game, &session_config()).unwrap();
+    let other = game
+        .available_cells()
+        .iter()
+        .map(|&idx| Coordinates::from_index(idx, 5))
+        .find(|&coords| coords != predicted)
+        .unwrap();
+    assert!(session.finish_pondering(&play(&game, 1, other)).is_none());
+}
+
+#[test]
+fn test_choose_after_ponder_hit_returns_move() {
+    let mut session = SearchSession::new();
+    let game = game_after_bot_move(&mut session);
+    let predicted = session.start_pondering(&game, &session_config()).unwrap();
+    std::thread::sleep(Duration::from_millis(20));
+
+    let hit = play(&game, 1, predicted);
+    let reply = choose_move_in_session(&hit, &session_config(), &mut session).unwrap();
+    assert!(hit.available_cells().contains(&reply.to_index(5)));
+    assert!(!session.is_pondering());
+}
+
+#[test]
+fn test_discard_pondering_drops_tables() {
+    let mut session = SearchSession::new();
+    let game = game_after_bot_move(&mut session);
+    session.start_pondering(&game, &session_config()).unwrap();
+
+    session.discard_pondering();
+    assert!(!session.is_pondering());
+    assert!(!session.has_tables());
+}
+
+#[test]
+fn test_no_pondering_for_reproducible_or_weakened_search() {
+    let mut session = SearchSession::new();
+    let game = game_after_bot_move(&mut session);
+
+    assert_eq!(session.start_pondering(&game, &session_config().with_node_limit(100)), None);
+    assert_eq!(session.start_pondering(&game, &session_config().with_skill(5)), None);
+    assert!(!session.is_pondering());
+}
+
+/// Plays the bot's first move on a size-9 board, which no ponder search runs
+/// out of work on, and returns the game with the opponent to move.
+fn game_after_bot_move_on_9(session: &mut SearchSession) -> GameY {
+    let mut game = GameY::new(9);
+    let config = MinimaxConfig::new(10, 10).with_tt_size_mb(1);
+    let mv = choose_move_in_session(&game, &config, session).unwrap();
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: mv,
+    })
+    .unwrap();
+    game
+}
+
+#[test]
+fn test_ponder_stops_with_the_enclosing_token() {
+    let mut session = SearchSession::new();
+    let config = MinimaxConfig::new(60_000, 60_000).with_tt_size_mb(1);
+    let game = game_after_bot_move_on_9(&mut session);
+    let (slot, ended) = std::sync::mpsc::channel::<()>();
+    session.set_ponder_slot(slot);
+
+    let server = StopToken::new();
+    server.scope(|| session.start_pondering(&game, &config)).unwrap();
+    server.stop();
+    // The slot is dropped once the ponder thread ends.
+    assert_eq!(
+        ended.recv_timeout(Duration::from_secs(5)),
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected)
+    );
+}
+
+#[test]
+fn test_ponder_ends_after_a_few_time_budgets() {
+    let mut session = SearchSession::new();
+    let config = MinimaxConfig::new(10, 10).with_tt_size_mb(1);
+    let game = game_after_bot_move_on_9(&mut session);
+    let (slot, ended) = std::sync::mpsc::channel::<()>();
+    session.set_ponder_slot(slot);
+
+    session.start_pondering(&game, &config).unwrap();
+    assert_eq!(
+        ended.recv_timeout(Duration::from_secs(5)),
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected)
+    );
+}
+
+// ============================================================================
+// Cancellation
+// ============================================================================
+
+#[test]
+fn test_stopped_token_ends_search_at_once() {
+    let mut state = create_empty_state(6);
+    let stop = StopToken::new();
+    stop.stop();
+
+    let start = Instant::now();
+    let evaluator = HeuristicEvaluator::default();
+    let (best_move, _) = iterative_deepening_search(&mut state, 60_000, 60_000, None, &stop, evaluator);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(state.available_mask.contains(best_move));
+}
+
+#[test]
+fn test_stop_from_another_thread_ends_search() {
+    let mut state = create_empty_state(7);
+    let stop = StopToken::new();
+    let stopper = {
+        let stop = stop.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            stop.stop();
+        })
+    };
+
+    let start = Instant::now();
+    let evaluator = HeuristicEvaluator::default();
+    iterative_deepening_search(&mut state, 60_000, 60_000, None, &stop, evaluator);
+    assert!(start.elapsed() < Duration::from_secs(10));
+    stopper.join().unwrap();
+}
+
+#[test]
+fn test_stopped_solver_reports_unknown() {
+    use super::solver::{SolveOutcome, solve_state};
+
+    let mut state = create_empty_state(4);
+    let stop = StopToken::new();
+    stop.stop();
+    let (outcome, proof_move, _) = solve_state(&mut state, 1_000_000, None, &stop);
+    assert_eq!(outcome, SolveOutcome::Unknown);
+    assert_eq!(proof_move, None);
+}
+
+// The public entry points keep their signatures and obey the scoped token.
+#[test]
+fn test_pie_decision_obeys_scoped_token() {
+    let mut game = GameY::new(6);
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: Coordinates::new(3, 1, 1),
+    })
+    .unwrap();
+    let stop = StopToken::new();
+    stop.stop();
+
+    let start = Instant::now();
+    stop.scope(|| crate::decide_pie_with_minimax(&game, 60_000));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+// ============================================================================
+// Progress reporting tests
+// ============================================================================
+
+#[test]
+fn test_progress_is_reported_for_every_completed_depth() {
+    let game = GameY::new(7);
+    let config = MinimaxConfig::new(0, 60_000).with_node_limit(5_000);
+    let mut reports = Vec::new();
+    let chosen = crate::choose_move_with_progress(&game, &config, &mut |progress| {
+        reports.push(progress.clone())
+    });
+
+    assert!(!reports.is_empty());
+    for (i, progress) in reports.iter().enumerate() {
+        assert_eq!(progress.depth, i as u32 + 1);
+        assert_eq!(progress.pv.first(), Some(&progress.best_move));
+    }
+    assert!(reports.windows(2).all(|pair| pair[0].nodes <= pair[1].nodes));
+    assert_eq!(chosen, Some(reports.last().unwrap().best_move));
+}
+
+// ============================================================================
+// Parallel search
+// ============================================================================
+
+#[test]
+fn test_root_moves_restrict_the_root_search() {
+    use super::search::root_moves;
+
+    let mut state = create_empty_state(5);
+    let share: Vec<usize> = root_moves(&mut state, true).into_iter().skip(1).step_by(3).collect();
+    let mut ctx = make_search_context(2, state.board.len()).with_root_moves(share.clone());
+
+    let (best_move, _) = search_best_move(&mut state, 2, -INFINITY, INFINITY, &mut ctx);
+    assert!(share.contains(&best_move));
+}
+
+// Each thread is node-limited and keeps its own tables, so a parallel search
+// with a node budget is as reproducible as a single-threaded one.
+#[test]
+fn test_parallel_search_is_reproducible_when_node_limited() {
+    let mut game = GameY::new(7);
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: Coordinates::new(3, 2, 1),
+    })
+    .unwrap();
+    let config = MinimaxConfig::new(0, 60_000)
+        .with_node_limit(20_000)
+        .with_threads(3);
+
+    let first = choose_move_with_config(&game, &config).unwrap();
+    assert!(game.available_cells().contains(&first.to_index(7)));
+    for _ in 0..2 {
+        assert_eq!(choose_move_with_config(&game, &config), Some(first));
+    }
+}
+
+#[test]
+fn test_parallel_search_reports_the_depths_all_threads_completed() {
+    let game = GameY::new(7);
+    let config = MinimaxConfig::new(0, 60_000)
+        .with_node_limit(20_000)
+        .with_threads(4);
+    let mut reports = Vec::new();
+    let (chosen, stats) = crate::collect_search_stats(|| {
+        crate::choose_move_with_progress(&game, &config, &mut |progress| {
+            reports.push(progress.clone())
+        })
+    });
+
+    assert!(!reports.is_empty());
+    for (i, progress) in reports.iter().enumerate() {
+        assert_eq!(progress.depth, i as u32 + 1);
+    }
+    assert_eq!(chosen, Some(reports.last().unwrap().best_move));
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].depth, reports.len() as u32);
+    assert!(stats[0].nodes <= 20_000 + 4);
+}
+
+// Threads whose share of the root loses must not outvote the thread that
+// found a win.
+#[test]
+fn test_parallel_search_plays_a_winning_move() {
+    use super::solver::{SolveOutcome, solve};
+
+    let mut game = GameY::new(3);
+    let config = MinimaxConfig::new(0, 60_000)
+        .with_node_limit(50_000)
+        .with_solver_max_empty(0)
+        .with_threads(3);
+    let coords = choose_move_with_config(&game, &config).unwrap();
+    game.add_move(Movement::Placement {
+        player: PlayerId::new(0),
+        coords,
+    })
+    .unwrap();
+
+    assert_eq!(solve(&game, 1_000_000).outcome, SolveOutcome::Loss);
+}
@@ -0,0 +1,458 @@
+//! Virtual connections: bridges and edge templates.
+//!
+//! Two stones are *virtually* connected when the opponent cannot separate
+//! them even though they do not touch. Two patterns are recognised:
+//!
+//! - A **bridge**: two stones with exactly two common empty neighbours (the
+//!   carriers). If the opponent takes one carrier, the other one connects.
+//! - An **edge template**: a stone one row away from a side whose two
+//!   neighbours on that side are both empty. It reaches the side the same
+//!   way a bridge reaches its partner.
+//!
+//! [`MinimaxState::connection_cost`] treats both patterns as free links, and
+//! move ordering tries moves that answer an intrusion into one of them
+//! first. [`virtual_connections`] exposes the patterns of a [`GameY`]
+//! position, e.g. for drawing them in a UI.
+
+use super::state::MinimaxState;
+use crate::{Coordinates, GameY, PlayerId};
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use utoipa::ToSchema;
+
+const SIDE_BITS: [u8; 3] = [0b001, 0b010, 0b100];
+
+/// A board side, named after the coordinate that is zero along it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum Side {
+    /// The side where `x == 0`.
+    A,
+    /// The side where `y == 0`.
+    B,
+    /// The side where `z == 0`.
+    C,
+}
+
+impl Side {
+    fn from_bit(bit: u8) -> Self {
+        match bit {
+            0b001 => Side::A,
+            0b010 => Side::B,
+            _ => Side::C,
+        }
+    }
+}
+
+/// Two stones of the same player linked through two empty carrier cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Bridge {
+    pub stones: [Coordinates; 2],
+    pub carriers: [Coordinates; 2],
+}
+
+/// A stone linked to a side through two empty carrier cells on that side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EdgeTemplate {
+    pub stone: Coordinates,
+    pub side: Side,
+    pub carriers: [Coordinates; 2],
+}
+
+/// The intact virtual connections of one player.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct VirtualConnections {
+    pub bridges: Vec<Bridge>,
+    pub edge_templates: Vec<EdgeTemplate>,
+}
+
+/// Returns the bridges and edge templates of `player` in `game`.
+///
+/// Only intact patterns are reported: both carriers must be empty.
+///
+/// # Example
+///
+/// ```
+/// use gamey::{Coordinates, GameY, Movement, PlayerId, virtual_connections};
+///
+/// let mut game = GameY::new(5);
+/// for (player, coords) in [(0, (2, 1, 1)), (1, (4, 0, 0)), (0, (0, 2, 2))] {
+///     game.add_move(Movement::Placement {
+///         player: PlayerId::new(player),
+///         coords: Coordinates::new(coords.0, coords.1, coords.2),
+///     })
+///     .unwrap();
+/// }
+///
+/// let vc = virtual_connections(&game, PlayerId::new(0));
+/// assert_eq!(vc.bridges.len(), 1);
+/// ```
+pub fn virtual_connections(game: &GameY, player: PlayerId) -> VirtualConnections {
+    let state = MinimaxState::new(game, player);
+    let size = game.board_size();
+    let coords = |idx: usize| Coordinates::from_index(idx as u32, size);
+
+    let bridges = state
+        .bridges(state.bot_id)
+        .into_iter()
+        .map(|(a, b, carriers)| Bridge {
+            stones: [coords(a), coords(b)],
+            carriers: carriers.map(coords),
+        })
+        .collect();
+    let edge_templates = state
+        .edge_templates(state.bot_id)
+        .into_iter()
+        .map(|(stone, side, carriers)| EdgeTemplate {
+            stone: coords(stone),
+            side: Side::from_bit(side),
+            carriers: carriers.map(coords),
+        })
+        .collect();
+    VirtualConnections {
+        bridges,
+        edge_templates,
+    }
+}
+
+/// A cell at bridge distance from the cell it is stored for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct BridgeSlot {
+    pub partner: usize,
+    pub carriers: [usize; 2],
+    /// Position of the same bridge in the partner's slots.
+    pub back: u8,
+}
+
+/// A bridge that runs through the cell it is stored for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct CarriedBridge {
+    pub stones: [usize; 2],
+    /// Position of the bridge in each stone's slots.
+    pub slots: [u8; 2],
+    /// The bridge's other carrier.
+    pub other: usize,
+}
+
+/// An edge template that runs through the cell it is stored for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct CarriedTemplate {
+    pub stone: usize,
+    /// The template's other carrier.
+    pub other: usize,
+}
+
+/// Precomputes, for every cell, the cells it can form a bridge with: cells
+/// that are not adjacent to it and share exactly two neighbours with it.
+pub(super) fn build_bridge_cache(neighbors: &[Vec<usize>]) -> Vec<SmallVec<[BridgeSlot; 6]>> {
+    let mut cache: Vec<SmallVec<[BridgeSlot; 6]>> = neighbors
+        .iter()
+        .enumerate()
+        .map(|(idx, own)| {
+            let mut slots: SmallVec<[BridgeSlot; 6]> = SmallVec::new();
+            for &via in own {
+                for &partner in &neighbors[via] {
+                    if partner == idx
+                        || own.contains(&partner)
+                        || slots.iter().any(|s| s.partner == partner)
+                    {
+                        continue;
+                    }
+                    let common: SmallVec<[usize; 2]> = own
+                        .iter()
+                        .copied()
+                        .filter(|n| neighbors[partner].contains(n))
+                        .collect();
+                    if let [c1, c2] = common[..] {
+                        slots.push(BridgeSlot {
+                            partner,
+                            carriers: [c1, c2],
+                            back: 0,
+                        });
+                    }
+                }
+            }
+            slots
+        })
+        .collect();
+    for idx in 0..cache.len() {
+        for j in 0..cache[idx].len() {
+            let partner = cache[idx][j].partner;
+            let back = cache[partner].iter().position(|s| s.partner == idx);
+            cache[idx][j].back = back.expect("bridges are symmetric") as u8;
+        }
+    }
+    cache
+}
+
+/// Inverts the bridge cache: for every cell, the bridges it is a carrier of.
+pub(super) fn build_carrier_cache(
+    bridge_cache: &[SmallVec<[BridgeSlot; 6]>],
+) -> Vec<SmallVec<[CarriedBridge; 6]>> {
+    let mut carried = vec![SmallVec::new(); bridge_cache.len()];
+    for (idx, slots) in bridge_cache.iter().enumerate() {
+        for (j, slot) in slots.iter().enumerate().filter(|(_, slot)| slot.partner > idx) {
+            let [c1, c2] = slot.carriers;
+            let stones = [idx, slot.partner];
+            let slots = [j as u8, slot.back];
+            carried[c1].push(CarriedBridge { stones, slots, other: c2 });
+            carried[c2].push(CarriedBridge { stones, slots, other: c1 });
+        }
+    }
+    carried
+}
+
+/// Inverts the template cache: for every cell on a side, the edge templates
+/// it is a carrier of.
+pub(super) fn build_template_carrier_cache(
+    template_cache: &[[Option<[usize; 2]>; 3]],
+) -> Vec<SmallVec<[CarriedTemplate; 4]>> {
+    let mut carried = vec![SmallVec::new(); template_cache.len()];
+    for (stone, sides) in template_cache.iter().enumerate() {
+        for [c1, c2] in sides.iter().flatten().copied() {
+            carried[c1].push(CarriedTemplate { stone, other: c2 });
+            carried[c2].push(CarriedTemplate { stone, other: c1 });
+        }
+    }
+    carried
+}
+
+/// Precomputes, for every cell and side, the two cells on that side an edge
+/// template from the cell would use. Only cells one row away from a side
+/// have them.
+pub(super) fn build_template_cache(
+    neighbors: &[Vec<usize>],
+    edges: &[u8],
+) -> Vec<[Option<[usize; 2]>; 3]> {
+    neighbors
+        .iter()
+        .enumerate()
+        .map(|(idx, own)| {
+            SIDE_BITS.map(|side| {
+                if edges[idx] & side != 0 {
+                    return None;
+                }
+                let on_side: SmallVec<[usize; 2]> =
+                    own.iter().copied().filter(|&n| edges[n] & side != 0).collect();
+                match on_side[..] {
+                    [a, b] => Some([a, b]),
+                    _ => None,
+                }
+            })
+        })
+        .collect()
+}
+
+impl MinimaxState {
+    /// Returns the intact bridges of `player` as `(stone, stone, carriers)`,
+    /// each pair once with the lower index first.
+    pub fn bridges(&self, player: u8) -> Vec<(usize, usize, [usize; 2])> {
+        let mut out = Vec::new();
+        for (idx, &cell) in self.board.iter().enumerate() {
+            if cell != player {
+                continue;
+            }
+            for slot in self.intact_bridges(idx, player) {
+                if slot.partner > idx {
+                    out.push((idx, slot.partner, slot.carriers));
+                }
+            }
+        }
+        out
+    }
+
+    /// Returns the intact edge templates of `player` as
+    /// `(stone, side bit, carriers)`; side bits are as in
+    /// [`sides`](Self::sides).
+    pub fn edge_templates(&self, player: u8) -> Vec<(usize, u8, [usize; 2])> {
+        let mut out = Vec::new();
+        for (idx, &cell) in self.board.iter().enumerate() {
+            if cell != player {
+                continue;
+            }
+            for side in SIDE_BITS {
+                if let Some(carriers) = self.edge_template(idx, side) {
+                    out.push((idx, side, carriers));
+                }
+            }
+        }
+        out
+    }
+
+    /// Bridges from the `player` stone at `idx` to another `player` stone
+    /// with both carriers empty.
+    pub(super) fn intact_bridges(
+        &self,
+        idx: usize,
+        player: u8,
+    ) -> impl Iterator<Item = &BridgeSlot> + '_ {
+        self.bridge_cache[idx]
+            .iter()
+            .filter(move |slot| self.bridge_intact(slot, player))
+    }
+
+    /// Returns `true` if `slot`'s partner is a `player` stone and both
+    /// carriers are empty.
+    #[inline]
+    pub(super) fn bridge_intact(&self, slot: &BridgeSlot, player: u8) -> bool {
+        self.board[slot.partner] == player
+            && self.board[slot.carriers[0]] == 0
+            && self.board[slot.carriers[1]] == 0
+    }
+
+    /// Returns the two carriers if `idx` is one row away from `side` and both
+    /// of its neighbours on that side are empty.
+    pub(super) fn edge_template(&self, idx: usize, side: u8) -> Option<[usize; 2]> {
+        let carriers = self.template_cache[idx][side.trailing_zeros() as usize]?;
+        (self.board[carriers[0]] == 0 && self.board[carriers[1]] == 0).then_some(carriers)
+    }
+
+    /// Returns `true` if `player` playing the empty cell `idx` keeps an
+    /// intruded bridge or edge template connected: the opponent holds the
+    /// other carrier.
+    ///
+    /// Only looks at the patterns running through `idx`, so move ordering
+    /// can ask it for every move.
+    pub(super) fn is_repair(&self, idx: usize, player: u8) -> bool {
+        let opponent = self.opponent_of(player);
+        let board = self.board.as_slice();
+        for carried in self.carrier_cache[idx].iter() {
+            let [a, b] = carried.stones;
+            if board[carried.other] == opponent && board[a] == player && board[b] == player {
+                return true;
+            }
+        }
+        for carried in self.template_carrier_cache[idx].iter() {
+            if board[carried.other] == opponent && board[carried.stone] == player {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Movement;
+
+    fn game_with(size: u32, stones: &[(u32, (u32, u32, u32))]) -> GameY {
+        let mut game = GameY::new(size);
+        for &(player, (x, y, z)) in stones {
+            game.add_move(Movement::Placement {
+                player: PlayerId::new(player),
+                coords: Coordinates::new(x, y, z),
+            })
+            .unwrap();
+        }
+        game
+    }
+
+    fn idx(size: u32, x: u32, y: u32, z: u32) -> usize {
+        Coordinates::new(x, y, z).to_index(size) as usize
+    }
+
+    #[test]
+    fn test_bridge_cache_interior_cell_has_six_partners() {
+        let game = GameY::new(7);
+        let state = MinimaxState::new(&game, PlayerId::new(0));
+        assert_eq!(state.bridge_cache[idx(7, 2, 2, 2)].len(), 6);
+        for slot in &state.bridge_cache[idx(7, 2, 2, 2)] {
+            let [c1, c2] = slot.carriers;
+            assert!(state.neighbors(c1).contains(&slot.partner));
+            assert!(state.neighbors(c2).contains(&slot.partner));
+        }
+    }
+
+    #[test]
+    fn test_bridge_detected_and_broken_by_intrusion() {
+        let stones = [(0, (2, 1, 1)), (1, (4, 0, 0)), (0, (0, 2, 2))];
+        let vc = virtual_connections(&game_with(5, &stones), PlayerId::new(0));
+        assert_eq!(vc.bridges.len(), 1);
+        let bridge = vc.bridges[0];
+        assert!(bridge.stones.contains(&Coordinates::new(2, 1, 1)));
+        assert!(bridge.stones.contains(&Coordinates::new(0, 2, 2)));
+        assert!(bridge.carriers.contains(&Coordinates::new(1, 2, 1)));
+        assert!(bridge.carriers.contains(&Coordinates::new(1, 1, 2)));
+
+        let mut intruded = stones.to_vec();
+        intruded.push((1, (1, 2, 1)));
+        let vc = virtual_connections(&game_with(5, &intruded), PlayerId::new(0));
+        assert!(vc.bridges.is_empty());
+    }
+
+    #[test]
+    fn test_edge_template_detected() {
+        let game = game_with(7, &[(0, (1, 3, 2))]);
+        let vc = virtual_connections(&game, PlayerId::new(0));
+        assert_eq!(vc.edge_templates.len(), 1);
+        let template = vc.edge_templates[0];
+        assert_eq!(template.side, Side::A);
+        assert!(template.carriers.contains(&Coordinates::new(0, 4, 2)));
+        assert!(template.carriers.contains(&Coordinates::new(0, 3, 3)));
+
+        let intruded = game_with(7, &[(0, (1, 3, 2)), (1, (0, 4, 2))]);
+        let vc = virtual_connections(&intruded, PlayerId::new(0));
+        assert!(vc.edge_templates.is_empty());
+    }
+
+    #[test]
+    fn test_repair_cell_after_intrusion() {
+        let game = game_with(
+            5,
+            &[(0, (2, 1, 1)), (1, (1, 2, 1)), (0, (0, 2, 2))],
+        );
+        let state = MinimaxState::new(&game, PlayerId::new(0));
+        let repairs: Vec<usize> = state
+            .available_cells()
+            .filter(|&cell| state.is_repair(cell, state.bot_id()))
+            .collect();
+        assert_eq!(repairs, [idx(5, 1, 1, 2)]);
+    }
+
+    #[test]
+    fn test_bridge_masks_follow_moves() {
+        fn assert_masks(state: &MinimaxState) {
+            for (idx, &cell) in state.cells().iter().enumerate() {
+                let expected = match cell {
+                    0 => 0,
+                    player => state.bridge_cache[idx]
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, slot)| state.bridge_intact(slot, player))
+                        .fold(0u8, |mask, (j, _)| mask | 1 << j),
+                };
+                assert_eq!(state.bridge_masks[idx], expected, "cell {}", idx);
+            }
+        }
+
+        let game = game_with(5, &[(0, (2, 1, 1)), (1, (4, 0, 0)), (0, (0, 2, 2))]);
+        let mut state = MinimaxState::new(&game, PlayerId::new(0));
+        assert_eq!(state.bridge_masks[idx(5, 2, 1, 1)].count_ones(), 1);
+
+        // Fill the board alternately, then take every stone back.
+        let played: Vec<usize> = state.available_cells().collect();
+        let mut player = state.human_id();
+        for &cell in &played {
+            state.make_move(cell, player);
+            assert_masks(&state);
+            player = state.opponent_of(player);
+        }
+        for cell in played.into_iter().rev() {
+            state.undo_move(cell);
+            assert_masks(&state);
+        }
+        assert_eq!(state.bridge_masks[idx(5, 2, 1, 1)].count_ones(), 1);
+    }
+
+    #[test]
+    fn test_bridge_lowers_connection_cost() {
+        // A bridge between two stones costs nothing; the same stones with
+        // one carrier taken need the other carrier filled.
+        let bridged = game_with(5, &[(0, (2, 1, 1)), (1, (4, 0, 0)), (0, (0, 2, 2))]);
+        let intruded = game_with(5, &[(0, (2, 1, 1)), (1, (1, 2, 1)), (0, (0, 2, 2))]);
+        let mut bridged = MinimaxState::new(&bridged, PlayerId::new(0));
+        let mut intruded = MinimaxState::new(&intruded, PlayerId::new(0));
+        let id = bridged.bot_id();
+        assert!(bridged.connection_cost(id) < intruded.connection_cost(id));
+    }
+}
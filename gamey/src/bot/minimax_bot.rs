@@ -0,0 +1,375 @@
+//! Configurable minimax bot strategy.
+//!
+//! Unlike [`FastBot`](crate::FastBot) and [`SmartBot`](crate::SmartBot), whose
+//! budgets are fixed, a [`MinimaxBot`] takes its name and parameters from a
+//! [`MinimaxConfig`]. This is how reduced skill levels are offered, e.g. for a
+//! ranked PvE ladder. A bot can also be given an [`OpeningBook`], which is
+//! consulted before the search runs.
+
+use std::sync::Arc;
+
+use crate::{
+    BotMetadata, CandidateMove, Coordinates, GameY, OpeningBook, SearchOptions, SearchProgress,
+    SearchSession, YBot,
+};
+
+use super::minimax::{
+    MinimaxConfig, analyze_with_config, choose_move_in_session, choose_move_with_config,
+    choose_move_with_progress, choose_pie_opening_with_config, decide_pie_with_config,
+};
+use super::ybot::PieChoice;
+
+/// A minimax bot whose time budget and skill level are configurable.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use gamey::{MinimaxBot, MinimaxConfig, YBotRegistry};
+///
+/// let registry = YBotRegistry::new()
+///     .with_bot(Arc::new(MinimaxBot::new("ladder_5", MinimaxConfig::new(100, 300).with_skill(5))));
+///
+/// assert!(registry.find("ladder_5").is_some());
+/// ```
+#[derive(Debug, Clone)]
+pub struct MinimaxBot {
+    name: String,
+    config: MinimaxConfig,
+    book: Option<Arc<OpeningBook>>,
+}
+
+impl MinimaxBot {
+    /// Creates a bot registered under `name` that plays with `config`.
+    pub fn new(name: impl Into<String>, config: MinimaxConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            book: None,
+        }
+    }
+
+    /// Attaches an opening book. It is only used while
+    /// [`MinimaxConfig::use_book`] is set, and never for a Pie Rule opening:
+    /// book moves are strong, while that opening should be balanced.
+    pub fn with_book(mut self, book: Arc<OpeningBook>) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    /// Enables or disables the attached opening book, i.e. sets
+    /// [`MinimaxConfig::use_book`].
+    pub fn with_book_enabled(mut self, enabled: bool) -> Self {
+        self.config.use_book = enabled;
+        self
+    }
+
+    /// Returns a book move for `game`, if the book is enabled and knows the
+    /// position.
+    fn book_move(&self, game: &GameY, seed: Option<u64>) -> Option<Coordinates> {
+        if !self.config.use_book {
+            return None;
+        }
+        self.book.as_ref()?.pick(game, seed)
+    }
+
+    /// Returns the bot's configuration.
+    pub fn config(&self) -> &MinimaxConfig {
+        &self.config
+    }
+}
+
+impl YBot for MinimaxBot {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn choose_move(&self, game: &GameY) -> Option<Coordinates> {
+        self.book_move(game, self.config.seed)
+            .or_else(|| choose_move_with_config(game, &self.config))
+    }
+
+    fn choose_move_with_options(&self, game: &GameY, options: &SearchOptions) -> Option<Coordinates> {
+        let config = self.config.clone().with_options(options);
+        self.book_move(game, config.seed)
+            .or_else(|| choose_move_with_config(game, &config))
+    }
+
+    fn choose_move_in_session(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+        session: &mut SearchSession,
+    ) -> Option<Coordinates> {
+        let config = self.config.clone().with_options(options);
+        self.book_move(game, config.seed)
+            .or_else(|| choose_move_in_session(game, &config, session))
+    }
+
+    fn choose_move_with_progress(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+        on_depth: &mut dyn FnMut(&SearchProgress),
+    ) -> Option<Coordinates> {
+        let config = self.config.clone().with_options(options);
+        self.book_move(game, config.seed)
+            .or_else(|| choose_move_with_progress(game, &config, on_depth))
+    }
+
+    fn ponder(&self, game: &GameY, session: &mut SearchSession) -> Option<Coordinates> {
+        session.start_pondering(game, &self.config)
+    }
+
+    fn choose_pie_opening(&self, game: &GameY) -> Option<Coordinates> {
+        choose_pie_opening_with_config(game, &self.config)
+    }
+
+    fn choose_pie_opening_with_options(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        choose_pie_opening_with_config(game, &self.config.clone().with_options(options))
+    }
+
+    fn decide_pie(&self, game: &GameY) -> PieChoice {
+        decide_pie_with_config(game, &self.config)
+    }
+
+    fn decide_pie_with_options(&self, game: &GameY, options: &SearchOptions) -> PieChoice {
+        decide_pie_with_config(game, &self.config.clone().with_options(options))
+    }
+
+    fn analyze(
+        &self,
+        game: &GameY,
+        multipv: usize,
+        options: &SearchOptions,
+    ) -> Option<Vec<CandidateMove>> {
+        Some(analyze_with_config(game, &self.config.clone().with_options(options), multipv))
+    }
+
+    fn metadata(&self) -> BotMetadata {
+        let config = &self.config;
+        let description = match config.max_nodes {
+            Some(max_nodes) => format!(
+                "Minimax search at skill {} with {} nodes per move",
+                config.skill, max_nodes
+            ),
+            None => format!(
+                "Minimax search at skill {} with a {}–{} ms budget per move",
+                config.skill, config.min_time_ms, config.max_time_ms
+            ),
+        };
+        let mut features = config.features();
+        // Book moves are drawn by weight, at random without a seed.
+        features.randomized |= config.use_book && self.book.is_some() && config.seed.is_none();
+        let metadata = BotMetadata::new(self.name())
+            .with_description(description)
+            .with_features(features);
+        match config.max_nodes {
+            Some(_) => metadata,
+            None => metadata.with_time_budget(config.min_time_ms, config.max_time_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MAX_SKILL, MIN_SKILL, Movement, PlayerId, YBotRegistry};
+    use std::sync::Arc;
+
+    fn weak_bot(seed: u64) -> MinimaxBot {
+        MinimaxBot::new("weak", MinimaxConfig::new(50, 200).with_skill(3).with_seed(seed))
+    }
+
+    // The configured name is the registry key used in HTTP routes.
+    #[test]
+    fn test_minimax_bot_is_findable_under_configured_name() {
+        let registry = YBotRegistry::new().with_bot(Arc::new(weak_bot(1)));
+        assert!(registry.find("weak").is_some());
+    }
+
+    #[test]
+    fn test_with_skill_clamps_to_supported_range() {
+        assert_eq!(MinimaxConfig::default().with_skill(0).skill, MIN_SKILL);
+        assert_eq!(MinimaxConfig::default().with_skill(99).skill, MAX_SKILL);
+    }
+
+    #[test]
+    fn test_low_skill_choose_move_returns_available_cell() {
+        let game = GameY::new(5);
+        let coords = weak_bot(7).choose_move(&game).expect("bot must return a move");
+        let idx = coords.to_index(game.board_size());
+        assert!(game.available_cells().contains(&idx));
+    }
+
+    // With a fixed seed the same position must always produce the same move,
+    // otherwise ladder games cannot be replayed. The node limit keeps the
+    // search itself independent of machine speed.
+    #[test]
+    fn test_low_skill_is_reproducible_with_seed() {
+        let mut game = GameY::new(5);
+        game.add_move(Movement::Placement {
+            player: PlayerId::new(0),
+            coords: Coordinates::new(2, 1, 1),
+        })
+        .unwrap();
+        let bot = || {
+            let config = MinimaxConfig::new(0, 0).with_skill(3).with_seed(1234);
+            MinimaxBot::new("weak", config.with_node_limit(2_000))
+        };
+
+        let first = bot().choose_move(&game);
+        for _ in 0..3 {
+            assert_eq!(bot().choose_move(&game), first);
+        }
+    }
+
+    // The Pie Rule searches take the node limit as well, so seeded games are
+    // reproducible from their first move. With a zero time budget only the
+    // node limit lets them search at all.
+    #[test]
+    fn test_pie_paths_are_node_limited_by_options() {
+        let empty = GameY::new(5);
+        let mut opened = GameY::new(5);
+        opened
+            .add_move(Movement::Placement {
+                player: PlayerId::new(0),
+                coords: Coordinates::new(2, 1, 1),
+            })
+            .unwrap();
+        let options = SearchOptions {
+            max_nodes: Some(4_000),
+            ..SearchOptions::default()
+        };
+        let bot = MinimaxBot::new("pie", MinimaxConfig::new(0, 0));
+
+        let opening = bot.choose_pie_opening_with_options(&empty, &options);
+        let decision = bot.decide_pie_with_options(&opened, &options);
+        assert!(opening.is_some());
+        for _ in 0..3 {
+            assert_eq!(bot.choose_pie_opening_with_options(&empty, &options), opening);
+            assert_eq!(bot.decide_pie_with_options(&opened, &options), decision);
+        }
+    }
+
+    // Like the Pie Rule searches, an analysis with a zero time budget can
+    // only search at all if the options' node limit reaches it.
+    #[test]
+    fn test_analysis_is_node_limited_by_options() {
+        let options = SearchOptions {
+            max_nodes: Some(4_000),
+            ..SearchOptions::default()
+        };
+        let bot = MinimaxBot::new("analyst", MinimaxConfig::new(0, 0));
+        let game = GameY::new(5);
+
+        let analysis = bot.analyze(&game, 3, &options).unwrap();
+        assert_eq!(analysis.len(), 3);
+        assert!(analysis[0].pv.len() > 1, "the search must go deeper than one ply");
+        for _ in 0..3 {
+            assert_eq!(bot.analyze(&game, 3, &options).unwrap(), analysis);
+        }
+    }
+
+    #[test]
+    fn test_skill_option_overrides_the_configured_skill() {
+        let options = SearchOptions {
+            skill: Some(99),
+            ..SearchOptions::default()
+        };
+        let config = MinimaxConfig::default().with_skill(3).with_options(&options);
+        assert_eq!(config.skill, MAX_SKILL);
+
+        // A weakened full-strength bot plays like a bot configured that weak.
+        let game = GameY::new(5);
+        let options = SearchOptions {
+            seed: Some(9),
+            max_nodes: Some(2_000),
+            skill: Some(3),
+        };
+        let strong = MinimaxBot::new("strong", MinimaxConfig::new(0, 0));
+        let weak = MinimaxBot::new("weak", MinimaxConfig::new(0, 0).with_skill(3));
+        assert_eq!(
+            strong.choose_move_with_options(&game, &options),
+            weak.choose_move_with_options(&game, &options)
+        );
+    }
+
+    // Even the weakest level must not miss a one-move win.
+    #[test]
+    fn test_min_skill_still_takes_immediate_win() {
+        let yen = crate::YEN::new(3, 0, vec!['B', 'R'], "B/BR/..R".to_string());
+        let game = GameY::try_from(yen).unwrap();
+        let bot = MinimaxBot::new("weakest", MinimaxConfig::new(50, 200).with_skill(MIN_SKILL));
+
+        let coords = bot.choose_move(&game).expect("bot must return a move");
+        assert_eq!(coords.x(), 0, "the winning move lies on side A");
+    }
+
+    fn book_for_empty_board(size: u32, coords: Coordinates) -> Arc<OpeningBook> {
+        let mut book = OpeningBook::new();
+        book.add(&GameY::new(size), coords, 1);
+        Arc::new(book)
+    }
+
+    #[test]
+    fn test_book_move_is_played_when_enabled() {
+        // An edge cell the search itself would not open with.
+        let book_move = Coordinates::new(4, 0, 0);
+        let bot = MinimaxBot::new("booked", MinimaxConfig::new(0, 0).with_node_limit(1))
+            .with_book(book_for_empty_board(5, book_move));
+
+        assert_eq!(bot.choose_move(&GameY::new(5)), Some(book_move));
+    }
+
+    #[test]
+    fn test_book_is_ignored_when_disabled() {
+        let book_move = Coordinates::new(4, 0, 0);
+        let config = MinimaxConfig::new(0, 0).with_node_limit(200).with_book_enabled(false);
+        let with_book = MinimaxBot::new("booked", config.clone())
+            .with_book(book_for_empty_board(5, book_move));
+        let without_book = MinimaxBot::new("plain", config);
+
+        let game = GameY::new(5);
+        assert_eq!(with_book.choose_move(&game), without_book.choose_move(&game));
+    }
+
+    // A book move is strong, while a Pie Rule opening should be balanced.
+    #[test]
+    fn test_book_is_not_used_for_the_pie_opening() {
+        let book_move = Coordinates::new(4, 0, 0);
+        let config = MinimaxConfig::new(0, 0);
+        let with_book = MinimaxBot::new("booked", config.clone())
+            .with_book(book_for_empty_board(5, book_move));
+        let without_book = MinimaxBot::new("plain", config);
+        let options = SearchOptions {
+            max_nodes: Some(4_000),
+            ..SearchOptions::default()
+        };
+
+        let game = GameY::new(5);
+        assert_eq!(
+            with_book.choose_pie_opening_with_options(&game, &options),
+            without_book.choose_pie_opening_with_options(&game, &options)
+        );
+        assert_eq!(with_book.choose_move_with_options(&game, &options), Some(book_move));
+    }
+
+    #[test]
+    fn test_randomized_feature_follows_skill_seed_and_book() {
+        let full = MinimaxBot::new("full", MinimaxConfig::new(0, 0));
+        assert!(!full.metadata().features.randomized);
+
+        let weak = MinimaxBot::new("weak", MinimaxConfig::new(0, 0).with_skill(MIN_SKILL));
+        assert!(weak.metadata().features.randomized);
+        assert!(!weak_bot(7).metadata().features.randomized);
+
+        let booked = MinimaxBot::new("booked", MinimaxConfig::new(0, 0))
+            .with_book(book_for_empty_board(5, Coordinates::new(4, 0, 0)));
+        assert!(booked.metadata().features.randomized);
+    }
+}
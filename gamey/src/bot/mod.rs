@@ -6,17 +6,35 @@
 //! - [`YBot`] - A trait that defines the interface for all bots
 //! - [`YBotRegistry`] - A registry for managing multiple bot implementations
 //! - [`RandomBot`] - A simple bot that makes random valid moves
+//! - [`SeededRandomBot`] - A [`RandomBot`] whose moves are fixed by a seed
 //! - [`FastBot`] - Minimax bot with a 500 ms time budget (MEDIUM difficulty)
 //! - [`SmartBot`] - Minimax bot with a 1 000–3 000 ms time budget (HARD difficulty)
+//! - [`MinimaxBot`] - Minimax bot with a configurable budget and skill level
+//! - [`solve`] - Exact proof-number solver for small positions
+//! - [`OpeningBook`] - Weighted book moves keyed by canonical position hash
+//! - [`SearchSession`] - Search tables reused across the moves of one game
 
 pub mod fast_bot;
 pub mod minimax;
+pub mod minimax_bot;
+pub mod opening_book;
 pub mod random;
 pub mod smart_bot;
 pub mod ybot;
 pub mod ybot_registry;
 pub use fast_bot::*;
-pub use minimax::{choose_move_with_minimax, choose_pie_opening_with_minimax, decide_pie_with_minimax};
+pub use minimax::{
+    Bridge, DEFAULT_SOLVER_MAX_EMPTY, DEFAULT_SOLVER_NODES, DEFAULT_TT_SIZE_MB, EdgeTemplate,
+    EvalWeights, Evaluator, EvaluatorKind, HeuristicEvaluator, MAX_SKILL, MIN_SKILL, MinimaxConfig,
+    MinimaxState, ResistanceEvaluator, SearchSession, SearchStats, Side, SolveOutcome, SolveResult,
+    StopOnDrop, StopToken, VirtualConnections, analyze_with_config, analyze_with_minimax,
+    choose_move_in_session, choose_move_with_config, choose_move_with_evaluator,
+    choose_move_with_minimax, choose_move_with_progress, choose_pie_opening_with_config,
+    choose_pie_opening_with_minimax, collect_search_stats, decide_pie_with_config,
+    decide_pie_with_minimax, solve, virtual_connections,
+};
+pub use minimax_bot::*;
+pub use opening_book::*;
 pub use random::*;
 pub use smart_bot::*;
 pub use ybot::*;
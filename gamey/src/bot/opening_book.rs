@@ -0,0 +1,311 @@
+//! Opening book for bots.
+//!
+//! The first moves of a game are searched from an (almost) empty board, which
+//! costs a full time budget and gives little information. An
+//! [`OpeningBook`] instead maps positions to weighted moves that were
+//! prepared offline, e.g. from self-play with the `build_book` tool.
+//!
+//! Positions are keyed by a canonical hash that is the same for all six
+//! symmetries of the triangle, so one entry covers every rotation and
+//! reflection of a position. Moves are stored in the canonical frame and
+//! mapped back to the board they are requested for.
+
+use crate::{Coordinates, GameY, GameYError, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// The six symmetries of the triangle, as permutations of `(x, y, z)`.
+const SYMMETRIES: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A move stored in the book together with its weight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookMove {
+    pub coords: Coordinates,
+    /// Relative frequency with which the move is chosen.
+    pub weight: u32,
+}
+
+/// A collection of book moves keyed by canonical position hash.
+///
+/// # Example
+///
+/// ```
+/// use gamey::{Coordinates, GameY, OpeningBook};
+///
+/// let game = GameY::new(5);
+/// let mut book = OpeningBook::new();
+/// book.add(&game, Coordinates::new(2, 1, 1), 3);
+///
+/// assert_eq!(book.pick(&game, Some(1)), Some(Coordinates::new(2, 1, 1)));
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpeningBook {
+    positions: HashMap<u64, Vec<BookMove>>,
+}
+
+impl OpeningBook {
+    /// Creates an empty book.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads a book from a JSON file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let filename = path.as_ref().display().to_string();
+        let content = std::fs::read_to_string(path).map_err(|e| GameYError::IoError {
+            message: format!("Failed to read opening book: {}", filename),
+            error: e.to_string(),
+        })?;
+        serde_json::from_str(&content).map_err(|e| GameYError::SerdeError { error: e })
+    }
+
+    /// Saves the book to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = serde_json::to_string(self).map_err(|e| GameYError::SerdeError { error: e })?;
+        let filename = path.as_ref().display().to_string();
+        std::fs::write(path, json).map_err(|e| GameYError::IoError {
+            message: format!("Failed to write opening book: {}", filename),
+            error: e.to_string(),
+        })
+    }
+
+    /// Returns the number of positions in the book.
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns `true` if the book has no positions.
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Adds `weight` to the move `coords` in position `game`.
+    pub fn add(&mut self, game: &GameY, coords: Coordinates, weight: u32) {
+        let (key, symmetry) = canonical_key(game);
+        let coords = apply_symmetry(&coords, symmetry);
+        let moves = self.positions.entry(key).or_default();
+        match moves.iter_mut().find(|m| m.coords == coords) {
+            Some(entry) => entry.weight = entry.weight.saturating_add(weight),
+            None => moves.push(BookMove { coords, weight }),
+        }
+    }
+
+    /// Returns the book moves for `game`, in `game`'s own frame.
+    ///
+    /// Moves that are not playable in `game` are left out.
+    pub fn moves(&self, game: &GameY) -> Vec<BookMove> {
+        let (key, symmetry) = canonical_key(game);
+        let Some(moves) = self.positions.get(&key) else {
+            return Vec::new();
+        };
+        let size = game.board_size();
+        moves
+            .iter()
+            .filter(|m| m.weight > 0)
+            .map(|m| BookMove {
+                coords: invert_symmetry(&m.coords, symmetry),
+                weight: m.weight,
+            })
+            .filter(|m| m.coords.is_valid(size) && game.cell_owner(&m.coords).is_none())
+            .collect()
+    }
+
+    /// Picks a book move for `game` at random, in proportion to the weights.
+    ///
+    /// With a `seed` the choice is reproducible. Returns `None` if the
+    /// position is not in the book.
+    pub fn pick(&self, game: &GameY, seed: Option<u64>) -> Option<Coordinates> {
+        let moves = self.moves(game);
+        let total: u64 = moves.iter().map(|m| m.weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ canonical_hash(game)),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let mut ticket = rng.random_range(0..total);
+        for m in &moves {
+            if ticket < m.weight as u64 {
+                return Some(m.coords);
+            }
+            ticket -= m.weight as u64;
+        }
+        None
+    }
+}
+
+/// Returns the canonical hash of `game`.
+///
+/// The hash covers the board size, the stones and the side to move, and is
+/// identical for all symmetric variants of a position. It is stable across
+/// runs and platforms, so it can be stored in book files.
+pub fn canonical_hash(game: &GameY) -> u64 {
+    canonical_key(game).0
+}
+
+/// Returns the canonical hash together with the symmetry that maps `game`
+/// onto its canonical frame.
+pub(crate) fn canonical_key(game: &GameY) -> (u64, usize) {
+    let size = game.board_size();
+    let total_cells = game.total_cells() as usize;
+    let turn = game.next_player().map_or(0xff, |p| p.id() as u8);
+
+    let mut cells = vec![0u8; total_cells];
+    (0..SYMMETRIES.len())
+        .map(|symmetry| {
+            cells.fill(0);
+            for (coords, (_, owner)) in game.board_map() {
+                let idx = apply_symmetry(coords, symmetry).to_index(size) as usize;
+                cells[idx] = owner.id() as u8 + 1;
+            }
+            (fnv1a(size, turn, &cells), symmetry)
+        })
+        .min()
+        .expect("there is always at least one symmetry")
+}
+
+fn fnv1a(size: u32, turn: u8, cells: &[u8]) -> u64 {
+    size.to_le_bytes()
+        .iter()
+        .chain(std::iter::once(&turn))
+        .chain(cells)
+        .fold(FNV_OFFSET, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+pub(crate) fn apply_symmetry(coords: &Coordinates, symmetry: usize) -> Coordinates {
+    let c = [coords.x(), coords.y(), coords.z()];
+    let p = SYMMETRIES[symmetry];
+    Coordinates::new(c[p[0]], c[p[1]], c[p[2]])
+}
+
+pub(crate) fn invert_symmetry(coords: &Coordinates, symmetry: usize) -> Coordinates {
+    let c = [coords.x(), coords.y(), coords.z()];
+    let p = SYMMETRIES[symmetry];
+    let mut out = [0u32; 3];
+    for i in 0..3 {
+        out[p[i]] = c[i];
+    }
+    Coordinates::new(out[0], out[1], out[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Movement, PlayerId};
+
+    fn game_with(size: u32, stones: &[(u32, u32, u32)]) -> GameY {
+        let mut game = GameY::new(size);
+        for (i, &(x, y, z)) in stones.iter().enumerate() {
+            game.add_move(Movement::Placement {
+                player: PlayerId::new(i as u32 % 2),
+                coords: Coordinates::new(x, y, z),
+            })
+            .unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_canonical_hash_is_symmetry_invariant() {
+        let base = canonical_hash(&game_with(5, &[(4, 0, 0), (2, 1, 1)]));
+        for stones in [[(0, 4, 0), (1, 2, 1)], [(0, 0, 4), (1, 1, 2)]] {
+            let game = game_with(5, &stones);
+            assert_eq!(canonical_hash(&game), base);
+        }
+    }
+
+    #[test]
+    fn test_canonical_hash_distinguishes_side_to_move_and_stones() {
+        let empty = canonical_hash(&GameY::new(5));
+        let one = canonical_hash(&game_with(5, &[(4, 0, 0)]));
+        let other = canonical_hash(&game_with(5, &[(2, 1, 1)]));
+        assert_ne!(empty, one);
+        assert_ne!(one, other);
+    }
+
+    #[test]
+    fn test_inverse_symmetry_round_trips() {
+        let coords = Coordinates::new(3, 1, 0);
+        for symmetry in 0..SYMMETRIES.len() {
+            let there = apply_symmetry(&coords, symmetry);
+            assert_eq!(invert_symmetry(&there, symmetry), coords);
+        }
+    }
+
+    #[test]
+    fn test_book_move_is_mapped_to_symmetric_position() {
+        let mut book = OpeningBook::new();
+        book.add(&game_with(5, &[(4, 0, 0)]), Coordinates::new(3, 1, 0), 1);
+
+        // Same position rotated: corner (0,4,0); the reply rotates with it.
+        let rotated = game_with(5, &[(0, 4, 0)]);
+        let moves = book.moves(&rotated);
+        assert_eq!(moves.len(), 1);
+        let reply = moves[0].coords;
+        assert!(reply.is_valid(5));
+        assert!(rotated.get_neighbors(&Coordinates::new(0, 4, 0)).contains(&reply));
+    }
+
+    #[test]
+    fn test_add_accumulates_weight() {
+        let game = GameY::new(4);
+        let mut book = OpeningBook::new();
+        book.add(&game, Coordinates::new(1, 1, 1), 2);
+        book.add(&game, Coordinates::new(1, 1, 1), 3);
+        assert_eq!(book.len(), 1);
+        assert_eq!(book.moves(&game)[0].weight, 5);
+    }
+
+    #[test]
+    fn test_pick_is_reproducible_with_seed() {
+        let game = GameY::new(5);
+        let mut book = OpeningBook::new();
+        book.add(&game, Coordinates::new(2, 1, 1), 5);
+        book.add(&game, Coordinates::new(1, 2, 1), 5);
+        book.add(&game, Coordinates::new(3, 1, 0), 5);
+        let first = book.pick(&game, Some(11));
+        assert!(first.is_some());
+        for _ in 0..5 {
+            assert_eq!(book.pick(&game, Some(11)), first);
+        }
+    }
+
+    #[test]
+    fn test_pick_unknown_position_returns_none() {
+        let book = OpeningBook::new();
+        assert_eq!(book.pick(&GameY::new(5), None), None);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let game = GameY::new(5);
+        let mut book = OpeningBook::new();
+        book.add(&game, Coordinates::new(2, 1, 1), 7);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        book.save(file.path()).unwrap();
+        let loaded = OpeningBook::load(file.path()).unwrap();
+
+        assert_eq!(loaded.moves(&game), book.moves(&game));
+    }
+
+    #[test]
+    fn test_load_missing_file_fails() {
+        let result = OpeningBook::load("/nonexistent/book.json");
+        assert!(matches!(result, Err(GameYError::IoError { .. })));
+    }
+}
@@ -1,12 +1,14 @@
-//! A simple random bot implementation.
+//! Random bot implementations.
 //!
-//! This module provides [`RandomBot`], a bot that makes random valid moves.
-//! It is useful for testing and as a baseline opponent.
+//! This module provides [`RandomBot`], a bot that makes random valid moves,
+//! and [`SeededRandomBot`], its deterministic counterpart. They are useful for
+//! testing and as baseline opponents.
 
-use crate::{Coordinates, GameY, YBot};
+use crate::{BotFeatures, BotMetadata, Coordinates, Difficulty, GameY, SearchOptions, YBot};
 use crate::bot::ybot::PieChoice;
-use rand::Rng;
 use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 /// A bot that chooses moves randomly from the available cells.
 ///
@@ -26,6 +28,7 @@ use rand::prelude::IndexedRandom;
 /// let chosen_move = bot.choose_move(&game);
 /// assert!(chosen_move.is_some());
 /// ```
+#[derive(Debug, Clone, Copy, Default)]
 pub struct RandomBot;
 
 impl YBot for RandomBot {
@@ -34,18 +37,139 @@ impl YBot for RandomBot {
     }
 
     fn choose_move(&self, board: &GameY) -> Option<Coordinates> {
-        let available_cells = board.available_cells();
-        let cell = available_cells.choose(&mut rand::rng())?;
-        let coordinates = Coordinates::from_index(*cell, board.board_size());
-        Some(coordinates)
+        pick_cell(None, board)
     }
 
-    fn decide_pie(&self, _board: &GameY) -> PieChoice {
-        if rand::rng().random_bool(0.5) {
-            PieChoice::Swap
-        } else {
-            PieChoice::Keep
+    fn choose_move_with_options(&self, board: &GameY, options: &SearchOptions) -> Option<Coordinates> {
+        pick_cell(options.seed, board)
+    }
+
+    fn choose_pie_opening_with_options(
+        &self,
+        board: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        pick_cell(options.seed, board)
+    }
+
+    fn decide_pie(&self, board: &GameY) -> PieChoice {
+        flip_pie(None, board)
+    }
+
+    fn decide_pie_with_options(&self, board: &GameY, options: &SearchOptions) -> PieChoice {
+        flip_pie(options.seed, board)
+    }
+
+    fn metadata(&self) -> BotMetadata {
+        BotMetadata::new(self.name())
+            .with_description("Plays a uniformly random legal move")
+            .with_difficulty(Difficulty::Easy)
+            .with_features(BotFeatures {
+                seed: true,
+                randomized: true,
+                ..BotFeatures::default()
+            })
+    }
+}
+
+/// A [`RandomBot`] with a fixed seed: identical positions yield identical
+/// moves.
+///
+/// A per-request [`SearchOptions::seed`] takes precedence over the bot's own.
+///
+/// ```
+/// use gamey::{GameY, SeededRandomBot, YBot};
+///
+/// let bot = SeededRandomBot::new(42);
+/// let game = GameY::new(5);
+/// assert_eq!(bot.choose_move(&game), bot.choose_move(&game));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeededRandomBot {
+    seed: u64,
+}
+
+impl SeededRandomBot {
+    /// Creates a bot drawing its moves from `seed`.
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Returns the bot's seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl YBot for SeededRandomBot {
+    fn name(&self) -> &str {
+        "seeded_random_bot"
+    }
+
+    fn choose_move(&self, board: &GameY) -> Option<Coordinates> {
+        pick_cell(Some(self.seed), board)
+    }
+
+    fn choose_move_with_options(&self, board: &GameY, options: &SearchOptions) -> Option<Coordinates> {
+        pick_cell(Some(options.seed.unwrap_or(self.seed)), board)
+    }
+
+    fn choose_pie_opening_with_options(
+        &self,
+        board: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        pick_cell(Some(options.seed.unwrap_or(self.seed)), board)
+    }
+
+    fn decide_pie(&self, board: &GameY) -> PieChoice {
+        flip_pie(Some(self.seed), board)
+    }
+
+    fn decide_pie_with_options(&self, board: &GameY, options: &SearchOptions) -> PieChoice {
+        flip_pie(Some(options.seed.unwrap_or(self.seed)), board)
+    }
+
+    fn metadata(&self) -> BotMetadata {
+        BotMetadata::new(self.name())
+            .with_description("Plays a random legal move fixed by its seed")
+            .with_difficulty(Difficulty::Easy)
+            .with_features(BotFeatures {
+                seed: true,
+                ..BotFeatures::default()
+            })
+    }
+}
+
+/// Builds the RNG for one decision on `board`.
+///
+/// Seeded RNGs are derived from the seed and the set of free cells, so the
+/// result depends only on the inputs and not on earlier calls.
+fn rng_for(seed: Option<u64>, board: &GameY) -> StdRng {
+    match seed {
+        Some(seed) => {
+            let position = board
+                .available_cells()
+                .iter()
+                .fold(0xCBF2_9CE4_8422_2325u64, |h, &cell| {
+                    (h ^ cell as u64).wrapping_mul(0x0000_0100_0000_01B3)
+                });
+            StdRng::seed_from_u64(seed ^ position)
         }
+        None => StdRng::from_rng(&mut rand::rng()),
+    }
+}
+
+fn pick_cell(seed: Option<u64>, board: &GameY) -> Option<Coordinates> {
+    let cell = board.available_cells().choose(&mut rng_for(seed, board))?;
+    Some(Coordinates::from_index(*cell, board.board_size()))
+}
+
+fn flip_pie(seed: Option<u64>, board: &GameY) -> PieChoice {
+    if rng_for(seed, board).random_bool(0.5) {
+        PieChoice::Swap
+    } else {
+        PieChoice::Keep
     }
 }
 
@@ -188,4 +312,71 @@ mod tests {
         assert!(saw_keep, "RandomBot.decide_pie never returned Keep across 50 trials");
         assert!(saw_swap, "RandomBot.decide_pie never returned Swap across 50 trials");
     }
+
+    // Seeded bots are used for regression tests and replays: the same
+    // position must always produce the same move.
+    #[test]
+    fn test_seeded_random_bot_is_deterministic() {
+        let game = GameY::new(7);
+        let first = SeededRandomBot::new(99).choose_move(&game);
+        for _ in 0..10 {
+            assert_eq!(SeededRandomBot::new(99).choose_move(&game), first);
+        }
+    }
+
+    #[test]
+    fn test_different_seeds_eventually_differ() {
+        let game = GameY::new(7);
+        let first = SeededRandomBot::new(0).choose_move(&game);
+        assert!(
+            (1..50).any(|seed| SeededRandomBot::new(seed).choose_move(&game) != first),
+            "the seed must influence the chosen move"
+        );
+    }
+
+    #[test]
+    fn test_request_seed_overrides_bot_seed() {
+        let game = GameY::new(7);
+        let options = SearchOptions {
+            seed: Some(7),
+            ..SearchOptions::default()
+        };
+        assert_eq!(
+            RandomBot.choose_move_with_options(&game, &options),
+            SeededRandomBot::new(7).choose_move(&game)
+        );
+        assert_eq!(
+            SeededRandomBot::new(1).choose_move_with_options(&game, &options),
+            SeededRandomBot::new(7).choose_move(&game)
+        );
+    }
+
+    // The Pie Rule paths take the request seed as well, so a seeded game is
+    // reproducible from its first move.
+    #[test]
+    fn test_request_seed_fixes_pie_decisions() {
+        let empty = GameY::new(7);
+        let mut opened = GameY::new(7);
+        opened
+            .add_move(Movement::Placement {
+                player: PlayerId::new(0),
+                coords: Coordinates::new(3, 2, 1),
+            })
+            .unwrap();
+        let options = SearchOptions {
+            seed: Some(11),
+            ..SearchOptions::default()
+        };
+        let seeded = SeededRandomBot::new(11);
+        for _ in 0..10 {
+            assert_eq!(
+                RandomBot.choose_pie_opening_with_options(&empty, &options),
+                seeded.choose_pie_opening(&empty)
+            );
+            assert_eq!(
+                RandomBot.decide_pie_with_options(&opened, &options),
+                seeded.decide_pie(&opened)
+            );
+        }
+    }
 }
@@ -3,45 +3,138 @@
 //! Uses the minimax engine with a wider time window (1 000 ms min, 3 000 ms max)
 //! allowing deeper search at the cost of slower responses.
 
-use crate::{Coordinates, GameY, YBot};
+use std::sync::Arc;
 
-use super::minimax::{choose_move_with_minimax, choose_pie_opening_with_minimax, decide_pie_with_minimax};
+use crate::{
+    BotMetadata, CandidateMove, Coordinates, Difficulty, GameY, MinimaxBot, OpeningBook,
+    SearchOptions, SearchProgress, SearchSession, YBot,
+};
+
+use super::minimax::MinimaxConfig;
 use super::ybot::PieChoice;
 
 /// A bot that runs minimax with a 1 000–3 000 ms time budget.
 ///
-/// Corresponds to the `HARD` difficulty level in the game service.
-pub struct SmartBot;
+/// Corresponds to the `HARD` difficulty level in the game service. It is a
+/// [`MinimaxBot`] with the preset [`SmartBot::config`].
+///
+/// An attached [`OpeningBook`] is consulted before the search runs, unless it
+/// is disabled with [`SmartBot::with_book_enabled`].
+#[derive(Debug, Clone)]
+pub struct SmartBot(MinimaxBot);
+
+impl SmartBot {
+    /// Creates the bot without an opening book.
+    pub fn new() -> Self {
+        Self(MinimaxBot::new("smart_bot", Self::config()))
+    }
+
+    /// The configuration the bot plays with: 1 000–3 000 ms per move and 2 000 ms per Pie
+    /// Rule search.
+    pub fn config() -> MinimaxConfig {
+        MinimaxConfig::new(1000, 3000).with_pie_time(2000)
+    }
+
+    /// Attaches an opening book.
+    pub fn with_book(self, book: Arc<OpeningBook>) -> Self {
+        Self(self.0.with_book(book))
+    }
+
+    /// Enables or disables the attached opening book.
+    pub fn with_book_enabled(self, enabled: bool) -> Self {
+        Self(self.0.with_book_enabled(enabled))
+    }
+}
+
+impl Default for SmartBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl YBot for SmartBot {
     fn name(&self) -> &str {
-        "smart_bot"
+        self.0.name()
     }
 
     fn choose_move(&self, game: &GameY) -> Option<Coordinates> {
-        choose_move_with_minimax(game, 1000, 3000)
+        self.0.choose_move(game)
+    }
+
+    fn choose_move_with_options(&self, game: &GameY, options: &SearchOptions) -> Option<Coordinates> {
+        self.0.choose_move_with_options(game, options)
+    }
+
+    fn choose_move_in_session(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+        session: &mut SearchSession,
+    ) -> Option<Coordinates> {
+        self.0.choose_move_in_session(game, options, session)
+    }
+
+    fn choose_move_with_progress(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+        on_depth: &mut dyn FnMut(&SearchProgress),
+    ) -> Option<Coordinates> {
+        self.0.choose_move_with_progress(game, options, on_depth)
+    }
+
+    fn ponder(&self, game: &GameY, session: &mut SearchSession) -> Option<Coordinates> {
+        self.0.ponder(game, session)
     }
 
     fn choose_pie_opening(&self, game: &GameY) -> Option<Coordinates> {
-        choose_pie_opening_with_minimax(game, 2000)
+        self.0.choose_pie_opening(game)
+    }
+
+    fn choose_pie_opening_with_options(
+        &self,
+        game: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        self.0.choose_pie_opening_with_options(game, options)
+    }
+
+    fn analyze(
+        &self,
+        game: &GameY,
+        multipv: usize,
+        options: &SearchOptions,
+    ) -> Option<Vec<CandidateMove>> {
+        self.0.analyze(game, multipv, options)
     }
 
     fn decide_pie(&self, game: &GameY) -> PieChoice {
-        decide_pie_with_minimax(game, 2000)
+        self.0.decide_pie(game)
+    }
+
+    fn decide_pie_with_options(&self, game: &GameY, options: &SearchOptions) -> PieChoice {
+        self.0.decide_pie_with_options(game, options)
+    }
+
+    fn metadata(&self) -> BotMetadata {
+        self.0
+            .metadata()
+            .with_description("Minimax search with a 1 000–3 000 ms budget per move")
+            .with_difficulty(Difficulty::Hard)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Coordinates, Movement, PlayerId, YBotRegistry};
+    use crate::{Coordinates, Movement, OpeningBook, PlayerId, YBotRegistry};
     use std::sync::Arc;
 
     // The bot name is the registry key used in HTTP routes.
     // A mismatch silently breaks all PvE games at hard difficulty.
     #[test]
     fn test_smart_bot_is_findable_in_registry() {
-        let registry = YBotRegistry::new().with_bot(Arc::new(SmartBot));
+        let registry = YBotRegistry::new().with_bot(Arc::new(SmartBot::new()));
         assert!(
             registry.find("smart_bot").is_some(),
             "SmartBot must be retrievable by its own name — name/key mismatch breaks the HTTP API"
@@ -53,7 +146,7 @@ mod tests {
     #[test]
     fn test_smart_bot_choose_move_returns_available_cell() {
         let game = GameY::new(3);
-        let coords = SmartBot.choose_move(&game).expect("bot must return a move on a non-empty board");
+        let coords = SmartBot::new().choose_move(&game).expect("bot must return a move on a non-empty board");
         let idx = coords.to_index(game.board_size());
         assert!(
             game.available_cells().contains(&idx),
@@ -72,7 +165,7 @@ mod tests {
         })
         .unwrap();
 
-        let chosen = SmartBot.choose_move(&game).expect("bot must return a move");
+        let chosen = SmartBot::new().choose_move(&game).expect("bot must return a move");
         assert_ne!(chosen, occupied, "bot must not choose the already-occupied cell");
         let idx = chosen.to_index(game.board_size());
         assert!(game.available_cells().contains(&idx));
@@ -82,7 +175,7 @@ mod tests {
     #[test]
     fn test_smart_bot_pie_opening_returns_available_cell() {
         let game = GameY::new(3);
-        let coords = SmartBot
+        let coords = SmartBot::new()
             .choose_pie_opening(&game)
             .expect("pie opening must return a move on a fresh board");
         let idx = coords.to_index(game.board_size());
@@ -104,7 +197,7 @@ mod tests {
         })
         .unwrap();
 
-        let choice = SmartBot.decide_pie(&game);
+        let choice = SmartBot::new().decide_pie(&game);
         assert_eq!(
             choice,
             PieChoice::Swap,
@@ -126,11 +219,39 @@ mod tests {
         })
         .unwrap();
 
-        let choice = SmartBot.decide_pie(&game);
+        let choice = SmartBot::new().decide_pie(&game);
         assert_eq!(
             choice,
             PieChoice::Keep,
             "SmartBot's minimax finds the corner counterable — keeping is the optimal response"
         );
     }
+
+    // A corner is a poor first move, so a search never picks it and only
+    // the book can.
+    fn corner_book() -> Arc<OpeningBook> {
+        let mut book = OpeningBook::new();
+        book.add(&GameY::new(5), Coordinates::new(4, 0, 0), 1);
+        Arc::new(book)
+    }
+
+    #[test]
+    fn test_smart_bot_plays_book_moves() {
+        let bot = SmartBot::new().with_book(corner_book());
+        let game = GameY::new(5);
+        assert_eq!(bot.choose_move(&game), Some(Coordinates::new(4, 0, 0)));
+        assert!(bot.metadata().features.randomized);
+    }
+
+    #[test]
+    fn test_smart_bot_ignores_a_disabled_book() {
+        let bot = SmartBot::new().with_book(corner_book()).with_book_enabled(false);
+        let options = SearchOptions {
+            max_nodes: Some(200),
+            ..SearchOptions::default()
+        };
+        let chosen = bot.choose_move_with_options(&GameY::new(5), &options);
+        assert_ne!(chosen, Some(Coordinates::new(4, 0, 0)));
+        assert!(!bot.metadata().features.randomized);
+    }
 }
@@ -1,12 +1,13 @@
-use crate::{Coordinates, GameY};
+use crate::{Coordinates, GameY, SearchSession};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// The outcome of a Pie Rule decision.
 ///
 /// After the first stone is placed, the second player may either **keep** their
 /// current side or **swap** (take ownership of the first stone, giving the
 /// initiative back to the opponent).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum PieChoice {
     /// Keep the current sides — the decider plays next as normal.
@@ -16,6 +17,167 @@ pub enum PieChoice {
     Swap,
 }
 
+/// Per-request overrides for a bot's move search.
+///
+/// Every field is optional; `None` keeps the bot's own configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct SearchOptions {
+    /// Seed for the bot's random choices. With a seed, identical positions
+    /// produce identical moves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Stops the search after this many nodes instead of using the bot's time
+    /// budget. Node-limited searches do not depend on machine speed or load,
+    /// so their results are reproducible.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_nodes: Option<u64>,
+    /// Plays at this skill level instead of the bot's own, clamped to
+    /// [`MIN_SKILL`](crate::MIN_SKILL)`..=`[`MAX_SKILL`](crate::MAX_SKILL).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<u8>,
+}
+
+/// A candidate move produced by a multi-PV analysis.
+///
+/// Candidates are returned best first. The score is from the perspective of
+/// the side to move: positive values favour that side, and values close to
+/// ±100 000 indicate a forced win or loss.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CandidateMove {
+    /// The candidate move.
+    pub coords: Coordinates,
+    /// Search score of the move for the side to move.
+    pub score: i32,
+    /// Expected continuation, starting with `coords` and alternating sides.
+    pub pv: Vec<Coordinates>,
+}
+
+/// Result of one completed iteration of a bot's iterative deepening search,
+/// reported while the search goes on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchProgress {
+    /// The depth just completed.
+    pub depth: u32,
+    /// Search score of the best move for the side to move.
+    pub score: i32,
+    /// The best move found so far.
+    pub best_move: Coordinates,
+    /// Expected continuation, starting with `best_move` and alternating sides.
+    pub pv: Vec<Coordinates>,
+    /// Nodes searched so far.
+    pub nodes: u64,
+}
+
+/// Difficulty level a bot is offered at in the game service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// Optional capabilities of a bot, as advertised by [`YBot::metadata`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct BotFeatures {
+    /// The bot plays balanced Pie Rule openings and decides keep or swap on
+    /// its own judgement.
+    pub pie_rule: bool,
+    /// The bot supports [`YBot::analyze`].
+    pub analysis: bool,
+    /// The bot can ponder between moves, see [`YBot::ponder`].
+    pub pondering: bool,
+    /// The bot reuses search tables across the moves of a game.
+    pub sessions: bool,
+    /// The bot honours [`SearchOptions::seed`].
+    pub seed: bool,
+    /// The bot honours [`SearchOptions::max_nodes`].
+    pub node_limit: bool,
+    /// The bot honours [`SearchOptions::skill`].
+    #[serde(default)]
+    pub skill: bool,
+    /// Without a [`SearchOptions::seed`], the bot may answer the same
+    /// position with different moves.
+    #[serde(default)]
+    pub randomized: bool,
+}
+
+/// Time a bot spends on a move, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct TimeBudget {
+    /// No new search depth is started after this time.
+    pub min_time_ms: u64,
+    /// A search in progress is abandoned after this time.
+    pub max_time_ms: u64,
+}
+
+/// Description of a bot for clients choosing an opponent.
+///
+/// ```
+/// use gamey::{BotMetadata, Difficulty, FastBot, YBot};
+///
+/// let metadata = FastBot::new().metadata();
+/// assert_eq!(metadata.name, "fast_bot");
+/// assert_eq!(metadata.difficulty, Some(Difficulty::Medium));
+/// assert!(metadata.features.analysis);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct BotMetadata {
+    /// The registry key of the bot, see [`YBot::name`].
+    pub name: String,
+    /// Short human-readable description.
+    pub description: String,
+    /// Difficulty level, if the bot is offered at one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    /// Capabilities beyond choosing moves.
+    pub features: BotFeatures,
+    /// Default time budget per move, if the bot searches on a clock.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time_budget: Option<TimeBudget>,
+}
+
+impl BotMetadata {
+    /// Creates metadata for `name` with no description, difficulty, features
+    /// or time budget.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            difficulty: None,
+            features: BotFeatures::default(),
+            time_budget: None,
+        }
+    }
+
+    /// Sets the description.
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Sets the difficulty level.
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    /// Sets the supported features.
+    pub fn with_features(mut self, features: BotFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Sets the default time budget.
+    pub fn with_time_budget(mut self, min_time_ms: u64, max_time_ms: u64) -> Self {
+        self.time_budget = Some(TimeBudget {
+            min_time_ms,
+            max_time_ms,
+        });
+        self
+    }
+}
+
 /// Trait representing a Y game bot (YBot)
 ///
 /// A YBot is an AI that can choose moves in the game of Y.
@@ -32,6 +194,55 @@ pub trait YBot: Send + Sync {
     /// Chooses a move based on the current game state.
     fn choose_move(&self, board: &GameY) -> Option<Coordinates>;
 
+    /// Chooses a move honouring the per-request `options`.
+    ///
+    /// The default implementation ignores the options and falls back to
+    /// [`choose_move`](YBot::choose_move).
+    fn choose_move_with_options(&self, board: &GameY, _options: &SearchOptions) -> Option<Coordinates> {
+        self.choose_move(board)
+    }
+
+    /// Chooses a move like [`choose_move_with_options`](YBot::choose_move_with_options),
+    /// reusing the search tables kept in `session` for this game.
+    ///
+    /// The caller keeps one session per game and passes it to every move of
+    /// that game. The default implementation ignores the session.
+    fn choose_move_in_session(
+        &self,
+        board: &GameY,
+        options: &SearchOptions,
+        _session: &mut SearchSession,
+    ) -> Option<Coordinates> {
+        self.choose_move_with_options(board, options)
+    }
+
+    /// Chooses a move like [`choose_move_with_options`](YBot::choose_move_with_options),
+    /// calling `on_depth` each time the search completes a depth.
+    ///
+    /// Used to show the search live. The default implementation reports no
+    /// progress.
+    fn choose_move_with_progress(
+        &self,
+        board: &GameY,
+        options: &SearchOptions,
+        _on_depth: &mut dyn FnMut(&SearchProgress),
+    ) -> Option<Coordinates> {
+        self.choose_move_with_options(board, options)
+    }
+
+    /// Starts pondering in `session` after the bot's move in `board`, with
+    /// the opponent to move, and returns the reply the bot expects.
+    ///
+    /// The next [`choose_move_in_session`](YBot::choose_move_in_session) call
+    /// with the same session ends the ponder search and profits from it if
+    /// the expected reply was played. Use
+    /// [`SearchSession::discard_pondering`] to drop it otherwise.
+    ///
+    /// The default implementation does not ponder.
+    fn ponder(&self, _board: &GameY, _session: &mut SearchSession) -> Option<Coordinates> {
+        None
+    }
+
     /// Chooses a balanced opening move under the Pie Rule.
     ///
     /// When the bot goes first and the Pie Rule is active, the opponent will
@@ -44,6 +255,18 @@ pub trait YBot: Send + Sync {
         self.choose_move(board)
     }
 
+    /// Chooses a Pie Rule opening honouring the per-request `options`.
+    ///
+    /// The default implementation ignores the options and falls back to
+    /// [`choose_pie_opening`](YBot::choose_pie_opening).
+    fn choose_pie_opening_with_options(
+        &self,
+        board: &GameY,
+        _options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        self.choose_pie_opening(board)
+    }
+
     /// Decides whether to **keep** or **swap** under the Pie Rule.
     ///
     /// Called when the bot is the second player and the first stone has just
@@ -54,4 +277,36 @@ pub trait YBot: Send + Sync {
     fn decide_pie(&self, _board: &GameY) -> PieChoice {
         PieChoice::Keep
     }
+
+    /// Decides whether to keep or swap honouring the per-request `options`.
+    ///
+    /// The default implementation ignores the options and falls back to
+    /// [`decide_pie`](YBot::decide_pie).
+    fn decide_pie_with_options(&self, board: &GameY, _options: &SearchOptions) -> PieChoice {
+        self.decide_pie(board)
+    }
+
+    /// Returns the best `multipv` candidate moves for the side to move,
+    /// best first, honouring the per-request `options`. An analysis makes no
+    /// random choices and reports exact scores, so only `max_nodes` applies.
+    ///
+    /// Used for hints and post-game review. Returns `None` if the bot does not
+    /// support analysis; an empty list means there is no move to analyse.
+    ///
+    /// The default implementation does not support analysis.
+    fn analyze(
+        &self,
+        _board: &GameY,
+        _multipv: usize,
+        _options: &SearchOptions,
+    ) -> Option<Vec<CandidateMove>> {
+        None
+    }
+
+    /// Describes the bot for discovery by clients.
+    ///
+    /// The default implementation only fills in the [`name`](YBot::name).
+    fn metadata(&self) -> BotMetadata {
+        BotMetadata::new(self.name())
+    }
 }
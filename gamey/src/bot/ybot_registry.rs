@@ -5,7 +5,7 @@
 
 use std::{collections::HashMap, sync::Arc};
 
-use crate::YBot;
+use crate::{BotMetadata, YBot};
 
 /// A registry that stores and manages [`YBot`] implementations.
 ///
@@ -55,6 +55,13 @@ impl YBotRegistry {
     pub fn names(&self) -> Vec<String> {
         self.bots.keys().cloned().collect()
     }
+
+    /// Returns the metadata of all registered bots, sorted by name.
+    pub fn metadata(&self) -> Vec<BotMetadata> {
+        let mut metadata: Vec<BotMetadata> = self.bots.values().map(|bot| bot.metadata()).collect();
+        metadata.sort_by(|a, b| a.name.cmp(&b.name));
+        metadata
+    }
 }
 
 impl Default for YBotRegistry {
@@ -144,4 +151,14 @@ mod tests {
 
         assert_eq!(registry.names().len(), 1);
     }
+
+    #[test]
+    fn test_metadata_is_sorted_by_name() {
+        let registry = YBotRegistry::new()
+            .with_bot(Arc::new(MockBot::new("zeta")))
+            .with_bot(Arc::new(MockBot::new("alpha")));
+
+        let names: Vec<String> = registry.metadata().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, ["alpha", "zeta"]);
+    }
 }
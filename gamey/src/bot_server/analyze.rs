@@ -0,0 +1,180 @@
+use crate::{
+    CandidateMove, GameY, SearchOptions, YEN,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable,
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Number of candidates returned when the request does not specify `multipv`.
+pub const DEFAULT_MULTIPV: usize = 3;
+
+/// Upper bound on `multipv`; larger requests are clamped to this value.
+pub const MAX_MULTIPV: usize = 10;
+
+/// Path parameters for the analyze endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct AnalyzeParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+}
+
+/// Query parameters for the analyze endpoint.
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnalyzeQuery {
+    /// Number of candidate moves to return (defaults to [`DEFAULT_MULTIPV`]).
+    multipv: Option<usize>,
+}
+
+/// Response returned by the analyze endpoint on success.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct AnalysisResponse {
+    pub api_version: String,
+    pub bot_id: String,
+    /// Candidate moves for the side to move, best first.
+    pub candidates: Vec<CandidateMove>,
+}
+
+/// Handler for the multi-PV analysis endpoint.
+///
+/// Returns the best `multipv` moves for the side to move, each with its score
+/// and expected continuation. Used by the webapp for hints and post-game
+/// review.
+///
+/// # Route
+/// `POST /{api_version}/ybot/analyze/{bot_id}?multipv=N`
+///
+/// # Request Body
+/// A JSON object in YEN format representing the position to analyse.
+///
+/// # Response
+/// On success, returns an [`AnalysisResponse`]. Bots that do not support
+/// analysis (such as `random_bot`) return an [`ErrorResponse`].
+#[utoipa::path(
+    post, path = "/{api_version}/ybot/analyze/{bot_id}", summary = "Top-N candidate moves",
+    params(AnalyzeParams, AnalyzeQuery),
+    request_body = YEN,
+    responses((status = 200, body = AnalysisResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn analyze(
+    State(state): State<AppState>,
+    Path(params): Path<AnalyzeParams>,
+    Query(query): Query<AnalyzeQuery>,
+    JsonBody(yen): JsonBody<YEN>,
+) -> Result<Json<AnalysisResponse>, ErrorResponse> {
+    let game_y = match GameY::try_from(yen) {
+        Ok(game) => game,
+        Err(err) => {
+            return Err(ErrorResponse::error(
+                &format!("Invalid YEN format: {}", err),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::from(&err)));
+        }
+    };
+
+    if game_y.check_game_over() {
+        return Err(ErrorResponse::error(
+            "The game is already over",
+            Some(params.api_version),
+            Some(params.bot_id),
+        )
+        .with_code(ErrorCode::GameOver));
+    }
+
+    let bot = match state.bots().find(&params.bot_id) {
+        Some(bot) => bot,
+        None => {
+            let available = state.bots().names().join(", ");
+            return Err(ErrorResponse::error(
+                &format!(
+                    "Bot not found: {}, available bots: [{}]",
+                    params.bot_id, available
+                ),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::BotNotFound));
+        }
+    };
+
+    let multipv = query.multipv.unwrap_or(DEFAULT_MULTIPV).clamp(1, MAX_MULTIPV);
+    let analysis =
+        run_cancellable(move || bot.analyze(&game_y, multipv, &SearchOptions::default()))
+        .await
+        .map_err(|err| {
+            ErrorResponse::error(
+                &format!("Search failed: {}", err),
+                Some(params.api_version.clone()),
+                Some(params.bot_id.clone()),
+            )
+        })?;
+    let candidates = match analysis {
+        Some(candidates) if !candidates.is_empty() => candidates,
+        Some(_) => {
+            return Err(ErrorResponse::error(
+                "No valid moves available for the bot",
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::NoMoves));
+        }
+        None => {
+            return Err(ErrorResponse::error(
+                &format!("Bot {} does not support analysis", params.bot_id),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::UnsupportedOperation));
+        }
+    };
+
+    Ok(Json(AnalysisResponse {
+        api_version: params.api_version,
+        bot_id: params.bot_id,
+        candidates,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Coordinates;
+
+    #[test]
+    fn test_analysis_response_serialize() {
+        let resp = AnalysisResponse {
+            api_version: "v1".to_string(),
+            bot_id: "fast_bot".to_string(),
+            candidates: vec![CandidateMove {
+                coords: Coordinates::new(1, 1, 1),
+                score: 42,
+                pv: vec![Coordinates::new(1, 1, 1), Coordinates::new(2, 0, 1)],
+            }],
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"candidates\":["));
+        assert!(json.contains("\"score\":42"));
+        assert!(json.contains("\"pv\":["));
+    }
+
+    #[test]
+    fn test_analysis_response_deserialize() {
+        let json = r#"{"api_version":"v1","bot_id":"test","candidates":[{"coords":{"x":0,"y":1,"z":2},"score":-5,"pv":[]}]}"#;
+        let resp: AnalysisResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.candidates.len(), 1);
+        assert_eq!(resp.candidates[0].score, -5);
+    }
+}
@@ -0,0 +1,211 @@
+use crate::{
+    Coordinates, GameAction, GameStatus, GameY, Movement, PlayerId, YEN,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+};
+use axum::{Json, extract::Path};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Path parameters for the apply endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct ApplyParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+}
+
+/// A move made by the side to move.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MoveRequest {
+    /// Place a stone on an empty cell.
+    Placement { coords: Coordinates },
+    /// Take over the opponent's opening stone under the Pie Rule.
+    Swap,
+    /// Give up the game.
+    Resign,
+}
+
+impl MoveRequest {
+    /// The movement of `player` making this move.
+    pub fn by(self, player: PlayerId) -> Movement {
+        match self {
+            MoveRequest::Placement { coords } => Movement::Placement { player, coords },
+            MoveRequest::Swap => Movement::Action {
+                player,
+                action: GameAction::Swap,
+            },
+            MoveRequest::Resign => Movement::Action {
+                player,
+                action: GameAction::Resign,
+            },
+        }
+    }
+}
+
+/// Request body of the apply endpoint: a position in YEN format plus the move
+/// to play in it.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ApplyRequest {
+    #[serde(flatten)]
+    pub yen: YEN,
+    #[serde(rename = "move")]
+    pub movement: MoveRequest,
+}
+
+/// Whether the game goes on after the move.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyStatus {
+    Ongoing,
+    Finished,
+}
+
+/// A position together with its status, as reported after a move.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GameSummary {
+    /// The position in YEN format.
+    pub yen: YEN,
+    pub status: ApplyStatus,
+    /// The player to move next, while the game goes on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_player: Option<u32>,
+    /// The winner, once the game is finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winner: Option<u32>,
+    /// The stones connecting all three sides, when the game was won by
+    /// connection rather than resignation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub winning_chain: Option<Vec<Coordinates>>,
+}
+
+impl From<&GameY> for GameSummary {
+    fn from(game: &GameY) -> Self {
+        let (status, next_player, winner) = match game.status() {
+            GameStatus::Ongoing { next_player } => {
+                (ApplyStatus::Ongoing, Some(next_player.id()), None)
+            }
+            GameStatus::Finished { winner } => (ApplyStatus::Finished, None, Some(winner.id())),
+        };
+        Self {
+            yen: YEN::from(game),
+            status,
+            next_player,
+            winner,
+            winning_chain: game.winning_chain(),
+        }
+    }
+}
+
+/// Response returned by the apply endpoint on success.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ApplyResponse {
+    pub api_version: String,
+    /// The position after the move.
+    #[serde(flatten)]
+    pub game: GameSummary,
+}
+
+/// Handler that plays a move and returns the resulting position.
+///
+/// Lets the game services apply moves and detect wins with the engine's
+/// rules instead of reimplementing them. The move is made by the side to
+/// move given by the YEN `turn`.
+///
+/// # Route
+/// `POST /{api_version}/game/apply`
+///
+/// # Request Body
+/// An [`ApplyRequest`]: a YEN position with an extra `move` field, e.g.
+/// `{"type": "placement", "coords": {"x": 1, "y": 1, "z": 1}}`,
+/// `{"type": "swap"}` or `{"type": "resign"}`.
+///
+/// # Response
+/// On success, returns an [`ApplyResponse`]. Moves that break the rules
+/// return an [`ErrorResponse`] with [`ErrorCode::IllegalMove`], or
+/// [`ErrorCode::GameOver`] if the game has already ended.
+#[utoipa::path(
+    post, path = "/{api_version}/game/apply", summary = "Play a move under the rules and report the result",
+    params(ApplyParams),
+    request_body = ApplyRequest,
+    responses((status = 200, body = ApplyResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn apply_move(
+    Path(params): Path<ApplyParams>,
+    JsonBody(request): JsonBody<ApplyRequest>,
+) -> Result<Json<ApplyResponse>, ErrorResponse> {
+    let mut game_y = match GameY::try_from(request.yen) {
+        Ok(game) => game,
+        Err(err) => {
+            return Err(ErrorResponse::error(
+                &format!("Invalid YEN format: {}", err),
+                Some(params.api_version),
+                None,
+            )
+            .with_code(ErrorCode::from(&err)));
+        }
+    };
+    let Some(player) = game_y.next_player() else {
+        return Err(ErrorResponse::error(
+            "The game is already over",
+            Some(params.api_version),
+            None,
+        )
+        .with_code(ErrorCode::GameOver));
+    };
+
+    if let Err(err) = game_y.play(request.movement.by(player)) {
+        return Err(ErrorResponse::error(
+            &format!("Illegal move: {}", err),
+            Some(params.api_version),
+            None,
+        )
+        .with_code(ErrorCode::from(&err)));
+    }
+
+    Ok(Json(ApplyResponse {
+        api_version: params.api_version,
+        game: GameSummary::from(&game_y),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_request_reads_flattened_yen_and_move() {
+        let json = r#"{"size":2,"turn":0,"players":["B","R"],"layout":"./..","move":{"type":"placement","coords":{"x":1,"y":0,"z":0}}}"#;
+        let request: ApplyRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.yen.size(), 2);
+        assert_eq!(
+            request.movement,
+            MoveRequest::Placement {
+                coords: Coordinates::new(1, 0, 0)
+            }
+        );
+    }
+
+    #[test]
+    fn test_actions_deserialize_by_type() {
+        let swap: MoveRequest = serde_json::from_str(r#"{"type":"swap"}"#).unwrap();
+        let resign: MoveRequest = serde_json::from_str(r#"{"type":"resign"}"#).unwrap();
+        assert_eq!(swap, MoveRequest::Swap);
+        assert_eq!(resign, MoveRequest::Resign);
+    }
+
+    #[test]
+    fn test_ongoing_response_omits_winner() {
+        let response = ApplyResponse {
+            api_version: "v1".to_string(),
+            game: GameSummary::from(&GameY::new(3)),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["status"], "ongoing");
+        assert_eq!(json["next_player"], 0);
+        assert!(json.get("winner").is_none());
+        assert!(json.get("winning_chain").is_none());
+    }
+}
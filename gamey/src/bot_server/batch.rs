@@ -0,0 +1,302 @@
+//! Batch move selection.
+//!
+//! Analysis scripts and other services ask for moves in many positions at
+//! once. One batch request searches them in parallel, each item holding a
+//! slot of the [`SearchLimiter`](crate::SearchLimiter) like a single choose
+//! request would, though a batch holds at most half of the slots at once so
+//! that other clients keep being served. Every item is answered on its
+//! own: a bad position fails its item, not the batch. Items found in the
+//! [`ResponseCache`](crate::ResponseCache) are answered without a search.
+
+use crate::{
+    Coordinates, GameY, SearchOptions, YEN,
+    cache::{self, Lookup},
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable,
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::Poll;
+use tokio::sync::Semaphore;
+use utoipa::{IntoParams, ToSchema};
+
+/// Largest number of positions accepted in one batch.
+pub const MAX_BATCH_SIZE: usize = 256;
+
+/// Path parameters for the batch endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct BatchParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+}
+
+/// One position of a batch: a YEN document plus the optional
+/// [`SearchOptions`] fields that limit its search.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BatchItem {
+    #[serde(flatten)]
+    pub yen: YEN,
+    #[serde(flatten)]
+    pub options: SearchOptions,
+}
+
+/// Request body of the batch endpoint.
+///
+/// Positions are read one by one, so that a malformed entry only fails its
+/// own item; each should be a [`BatchItem`].
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BatchRequest {
+    pub positions: Vec<serde_json::Value>,
+}
+
+/// Why one item of a batch has no move.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct BatchError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// Result of one item of a batch: either the chosen move or an error.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct BatchResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coords: Option<Coordinates>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchError>,
+}
+
+impl BatchResult {
+    fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            coords: None,
+            error: Some(BatchError {
+                code,
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+impl From<Result<Coordinates, BatchError>> for BatchResult {
+    fn from(result: Result<Coordinates, BatchError>) -> Self {
+        match result {
+            Ok(coords) => Self {
+                coords: Some(coords),
+                error: None,
+            },
+            Err(error) => Self {
+                coords: None,
+                error: Some(error),
+            },
+        }
+    }
+}
+
+/// Response returned by the batch endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct BatchResponse {
+    pub api_version: String,
+    pub bot_id: String,
+    /// One result per position, in the order of the request.
+    pub results: Vec<BatchResult>,
+}
+
+/// Handler that chooses moves in many positions at once.
+///
+/// # Route
+/// `POST /{api_version}/ybot/choose-batch/{bot_id}`
+///
+/// # Request Body
+/// A [`BatchRequest`] with up to [`MAX_BATCH_SIZE`] positions, e.g.
+/// `{"positions": [{"size": 3, "turn": 0, "players": ["B","R"], "layout": "./../...", "max_nodes": 5000}]}`.
+///
+/// # Response
+/// A [`BatchResponse`] with a [`BatchResult`] per position, in order. Items
+/// fail on their own with the [`ErrorCode`] a choose request would get,
+/// e.g. [`ErrorCode::InvalidYen`], or [`ErrorCode::Overloaded`] if the
+/// server had no room for their search. An unknown bot or an oversized
+/// batch fails the whole request. A request timeout applies to the whole
+/// batch.
+#[utoipa::path(
+    post, path = "/{api_version}/ybot/choose-batch/{bot_id}", summary = "Moves for many positions, searched in parallel",
+    params(BatchParams),
+    request_body = BatchRequest,
+    responses((status = 200, body = BatchResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn choose_batch(
+    State(state): State<AppState>,
+    Path(params): Path<BatchParams>,
+    JsonBody(request): JsonBody<BatchRequest>,
+) -> Result<Json<BatchResponse>, ErrorResponse> {
+    if request.positions.len() > MAX_BATCH_SIZE {
+        return Err(ErrorResponse::error(
+            &format!(
+                "A batch holds at most {} positions, got {}",
+                MAX_BATCH_SIZE,
+                request.positions.len()
+            ),
+            Some(params.api_version),
+            Some(params.bot_id),
+        )
+        .with_code(ErrorCode::InvalidRequest));
+    }
+    let Some(bot) = state.bots().find(&params.bot_id) else {
+        let available = state.bots().names().join(", ");
+        return Err(ErrorResponse::error(
+            &format!(
+                "Bot not found: {}, available bots: [{}]",
+                params.bot_id, available
+            ),
+            Some(params.api_version),
+            Some(params.bot_id),
+        )
+        .with_code(ErrorCode::BotNotFound));
+    };
+
+    // Items queue for the shared search slots a few at a time, so that a
+    // large batch neither fills the queue other clients wait in nor takes
+    // every slot while it runs.
+    let searches = state.searches();
+    let state = &state;
+    let batch_slots = Semaphore::new(batch_slots(searches.max_concurrent()));
+    let items = request.positions.into_iter().map(|position| {
+        let bot = bot.clone();
+        let searches = &searches;
+        let batch_slots = &batch_slots;
+        async move {
+            let (game, options) = match prepare(position) {
+                Ok(item) => item,
+                Err(error) => return BatchResult::from(Err(error)),
+            };
+            let cache = match cache::lookup(state, bot.as_ref(), &game, &options) {
+                Lookup::Hit(coords) => return BatchResult::from(Ok(coords)),
+                Lookup::Miss(cache) => Some((cache, game.clone(), options.clone())),
+                Lookup::Uncached => None,
+            };
+            let Ok(_batch_slot) = batch_slots.acquire().await else {
+                return BatchResult::error(ErrorCode::Internal, "Batch was cancelled");
+            };
+            let Ok(_permit) = searches.acquire().await else {
+                return BatchResult::error(ErrorCode::Overloaded, "Too many searches in progress");
+            };
+            let bot_id = bot.name().to_string();
+            match run_cancellable(move || bot.choose_move_with_options(&game, &options)).await {
+                Ok(Some(coords)) => {
+                    if let Some((cache, game, options)) = cache {
+                        cache.insert(&bot_id, &game, &options, coords);
+                    }
+                    BatchResult::from(Ok(coords))
+                }
+                Ok(None) => {
+                    BatchResult::error(ErrorCode::NoMoves, "No valid moves available for the bot")
+                }
+                Err(err) => {
+                    BatchResult::error(ErrorCode::Internal, format!("Search failed: {}", err))
+                }
+            }
+        }
+    });
+    let results = join_all(items.collect()).await;
+
+    Ok(Json(BatchResponse {
+        api_version: params.api_version,
+        bot_id: params.bot_id,
+        results,
+    }))
+}
+
+/// Number of search slots one batch may hold at once: half of the shared
+/// ones, but at least one.
+fn batch_slots(max_concurrent: usize) -> usize {
+    (max_concurrent / 2).max(1)
+}
+
+/// Reads one position of a batch and checks that there is a move to find.
+fn prepare(position: serde_json::Value) -> Result<(GameY, SearchOptions), BatchError> {
+    let item: BatchItem = serde_json::from_value(position).map_err(|err| BatchError {
+        code: ErrorCode::InvalidYen,
+        message: format!("Invalid YEN format: {}", err),
+    })?;
+    let game = GameY::try_from(item.yen).map_err(|err| BatchError {
+        code: ErrorCode::from(&err),
+        message: format!("Invalid YEN format: {}", err),
+    })?;
+    if game.check_game_over() {
+        return Err(BatchError {
+            code: ErrorCode::GameOver,
+            message: "The game is already over".to_string(),
+        });
+    }
+    Ok((game, item.options))
+}
+
+/// Runs `futures` concurrently and returns their outputs in order.
+///
+/// The futures are polled by the calling task rather than spawned, so they
+/// keep its task-local context: the searches of every item are attributed
+/// to the request in the metrics and are cancelled with it.
+async fn join_all<F: Future>(futures: Vec<F>) -> Vec<F::Output> {
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    std::future::poll_fn(|cx| {
+        let mut done = true;
+        for (future, output) in futures.iter_mut().zip(outputs.iter_mut()) {
+            if output.is_none() {
+                match future.as_mut().poll(cx) {
+                    Poll::Ready(value) => *output = Some(value),
+                    Poll::Pending => done = false,
+                }
+            }
+        }
+        if done { Poll::Ready(()) } else { Poll::Pending }
+    })
+    .await;
+    outputs.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_join_all_keeps_order() {
+        let slow = async {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            1
+        };
+        let fast = async { 2 };
+        let futures: Vec<Pin<Box<dyn Future<Output = i32>>>> = vec![Box::pin(slow), Box::pin(fast)];
+        assert_eq!(join_all(futures).await, [1, 2]);
+    }
+
+    #[test]
+    fn test_malformed_position_fails_its_item() {
+        let error = prepare(serde_json::json!({"size": 3})).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidYen);
+
+        let finished = serde_json::json!({
+            "size": 1, "turn": 0, "players": ["B", "R"], "layout": "B"
+        });
+        assert_eq!(prepare(finished).unwrap_err().code, ErrorCode::GameOver);
+    }
+
+    // A batch leaves half of the slots to other clients.
+    #[test]
+    fn test_batch_holds_at_most_half_of_the_slots() {
+        assert_eq!(batch_slots(1), 1);
+        assert_eq!(batch_slots(2), 1);
+        assert_eq!(batch_slots(5), 2);
+        assert_eq!(batch_slots(8), 4);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{
+    BotMetadata,
+    error::{ErrorCode, ErrorResponse},
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Path parameters for the bot list endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct ListBotsParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+}
+
+/// Path parameters for the single-bot endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct BotParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+}
+
+/// Response returned by the bot list endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct BotListResponse {
+    pub api_version: String,
+    /// Every registered bot, sorted by name.
+    pub bots: Vec<BotMetadata>,
+}
+
+/// Response returned by the single-bot endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct BotResponse {
+    pub api_version: String,
+    #[serde(flatten)]
+    pub bot: BotMetadata,
+}
+
+/// Handler listing the bots the server offers.
+///
+/// Lets clients discover bots, their difficulty, features and time budget
+/// instead of hardcoding bot ids.
+///
+/// # Route
+/// `GET /{api_version}/ybot`
+///
+/// # Response
+/// A [`BotListResponse`].
+#[utoipa::path(
+    get, path = "/{api_version}/ybot", summary = "List the bots with their metadata",
+    params(ListBotsParams),
+    responses((status = 200, body = BotListResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn list_bots(
+    State(state): State<AppState>,
+    Path(params): Path<ListBotsParams>,
+) -> Result<Json<BotListResponse>, ErrorResponse> {
+    Ok(Json(BotListResponse {
+        api_version: params.api_version,
+        bots: state.bots().metadata(),
+    }))
+}
+
+/// Handler describing one bot.
+///
+/// # Route
+/// `GET /{api_version}/ybot/{bot_id}`
+///
+/// # Response
+/// On success, returns a [`BotResponse`]. Unknown bots return an
+/// [`ErrorResponse`] with [`ErrorCode::BotNotFound`].
+#[utoipa::path(
+    get, path = "/{api_version}/ybot/{bot_id}", summary = "Metadata of one bot",
+    params(BotParams),
+    responses((status = 200, body = BotResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn bot_info(
+    State(state): State<AppState>,
+    Path(params): Path<BotParams>,
+) -> Result<Json<BotResponse>, ErrorResponse> {
+    let bot = match state.bots().find(&params.bot_id) {
+        Some(bot) => bot,
+        None => {
+            let available = state.bots().names().join(", ");
+            return Err(ErrorResponse::error(
+                &format!(
+                    "Bot not found: {}, available bots: [{}]",
+                    params.bot_id, available
+                ),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::BotNotFound));
+        }
+    };
+
+    Ok(Json(BotResponse {
+        api_version: params.api_version,
+        bot: bot.metadata(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Difficulty, RandomBot, YBot};
+
+    #[test]
+    fn test_bot_response_flattens_metadata() {
+        let response = BotResponse {
+            api_version: "v1".to_string(),
+            bot: RandomBot.metadata(),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["name"], "random_bot");
+        assert_eq!(json["difficulty"], "easy");
+        assert_eq!(json["features"]["seed"], true);
+        assert!(json.get("time_budget").is_none());
+
+        let back: BotResponse = serde_json::from_value(json).unwrap();
+        assert_eq!(back.bot.difficulty, Some(Difficulty::Easy));
+    }
+}
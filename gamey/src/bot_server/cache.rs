@@ -0,0 +1,520 @@
+//! Cache of the moves and Pie Rule decisions chosen by the bots.
+//!
+//! Clients often ask for a move in the same position again, e.g. every game
+//! against a bot starts from the same few openings, and the pie opening on
+//! an empty board is asked for at the start of every game. With a
+//! [`ResponseCache`] in the [`AppState`](crate::AppState), choose, pie-opening
+//! and pie-decide requests in a position the bot has already searched with
+//! the same [`SearchOptions`] are answered without searching.
+//!
+//! Positions are keyed by their [`canonical_hash`](crate::canonical_hash), so
+//! the mirrored and rotated variants of a position share one entry; moves
+//! are stored in the canonical frame and mapped back on lookup. Requests that
+//! expect a random move, i.e. to a [`randomized`](crate::BotFeatures::randomized)
+//! bot without a seed, are never cached. Requests with a `game_id` are not
+//! cached either, so that the bot's session follows the game.
+//!
+//! Hits and misses are counted per bot in the [`Metrics`](crate::Metrics),
+//! and `DELETE /admin/cache` empties the cache, e.g. after a bot was retuned.
+//! That endpoint takes the server's admin token as a bearer token and is
+//! refused if the server has none.
+
+use crate::{
+    Coordinates, GameY, MAX_SKILL, PieChoice, SearchOptions, YBot, apply_symmetry, canonical_key,
+    error::{ErrorCode, ErrorResponse},
+    invert_symmetry,
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, header},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError};
+use utoipa::ToSchema;
+
+/// The bot call a cached answer comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Query {
+    Move,
+    PieOpening,
+    PieDecision,
+}
+
+/// What a cached answer depends on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    query: Query,
+    bot_id: String,
+    position: u64,
+    options: SearchOptions,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Answer {
+    /// A move in the canonical frame of the position.
+    Coords(Coordinates),
+    Pie(PieChoice),
+}
+
+struct CachedAnswer {
+    answer: Answer,
+    /// Position of the entry in [`Entries::recency`].
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    moves: HashMap<CacheKey, CachedAnswer>,
+    /// Keys by the tick of their last use, least recent first.
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &CacheKey) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.moves.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = tick;
+            self.recency.insert(tick, key.clone());
+        }
+    }
+}
+
+/// A least-recently-used cache of chosen moves and Pie Rule decisions, keyed
+/// by request kind, bot, canonical position and search options.
+pub struct ResponseCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ResponseCache {
+    /// Creates a cache holding at most `capacity` answers, evicting the least
+    /// recently used one when full.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Returns the move cached for `bot_id` in `game` under `options`, in
+    /// `game`'s own frame.
+    pub fn get(&self, bot_id: &str, game: &GameY, options: &SearchOptions) -> Option<Coordinates> {
+        self.get_coords(Query::Move, bot_id, game, options)
+    }
+
+    /// Stores `coords` as the move of `bot_id` in `game` under `options`.
+    pub fn insert(&self, bot_id: &str, game: &GameY, options: &SearchOptions, coords: Coordinates) {
+        self.insert_coords(Query::Move, bot_id, game, options, coords);
+    }
+
+    /// Returns the pie opening cached for `bot_id` in `game` under
+    /// `options`, in `game`'s own frame.
+    pub fn get_pie_opening(
+        &self,
+        bot_id: &str,
+        game: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        self.get_coords(Query::PieOpening, bot_id, game, options)
+    }
+
+    /// Stores `coords` as the pie opening of `bot_id` in `game` under
+    /// `options`.
+    pub fn insert_pie_opening(
+        &self,
+        bot_id: &str,
+        game: &GameY,
+        options: &SearchOptions,
+        coords: Coordinates,
+    ) {
+        self.insert_coords(Query::PieOpening, bot_id, game, options, coords);
+    }
+
+    /// Returns the Pie Rule decision cached for `bot_id` in `game` under
+    /// `options`.
+    pub fn get_pie_decision(
+        &self,
+        bot_id: &str,
+        game: &GameY,
+        options: &SearchOptions,
+    ) -> Option<PieChoice> {
+        let (key, _) = cache_key(Query::PieDecision, bot_id, game, options);
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let Answer::Pie(choice) = entries.moves.get(&key)?.answer else {
+            return None;
+        };
+        entries.touch(&key);
+        Some(choice)
+    }
+
+    /// Stores `choice` as the Pie Rule decision of `bot_id` in `game` under
+    /// `options`.
+    pub fn insert_pie_decision(
+        &self,
+        bot_id: &str,
+        game: &GameY,
+        options: &SearchOptions,
+        choice: PieChoice,
+    ) {
+        let (key, _) = cache_key(Query::PieDecision, bot_id, game, options);
+        self.insert_answer(key, Answer::Pie(choice));
+    }
+
+    fn get_coords(
+        &self,
+        query: Query,
+        bot_id: &str,
+        game: &GameY,
+        options: &SearchOptions,
+    ) -> Option<Coordinates> {
+        let (key, symmetry) = cache_key(query, bot_id, game, options);
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let Answer::Coords(coords) = entries.moves.get(&key)?.answer else {
+            return None;
+        };
+        let coords = invert_symmetry(&coords, symmetry);
+        // A hash collision could map another position here; its move need
+        // not be playable in this one.
+        if !coords.is_valid(game.board_size()) || game.cell_owner(&coords).is_some() {
+            return None;
+        }
+        entries.touch(&key);
+        Some(coords)
+    }
+
+    fn insert_coords(
+        &self,
+        query: Query,
+        bot_id: &str,
+        game: &GameY,
+        options: &SearchOptions,
+        coords: Coordinates,
+    ) {
+        let (key, symmetry) = cache_key(query, bot_id, game, options);
+        self.insert_answer(key, Answer::Coords(apply_symmetry(&coords, symmetry)));
+    }
+
+    fn insert_answer(&self, key: CacheKey, answer: Answer) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = entries.moves.get_mut(&key) {
+            entry.answer = answer;
+            entries.touch(&key);
+            return;
+        }
+        if entries.moves.len() >= self.capacity
+            && let Some((_, oldest)) = entries.recency.pop_first()
+        {
+            entries.moves.remove(&oldest);
+        }
+        entries.tick += 1;
+        let tick = entries.tick;
+        entries.recency.insert(tick, key.clone());
+        entries.moves.insert(
+            key,
+            CachedAnswer {
+                answer,
+                last_used: tick,
+            },
+        );
+    }
+
+    /// Removes every cached move and returns how many there were.
+    pub fn clear(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let cleared = entries.moves.len();
+        *entries = Entries::default();
+        cleared
+    }
+
+    /// Largest number of moves kept at once.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of moves currently cached.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .moves
+            .len()
+    }
+
+    /// Returns `true` if no move is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Outcome of looking up a request in the cache.
+pub(crate) enum Lookup<T = Coordinates> {
+    /// The answer is cached.
+    Hit(T),
+    /// The answer must be searched and then stored in this cache.
+    Miss(Arc<ResponseCache>),
+    /// The server has no cache or the request may not be cached.
+    Uncached,
+}
+
+impl<T> Lookup<T> {
+    /// Maps a cached answer with `f`.
+    pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> Lookup<U> {
+        match self {
+            Lookup::Hit(answer) => Lookup::Hit(f(answer)),
+            Lookup::Miss(cache) => Lookup::Miss(cache),
+            Lookup::Uncached => Lookup::Uncached,
+        }
+    }
+}
+
+/// Looks up the move of `bot` in `game` under `options`, counting the hit or
+/// miss in the metrics of `state`.
+///
+/// Only requests the bot answers the same way every time are cached: those
+/// to bots that are not [`randomized`](crate::BotFeatures::randomized),
+/// either by themselves or through a reduced [`SearchOptions::skill`], and
+/// seeded ones to bots that honour the seed.
+pub(crate) fn lookup(
+    state: &AppState,
+    bot: &dyn YBot,
+    game: &GameY,
+    options: &SearchOptions,
+) -> Lookup {
+    lookup_with(state, bot, options, |cache| cache.get(bot.name(), game, options))
+}
+
+/// Like [`lookup`], for the pie opening of `bot` in `game`.
+pub(crate) fn lookup_pie_opening(
+    state: &AppState,
+    bot: &dyn YBot,
+    game: &GameY,
+    options: &SearchOptions,
+) -> Lookup {
+    lookup_with(state, bot, options, |cache| {
+        cache.get_pie_opening(bot.name(), game, options)
+    })
+}
+
+/// Like [`lookup`], for the Pie Rule decision of `bot` in `game`.
+pub(crate) fn lookup_pie_decision(
+    state: &AppState,
+    bot: &dyn YBot,
+    game: &GameY,
+    options: &SearchOptions,
+) -> Lookup<PieChoice> {
+    lookup_with(state, bot, options, |cache| {
+        cache.get_pie_decision(bot.name(), game, options)
+    })
+}
+
+fn lookup_with<T>(
+    state: &AppState,
+    bot: &dyn YBot,
+    options: &SearchOptions,
+    get: impl FnOnce(&ResponseCache) -> Option<T>,
+) -> Lookup<T> {
+    let Some(cache) = state.cache() else {
+        return Lookup::Uncached;
+    };
+    let features = bot.metadata().features;
+    let weakened = features.skill && options.skill.is_some_and(|skill| skill < MAX_SKILL);
+    if (features.randomized || weakened) && !(features.seed && options.seed.is_some()) {
+        return Lookup::Uncached;
+    }
+    let cached = get(&cache);
+    state
+        .metrics()
+        .record_cache_lookup(bot.name(), cached.is_some());
+    match cached {
+        Some(answer) => Lookup::Hit(answer),
+        None => Lookup::Miss(cache),
+    }
+}
+
+fn cache_key(
+    query: Query,
+    bot_id: &str,
+    game: &GameY,
+    options: &SearchOptions,
+) -> (CacheKey, usize) {
+    let (position, symmetry) = canonical_key(game);
+    let key = CacheKey {
+        query,
+        bot_id: bot_id.to_string(),
+        position,
+        options: options.clone(),
+    };
+    (key, symmetry)
+}
+
+/// Returns `true` if `headers` carry `Authorization: Bearer <token>` with
+/// the admin token of the server. Without a configured token nothing is
+/// authorized.
+fn is_admin(state: &AppState, headers: &HeaderMap) -> bool {
+    let Some(expected) = state.admin_token() else {
+        return false;
+    };
+    let presented = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    // Compare every byte, so the time taken does not reveal how much of the
+    // token was right.
+    presented.is_some_and(|token| {
+        token.len() == expected.len()
+            && token
+                .bytes()
+                .zip(expected.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    })
+}
+
+/// Response returned by the cache clearing endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ClearCacheResponse {
+    /// Whether the server caches answers at all.
+    pub enabled: bool,
+    /// Number of answers removed.
+    pub cleared: usize,
+}
+
+/// Handler that empties the response cache.
+///
+/// # Route
+/// `DELETE /admin/cache`
+///
+/// # Authorization
+/// The admin token of the server, as `Authorization: Bearer <token>`. A
+/// server without an admin token refuses every request.
+///
+/// # Response
+/// A [`ClearCacheResponse`] with the number of answers removed, with
+/// `enabled` unset if the server has no cache. A missing or wrong token is
+/// answered with [`ErrorCode::Unauthorized`].
+#[utoipa::path(
+    delete, path = "/admin/cache", summary = "Empty the response cache",
+    responses((status = 200, body = ClearCacheResponse), ErrorResponse)
+)]
+pub async fn clear_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<ClearCacheResponse>, ErrorResponse> {
+    if !is_admin(&state, &headers) {
+        return Err(
+            ErrorResponse::error("A valid admin token is required", None, None)
+                .with_code(ErrorCode::Unauthorized),
+        );
+    }
+    let cache = state.cache();
+    Ok(Json(ClearCacheResponse {
+        enabled: cache.is_some(),
+        cleared: cache.map_or(0, |cache| cache.clear()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Movement, PlayerId};
+
+    fn opened_game(coords: Coordinates) -> GameY {
+        let mut game = GameY::new(5);
+        game.add_move(Movement::Placement {
+            player: PlayerId::new(0),
+            coords,
+        })
+        .unwrap();
+        game
+    }
+
+    #[test]
+    fn test_moves_are_keyed_by_bot_and_options() {
+        let cache = ResponseCache::new(8);
+        let game = GameY::new(5);
+        let options = SearchOptions {
+            seed: Some(1),
+            max_nodes: None,
+            skill: None,
+        };
+        cache.insert("fast_bot", &game, &options, Coordinates::new(2, 1, 1));
+
+        assert_eq!(
+            cache.get("fast_bot", &game, &options),
+            Some(Coordinates::new(2, 1, 1))
+        );
+        assert_eq!(cache.get("smart_bot", &game, &options), None);
+        assert_eq!(cache.get("fast_bot", &game, &SearchOptions::default()), None);
+    }
+
+    #[test]
+    fn test_pie_answers_are_kept_apart_from_moves() {
+        let options = SearchOptions::default();
+        let cache = ResponseCache::new(8);
+        let empty = GameY::new(5);
+        cache.insert("fast_bot", &empty, &options, Coordinates::new(2, 1, 1));
+        assert_eq!(cache.get_pie_opening("fast_bot", &empty, &options), None);
+
+        cache.insert_pie_opening("fast_bot", &empty, &options, Coordinates::new(4, 0, 0));
+        assert_eq!(
+            cache.get_pie_opening("fast_bot", &empty, &options),
+            Some(Coordinates::new(4, 0, 0))
+        );
+        assert_eq!(
+            cache.get("fast_bot", &empty, &options),
+            Some(Coordinates::new(2, 1, 1))
+        );
+
+        let opened = opened_game(Coordinates::new(4, 0, 0));
+        cache.insert_pie_decision("fast_bot", &opened, &options, PieChoice::Swap);
+        assert_eq!(
+            cache.get_pie_decision("fast_bot", &opened, &options),
+            Some(PieChoice::Swap)
+        );
+        assert_eq!(cache.get("fast_bot", &opened, &options), None);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_symmetric_positions_share_an_entry() {
+        let options = SearchOptions::default();
+        let cache = ResponseCache::new(8);
+        let game = opened_game(Coordinates::new(4, 0, 0));
+        cache.insert("fast_bot", &game, &options, Coordinates::new(3, 1, 0));
+
+        // The same opening in another corner gets the mirrored reply.
+        let mirrored = opened_game(Coordinates::new(0, 4, 0));
+        assert_eq!(
+            cache.get("fast_bot", &mirrored, &options),
+            Some(Coordinates::new(1, 3, 0))
+        );
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_move_is_evicted() {
+        let options = SearchOptions::default();
+        let cache = ResponseCache::new(2);
+        let empty = GameY::new(5);
+        let first = opened_game(Coordinates::new(4, 0, 0));
+        let second = opened_game(Coordinates::new(2, 1, 1));
+        cache.insert("fast_bot", &empty, &options, Coordinates::new(2, 1, 1));
+        cache.insert("fast_bot", &first, &options, Coordinates::new(2, 1, 1));
+        assert!(cache.get("fast_bot", &empty, &options).is_some());
+
+        cache.insert("fast_bot", &second, &options, Coordinates::new(1, 2, 1));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("fast_bot", &empty, &options).is_some());
+        assert!(cache.get("fast_bot", &first, &options).is_none());
+        assert!(cache.get("fast_bot", &second, &options).is_some());
+
+        assert_eq!(cache.clear(), 2);
+        assert!(cache.is_empty());
+    }
+}
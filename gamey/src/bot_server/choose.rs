@@ -1,24 +1,54 @@
-use crate::{Coordinates, GameY, YEN, check_api_version, error::ErrorResponse, state::AppState};
+use crate::{
+    Coordinates, GameY, SearchOptions, YEN,
+    cache::{self, Lookup},
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable,
+    state::AppState,
+};
 use axum::{
     Json,
     extract::{Path, State},
 };
 use serde::{Deserialize, Serialize};
+use std::sync::PoisonError;
+use utoipa::{IntoParams, ToSchema};
 
 /// Path parameters extracted from the choose endpoint URL.
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
 pub struct ChooseParams {
-    /// The API version (e.g., "v1").
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
     api_version: String,
     /// The identifier of the bot to use for move selection.
     bot_id: String,
 }
 
+/// Request body of the choose endpoint.
+///
+/// The YEN fields sit at the top level, so a plain YEN document is still a
+/// valid request. The optional search fields (`seed`, `max_nodes`) make the
+/// bot's answer reproducible, and `skill` weakens a bot that supports it.
+/// Requests that share a `game_id` share the bot's search tables, see
+/// [`SessionStore`](crate::SessionStore).
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ChooseRequest {
+    /// The game state to choose a move for.
+    #[serde(flatten)]
+    pub yen: YEN,
+    /// Per-request search overrides.
+    #[serde(flatten)]
+    pub options: SearchOptions,
+    /// Identifies the game the position belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+}
+
 /// Response returned by the choose endpoint on success.
 ///
 /// Contains the bot's chosen move coordinates along with context
 /// about which API version and bot were used.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct MoveResponse {
     /// The API version used for this request.
     pub api_version: String,
@@ -37,51 +67,113 @@ pub struct MoveResponse {
 /// `POST /{api_version}/ybot/choose/{bot_id}`
 ///
 /// # Request Body
-/// A JSON object in YEN format representing the current game state.
+/// A JSON object in YEN format representing the current game state,
+/// optionally extended with the [`SearchOptions`] fields.
 ///
 /// # Response
 /// On success, returns a `MoveResponse` with the chosen coordinates.
 /// On failure, returns an `ErrorResponse` with details about what went wrong.
+///
+/// Without a `game_id`, the move may come from the server's
+/// [`ResponseCache`](crate::ResponseCache).
+#[utoipa::path(
+    post,
+    path = "/{api_version}/ybot/choose/{bot_id}",
+    summary = "Request a move from a bot",
+    params(ChooseParams),
+    request_body = ChooseRequest,
+    responses((status = 200, body = MoveResponse), ErrorResponse)
+)]
 #[axum::debug_handler]
 pub async fn choose(
     State(state): State<AppState>,
     Path(params): Path<ChooseParams>,
-    Json(yen): Json<YEN>,
-) -> Result<Json<MoveResponse>, Json<ErrorResponse>> {
-    check_api_version(&params.api_version)?;
-    let game_y = match GameY::try_from(yen) {
+    JsonBody(request): JsonBody<ChooseRequest>,
+) -> Result<Json<MoveResponse>, ErrorResponse> {
+    let game_y = match GameY::try_from(request.yen) {
         Ok(game) => game,
         Err(err) => {
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 &format!("Invalid YEN format: {}", err),
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::from(&err)));
         }
     };
+    if game_y.check_game_over() {
+        return Err(ErrorResponse::error(
+            "The game is already over",
+            Some(params.api_version),
+            Some(params.bot_id),
+        )
+        .with_code(ErrorCode::GameOver));
+    }
     let bot = match state.bots().find(&params.bot_id) {
         Some(bot) => bot,
         None => {
             let available_bots = state.bots().names().join(", ");
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 &format!(
                     "Bot not found: {}, available bots: [{}]",
                     params.bot_id, available_bots
                 ),
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::BotNotFound));
         }
     };
-    let coords = match bot.choose_move(&game_y) {
-        Some(coords) => coords,
+    let session = request
+        .game_id
+        .as_ref()
+        .map(|game_id| state.sessions().session(&params.bot_id, game_id));
+    let options = request.options;
+    let cached = match session {
+        Some(_) => Lookup::Uncached,
+        None => cache::lookup(&state, bot.as_ref(), &game_y, &options),
+    };
+    let cache = match cached {
+        Lookup::Hit(coords) => {
+            return Ok(Json(MoveResponse {
+                api_version: params.api_version,
+                bot_id: params.bot_id,
+                coords,
+            }));
+        }
+        Lookup::Miss(cache) => Some((cache, game_y.clone(), options.clone())),
+        Lookup::Uncached => None,
+    };
+    let chosen = run_cancellable(move || match session {
+        Some(session) => {
+            let mut session = session.lock().unwrap_or_else(PoisonError::into_inner);
+            bot.choose_move_in_session(&game_y, &options, &mut session)
+        }
+        None => bot.choose_move_with_options(&game_y, &options),
+    })
+    .await
+    .map_err(|err| {
+        ErrorResponse::error(
+            &format!("Search failed: {}", err),
+            Some(params.api_version.clone()),
+            Some(params.bot_id.clone()),
+        )
+    })?;
+    let coords = match chosen {
+        Some(coords) => {
+            if let Some((cache, game, options)) = cache {
+                cache.insert(&params.bot_id, &game, &options, coords);
+            }
+            coords
+        }
         None => {
             // Handle the case where the bot has no valid moves
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 "No valid moves available for the bot",
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::NoMoves));
         }
     };
     let response = MoveResponse {
@@ -128,6 +220,29 @@ mod tests {
         assert_eq!(response.bot_id, "test");
     }
 
+    #[test]
+    fn test_choose_request_accepts_plain_yen() {
+        let json = r#"{"size":2,"turn":0,"players":["B","R"],"layout":"./.."}"#;
+        let request: ChooseRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.options, SearchOptions::default());
+        assert_eq!(request.game_id, None);
+    }
+
+    #[test]
+    fn test_choose_request_reads_game_id() {
+        let json = r#"{"size":2,"turn":0,"players":["B","R"],"layout":"./..","game_id":"g-42"}"#;
+        let request: ChooseRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.game_id.as_deref(), Some("g-42"));
+    }
+
+    #[test]
+    fn test_choose_request_reads_search_options() {
+        let json = r#"{"size":2,"turn":0,"players":["B","R"],"layout":"./..","seed":7,"max_nodes":1000}"#;
+        let request: ChooseRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.options.seed, Some(7));
+        assert_eq!(request.options.max_nodes, Some(1000));
+    }
+
     #[test]
     fn test_move_response_clone() {
         let response = MoveResponse {
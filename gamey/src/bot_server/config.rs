@@ -0,0 +1,675 @@
+//! Configuration of the bot server.
+//!
+//! A [`ServerConfig`] describes everything [`run_bot_server`](crate::run_bot_server)
+//! needs: where to listen, which bots to offer and with which parameters,
+//! and the limits that protect the server. It is assembled in layers, each
+//! overriding the previous one:
+//!
+//! 1. the defaults, which match [`create_default_state`](crate::create_default_state);
+//! 2. a TOML or JSON file, chosen by its extension;
+//! 3. `GAMEY_*` environment variables (see [`ServerConfig::with_env_vars`]);
+//! 4. command-line flags, e.g. `--port`.
+//!
+//! # Example
+//!
+//! ```toml
+//! bind = "127.0.0.1:8080"
+//! max_concurrent_searches = 4
+//! request_timeout_ms = 10000
+//! cache_entries = 10000
+//! max_sessions = 16
+//! session_tt_size_mb = 8
+//! admin_token = "change-me"
+//! log_format = "json"
+//!
+//! [[bots]]
+//! type = "random"
+//!
+//! [[bots]]
+//! type = "smart"
+//! book = "book.json"
+//!
+//! [[bots]]
+//! type = "minimax"
+//! name = "ladder_5"
+//! min_time_ms = 100
+//! max_time_ms = 300
+//! skill = 5
+//! threads = 2
+//! tt_size_mb = 16
+//! ```
+
+use crate::{
+    DEFAULT_MAX_QUEUED_SEARCHES, DEFAULT_MAX_SESSIONS, DEFAULT_SESSION_TT_SIZE_MB,
+    DEFAULT_SESSION_TTL, DEFAULT_SHUTDOWN_TIMEOUT, FastBot, GameYError, MinimaxBot, MinimaxConfig,
+    OpeningBook, RandomBot, ResponseCache, SearchLimiter, SessionStore, SmartBot, YBot,
+    YBotRegistry, default_max_concurrent_searches, state::AppState,
+};
+use serde::{Deserialize, Serialize};
+use serde::de::IgnoredAny;
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default address the server listens on: every interface, port 3000.
+pub const DEFAULT_BIND_ADDRESS: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 3000));
+
+/// Default limit on the size of a request body, the same as Axum's.
+pub const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Default time after which a request is abandoned.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Prefix of the environment variables read by [`ServerConfig::with_env`].
+const ENV_PREFIX: &str = "GAMEY_";
+
+/// How the server writes its log lines.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "unknown log format '{}', expected text or json",
+                other
+            )),
+        }
+    }
+}
+
+/// A bot offered by the server.
+///
+/// The built-in bots keep their fixed budgets but may use an opening book;
+/// `minimax` bots take their name and every [`MinimaxConfig`] field, e.g.
+/// time budgets, skill, threads and transposition table size, and may use an
+/// opening book.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case", try_from = "BotTable")]
+pub enum BotConfig {
+    /// The [`RandomBot`], registered as `random_bot`.
+    Random,
+    /// The [`FastBot`], registered as `fast_bot`.
+    Fast(BookConfig),
+    /// The [`SmartBot`], registered as `smart_bot`.
+    Smart(BookConfig),
+    /// A [`MinimaxBot`] with its own name and parameters.
+    Minimax {
+        name: String,
+        /// Opening book file, as written by `build_book`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        book: Option<PathBuf>,
+        #[serde(flatten)]
+        config: MinimaxConfig,
+    },
+}
+
+impl BotConfig {
+    /// The name the bot is registered under.
+    pub fn name(&self) -> &str {
+        match self {
+            BotConfig::Random => "random_bot",
+            BotConfig::Fast(_) => "fast_bot",
+            BotConfig::Smart(_) => "smart_bot",
+            BotConfig::Minimax { name, .. } => name,
+        }
+    }
+
+    /// Creates the bot, loading its opening book if it has one.
+    pub fn build(&self) -> Result<Arc<dyn YBot>, GameYError> {
+        Ok(match self {
+            BotConfig::Random => Arc::new(RandomBot),
+            BotConfig::Fast(books) => {
+                let bot = FastBot::new().with_book_enabled(books.use_book);
+                match books.load()? {
+                    Some(book) => Arc::new(bot.with_book(book)),
+                    None => Arc::new(bot),
+                }
+            }
+            BotConfig::Smart(books) => {
+                let bot = SmartBot::new().with_book_enabled(books.use_book);
+                match books.load()? {
+                    Some(book) => Arc::new(bot.with_book(book)),
+                    None => Arc::new(bot),
+                }
+            }
+            BotConfig::Minimax { name, book, config } => {
+                let bot = MinimaxBot::new(name.clone(), config.clone());
+                match book {
+                    Some(path) => Arc::new(bot.with_book(Arc::new(OpeningBook::load(path)?))),
+                    None => Arc::new(bot),
+                }
+            }
+        })
+    }
+}
+
+/// A [`BotConfig`] as written in the configuration file.
+///
+/// `deny_unknown_fields` does not reach through `flatten`, so the settings a
+/// `minimax` bot leaves over after [`MinimaxConfig`] are collected here and
+/// rejected when converting.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BotTable {
+    Random(NoSettings),
+    Fast(BookConfig),
+    Smart(BookConfig),
+    Minimax {
+        name: String,
+        #[serde(default)]
+        book: Option<PathBuf>,
+        #[serde(flatten)]
+        config: MinimaxConfig,
+        #[serde(flatten)]
+        unknown: BTreeMap<String, IgnoredAny>,
+    },
+}
+
+/// The settings of a bot that has none.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NoSettings {}
+
+impl TryFrom<BotTable> for BotConfig {
+    type Error = String;
+
+    fn try_from(table: BotTable) -> Result<Self, Self::Error> {
+        Ok(match table {
+            BotTable::Random(NoSettings {}) => BotConfig::Random,
+            BotTable::Fast(books) => BotConfig::Fast(books),
+            BotTable::Smart(books) => BotConfig::Smart(books),
+            BotTable::Minimax {
+                name,
+                book,
+                config,
+                unknown,
+            } => {
+                if let Some(field) = unknown.keys().next() {
+                    return Err(format!("unknown setting `{}` of bot {}", field, name));
+                }
+                BotConfig::Minimax { name, book, config }
+            }
+        })
+    }
+}
+
+/// Opening book settings of a built-in bot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct BookConfig {
+    /// Opening book file, as written by `build_book`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub book: Option<PathBuf>,
+    /// Whether the opening book is consulted before searching.
+    pub use_book: bool,
+}
+
+impl Default for BookConfig {
+    fn default() -> Self {
+        Self {
+            book: None,
+            use_book: true,
+        }
+    }
+}
+
+impl BookConfig {
+    /// Loads the opening book, if one is configured.
+    fn load(&self) -> Result<Option<Arc<OpeningBook>>, GameYError> {
+        self.book
+            .as_ref()
+            .map(|path| OpeningBook::load(path).map(Arc::new))
+            .transpose()
+    }
+}
+
+/// Settings of the bot server.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address and port to listen on.
+    pub bind: SocketAddr,
+    /// Bots offered by the server.
+    pub bots: Vec<BotConfig>,
+    /// Largest accepted request body, in bytes.
+    pub body_limit_bytes: usize,
+    /// Searches allowed to run at once.
+    pub max_concurrent_searches: usize,
+    /// Searches allowed to wait for a free slot.
+    pub max_queued_searches: usize,
+    /// Time after which a request is abandoned and its search stopped, in
+    /// ms. `0` disables the timeout.
+    pub request_timeout_ms: u64,
+    /// Time the searches in flight get to finish once shutdown starts, in
+    /// ms; those still running are then cancelled.
+    pub shutdown_timeout_ms: u64,
+    /// Moves kept in the response cache. `0` disables the cache.
+    pub cache_entries: usize,
+    /// Search sessions of games kept at once; the least recently used one
+    /// is dropped to make room.
+    pub max_sessions: usize,
+    /// Time after which an unused search session is dropped, in ms.
+    pub session_ttl_ms: u64,
+    /// Cap on the transposition table of each search session, in MB.
+    pub session_tt_size_mb: usize,
+    /// Bearer token required by the `/admin` endpoints. Without one they
+    /// refuse every request.
+    pub admin_token: Option<String>,
+    pub log_format: LogFormat,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: DEFAULT_BIND_ADDRESS,
+            bots: vec![
+                BotConfig::Random,
+                BotConfig::Fast(BookConfig::default()),
+                BotConfig::Smart(BookConfig::default()),
+            ],
+            body_limit_bytes: DEFAULT_BODY_LIMIT,
+            max_concurrent_searches: default_max_concurrent_searches(),
+            max_queued_searches: DEFAULT_MAX_QUEUED_SEARCHES,
+            request_timeout_ms: DEFAULT_REQUEST_TIMEOUT.as_millis() as u64,
+            shutdown_timeout_ms: DEFAULT_SHUTDOWN_TIMEOUT.as_millis() as u64,
+            cache_entries: 0,
+            max_sessions: DEFAULT_MAX_SESSIONS,
+            session_ttl_ms: DEFAULT_SESSION_TTL.as_millis() as u64,
+            session_tt_size_mb: DEFAULT_SESSION_TT_SIZE_MB,
+            admin_token: None,
+            log_format: LogFormat::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads the file at `path`, if any, on top of the defaults, then
+    /// applies the `GAMEY_*` environment variables.
+    pub fn load(path: Option<&Path>) -> Result<Self, GameYError> {
+        let config = match path {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        config.with_env()
+    }
+
+    /// Reads a configuration file. Files ending in `.toml` are read as TOML,
+    /// all others as JSON. Missing settings keep their defaults.
+    pub fn from_file(path: &Path) -> Result<Self, GameYError> {
+        let filename = path.display().to_string();
+        let content = std::fs::read_to_string(path).map_err(|e| GameYError::IoError {
+            message: format!("Failed to read server configuration: {}", filename),
+            error: e.to_string(),
+        })?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            Self::from_toml(&content)
+        } else {
+            Self::from_json(&content)
+        }
+    }
+
+    /// Parses a configuration written in JSON.
+    pub fn from_json(json: &str) -> Result<Self, GameYError> {
+        serde_json::from_str(json).map_err(|e| GameYError::ConfigError {
+            message: e.to_string(),
+        })
+    }
+
+    /// Parses a configuration written in TOML.
+    ///
+    /// ```
+    /// use gamey::ServerConfig;
+    ///
+    /// let config = ServerConfig::from_toml("bind = \"127.0.0.1:8080\"").unwrap();
+    /// assert_eq!(config.bind.port(), 8080);
+    /// assert_eq!(config.bots.len(), 3);
+    /// ```
+    pub fn from_toml(toml: &str) -> Result<Self, GameYError> {
+        toml::from_str(toml).map_err(|e| GameYError::ConfigError {
+            message: e.to_string(),
+        })
+    }
+
+    /// Applies the `GAMEY_*` variables of the process environment.
+    pub fn with_env(self) -> Result<Self, GameYError> {
+        self.with_env_vars(std::env::vars())
+    }
+
+    /// Applies the `GAMEY_*` variables among `vars`, ignoring all others:
+    ///
+    /// | Variable | Setting |
+    /// |---|---|
+    /// | `GAMEY_BIND` | `bind` |
+    /// | `GAMEY_PORT` | the port of `bind` |
+    /// | `GAMEY_BOTS` | comma-separated names of the configured bots to keep |
+    /// | `GAMEY_BODY_LIMIT_BYTES` | `body_limit_bytes` |
+    /// | `GAMEY_MAX_CONCURRENT_SEARCHES` | `max_concurrent_searches` |
+    /// | `GAMEY_MAX_QUEUED_SEARCHES` | `max_queued_searches` |
+    /// | `GAMEY_REQUEST_TIMEOUT_MS` | `request_timeout_ms` |
+    /// | `GAMEY_SHUTDOWN_TIMEOUT_MS` | `shutdown_timeout_ms` |
+    /// | `GAMEY_CACHE_ENTRIES` | `cache_entries` |
+    /// | `GAMEY_MAX_SESSIONS` | `max_sessions` |
+    /// | `GAMEY_SESSION_TTL_MS` | `session_ttl_ms` |
+    /// | `GAMEY_SESSION_TT_SIZE_MB` | `session_tt_size_mb` |
+    /// | `GAMEY_ADMIN_TOKEN` | `admin_token` |
+    /// | `GAMEY_LOG_FORMAT` | `log_format` |
+    pub fn with_env_vars<K, V>(
+        mut self,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Self, GameYError>
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        for (key, value) in vars {
+            let Some(setting) = key.as_ref().strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let value = value.as_ref();
+            match setting {
+                "BIND" => self.bind = parse_env(setting, value)?,
+                "PORT" => self.bind.set_port(parse_env(setting, value)?),
+                "BOTS" => self.keep_bots(value)?,
+                "BODY_LIMIT_BYTES" => self.body_limit_bytes = parse_env(setting, value)?,
+                "MAX_CONCURRENT_SEARCHES" => {
+                    self.max_concurrent_searches = parse_env(setting, value)?
+                }
+                "MAX_QUEUED_SEARCHES" => self.max_queued_searches = parse_env(setting, value)?,
+                "REQUEST_TIMEOUT_MS" => self.request_timeout_ms = parse_env(setting, value)?,
+                "SHUTDOWN_TIMEOUT_MS" => self.shutdown_timeout_ms = parse_env(setting, value)?,
+                "CACHE_ENTRIES" => self.cache_entries = parse_env(setting, value)?,
+                "MAX_SESSIONS" => self.max_sessions = parse_env(setting, value)?,
+                "SESSION_TTL_MS" => self.session_ttl_ms = parse_env(setting, value)?,
+                "SESSION_TT_SIZE_MB" => self.session_tt_size_mb = parse_env(setting, value)?,
+                "ADMIN_TOKEN" => self.admin_token = Some(value.to_string()),
+                "LOG_FORMAT" => self.log_format = parse_env(setting, value)?,
+                _ => {}
+            }
+        }
+        Ok(self)
+    }
+
+    /// Listens on `port` instead of the configured one.
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.bind.set_port(port);
+        self
+    }
+
+    /// Keeps only the bots named in the comma-separated list `names`.
+    fn keep_bots(&mut self, names: &str) -> Result<(), GameYError> {
+        let names: Vec<&str> = names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if let Some(unknown) = names
+            .iter()
+            .find(|name| !self.bots.iter().any(|bot| bot.name() == **name))
+        {
+            return Err(GameYError::ConfigError {
+                message: format!("{}BOTS names an unconfigured bot: {}", ENV_PREFIX, unknown),
+            });
+        }
+        self.bots.retain(|bot| names.contains(&bot.name()));
+        Ok(())
+    }
+
+    /// The request timeout, or `None` if it is disabled.
+    pub fn request_timeout(&self) -> Option<Duration> {
+        (self.request_timeout_ms > 0).then(|| Duration::from_millis(self.request_timeout_ms))
+    }
+
+    /// The time searches get to finish once shutdown starts.
+    pub fn shutdown_timeout(&self) -> Duration {
+        Duration::from_millis(self.shutdown_timeout_ms)
+    }
+
+    /// Creates the configured bots.
+    ///
+    /// Fails if two bots share a name or an opening book cannot be read.
+    pub fn registry(&self) -> Result<YBotRegistry, GameYError> {
+        let mut registry = YBotRegistry::new();
+        for (i, bot) in self.bots.iter().enumerate() {
+            if self.bots[..i]
+                .iter()
+                .any(|other| other.name() == bot.name())
+            {
+                return Err(GameYError::ConfigError {
+                    message: format!("Bot {} is configured twice", bot.name()),
+                });
+            }
+            registry = registry.with_bot(bot.build()?);
+        }
+        Ok(registry)
+    }
+
+    /// The response cache, or `None` if it is disabled.
+    pub fn cache(&self) -> Option<ResponseCache> {
+        (self.cache_entries > 0).then(|| ResponseCache::new(self.cache_entries))
+    }
+
+    /// The store of the search sessions, with the configured limits.
+    pub fn sessions(&self) -> SessionStore {
+        SessionStore::new(Duration::from_millis(self.session_ttl_ms), self.max_sessions)
+            .with_tt_size_mb(self.session_tt_size_mb)
+    }
+
+    /// Creates the application state with the configured bots, limits,
+    /// sessions, cache and admin token.
+    pub fn state(&self) -> Result<AppState, GameYError> {
+        Ok(AppState::new(self.registry()?)
+            .with_search_limiter(SearchLimiter::new(
+                self.max_concurrent_searches,
+                self.max_queued_searches,
+            ))
+            .with_sessions(self.sessions())
+            .with_body_limit(self.body_limit_bytes)
+            .with_request_timeout(self.request_timeout())
+            .with_cache(self.cache())
+            .with_admin_token(self.admin_token.clone()))
+    }
+}
+
+fn parse_env<T: FromStr>(setting: &str, value: &str) -> Result<T, GameYError>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| GameYError::ConfigError {
+        message: format!("{}{}={}: {}", ENV_PREFIX, setting, value, e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_offers_the_standard_bots() {
+        let mut names = ServerConfig::default().registry().unwrap().names();
+        names.sort();
+        assert_eq!(names, ["fast_bot", "random_bot", "smart_bot"]);
+    }
+
+    #[test]
+    fn test_toml_and_json_give_the_same_config() {
+        let toml = r#"
+            bind = "127.0.0.1:8080"
+            request_timeout_ms = 0
+            log_format = "json"
+
+            [[bots]]
+            type = "minimax"
+            name = "ladder_5"
+            max_time_ms = 300
+            skill = 5
+            threads = 2
+        "#;
+        let json = r#"{
+            "bind": "127.0.0.1:8080",
+            "request_timeout_ms": 0,
+            "log_format": "json",
+            "bots": [{"type": "minimax", "name": "ladder_5", "max_time_ms": 300, "skill": 5, "threads": 2}]
+        }"#;
+
+        let config = ServerConfig::from_toml(toml).unwrap();
+        assert_eq!(config, ServerConfig::from_json(json).unwrap());
+        assert_eq!(config.request_timeout(), None);
+        assert_eq!(config.log_format, LogFormat::Json);
+        let BotConfig::Minimax { config: bot, .. } = &config.bots[0] else {
+            panic!("expected a minimax bot");
+        };
+        assert_eq!((bot.skill, bot.max_time_ms, bot.threads), (5, 300, 2));
+        assert_eq!(bot.min_time_ms, MinimaxConfig::default().min_time_ms);
+    }
+
+    #[test]
+    fn test_unknown_settings_are_rejected() {
+        let result = ServerConfig::from_toml("max_concurent_searches = 2");
+        assert!(matches!(result, Err(GameYError::ConfigError { .. })));
+    }
+
+    // A misspelt bot setting would otherwise be dropped silently and the bot
+    // would run with the default instead.
+    #[test]
+    fn test_unknown_bot_settings_are_rejected() {
+        let minimax = ServerConfig::from_toml(
+            r#"
+            [[bots]]
+            type = "minimax"
+            name = "ladder_5"
+            max_time = 300
+            "#,
+        );
+        let Err(GameYError::ConfigError { message }) = minimax else {
+            panic!("expected a configuration error, got {:?}", minimax);
+        };
+        assert!(message.contains("max_time"), "{}", message);
+
+        for json in [
+            r#"{"bots": [{"type": "minimax", "name": "a", "skil": 3}]}"#,
+            r#"{"bots": [{"type": "random", "book": "book.json"}]}"#,
+            r#"{"bots": [{"type": "fast", "use_bok": false}]}"#,
+        ] {
+            let result = ServerConfig::from_json(json);
+            assert!(matches!(result, Err(GameYError::ConfigError { .. })), "{}", json);
+        }
+    }
+
+    #[test]
+    fn test_env_vars_override_the_file() {
+        let config = ServerConfig::from_json(r#"{"max_queued_searches": 4}"#)
+            .unwrap()
+            .with_env_vars([
+                ("GAMEY_PORT", "9000"),
+                ("GAMEY_MAX_QUEUED_SEARCHES", "8"),
+                ("GAMEY_BOTS", "random_bot, smart_bot"),
+                ("HOME", "/root"),
+            ])
+            .unwrap();
+
+        assert_eq!(config.bind.port(), 9000);
+        assert_eq!(config.max_queued_searches, 8);
+        assert_eq!(
+            config.bots,
+            [BotConfig::Random, BotConfig::Smart(BookConfig::default())]
+        );
+    }
+
+    #[test]
+    fn test_cache_is_off_unless_sized() {
+        assert!(ServerConfig::default().state().unwrap().cache().is_none());
+
+        let config = ServerConfig::default()
+            .with_env_vars([("GAMEY_CACHE_ENTRIES", "100")])
+            .unwrap();
+        assert_eq!(config.cache().map(|cache| cache.capacity()), Some(100));
+    }
+
+    #[test]
+    fn test_session_limits_reach_the_state() {
+        let config = ServerConfig::from_toml("max_sessions = 1")
+            .unwrap()
+            .with_env_vars([("GAMEY_SESSION_TT_SIZE_MB", "2")])
+            .unwrap();
+        assert_eq!(config.session_tt_size_mb, 2);
+
+        let sessions = config.state().unwrap().sessions();
+        sessions.session("fast_bot", "g1");
+        sessions.session("fast_bot", "g2");
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_admin_token_reaches_the_state() {
+        assert_eq!(ServerConfig::default().state().unwrap().admin_token(), None);
+
+        let config = ServerConfig::from_toml(r#"admin_token = "secret""#).unwrap();
+        assert_eq!(config.state().unwrap().admin_token(), Some("secret"));
+
+        let config = config.with_env_vars([("GAMEY_ADMIN_TOKEN", "")]).unwrap();
+        assert_eq!(config.state().unwrap().admin_token(), None);
+    }
+
+    #[test]
+    fn test_invalid_env_vars_are_reported() {
+        let result = ServerConfig::default().with_env_vars([("GAMEY_LOG_FORMAT", "xml")]);
+        assert!(result.unwrap_err().to_string().contains("GAMEY_LOG_FORMAT"));
+
+        let result = ServerConfig::default().with_env_vars([("GAMEY_BOTS", "mystery_bot")]);
+        assert!(matches!(result, Err(GameYError::ConfigError { .. })));
+    }
+
+    #[test]
+    fn test_duplicate_bot_names_are_rejected() {
+        let config = ServerConfig {
+            bots: vec![
+                BotConfig::Fast(BookConfig::default()),
+                BotConfig::Fast(BookConfig::default()),
+            ],
+            ..ServerConfig::default()
+        };
+        assert!(config.registry().is_err());
+    }
+
+    #[test]
+    fn test_built_in_bots_take_book_settings() {
+        let config = ServerConfig::from_toml(
+            r#"
+            [[bots]]
+            type = "fast"
+            use_book = false
+
+            [[bots]]
+            type = "smart"
+            book = "/nonexistent/book.json"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.bots[0],
+            BotConfig::Fast(BookConfig {
+                book: None,
+                use_book: false,
+            })
+        );
+        let result = config.registry();
+        assert!(matches!(result, Err(GameYError::IoError { .. })));
+    }
+}
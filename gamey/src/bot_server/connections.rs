@@ -0,0 +1,82 @@
+use crate::{
+    GameY, PlayerId, VirtualConnections, YEN,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    virtual_connections,
+};
+use axum::{Json, extract::Path};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Path parameters for the connections endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct ConnectionsParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+}
+
+/// Virtual connections of one player.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct PlayerConnections {
+    pub player: u32,
+    #[serde(flatten)]
+    pub connections: VirtualConnections,
+}
+
+/// Response returned by the connections endpoint on success.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ConnectionsResponse {
+    pub api_version: String,
+    /// One entry per player, in player order.
+    pub players: Vec<PlayerConnections>,
+}
+
+/// Handler for the virtual-connection endpoint.
+///
+/// Lists the intact bridges and edge templates of both players, so that a
+/// UI can draw them for learners.
+///
+/// # Route
+/// `POST /{api_version}/connections`
+///
+/// # Request Body
+/// A JSON object in YEN format representing the position.
+///
+/// # Response
+/// On success, returns a [`ConnectionsResponse`]. On failure, returns an
+/// [`ErrorResponse`].
+#[utoipa::path(
+    post, path = "/{api_version}/connections", summary = "Bridges and edge templates of both players",
+    params(ConnectionsParams),
+    request_body = YEN,
+    responses((status = 200, body = ConnectionsResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn connections(
+    Path(params): Path<ConnectionsParams>,
+    JsonBody(yen): JsonBody<YEN>,
+) -> Result<Json<ConnectionsResponse>, ErrorResponse> {
+    let game_y = match GameY::try_from(yen) {
+        Ok(game) => game,
+        Err(err) => {
+            return Err(ErrorResponse::error(
+                &format!("Invalid YEN format: {}", err),
+                Some(params.api_version),
+                None,
+            )
+            .with_code(ErrorCode::from(&err)));
+        }
+    };
+
+    let players = (0..2)
+        .map(|id| PlayerConnections {
+            player: id,
+            connections: virtual_connections(&game_y, PlayerId::new(id)),
+        })
+        .collect();
+    Ok(Json(ConnectionsResponse {
+        api_version: params.api_version,
+        players,
+    }))
+}
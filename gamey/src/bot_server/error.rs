@@ -1,22 +1,158 @@
-use axum::{Json, http::StatusCode, response::IntoResponse};
-use serde::{Deserialize, Serialize};
+use crate::GameYError;
+use axum::{
+    Json,
+    extract::{FromRequest, Request, rejection::JsonRejection},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::BTreeMap;
+use utoipa::openapi::{ContentBuilder, Ref, RefOr, ResponseBuilder, response::Response};
+use utoipa::{IntoResponses, ToSchema};
+
+/// Machine-readable category of an [`ErrorResponse`].
+///
+/// Serialized in `SCREAMING_SNAKE_CASE`, e.g. `"BOT_NOT_FOUND"`, so clients
+/// can branch on it instead of parsing the message. Each code maps to the
+/// HTTP status the error is returned with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The API version in the path is not supported.
+    UnsupportedVersion,
+    /// The request does not describe a valid position.
+    InvalidYen,
+    /// The request is malformed in another way, e.g. an empty board size.
+    InvalidRequest,
+    /// The request body is larger than the server accepts.
+    BodyTooLarge,
+    /// No bot is registered under the requested id.
+    BotNotFound,
+    /// No hosted game exists under the requested id, or it has expired.
+    GameNotFound,
+    /// The bot found no move to play.
+    NoMoves,
+    /// The position is already decided.
+    GameOver,
+    /// The move breaks the rules, e.g. it targets an occupied cell or is
+    /// played out of turn.
+    IllegalMove,
+    /// The bot does not support the requested operation.
+    UnsupportedOperation,
+    /// The request lacks valid credentials, e.g. the admin token.
+    Unauthorized,
+    /// The search did not finish in time.
+    Timeout,
+    /// The server is busy; the client should retry later.
+    Overloaded,
+    /// An unexpected failure on the server.
+    #[default]
+    Internal,
+}
+
+impl ErrorCode {
+    /// Every code, in declaration order.
+    pub const ALL: [ErrorCode; 14] = [
+        ErrorCode::UnsupportedVersion,
+        ErrorCode::InvalidYen,
+        ErrorCode::InvalidRequest,
+        ErrorCode::BodyTooLarge,
+        ErrorCode::BotNotFound,
+        ErrorCode::GameNotFound,
+        ErrorCode::NoMoves,
+        ErrorCode::GameOver,
+        ErrorCode::IllegalMove,
+        ErrorCode::UnsupportedOperation,
+        ErrorCode::Unauthorized,
+        ErrorCode::Timeout,
+        ErrorCode::Overloaded,
+        ErrorCode::Internal,
+    ];
+
+    /// The HTTP status an error with this code is returned with.
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::UnsupportedVersion | ErrorCode::InvalidYen | ErrorCode::InvalidRequest => {
+                StatusCode::BAD_REQUEST
+            }
+            ErrorCode::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorCode::BotNotFound | ErrorCode::GameNotFound => StatusCode::NOT_FOUND,
+            ErrorCode::GameOver => StatusCode::CONFLICT,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::NoMoves | ErrorCode::IllegalMove | ErrorCode::UnsupportedOperation => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ErrorCode::Timeout | ErrorCode::Overloaded => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// The code as serialized, e.g. `"BOT_NOT_FOUND"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::UnsupportedVersion => "UNSUPPORTED_VERSION",
+            ErrorCode::InvalidYen => "INVALID_YEN",
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::BodyTooLarge => "BODY_TOO_LARGE",
+            ErrorCode::BotNotFound => "BOT_NOT_FOUND",
+            ErrorCode::GameNotFound => "GAME_NOT_FOUND",
+            ErrorCode::NoMoves => "NO_MOVES",
+            ErrorCode::GameOver => "GAME_OVER",
+            ErrorCode::IllegalMove => "ILLEGAL_MOVE",
+            ErrorCode::UnsupportedOperation => "UNSUPPORTED_OPERATION",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::Overloaded => "OVERLOADED",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+}
+
+impl From<&GameYError> for ErrorCode {
+    fn from(err: &GameYError) -> Self {
+        match err {
+            GameYError::GameOver { .. } => ErrorCode::GameOver,
+            GameYError::NoMoveToUndo => ErrorCode::InvalidRequest,
+            GameYError::IoError { .. }
+            | GameYError::ServerError { .. }
+            | GameYError::ConfigError { .. } => ErrorCode::Internal,
+            GameYError::BadCoordsNumber { .. }
+            | GameYError::CoordOutOfRange { .. }
+            | GameYError::InvalidCoordinates { .. }
+            | GameYError::Occupied { .. }
+            | GameYError::InvalidPlayerTurn { .. }
+            | GameYError::SwapNotAllowed { .. } => ErrorCode::IllegalMove,
+            GameYError::SerdeError { .. }
+            | GameYError::InvalidCharInLayout { .. }
+            | GameYError::InvalidNumPlayers { .. }
+            | GameYError::InvalidYENLayout { .. }
+            | GameYError::InvalidYENLayoutLine { .. } => ErrorCode::InvalidYen,
+        }
+    }
+}
 
 /// A structured error response returned by the bot server API.
 ///
 /// This type is serialized to JSON and returned when API requests fail.
-/// It includes context about which API version and bot were involved.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+/// It includes context about which API version and bot were involved, and
+/// an [`ErrorCode`] that decides the HTTP status.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct ErrorResponse {
     /// The API version that was requested, if available.
     pub api_version: Option<String>,
     /// The bot ID that was requested, if available.
     pub bot_id: Option<String>,
+    /// Machine-readable category of the error.
+    #[serde(default)]
+    pub code: ErrorCode,
     /// A human-readable error message describing what went wrong.
     pub message: String,
 }
 
 impl ErrorResponse {
-    /// Creates a new error response with the given message and optional context.
+    /// Creates a new error response with the given message and optional
+    /// context. The code is [`ErrorCode::Internal`] until set with
+    /// [`with_code`](Self::with_code).
     ///
     /// # Arguments
     /// * `message` - A description of the error
@@ -26,14 +162,78 @@ impl ErrorResponse {
         Self {
             bot_id,
             api_version,
+            code: ErrorCode::default(),
             message: message.to_string(),
         }
     }
+
+    /// Sets the error code.
+    pub fn with_code(mut self, code: ErrorCode) -> Self {
+        self.code = code;
+        self
+    }
+
+    /// The HTTP status this error is returned with.
+    pub fn status(&self) -> StatusCode {
+        self.code.status()
+    }
 }
 
 impl IntoResponse for ErrorResponse {
+    /// Also stores the [`ErrorCode`] in the response extensions, where
+    /// middleware such as the metrics layer can read it.
     fn into_response(self) -> axum::response::Response {
-        (StatusCode::BAD_REQUEST, Json(self)).into_response()
+        let code = self.code;
+        let mut response = (self.status(), Json(self)).into_response();
+        response.extensions_mut().insert(code);
+        response
+    }
+}
+
+impl From<JsonRejection> for ErrorResponse {
+    /// A body over the server's limit is an [`ErrorCode::BodyTooLarge`]; one
+    /// that is not JSON, does not fit the expected type or is sent without a
+    /// JSON content type is an [`ErrorCode::InvalidRequest`].
+    fn from(rejection: JsonRejection) -> Self {
+        let code = if rejection.status() == StatusCode::PAYLOAD_TOO_LARGE {
+            ErrorCode::BodyTooLarge
+        } else {
+            ErrorCode::InvalidRequest
+        };
+        ErrorResponse::error(&rejection.body_text(), None, None).with_code(code)
+    }
+}
+
+/// A JSON request body, like Axum's [`Json`] extractor, whose rejections are
+/// answered with an [`ErrorResponse`] instead of Axum's plain-text errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonBody<T>(pub T);
+
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ErrorResponse;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state).await?;
+        Ok(JsonBody(value))
+    }
+}
+
+impl IntoResponses for ErrorResponse {
+    /// Documents the error as the `default` response of an operation, since
+    /// its status depends on the [`ErrorCode`].
+    fn responses() -> BTreeMap<String, RefOr<Response>> {
+        let content = ContentBuilder::new()
+            .schema(Some(Ref::from_schema_name("ErrorResponse")))
+            .build();
+        let response = ResponseBuilder::new()
+            .description("The request failed; the code decides the HTTP status.")
+            .content("application/json", content)
+            .build();
+        BTreeMap::from([("default".to_string(), RefOr::T(response))])
     }
 }
 
@@ -87,6 +287,78 @@ mod tests {
         assert_eq!(err.bot_id, Some("random".to_string()));
     }
 
+    #[test]
+    fn test_deserialize_without_code_is_internal() {
+        let json = r#"{"api_version":null,"bot_id":null,"message":"old"}"#;
+        let err: ErrorResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(err.code, ErrorCode::Internal);
+    }
+
+    #[test]
+    fn test_code_serializes_in_screaming_snake_case() {
+        let err = ErrorResponse::error("Missing", None, None).with_code(ErrorCode::BotNotFound);
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(json.contains("\"code\":\"BOT_NOT_FOUND\""));
+    }
+
+    #[test]
+    fn test_codes_map_to_statuses() {
+        assert_eq!(
+            ErrorCode::UnsupportedVersion.status(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(ErrorCode::InvalidYen.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(ErrorCode::BotNotFound.status(), StatusCode::NOT_FOUND);
+        assert_eq!(ErrorCode::GameOver.status(), StatusCode::CONFLICT);
+        assert_eq!(ErrorCode::Unauthorized.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            ErrorCode::NoMoves.status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            ErrorCode::Overloaded.status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(ErrorCode::Timeout.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_code_from_game_error() {
+        let layout = GameYError::InvalidYENLayout {
+            expected: 3,
+            found: 2,
+        };
+        assert_eq!(ErrorCode::from(&layout), ErrorCode::InvalidYen);
+        let server = GameYError::ServerError {
+            message: "down".to_string(),
+        };
+        assert_eq!(ErrorCode::from(&server), ErrorCode::Internal);
+        assert_eq!(
+            ErrorCode::from(&GameYError::NoMoveToUndo),
+            ErrorCode::InvalidRequest
+        );
+    }
+
+    #[test]
+    fn test_into_response_uses_code_status() {
+        let response = ErrorResponse::error("Missing", None, None)
+            .with_code(ErrorCode::BotNotFound)
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.extensions().get::<ErrorCode>(),
+            Some(&ErrorCode::BotNotFound)
+        );
+    }
+
+    #[test]
+    fn test_code_as_str_matches_serialized_name() {
+        for code in [ErrorCode::InvalidYen, ErrorCode::GameNotFound, ErrorCode::Internal] {
+            let json = serde_json::to_value(code).unwrap();
+            assert_eq!(json, code.as_str());
+        }
+    }
+
     #[test]
     fn test_clone() {
         let err = ErrorResponse::error("Clone test", Some("v1".to_string()), None);
@@ -0,0 +1,357 @@
+//! Games hosted by the server.
+//!
+//! Instead of sending the whole board with every request, a client can create
+//! a game on the server and send only its moves. The server keeps the
+//! authoritative [`GameY`], the move history and, for each bot in the game, a
+//! [`SearchSession`] so that the bot reuses its tables between moves. Games
+//! expire when unused for a while.
+
+use crate::{
+    GameY, GameYError, MoveRequest, Movement, PieChoice, PlayerId, SearchOptions, SearchSession,
+    YBot,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Default time after which an unused game is dropped.
+pub const DEFAULT_GAME_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Default number of games kept at once.
+pub const DEFAULT_MAX_GAMES: usize = 256;
+
+/// Rules a hosted game is played under.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(default)]
+pub struct GameRules {
+    /// Whether the second player may swap in reply to the opening stone.
+    pub pie_rule: bool,
+}
+
+/// A move in the history of a hosted game.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub struct HistoryEntry {
+    pub player: u32,
+    #[serde(flatten)]
+    pub movement: MoveRequest,
+}
+
+/// A game hosted by the server.
+pub struct HostedGame {
+    game: GameY,
+    rules: GameRules,
+    /// Bot playing each side, by player index; `None` for a client player.
+    bots: Vec<Option<String>>,
+    history: Vec<HistoryEntry>,
+    /// Search tables of each side's bot, by player index.
+    sessions: Vec<SearchSession>,
+}
+
+impl HostedGame {
+    /// Creates an empty game of `size` with the given rules and bots.
+    pub fn new(size: u32, rules: GameRules, bots: Vec<Option<String>>) -> Self {
+        Self {
+            game: GameY::new(size),
+            rules,
+            sessions: bots.iter().map(|_| SearchSession::new()).collect(),
+            bots,
+            history: Vec::new(),
+        }
+    }
+
+    /// The current position.
+    pub fn game(&self) -> &GameY {
+        &self.game
+    }
+
+    /// The rules the game is played under.
+    pub fn rules(&self) -> GameRules {
+        self.rules
+    }
+
+    /// Bot playing each side, by player index.
+    pub fn bots(&self) -> &[Option<String>] {
+        &self.bots
+    }
+
+    /// Moves played so far, oldest first.
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+
+    /// The bot to move, if the game goes on and a bot plays the side to move.
+    pub fn bot_to_move(&self) -> Option<&str> {
+        let player = self.game.next_player()?;
+        self.bots.get(player.id() as usize)?.as_deref()
+    }
+
+    /// Returns `true` if the side to move may still swap under the Pie Rule.
+    pub fn can_swap(&self) -> bool {
+        self.rules.pie_rule
+            && matches!(
+                self.history.as_slice(),
+                [HistoryEntry {
+                    movement: MoveRequest::Placement { .. },
+                    ..
+                }]
+            )
+    }
+
+    /// Returns `true` if the next move is the opening of a Pie Rule game.
+    pub fn is_pie_opening(&self) -> bool {
+        self.rules.pie_rule && self.history.is_empty()
+    }
+
+    /// Plays `movement` for the side to move, checking it against the rules.
+    pub fn play(&mut self, movement: MoveRequest) -> Result<(), GameYError> {
+        let Some(player) = self.game.next_player() else {
+            return Err(GameYError::GameOver {
+                movement: movement.by(PlayerId::new(0)),
+            });
+        };
+        if movement == MoveRequest::Swap && !self.can_swap() {
+            return Err(GameYError::SwapNotAllowed { player });
+        }
+        self.game.play(movement.by(player))?;
+        self.history.push(HistoryEntry {
+            player: player.id(),
+            movement,
+        });
+        Ok(())
+    }
+
+    /// Lets `bot` choose and play the move of the side to move.
+    ///
+    /// Under the Pie Rule the bot picks a balanced opening as first player
+    /// and decides whether to swap as second player. Later moves are searched
+    /// in the bot's session for its side, so its tables carry over between
+    /// moves. Returns `None` if the bot finds no move.
+    pub fn play_bot(&mut self, bot: &dyn YBot) -> Result<Option<MoveRequest>, GameYError> {
+        let Some(player) = self.game.next_player() else {
+            return Ok(None);
+        };
+        let movement = if self.is_pie_opening() {
+            bot.choose_pie_opening(&self.game)
+                .map(|coords| MoveRequest::Placement { coords })
+        } else if self.can_swap() && bot.decide_pie(&self.game) == PieChoice::Swap {
+            Some(MoveRequest::Swap)
+        } else {
+            let session = &mut self.sessions[player.id() as usize];
+            bot.choose_move_in_session(&self.game, &SearchOptions::default(), session)
+                .map(|coords| MoveRequest::Placement { coords })
+        };
+        let Some(movement) = movement else {
+            return Ok(None);
+        };
+        self.play(movement)?;
+        Ok(Some(movement))
+    }
+}
+
+impl From<&HistoryEntry> for Movement {
+    fn from(entry: &HistoryEntry) -> Self {
+        entry.movement.by(PlayerId::new(entry.player))
+    }
+}
+
+/// A hosted game shared between the requests that play it.
+pub type SharedGame = Arc<Mutex<HostedGame>>;
+
+struct StoredGame {
+    game: SharedGame,
+    last_used: Instant,
+}
+
+/// Games hosted by the server, keyed by game id.
+pub struct GameStore {
+    ttl: Duration,
+    max_games: usize,
+    games: Mutex<HashMap<String, StoredGame>>,
+}
+
+impl GameStore {
+    /// Creates a store that drops games unused for `ttl` and keeps at most
+    /// `max_games` at once, evicting the least recently used one.
+    pub fn new(ttl: Duration, max_games: usize) -> Self {
+        Self {
+            ttl,
+            max_games: max_games.max(1),
+            games: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Stores `game` under a new random id and returns the id.
+    ///
+    /// Expired games are dropped on every call.
+    pub fn insert(&self, game: HostedGame) -> String {
+        let now = Instant::now();
+        let mut games = self.games.lock().unwrap_or_else(PoisonError::into_inner);
+        games.retain(|_, stored| now.duration_since(stored.last_used) < self.ttl);
+
+        if games.len() >= self.max_games {
+            let oldest = games
+                .iter()
+                .min_by_key(|(_, stored)| stored.last_used)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                games.remove(&oldest);
+            }
+        }
+
+        let mut rng = rand::rng();
+        let id = loop {
+            let id = format!("{:016x}", rng.random::<u64>());
+            if !games.contains_key(&id) {
+                break id;
+            }
+        };
+        games.insert(
+            id.clone(),
+            StoredGame {
+                game: Arc::new(Mutex::new(game)),
+                last_used: now,
+            },
+        );
+        id
+    }
+
+    /// Returns the game `id` if it exists and has not expired, and marks it
+    /// as used.
+    pub fn get(&self, id: &str) -> Option<SharedGame> {
+        let now = Instant::now();
+        let mut games = self.games.lock().unwrap_or_else(PoisonError::into_inner);
+        let stored = games
+            .get_mut(id)
+            .filter(|stored| now.duration_since(stored.last_used) < self.ttl)?;
+        stored.last_used = now;
+        Some(Arc::clone(&stored.game))
+    }
+
+    /// Removes the game `id` and returns it if it had not expired.
+    pub fn remove(&self, id: &str) -> Option<SharedGame> {
+        let mut games = self.games.lock().unwrap_or_else(PoisonError::into_inner);
+        games
+            .remove(id)
+            .filter(|stored| stored.last_used.elapsed() < self.ttl)
+            .map(|stored| stored.game)
+    }
+
+    /// Number of games currently stored, including expired ones that have
+    /// not been dropped yet.
+    pub fn len(&self) -> usize {
+        self.games
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns `true` if no game is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for GameStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_GAME_TTL, DEFAULT_MAX_GAMES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Coordinates, RandomBot};
+
+    fn pie_game() -> HostedGame {
+        HostedGame::new(4, GameRules { pie_rule: true }, vec![None, None])
+    }
+
+    #[test]
+    fn test_play_records_history() {
+        let mut game = pie_game();
+        let coords = Coordinates::new(1, 1, 1);
+        game.play(MoveRequest::Placement { coords }).unwrap();
+
+        assert_eq!(
+            game.history(),
+            [HistoryEntry {
+                player: 0,
+                movement: MoveRequest::Placement { coords },
+            }]
+        );
+        assert!(game.can_swap());
+    }
+
+    #[test]
+    fn test_swap_needs_pie_rule() {
+        let mut game = HostedGame::new(4, GameRules::default(), vec![None, None]);
+        game.play(MoveRequest::Placement {
+            coords: Coordinates::new(1, 1, 1),
+        })
+        .unwrap();
+
+        let result = game.play(MoveRequest::Swap);
+        assert!(matches!(result, Err(GameYError::SwapNotAllowed { .. })));
+        assert_eq!(game.history().len(), 1);
+    }
+
+    #[test]
+    fn test_bot_to_move_follows_turn() {
+        let game = HostedGame::new(4, GameRules::default(), vec![None, Some("fast_bot".into())]);
+        assert_eq!(game.bot_to_move(), None);
+
+        let mut game = game;
+        game.play(MoveRequest::Placement {
+            coords: Coordinates::new(1, 1, 1),
+        })
+        .unwrap();
+        assert_eq!(game.bot_to_move(), Some("fast_bot"));
+    }
+
+    #[test]
+    fn test_bot_opens_and_may_swap_under_pie_rule() {
+        let mut game = pie_game();
+        let bot = RandomBot;
+        let opening = game.play_bot(&bot).unwrap();
+        assert!(matches!(opening, Some(MoveRequest::Placement { .. })));
+
+        let reply = game.play_bot(&bot).unwrap().unwrap();
+        assert_eq!(game.history()[1].movement, reply);
+        assert!(!game.can_swap());
+    }
+
+    #[test]
+    fn test_store_returns_inserted_game() {
+        let store = GameStore::default();
+        let id = store.insert(pie_game());
+        assert!(store.get(&id).is_some());
+        assert!(store.get("missing").is_none());
+        assert!(store.remove(&id).is_some());
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_expired_games_are_dropped() {
+        let store = GameStore::new(Duration::ZERO, DEFAULT_MAX_GAMES);
+        let id = store.insert(pie_game());
+        assert!(store.get(&id).is_none());
+        store.insert(pie_game());
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_game_is_evicted() {
+        let store = GameStore::new(DEFAULT_GAME_TTL, 2);
+        let first = store.insert(pie_game());
+        let second = store.insert(pie_game());
+        store.get(&first);
+        store.insert(pie_game());
+
+        assert_eq!(store.len(), 2);
+        assert!(store.get(&first).is_some());
+        assert!(store.get(&second).is_none());
+    }
+}
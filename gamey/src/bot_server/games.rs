@@ -0,0 +1,363 @@
+use crate::{
+    GameRules, GameSummary, GameYError, HistoryEntry, HostedGame, MoveRequest,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable,
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, PoisonError};
+use tokio::task::JoinError;
+use utoipa::{IntoParams, ToSchema};
+
+/// Number of sides in a game of Y.
+const PLAYERS: usize = 2;
+
+/// Path parameters for the game creation endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct CreateGameParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+}
+
+/// Path parameters for the endpoints of one hosted game.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct GameParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Identifier of the game.
+    game_id: String,
+}
+
+/// Request body of the game creation endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct CreateGameRequest {
+    /// The board size.
+    pub size: u32,
+    #[serde(default)]
+    pub rules: GameRules,
+    /// Bot playing each side, by player index; `null` or a missing entry
+    /// leaves the side to the client.
+    #[serde(default)]
+    pub bots: Vec<Option<String>>,
+}
+
+fn default_bot_reply() -> bool {
+    true
+}
+
+/// Request body of the move endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct GameMoveRequest {
+    /// The client's move. Without it, the bot to move plays.
+    #[serde(rename = "move", default, skip_serializing_if = "Option::is_none")]
+    pub movement: Option<MoveRequest>,
+    /// Whether a bot playing the next side replies at once.
+    #[serde(default = "default_bot_reply")]
+    pub bot_reply: bool,
+}
+
+/// State of a hosted game, returned by all game endpoints.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GameStateResponse {
+    pub api_version: String,
+    pub game_id: String,
+    /// The current position and its status.
+    #[serde(flatten)]
+    pub game: GameSummary,
+    pub rules: GameRules,
+    /// Bot playing each side, by player index.
+    pub bots: Vec<Option<String>>,
+    /// Moves played so far, oldest first.
+    pub history: Vec<HistoryEntry>,
+}
+
+impl GameStateResponse {
+    fn new(api_version: String, game_id: String, game: &HostedGame) -> Self {
+        Self {
+            api_version,
+            game_id,
+            game: GameSummary::from(game.game()),
+            rules: game.rules(),
+            bots: game.bots().to_vec(),
+            history: game.history().to_vec(),
+        }
+    }
+}
+
+fn game_not_found(params: GameParams) -> ErrorResponse {
+    ErrorResponse::error(
+        &format!("Game not found: {}", params.game_id),
+        Some(params.api_version),
+        None,
+    )
+    .with_code(ErrorCode::GameNotFound)
+}
+
+/// Handler that starts a game hosted by the server.
+///
+/// The server keeps the position, the history and the search tables of the
+/// game's bots; the client then only sends moves. Creating a game never
+/// searches: if a bot plays first, post an empty move to let it play.
+///
+/// # Route
+/// `POST /{api_version}/games`
+///
+/// # Request Body
+/// A [`CreateGameRequest`], e.g.
+/// `{"size": 7, "rules": {"pie_rule": true}, "bots": [null, "fast_bot"]}`.
+///
+/// # Response
+/// On success, returns the new game as a [`GameStateResponse`]. Unknown bots
+/// return an [`ErrorResponse`] with [`ErrorCode::BotNotFound`].
+#[utoipa::path(
+    post, path = "/{api_version}/games", summary = "Start a game hosted by the server",
+    params(CreateGameParams),
+    request_body = CreateGameRequest,
+    responses((status = 200, body = GameStateResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn create_game(
+    State(state): State<AppState>,
+    Path(params): Path<CreateGameParams>,
+    JsonBody(request): JsonBody<CreateGameRequest>,
+) -> Result<Json<GameStateResponse>, ErrorResponse> {
+    if request.size == 0 || request.bots.len() > PLAYERS {
+        return Err(ErrorResponse::error(
+            &format!(
+                "A game needs a board size of at least 1 and at most {} bots",
+                PLAYERS
+            ),
+            Some(params.api_version),
+            None,
+        )
+        .with_code(ErrorCode::InvalidRequest));
+    }
+    if let Some(bot_id) = request
+        .bots
+        .iter()
+        .flatten()
+        .find(|bot_id| state.bots().find(bot_id).is_none())
+    {
+        let available = state.bots().names().join(", ");
+        return Err(ErrorResponse::error(
+            &format!("Bot not found: {}, available bots: [{}]", bot_id, available),
+            Some(params.api_version),
+            Some(bot_id.clone()),
+        )
+        .with_code(ErrorCode::BotNotFound));
+    }
+
+    let mut bots = request.bots;
+    bots.resize(PLAYERS, None);
+    let game = HostedGame::new(request.size, request.rules, bots);
+    let response = GameStateResponse::new(params.api_version, String::new(), &game);
+    let game_id = state.games().insert(game);
+    Ok(Json(GameStateResponse {
+        game_id,
+        ..response
+    }))
+}
+
+/// Handler returning the state of a hosted game.
+///
+/// # Route
+/// `GET /{api_version}/games/{game_id}`
+///
+/// # Response
+/// On success, returns a [`GameStateResponse`]. Unknown or expired games
+/// return an [`ErrorResponse`] with [`ErrorCode::GameNotFound`].
+#[utoipa::path(
+    get, path = "/{api_version}/games/{game_id}", summary = "Position, history and status of a hosted game",
+    params(GameParams),
+    responses((status = 200, body = GameStateResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn get_game(
+    State(state): State<AppState>,
+    Path(params): Path<GameParams>,
+) -> Result<Json<GameStateResponse>, ErrorResponse> {
+    let Some(game) = state.games().get(&params.game_id) else {
+        return Err(game_not_found(params));
+    };
+    let game = game.lock().unwrap_or_else(PoisonError::into_inner);
+    Ok(Json(GameStateResponse::new(
+        params.api_version,
+        params.game_id,
+        &game,
+    )))
+}
+
+/// Handler that ends a hosted game and returns its final state.
+///
+/// # Route
+/// `DELETE /{api_version}/games/{game_id}`
+///
+/// # Response
+/// On success, returns the last [`GameStateResponse`] of the game. Unknown or
+/// expired games return an [`ErrorResponse`] with [`ErrorCode::GameNotFound`].
+#[utoipa::path(
+    delete, path = "/{api_version}/games/{game_id}", summary = "End a hosted game",
+    params(GameParams),
+    responses((status = 200, body = GameStateResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn delete_game(
+    State(state): State<AppState>,
+    Path(params): Path<GameParams>,
+) -> Result<Json<GameStateResponse>, ErrorResponse> {
+    let Some(game) = state.games().remove(&params.game_id) else {
+        return Err(game_not_found(params));
+    };
+    let game = game.lock().unwrap_or_else(PoisonError::into_inner);
+    Ok(Json(GameStateResponse::new(
+        params.api_version,
+        params.game_id,
+        &game,
+    )))
+}
+
+/// Handler that plays a move in a hosted game.
+///
+/// The client's move is checked against the rules of the game; a swap is
+/// only allowed under the Pie Rule, in reply to the opening stone. If a bot
+/// plays the next side and `bot_reply` is not `false`, the bot answers in
+/// the same request. Without a move, the bot to move plays.
+///
+/// # Route
+/// `POST /{api_version}/games/{game_id}/moves`
+///
+/// # Request Body
+/// A [`GameMoveRequest`], e.g.
+/// `{"move": {"type": "placement", "coords": {"x": 1, "y": 1, "z": 1}}}`.
+///
+/// # Response
+/// On success, returns the [`GameStateResponse`] after the moves. Moves that
+/// break the rules return an [`ErrorResponse`] with
+/// [`ErrorCode::IllegalMove`], or [`ErrorCode::GameOver`] once the game has
+/// ended. Only the bot's reply takes a search slot; on a saturated server it
+/// is refused with [`ErrorCode::Overloaded`] after the client's move.
+#[utoipa::path(
+    post, path = "/{api_version}/games/{game_id}/moves", summary = "Play a move, optionally with the bot's reply",
+    params(GameParams),
+    request_body = GameMoveRequest,
+    responses((status = 200, body = GameStateResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn play_move(
+    State(state): State<AppState>,
+    Path(params): Path<GameParams>,
+    JsonBody(request): JsonBody<GameMoveRequest>,
+) -> Result<Json<GameStateResponse>, Response> {
+    let Some(game) = state.games().get(&params.game_id) else {
+        return Err(game_not_found(params).into_response());
+    };
+    let failed = |err: JoinError| {
+        ErrorResponse::error(
+            &format!("Search failed: {}", err),
+            Some(params.api_version.clone()),
+            None,
+        )
+        .into_response()
+    };
+    let rejected = |(message, code): (String, ErrorCode)| {
+        ErrorResponse::error(&message, Some(params.api_version.clone()), None)
+            .with_code(code)
+            .into_response()
+    };
+
+    // Locking waits for a bot search of the same game, so even the client's
+    // move is played off the async workers.
+    let bots = state.bots();
+    let client_game = Arc::clone(&game);
+    let replying = tokio::task::spawn_blocking(move || {
+        let mut game = client_game.lock().unwrap_or_else(PoisonError::into_inner);
+        let bot_turn = match request.movement {
+            Some(movement) => {
+                if let Some(bot_id) = game.bot_to_move() {
+                    return Err((format!("It is {}'s turn", bot_id), ErrorCode::IllegalMove));
+                }
+                game.play(movement).map_err(illegal_move)?;
+                request.bot_reply
+            }
+            None => true,
+        };
+
+        let bot = game.bot_to_move().and_then(|bot_id| bots.find(bot_id));
+        match (bot, request.movement) {
+            (Some(bot), _) if bot_turn => Ok(Some(bot)),
+            (None, None) if game.game().check_game_over() => {
+                Err(("The game is already over".to_string(), ErrorCode::GameOver))
+            }
+            (None, None) => Err((
+                "No bot plays the side to move".to_string(),
+                ErrorCode::InvalidRequest,
+            )),
+            _ => Ok(None),
+        }
+    })
+    .await
+    .map_err(failed)?
+    .map_err(rejected)?;
+
+    // Only the bot's reply searches, so only it waits for a search slot.
+    let _permit = match replying {
+        Some(_) => Some(state.searches().acquire().await.map_err(IntoResponse::into_response)?),
+        None => None,
+    };
+    let (api_version, game_id) = (params.api_version.clone(), params.game_id.clone());
+    let played = run_cancellable(move || {
+        let mut game = game.lock().unwrap_or_else(PoisonError::into_inner);
+        // Another request may have moved while this one waited for a slot.
+        let bot = replying.filter(|bot| game.bot_to_move() == Some(bot.name()));
+        if let Some(bot) = bot {
+            game.play_bot(bot.as_ref())
+                .map_err(illegal_move)?
+                .ok_or_else(|| {
+                    (
+                        "No valid moves available for the bot".to_string(),
+                        ErrorCode::NoMoves,
+                    )
+                })?;
+        }
+        Ok(GameStateResponse::new(api_version, game_id, &game))
+    })
+    .await
+    .map_err(failed)?;
+
+    played.map(Json).map_err(rejected)
+}
+
+fn illegal_move(err: GameYError) -> (String, ErrorCode) {
+    (format!("Illegal move: {}", err), ErrorCode::from(&err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_request_defaults_to_bot_reply() {
+        let request: GameMoveRequest = serde_json::from_str(r#"{"move":{"type":"swap"}}"#).unwrap();
+        assert_eq!(request.movement, Some(MoveRequest::Swap));
+        assert!(request.bot_reply);
+
+        let request: GameMoveRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(request.movement, None);
+    }
+
+    #[test]
+    fn test_create_request_defaults_to_client_players() {
+        let request: CreateGameRequest = serde_json::from_str(r#"{"size":5}"#).unwrap();
+        assert_eq!(request.rules, GameRules::default());
+        assert!(request.bots.is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Health and status endpoints for container orchestration.
+//!
+//! `/health/live` only tells that the process answers, so a failing probe
+//! means it should be restarted. `/health/ready` also tells whether new
+//! searches would be served: it fails while the server drains for shutdown
+//! (see [`Lifecycle`](crate::Lifecycle)) and while every search slot and
+//! queue place is taken. None of these endpoints is limited or searches.
+
+use crate::{SUPPORTED_VERSION, state::AppState};
+use axum::{Json, extract::State, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Readiness of the server to take new searches.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Readiness {
+    /// New searches run at once or wait in the queue.
+    Ready,
+    /// All search slots and queue places are taken; new searches get `503`.
+    Saturated,
+    /// The server is shutting down.
+    Draining,
+}
+
+/// Response returned by the liveness endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct LivenessResponse {
+    /// Always `"alive"`.
+    pub status: String,
+}
+
+/// Response returned by the readiness endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ReadinessResponse {
+    pub status: Readiness,
+    /// Searches running now.
+    pub searches_running: usize,
+    /// Searches waiting for a slot.
+    pub searches_queued: usize,
+    pub max_concurrent_searches: usize,
+    pub max_queued_searches: usize,
+    /// Share of the search slots in use, from `0.0` to `1.0`.
+    pub saturation: f64,
+}
+
+/// Response returned by the status endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct StatusResponse {
+    /// Always `"ok"`; a server that cannot answer has no status.
+    pub status: String,
+    /// Version of the `gamey` crate the server was built from.
+    pub version: String,
+    pub api_version: String,
+    /// Time since the server started.
+    pub uptime_seconds: f64,
+    /// Names of the registered bots, sorted.
+    pub bots: Vec<String>,
+    /// Whether the server is shutting down.
+    pub draining: bool,
+}
+
+/// Handler for the liveness probe.
+///
+/// # Route
+/// `GET /health/live`
+///
+/// # Response
+/// A [`LivenessResponse`], always with status `200`.
+#[utoipa::path(
+    get, path = "/health/live", summary = "Liveness probe",
+    responses((status = 200, body = LivenessResponse))
+)]
+pub async fn live() -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        status: "alive".to_string(),
+    })
+}
+
+/// Handler for the readiness probe.
+///
+/// # Route
+/// `GET /health/ready`
+///
+/// # Response
+/// A [`ReadinessResponse`] with the load of the search slots, with status
+/// `200` while [`Readiness::Ready`] and `503` otherwise.
+#[utoipa::path(
+    get, path = "/health/ready", summary = "Readiness probe with the load of the search slots",
+    responses(
+        (status = 200, body = ReadinessResponse),
+        (status = 503, description = "Saturated or draining", body = ReadinessResponse)
+    )
+)]
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let searches = state.searches();
+    let (running, queued) = (searches.running(), searches.queued());
+    let status = if state.lifecycle().is_draining() {
+        Readiness::Draining
+    } else if running >= searches.max_concurrent() && queued >= searches.max_queued() {
+        Readiness::Saturated
+    } else {
+        Readiness::Ready
+    };
+    let code = match status {
+        Readiness::Ready => StatusCode::OK,
+        Readiness::Saturated | Readiness::Draining => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (
+        code,
+        Json(ReadinessResponse {
+            status,
+            searches_running: running,
+            searches_queued: queued,
+            max_concurrent_searches: searches.max_concurrent(),
+            max_queued_searches: searches.max_queued(),
+            saturation: running as f64 / searches.max_concurrent() as f64,
+        }),
+    )
+}
+
+/// Handler for the status endpoint.
+///
+/// # Route
+/// `GET /status`
+///
+/// # Response
+/// A [`StatusResponse`] with the version, uptime and bots of the server.
+#[utoipa::path(
+    get, path = "/status", summary = "Version, uptime and bots of the server",
+    responses((status = 200, body = StatusResponse))
+)]
+pub async fn status(State(state): State<AppState>) -> Json<StatusResponse> {
+    let lifecycle = state.lifecycle();
+    let mut bots = state.bots().names();
+    bots.sort();
+    Json(StatusResponse {
+        status: "ok".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        api_version: SUPPORTED_VERSION.to_string(),
+        uptime_seconds: lifecycle.uptime().as_secs_f64(),
+        bots,
+        draining: lifecycle.is_draining(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SearchLimiter, YBotRegistry};
+
+    #[tokio::test]
+    async fn test_ready_reports_saturation_and_draining() {
+        let state =
+            AppState::new(YBotRegistry::new()).with_search_limiter(SearchLimiter::new(1, 0));
+        let (code, Json(body)) = ready(State(state.clone())).await;
+        assert_eq!((code, body.status), (StatusCode::OK, Readiness::Ready));
+
+        let permit = state.searches().acquire().await.unwrap();
+        let (code, Json(body)) = ready(State(state.clone())).await;
+        assert_eq!(
+            (code, body.status),
+            (StatusCode::SERVICE_UNAVAILABLE, Readiness::Saturated)
+        );
+        assert_eq!(body.saturation, 1.0);
+        drop(permit);
+
+        state.lifecycle().start_draining();
+        let (code, Json(body)) = ready(State(state)).await;
+        assert_eq!(
+            (code, body.status),
+            (StatusCode::SERVICE_UNAVAILABLE, Readiness::Draining)
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Bounded concurrency for bot searches.
+//!
+//! Every search occupies a CPU core for its whole time budget. The
+//! [`SearchLimiter`] lets a fixed number of searches run at once and a fixed
+//! number wait for a free slot; further requests are turned away with
+//! `503 Service Unavailable` and a `Retry-After` header instead of piling up.
+//! Requests that do not search, such as `/status`, are never limited.
+//!
+//! Background searches such as pondering take a slot as well, but they never
+//! wait for one and at most half of the slots go to them, so they cannot
+//! starve the requests of other clients.
+
+use crate::{
+    error::{ErrorCode, ErrorResponse},
+    state::AppState,
+};
+use axum::{
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Default number of requests allowed to wait for a search slot.
+pub const DEFAULT_MAX_QUEUED_SEARCHES: usize = 32;
+
+/// Default delay suggested to clients turned away because the server is busy.
+pub const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Limits the number of searches running and waiting at once.
+pub struct SearchLimiter {
+    max_concurrent: usize,
+    max_queued: usize,
+    retry_after: Duration,
+    permits: Arc<Semaphore>,
+    queued: AtomicUsize,
+    max_background: usize,
+    background: Arc<Semaphore>,
+}
+
+/// The slots held by a background search: one of the shared search slots
+/// and one of the background slots. Both are released when it is dropped.
+#[derive(Debug)]
+pub struct BackgroundPermit {
+    _search: OwnedSemaphorePermit,
+    _background: OwnedSemaphorePermit,
+}
+
+/// A request was turned away because all search slots and queue places are
+/// taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overloaded {
+    /// How long the client should wait before trying again.
+    pub retry_after: Duration,
+}
+
+/// Number of searches allowed to run at once when none is configured: one
+/// per available core.
+pub fn default_max_concurrent_searches() -> usize {
+    std::thread::available_parallelism().map_or(1, |cores| cores.get())
+}
+
+impl SearchLimiter {
+    /// Creates a limiter running at most `max_concurrent` searches (at least
+    /// one) while at most `max_queued` more wait for a slot. Half of the
+    /// slots, but at least one, may go to background searches.
+    pub fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        let max_concurrent = max_concurrent.max(1);
+        let max_background = (max_concurrent / 2).max(1);
+        Self {
+            max_concurrent,
+            max_queued,
+            retry_after: DEFAULT_RETRY_AFTER,
+            permits: Arc::new(Semaphore::new(max_concurrent)),
+            queued: AtomicUsize::new(0),
+            max_background,
+            background: Arc::new(Semaphore::new(max_background)),
+        }
+    }
+
+    /// Sets the delay suggested to clients that are turned away.
+    pub fn with_retry_after(mut self, retry_after: Duration) -> Self {
+        self.retry_after = retry_after;
+        self
+    }
+
+    /// Maximum number of searches running at once.
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
+    /// Maximum number of requests waiting for a search slot.
+    pub fn max_queued(&self) -> usize {
+        self.max_queued
+    }
+
+    /// Number of searches currently running.
+    pub fn running(&self) -> usize {
+        self.max_concurrent - self.permits.available_permits()
+    }
+
+    /// Number of requests currently waiting for a search slot.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Maximum number of background searches running at once.
+    pub fn max_background(&self) -> usize {
+        self.max_background
+    }
+
+    /// Number of background searches currently running.
+    pub fn running_background(&self) -> usize {
+        self.max_background - self.background.available_permits()
+    }
+
+    /// Takes a search slot for a background search, or returns `None` if no
+    /// search slot or no background slot is free. Never waits.
+    pub fn try_acquire_background(&self) -> Option<BackgroundPermit> {
+        let background = Arc::clone(&self.background).try_acquire_owned().ok()?;
+        let search = Arc::clone(&self.permits).try_acquire_owned().ok()?;
+        Some(BackgroundPermit {
+            _search: search,
+            _background: background,
+        })
+    }
+
+    /// Waits for a search slot. The slot is released when the returned
+    /// permit is dropped.
+    ///
+    /// Fails at once if no slot is free and the queue is full. A request
+    /// dropped while waiting gives its queue place back.
+    pub async fn acquire(&self) -> Result<OwnedSemaphorePermit, Overloaded> {
+        if let Ok(permit) = Arc::clone(&self.permits).try_acquire_owned() {
+            return Ok(permit);
+        }
+
+        let overloaded = Overloaded {
+            retry_after: self.retry_after,
+        };
+        self.queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < self.max_queued).then_some(queued + 1)
+            })
+            .map_err(|_| overloaded)?;
+
+        struct Dequeue<'a>(&'a AtomicUsize);
+        impl Drop for Dequeue<'_> {
+            fn drop(&mut self) {
+                self.0.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+
+        let _dequeue = Dequeue(&self.queued);
+        Arc::clone(&self.permits)
+            .acquire_owned()
+            .await
+            .map_err(|_| overloaded)
+    }
+}
+
+impl Default for SearchLimiter {
+    fn default() -> Self {
+        Self::new(
+            default_max_concurrent_searches(),
+            DEFAULT_MAX_QUEUED_SEARCHES,
+        )
+    }
+}
+
+impl IntoResponse for Overloaded {
+    fn into_response(self) -> Response {
+        let seconds = self.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        let error = ErrorResponse::error(
+            &format!("Too many searches in progress, retry in {} s", seconds),
+            None,
+            None,
+        )
+        .with_code(ErrorCode::Overloaded);
+        ([(header::RETRY_AFTER, seconds.to_string())], error).into_response()
+    }
+}
+
+/// Middleware that holds a search slot for the whole request, or answers
+/// `503` with a retry hint when the server is saturated.
+pub async fn limit_searches(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match state.searches().acquire().await {
+        Ok(_permit) => next.run(request).await,
+        Err(overloaded) => overloaded.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_searches_beyond_the_limit_wait_in_queue() {
+        let limiter = Arc::new(SearchLimiter::new(1, 1));
+        let running = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.running(), 1);
+
+        let waiting = {
+            let limiter = Arc::clone(&limiter);
+            tokio::spawn(async move { limiter.acquire().await.map(drop) })
+        };
+        while limiter.queued() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        drop(running);
+        assert!(waiting.await.unwrap().is_ok());
+        assert_eq!(limiter.queued(), 0);
+        assert_eq!(limiter.running(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_is_overloaded() {
+        let limiter = SearchLimiter::new(1, 0).with_retry_after(Duration::from_secs(3));
+        let _running = limiter.acquire().await.unwrap();
+
+        let overloaded = limiter.acquire().await.unwrap_err();
+        assert_eq!(overloaded.retry_after, Duration::from_secs(3));
+
+        let response = overloaded.into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+    }
+
+    #[tokio::test]
+    async fn test_background_searches_take_at_most_half_the_slots() {
+        let limiter = SearchLimiter::new(4, 0);
+        assert_eq!(limiter.max_background(), 2);
+
+        let first = limiter.try_acquire_background().unwrap();
+        let _second = limiter.try_acquire_background().unwrap();
+        assert!(limiter.try_acquire_background().is_none());
+        assert_eq!(limiter.running(), 2);
+        assert_eq!(limiter.running_background(), 2);
+
+        let _foreground = limiter.acquire().await.unwrap();
+        drop(first);
+        assert!(limiter.try_acquire_background().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_background_search_needs_a_free_slot() {
+        let limiter = SearchLimiter::new(1, 1);
+        let running = limiter.acquire().await.unwrap();
+        assert!(limiter.try_acquire_background().is_none());
+        assert_eq!(limiter.running_background(), 0);
+        assert_eq!(limiter.queued(), 0);
+
+        drop(running);
+        let _background = limiter.try_acquire_background().unwrap();
+        assert_eq!(limiter.running(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_waiter_leaves_queue() {
+        let limiter = SearchLimiter::new(1, 1);
+        let _running = limiter.acquire().await.unwrap();
+
+        let waited = tokio::time::timeout(Duration::from_millis(10), limiter.acquire()).await;
+        assert!(waited.is_err());
+        assert_eq!(limiter.queued(), 0);
+    }
+}
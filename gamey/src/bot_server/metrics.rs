@@ -0,0 +1,484 @@
+//! Prometheus metrics for the bot server.
+//!
+//! The [`track_requests`] middleware wraps every route. It counts requests
+//! and their latency per endpoint and bot, and errors per [`ErrorCode`]. It
+//! also records the depth, node count and transposition table hits of the
+//! searches each request ran (see [`collect_search_stats`]). `GET /metrics`
+//! renders these together with the current load of the [`SearchLimiter`] in
+//! the Prometheus text format, along with the hits and misses of the
+//! [`ResponseCache`](crate::ResponseCache). The middleware also logs one line
+//! per request.
+
+use crate::{SearchLimiter, SearchStats, collect_search_stats, error::ErrorCode, state::AppState};
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Upper bounds of the request latency buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Upper bounds of the search depth buckets.
+const DEPTH_BUCKETS: &[f64] = &[1.0, 2.0, 4.0, 6.0, 8.0, 10.0, 12.0, 16.0, 20.0, 30.0];
+
+/// Upper bounds of the searched node buckets.
+const NODE_BUCKETS: &[f64] = &[1e3, 1e4, 1e5, 1e6, 1e7, 1e8];
+
+/// Label used for requests that name no bot, or an unknown one.
+const NO_BOT: &str = "";
+
+/// Counts of observations per bucket, as in a Prometheus histogram.
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    /// Observations per bucket, not cumulative; the last one is `+Inf`.
+    buckets: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self {
+            bounds,
+            buckets: vec![0; bounds.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(self.bounds.len());
+        self.buckets[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", self.count);
+        let _ = writeln!(out, "{name}_sum{{{labels}}} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{{{labels}}} {}", self.count);
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    /// Requests by endpoint, bot and status.
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// Latency by endpoint and bot.
+    latency: BTreeMap<(String, String), Histogram>,
+    /// Errors by code.
+    errors: BTreeMap<&'static str, u64>,
+    /// Completed search depth by bot.
+    depth: BTreeMap<String, Histogram>,
+    /// Searched nodes by bot.
+    nodes: BTreeMap<String, Histogram>,
+    tt_probes: u64,
+    tt_hits: u64,
+    /// Response cache hits by bot.
+    cache_hits: BTreeMap<String, u64>,
+    /// Response cache misses by bot.
+    cache_misses: BTreeMap<String, u64>,
+}
+
+/// Metrics collected by the server since it started.
+#[derive(Default)]
+pub struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+/// One request as seen by [`Metrics::record_request`].
+#[derive(Debug, Clone)]
+pub struct RequestRecord<'a> {
+    /// The route the request matched, e.g. `/{api_version}/ybot/choose/{bot_id}`.
+    pub endpoint: &'a str,
+    /// The bot named in the path, or `""`.
+    pub bot: &'a str,
+    pub status: StatusCode,
+    pub latency: Duration,
+    /// Code of the error returned, if any.
+    pub error: Option<ErrorCode>,
+    /// The searches the request ran.
+    pub searches: &'a [SearchStats],
+}
+
+impl Metrics {
+    /// Creates an empty set of metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a finished request.
+    pub fn record_request(&self, request: &RequestRecord<'_>) {
+        let mut registry = self.registry.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (request.endpoint.to_string(), request.bot.to_string());
+        *registry
+            .requests
+            .entry((key.0.clone(), key.1.clone(), request.status.as_u16()))
+            .or_default() += 1;
+        registry
+            .latency
+            .entry(key)
+            .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+            .observe(request.latency.as_secs_f64());
+        if let Some(code) = request.error {
+            *registry.errors.entry(code.as_str()).or_default() += 1;
+        }
+        for search in request.searches {
+            registry
+                .depth
+                .entry(request.bot.to_string())
+                .or_insert_with(|| Histogram::new(DEPTH_BUCKETS))
+                .observe(search.depth as f64);
+            registry
+                .nodes
+                .entry(request.bot.to_string())
+                .or_insert_with(|| Histogram::new(NODE_BUCKETS))
+                .observe(search.nodes as f64);
+            registry.tt_probes += search.tt_probes;
+            registry.tt_hits += search.tt_hits;
+        }
+    }
+
+    /// Records a lookup of `bot`'s move in the response cache.
+    pub fn record_cache_lookup(&self, bot: &str, hit: bool) {
+        let mut registry = self.registry.lock().unwrap_or_else(PoisonError::into_inner);
+        let counts = if hit {
+            &mut registry.cache_hits
+        } else {
+            &mut registry.cache_misses
+        };
+        *counts.entry(bot.to_string()).or_default() += 1;
+    }
+
+    /// Renders the metrics, plus the current load of `searches`, in the
+    /// Prometheus text exposition format.
+    pub fn render(&self, searches: &SearchLimiter) -> String {
+        let registry = self.registry.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut out = String::new();
+
+        header_lines(
+            &mut out,
+            "gamey_http_requests_total",
+            "counter",
+            "Requests handled, by endpoint, bot and status.",
+        );
+        for ((endpoint, bot, status), count) in &registry.requests {
+            let labels = endpoint_labels(endpoint, bot);
+            let _ = writeln!(
+                out,
+                "gamey_http_requests_total{{{labels},status=\"{status}\"}} {count}"
+            );
+        }
+
+        header_lines(
+            &mut out,
+            "gamey_http_request_duration_seconds",
+            "histogram",
+            "Request latency, by endpoint and bot.",
+        );
+        for ((endpoint, bot), histogram) in &registry.latency {
+            histogram.render(
+                &mut out,
+                "gamey_http_request_duration_seconds",
+                &endpoint_labels(endpoint, bot),
+            );
+        }
+
+        header_lines(
+            &mut out,
+            "gamey_errors_total",
+            "counter",
+            "Error responses, by error code.",
+        );
+        for (code, count) in &registry.errors {
+            let _ = writeln!(out, "gamey_errors_total{{code=\"{code}\"}} {count}");
+        }
+
+        header_lines(
+            &mut out,
+            "gamey_search_depth",
+            "histogram",
+            "Deepest completed depth of each search, by bot.",
+        );
+        for (bot, histogram) in &registry.depth {
+            histogram.render(&mut out, "gamey_search_depth", &bot_label(bot));
+        }
+
+        header_lines(
+            &mut out,
+            "gamey_search_nodes",
+            "histogram",
+            "Nodes visited by each search, by bot.",
+        );
+        for (bot, histogram) in &registry.nodes {
+            histogram.render(&mut out, "gamey_search_nodes", &bot_label(bot));
+        }
+
+        header_lines(
+            &mut out,
+            "gamey_tt_probes_total",
+            "counter",
+            "Transposition table lookups.",
+        );
+        let _ = writeln!(out, "gamey_tt_probes_total {}", registry.tt_probes);
+        header_lines(
+            &mut out,
+            "gamey_tt_hits_total",
+            "counter",
+            "Transposition table lookups that found a usable entry.",
+        );
+        let _ = writeln!(out, "gamey_tt_hits_total {}", registry.tt_hits);
+        header_lines(
+            &mut out,
+            "gamey_tt_hit_ratio",
+            "gauge",
+            "Share of transposition table lookups that hit.",
+        );
+        let ratio = if registry.tt_probes == 0 {
+            0.0
+        } else {
+            registry.tt_hits as f64 / registry.tt_probes as f64
+        };
+        let _ = writeln!(out, "gamey_tt_hit_ratio {ratio}");
+
+        header_lines(
+            &mut out,
+            "gamey_cache_hits_total",
+            "counter",
+            "Moves answered from the response cache, by bot.",
+        );
+        for (bot, count) in &registry.cache_hits {
+            let _ = writeln!(out, "gamey_cache_hits_total{{{}}} {count}", bot_label(bot));
+        }
+        header_lines(
+            &mut out,
+            "gamey_cache_misses_total",
+            "counter",
+            "Cacheable moves that had to be searched, by bot.",
+        );
+        for (bot, count) in &registry.cache_misses {
+            let _ = writeln!(out, "gamey_cache_misses_total{{{}}} {count}", bot_label(bot));
+        }
+
+        header_lines(
+            &mut out,
+            "gamey_searches_running",
+            "gauge",
+            "Searches running now.",
+        );
+        let _ = writeln!(out, "gamey_searches_running {}", searches.running());
+        header_lines(
+            &mut out,
+            "gamey_searches_queued",
+            "gauge",
+            "Requests waiting for a search slot.",
+        );
+        let _ = writeln!(out, "gamey_searches_queued {}", searches.queued());
+        header_lines(
+            &mut out,
+            "gamey_searches_max_concurrent",
+            "gauge",
+            "Searches allowed to run at once.",
+        );
+        let _ = writeln!(
+            out,
+            "gamey_searches_max_concurrent {}",
+            searches.max_concurrent()
+        );
+
+        out
+    }
+}
+
+fn header_lines(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escapes a label value as the text format requires.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn bot_label(bot: &str) -> String {
+    format!("bot=\"{}\"", escape(bot))
+}
+
+fn endpoint_labels(endpoint: &str, bot: &str) -> String {
+    format!("endpoint=\"{}\",{}", escape(endpoint), bot_label(bot))
+}
+
+tokio::task_local! {
+    /// Searches run by the request being handled on this task.
+    static SEARCHES: Arc<Mutex<Vec<SearchStats>>>;
+}
+
+/// Runs `search` like [`collect_search_stats`] and, when called while the
+/// [`track_requests`] middleware handles a request, attributes the searches
+/// to that request.
+///
+/// Call it before moving `search` to another thread, as
+/// [`run_cancellable`](crate::run_cancellable) does.
+pub fn record_searches<T, F>(search: F) -> impl FnOnce() -> T
+where
+    F: FnOnce() -> T,
+{
+    let sink = SEARCHES.try_with(Arc::clone).ok();
+    move || {
+        let (result, stats) = collect_search_stats(search);
+        if let Some(sink) = sink {
+            sink.lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .extend(stats);
+        }
+        result
+    }
+}
+
+/// Picks the bot label of a request: the `{bot_id}` segment of `path` if the
+/// route has one and it names a registered bot. Unknown ids are not used as
+/// labels so that clients cannot create new series at will.
+fn request_bot(state: &AppState, endpoint: &str, path: &str) -> String {
+    endpoint
+        .split('/')
+        .zip(path.split('/'))
+        .find(|(segment, _)| *segment == "{bot_id}")
+        .map(|(_, bot)| bot)
+        .filter(|bot| state.bots().find(bot).is_some())
+        .unwrap_or(NO_BOT)
+        .to_string()
+}
+
+/// Middleware that records every request in the server's [`Metrics`].
+pub async fn track_requests(
+    State(state): State<AppState>,
+    matched: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let endpoint =
+        matched.map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let bot = request_bot(&state, &endpoint, request.uri().path());
+    let method = request.method().clone();
+    let searches = Arc::new(Mutex::new(Vec::new()));
+
+    let start = Instant::now();
+    let response = SEARCHES
+        .scope(Arc::clone(&searches), next.run(request))
+        .await;
+
+    let latency = start.elapsed();
+    tracing::info!(
+        method = %method,
+        endpoint = %endpoint,
+        bot = %bot,
+        status = response.status().as_u16(),
+        latency_ms = latency.as_secs_f64() * 1000.0,
+        "request"
+    );
+
+    let searches = searches.lock().unwrap_or_else(PoisonError::into_inner);
+    state.metrics().record_request(&RequestRecord {
+        endpoint: &endpoint,
+        bot: &bot,
+        status: response.status(),
+        latency,
+        error: response.extensions().get::<ErrorCode>().copied(),
+        searches: &searches,
+    });
+    response
+}
+
+/// Handler for the Prometheus scrape endpoint.
+///
+/// # Route
+/// `GET /metrics`
+///
+/// # Response
+/// The server's metrics in the Prometheus text format.
+#[utoipa::path(
+    get, path = "/metrics", summary = "Request and search metrics in the Prometheus text format",
+    responses((status = 200, body = String, content_type = "text/plain"))
+)]
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics().render(&state.searches()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let mut histogram = Histogram::new(&[1.0, 10.0]);
+        histogram.observe(0.5);
+        histogram.observe(5.0);
+        histogram.observe(50.0);
+
+        let mut out = String::new();
+        histogram.render(&mut out, "h", "bot=\"b\"");
+        assert!(out.contains("h_bucket{bot=\"b\",le=\"1\"} 1\n"));
+        assert!(out.contains("h_bucket{bot=\"b\",le=\"10\"} 2\n"));
+        assert!(out.contains("h_bucket{bot=\"b\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("h_sum{bot=\"b\"} 55.5\n"));
+    }
+
+    #[test]
+    fn test_render_includes_requests_searches_and_errors() {
+        let metrics = Metrics::new();
+        metrics.record_request(&RequestRecord {
+            endpoint: "/{api_version}/ybot/choose/{bot_id}",
+            bot: "fast_bot",
+            status: StatusCode::NOT_FOUND,
+            latency: Duration::from_millis(3),
+            error: Some(ErrorCode::BotNotFound),
+            searches: &[SearchStats {
+                depth: 5,
+                nodes: 2_000,
+                tt_probes: 4,
+                tt_hits: 1,
+            }],
+        });
+
+        let out = metrics.render(&SearchLimiter::new(2, 0));
+        assert!(out.contains(
+            "gamey_http_requests_total{endpoint=\"/{api_version}/ybot/choose/{bot_id}\",bot=\"fast_bot\",status=\"404\"} 1"
+        ));
+        assert!(out.contains("gamey_errors_total{code=\"BOT_NOT_FOUND\"} 1"));
+        assert!(out.contains("gamey_search_depth_count{bot=\"fast_bot\"} 1"));
+        assert!(out.contains("gamey_tt_hit_ratio 0.25"));
+        assert!(out.contains("gamey_searches_max_concurrent 2"));
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
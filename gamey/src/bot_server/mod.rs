@@ -4,47 +4,169 @@
 //! The server exposes endpoints for checking bot status and requesting moves.
 //!
 //! # Endpoints
-//! - `GET /status` - Health check endpoint
+//! - `GET /status` - Version, uptime and bots of the server
+//! - `GET /health/live` - Liveness probe
+//! - `GET /health/ready` - Readiness probe with the load of the search slots
+//! - `GET /metrics` - Request and search metrics in the Prometheus text format
+//! - `GET /openapi.json` - OpenAPI 3.1 description of these endpoints
+//! - `GET /{api_version}/ybot` - List the bots with their metadata
+//! - `GET /{api_version}/ybot/{bot_id}` - Metadata of one bot
 //! - `POST /{api_version}/ybot/choose/{bot_id}` - Request a move from a bot
+//! - `POST /{api_version}/ybot/choose-batch/{bot_id}` - Moves for many positions, searched in parallel
+//! - `POST /{api_version}/ybot/analyze/{bot_id}?multipv=N` - Top-N candidate moves
+//! - `POST /{api_version}/solve?max_nodes=N` - Exact win/loss for the side to move
+//! - `POST /{api_version}/connections` - Bridges and edge templates of both players
+//! - `POST /{api_version}/game/apply` - Play a move under the rules and report the result
+//! - `POST /{api_version}/games` - Start a game hosted by the server
+//! - `GET /{api_version}/games/{game_id}` - Position, history and status of a hosted game
+//! - `POST /{api_version}/games/{game_id}/moves` - Play a move, optionally with the bot's reply
+//! - `DELETE /{api_version}/games/{game_id}` - End a hosted game
+//! - `POST /{api_version}/ybot/stream/{bot_id}` - Stream search progress as Server-Sent Events
+//! - `DELETE /{api_version}/ybot/stream/{bot_id}/{search_id}` - Stop a streamed search
+//! - `POST /{api_version}/ybot/ponder/{bot_id}` - Think on the opponent's time
+//! - `DELETE /{api_version}/ybot/ponder/{bot_id}/{game_id}` - Discard a ponder search
+//! - `POST /v2/ybot/choose/{bot_id}` - Request a move with the v2 envelope, see [`v2`]
+//! - `DELETE /admin/cache` - Empty the response cache
+//!
+//! Failed requests answer with an [`ErrorResponse`] whose [`ErrorCode`]
+//! decides the HTTP status, e.g. `404` for an unknown bot.
+//!
+//! The `{api_version}` endpoints serve only v1 and answer other versions
+//! with `UNSUPPORTED_VERSION`. Version 2 wraps requests in an envelope with
+//! rules, limits and options; its only endpoint is move selection, which
+//! also covers the Pie Rule (see [`v2`]). Analysis, batches, streaming,
+//! pondering and hosted games remain v1 endpoints.
+//!
+//! Choose requests may name the game they belong to with a `game_id`; the bot
+//! then keeps its search tables between the moves of that game (see
+//! [`SessionStore`]) and can ponder between requests. Alternatively, the
+//! server can host the whole game (see [`GameStore`]), so that clients only
+//! send their moves.
+//!
+//! Searches run on the blocking thread pool and are cancelled when their
+//! request is dropped (see [`run_cancellable`]). A [`SearchLimiter`] bounds
+//! how many run and wait at once; requests beyond that get
+//! `503 Service Unavailable` with a `Retry-After` header. Ponder searches
+//! hold a slot too, and are not started when none is free.
+//!
+//! Moves and Pie Rule answers chosen for a position can be cached and served
+//! to later requests in the same position (see [`ResponseCache`]).
+//!
+//! Every request passes through a metrics layer (see [`Metrics`]) that
+//! counts requests, latencies, errors and search statistics for `/metrics`.
+//!
+//! The address, the bots offered and the limits on request size, concurrent
+//! searches and request time come from a [`ServerConfig`].
+//!
+//! On `SIGTERM` or `SIGINT` the server shuts down gracefully: it stops
+//! accepting connections, lets the searches in flight finish until the
+//! configured deadline and then cancels them (see [`Lifecycle`]).
 //!
 //! # Example
 //! ```no_run
-//! use gamey::run_bot_server;
+//! use gamey::{ServerConfig, run_bot_server};
 //!
 //! #[tokio::main]
 //! async fn main() {
-//!     if let Err(e) = run_bot_server(3000).await {
+//!     let config = ServerConfig::default().with_port(3000);
+//!     if let Err(e) = run_bot_server(config).await {
 //!         eprintln!("Server error: {}", e);
 //!     }
 //! }
 //! ```
 
+pub mod analyze;
+pub mod apply;
+pub mod batch;
+pub mod bots;
+pub mod cache;
 pub mod choose;
+pub mod config;
+pub mod connections;
 pub mod error;
+pub mod game_store;
+pub mod games;
+pub mod health;
+pub mod limiter;
+pub mod logging;
+pub mod metrics;
+pub mod openapi;
 pub mod pie_decide;
 pub mod pie_opening;
+pub mod ponder;
+pub mod session;
+pub mod shutdown;
+pub mod solve;
 pub mod state;
+pub mod stream;
+pub mod task;
+pub mod v2;
 pub mod version;
-use axum::response::IntoResponse;
-pub use choose::MoveResponse;
-pub use error::ErrorResponse;
+pub use analyze::AnalysisResponse;
+pub use apply::{ApplyRequest, ApplyResponse, ApplyStatus, GameSummary, MoveRequest};
+pub use batch::{BatchError, BatchItem, BatchRequest, BatchResponse, BatchResult, MAX_BATCH_SIZE};
+pub use bots::{BotListResponse, BotResponse};
+pub use cache::{ClearCacheResponse, ResponseCache};
+pub use choose::{ChooseRequest, MoveResponse};
+pub use config::{
+    BookConfig, BotConfig, DEFAULT_BIND_ADDRESS, DEFAULT_BODY_LIMIT, DEFAULT_REQUEST_TIMEOUT, LogFormat,
+    ServerConfig,
+};
+pub use connections::{ConnectionsResponse, PlayerConnections};
+pub use error::{ErrorCode, ErrorResponse, JsonBody};
+pub use game_store::{
+    DEFAULT_GAME_TTL, DEFAULT_MAX_GAMES, GameRules, GameStore, HistoryEntry, HostedGame, SharedGame,
+};
+pub use games::{CreateGameRequest, GameMoveRequest, GameStateResponse};
+pub use health::{LivenessResponse, Readiness, ReadinessResponse, StatusResponse};
+pub use limiter::{
+    BackgroundPermit, DEFAULT_MAX_QUEUED_SEARCHES, DEFAULT_RETRY_AFTER, Overloaded,
+    SearchLimiter, default_max_concurrent_searches,
+};
+pub use logging::{JsonLines, init_logging};
+pub use metrics::{Metrics, RequestRecord, record_searches};
+pub use openapi::openapi_document;
 pub use pie_decide::PieDecideResponse;
 pub use pie_opening::PieOpeningResponse;
+pub use ponder::{PonderRequest, PonderResponse};
+pub use session::{
+    DEFAULT_MAX_SESSIONS, DEFAULT_SESSION_TT_SIZE_MB, DEFAULT_SESSION_TTL, SESSION_SWEEP_INTERVAL,
+    SessionStore, SharedSession, sweep_sessions,
+};
+pub use shutdown::{
+    DEFAULT_SHUTDOWN_TIMEOUT, Lifecycle, cancel_after_deadline, search_token, shutdown_signal,
+};
+pub use solve::SolveResponse;
+pub use stream::{
+    RunningSearches, SearchEvents, SearchStarted, StopSearchResponse, StreamRequest,
+};
+pub use task::{limit_request_time, run_cancellable, run_cancellable_for};
 use std::sync::Arc;
+pub use v2::{
+    ChooseV2Request, ChooseV2Response, EngineInfo, Evaluation, RequestOptions, SearchLimits, Timing,
+};
 pub use version::*;
 
 use crate::{FastBot, GameYError, RandomBot, SmartBot, YBotRegistry, state::AppState};
 
 /// Creates the Axum router with the given state.
 ///
-/// This is useful for testing the API without binding to a network port.
+/// Request bodies and request times are limited as set in the state. This is
+/// useful for testing the API without binding to a network port.
+///
+/// The routes under `/{api_version}` serve v1 and reject other versions in
+/// one [`accept_version`] layer; v2 routes have a literal `/v2` prefix, which
+/// takes precedence.
 pub fn create_router(state: AppState) -> axum::Router {
-    axum::Router::new()
-        .route("/status", axum::routing::get(status))
+    let searches = axum::Router::new()
         .route(
             "/{api_version}/ybot/choose/{bot_id}",
             axum::routing::post(choose::choose),
         )
+        .route(
+            "/{api_version}/ybot/analyze/{bot_id}",
+            axum::routing::post(analyze::analyze),
+        )
         .route(
             "/{api_version}/ybot/pie-decide/{bot_id}",
             axum::routing::post(pie_decide::pie_decide),
@@ -53,6 +175,91 @@ pub fn create_router(state: AppState) -> axum::Router {
             "/{api_version}/ybot/pie-opening/{bot_id}",
             axum::routing::post(pie_opening::pie_opening),
         )
+        .route(
+            "/{api_version}/solve",
+            axum::routing::post(solve::solve_position),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            limiter::limit_searches,
+        ));
+
+    let v1 = axum::Router::new()
+        .route("/{api_version}/ybot", axum::routing::get(bots::list_bots))
+        .route("/{api_version}/ybot/{bot_id}", axum::routing::get(bots::bot_info))
+        .merge(searches)
+        .route(
+            "/{api_version}/ybot/choose-batch/{bot_id}",
+            axum::routing::post(batch::choose_batch),
+        )
+        .route(
+            "/{api_version}/ybot/stream/{bot_id}",
+            axum::routing::post(stream::stream_search),
+        )
+        .route(
+            "/{api_version}/ybot/stream/{bot_id}/{search_id}",
+            axum::routing::delete(stream::stop_search),
+        )
+        .route(
+            "/{api_version}/ybot/ponder/{bot_id}",
+            axum::routing::post(ponder::ponder),
+        )
+        .route(
+            "/{api_version}/ybot/ponder/{bot_id}/{game_id}",
+            axum::routing::delete(ponder::discard_ponder),
+        )
+        .route(
+            "/{api_version}/connections",
+            axum::routing::post(connections::connections),
+        )
+        .route(
+            "/{api_version}/game/apply",
+            axum::routing::post(apply::apply_move),
+        )
+        .route("/{api_version}/games", axum::routing::post(games::create_game))
+        .route(
+            "/{api_version}/games/{game_id}",
+            axum::routing::get(games::get_game).delete(games::delete_game),
+        )
+        // Takes a search slot for the bot's reply only.
+        .route(
+            "/{api_version}/games/{game_id}/moves",
+            axum::routing::post(games::play_move),
+        )
+        .route_layer(axum::middleware::from_fn_with_state(
+            ApiVersion::V1,
+            version::accept_version,
+        ));
+
+    let v2 = axum::Router::new()
+        .route("/v2/ybot/choose/{bot_id}", axum::routing::post(v2::choose))
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            limiter::limit_searches,
+        ));
+
+    axum::Router::new()
+        .route("/status", axum::routing::get(health::status))
+        .route("/health/live", axum::routing::get(health::live))
+        .route("/health/ready", axum::routing::get(health::ready))
+        .route("/metrics", axum::routing::get(metrics::metrics))
+        .route("/admin/cache", axum::routing::delete(cache::clear_cache))
+        .route("/openapi.json", axum::routing::get(openapi::openapi))
+        .merge(v1)
+        .merge(v2)
+        .layer(axum::extract::DefaultBodyLimit::max(state.body_limit()))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            task::limit_request_time,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            shutdown::watch_shutdown,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            metrics::track_requests,
+        ))
         .with_state(state)
 }
 
@@ -65,47 +272,57 @@ pub fn create_router(state: AppState) -> axum::Router {
 pub fn create_default_state() -> AppState {
     let bots = YBotRegistry::new()
         .with_bot(Arc::new(RandomBot))
-        .with_bot(Arc::new(FastBot))
-        .with_bot(Arc::new(SmartBot));
+        .with_bot(Arc::new(FastBot::new()))
+        .with_bot(Arc::new(SmartBot::new()));
     AppState::new(bots)
 }
 
-/// Starts the bot server on the specified port.
+/// Starts the bot server as described by `config`.
 ///
-/// This function blocks until the server is shut down.
+/// This function blocks until the server is shut down. On `SIGTERM` or
+/// `SIGINT` it stops accepting connections and waits for the requests in
+/// flight; searches still running after the configured shutdown timeout are
+/// cancelled and return their best move so far.
 ///
 /// # Arguments
-/// * `port` - The TCP port to listen on
+/// * `config` - The address, bots and limits of the server
 ///
 /// # Errors
-/// Returns `GameYError::ServerError` if:
-/// - The TCP port cannot be bound (e.g., port already in use, permission denied)
+/// Returns `GameYError::ConfigError` if the configured bots cannot be
+/// created, and `GameYError::ServerError` if:
+/// - The address cannot be bound (e.g., port already in use, permission denied)
 /// - The server encounters an error while running
-pub async fn run_bot_server(port: u16) -> Result<(), GameYError> {
-    let state = create_default_state();
+pub async fn run_bot_server(config: ServerConfig) -> Result<(), GameYError> {
+    let state = config.state()?;
+    let lifecycle = state.lifecycle();
+    let sweeper = tokio::spawn(sweep_sessions(state.sessions(), SESSION_SWEEP_INTERVAL));
     let app = create_router(state);
 
-    let addr = format!("0.0.0.0:{}", port);
+    let addr = config.bind;
     let listener =
-        tokio::net::TcpListener::bind(&addr)
+        tokio::net::TcpListener::bind(addr)
             .await
             .map_err(|e| GameYError::ServerError {
                 message: format!("Failed to bind to {}: {}", addr, e),
             })?;
 
     println!("Server mode: Listening on http://{}", addr);
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| GameYError::ServerError {
-            message: format!("Server error: {}", e),
-        })?;
+    let deadline = tokio::spawn(cancel_after_deadline(
+        Arc::clone(&lifecycle),
+        config.shutdown_timeout(),
+    ));
+    let served = axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            println!("Server mode: Shutting down, draining requests in flight");
+            lifecycle.start_draining();
+        })
+        .await;
+    deadline.abort();
+    sweeper.abort();
+    served.map_err(|e| GameYError::ServerError {
+        message: format!("Server error: {}", e),
+    })?;
 
     Ok(())
 }
-
-/// Health check endpoint handler.
-///
-/// Returns "OK" to indicate the server is running.
-pub async fn status() -> impl IntoResponse {
-    "OK"
-}
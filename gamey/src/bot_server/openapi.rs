@@ -0,0 +1,406 @@
+//! OpenAPI description of the bot server.
+//!
+//! `GET /openapi.json` serves an OpenAPI 3.1 document of every route in
+//! [`create_router`](crate::create_router). It is generated with `utoipa`:
+//! each handler carries a `#[utoipa::path]` attribute and each request and
+//! response type derives `ToSchema`, so the schemas follow the Rust types,
+//! including their serde attributes and doc comments.
+//!
+//! The tests compare the document with the routes of the router and with
+//! serialized values of the documented types, so a route that is not listed
+//! here or a schema that disagrees with its type fails the build.
+
+use crate::bot_server::{
+    analyze, apply, batch, bots, cache, choose, connections, games, health, metrics, pie_decide,
+    pie_opening, ponder, solve, stream, v2,
+};
+use crate::ErrorResponse;
+use axum::Json;
+use serde_json::Value;
+use utoipa::OpenApi;
+
+/// Handler that serves the OpenAPI document.
+///
+/// # Route
+/// `GET /openapi.json`
+///
+/// # Response
+/// The document built by [`openapi_document`].
+#[utoipa::path(
+    get, path = "/openapi.json", summary = "This document",
+    responses((status = 200, body = Object))
+)]
+pub async fn openapi() -> Json<Value> {
+    Json(openapi_document())
+}
+
+/// The operations and schemas of the bot server.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "gamey bot server",
+        description = "Bots, analysis and hosted games for the game of Y. \
+            Positions are exchanged in YEN notation. The `{api_version}` \
+            endpoints serve only `v1`; `v2` offers only \
+            `POST /v2/ybot/choose/{bot_id}`."
+    ),
+    paths(
+        health::status,
+        health::live,
+        health::ready,
+        metrics::metrics,
+        cache::clear_cache,
+        openapi,
+        bots::list_bots,
+        bots::bot_info,
+        choose::choose,
+        v2::choose,
+        batch::choose_batch,
+        analyze::analyze,
+        pie_decide::pie_decide,
+        pie_opening::pie_opening,
+        solve::solve_position,
+        connections::connections,
+        apply::apply_move,
+        games::create_game,
+        games::get_game,
+        games::delete_game,
+        games::play_move,
+        stream::stream_search,
+        stream::stop_search,
+        ponder::ponder,
+        ponder::discard_ponder,
+    ),
+    components(schemas(ErrorResponse))
+)]
+struct ApiDoc;
+
+/// Builds the OpenAPI document of the bot server.
+pub fn openapi_document() -> Value {
+    serde_json::to_value(ApiDoc::openapi()).expect("the OpenAPI document serializes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ChooseRequest, ChooseV2Request, ChooseV2Response, Coordinates, EngineInfo, ErrorCode,
+        Evaluation, GameRules, MoveRequest, MoveResponse, PieChoice, PieDecideResponse,
+        PieOpeningResponse, RequestOptions, SearchLimits, SearchOptions, Timing, YEN,
+    };
+    use serde::Serialize;
+    use serde_json::json;
+    use std::collections::BTreeSet;
+
+    const METHODS: [&str; 3] = ["get", "post", "delete"];
+
+    /// The routes registered in `create_router`, read from its source.
+    fn router_routes() -> BTreeSet<(String, String)> {
+        let source = include_str!("mod.rs");
+        let router = source
+            .split("pub fn create_router")
+            .nth(1)
+            .and_then(|rest| rest.split("\n}\n").next())
+            .expect("create_router is defined in mod.rs");
+        router
+            .split(".route(")
+            .skip(1)
+            .flat_map(|route| {
+                let path = route
+                    .split('"')
+                    .nth(1)
+                    .expect("routes start with their path");
+                METHODS
+                    .iter()
+                    .filter(|method| {
+                        route.contains(&format!("routing::{}(", method))
+                            || route.contains(&format!(").{}(", method))
+                    })
+                    .map(|method| (method.to_string(), path.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn documented_routes(document: &Value) -> BTreeSet<(String, String)> {
+        document["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                METHODS
+                    .iter()
+                    .filter(|method| item.get(**method).is_some())
+                    .map(move |method| (method.to_string(), path.clone()))
+            })
+            .collect()
+    }
+
+    fn resolve<'a>(document: &'a Value, schema: &'a Value) -> &'a Value {
+        match schema["$ref"].as_str() {
+            Some(reference) => document
+                .pointer(reference.trim_start_matches('#'))
+                .unwrap_or_else(|| panic!("unresolved reference {}", reference)),
+            None => schema,
+        }
+    }
+
+    fn schema_ref(name: &str) -> Value {
+        json!({ "$ref": format!("#/components/schemas/{}", name) })
+    }
+
+    /// The non-null types a schema allows, and whether it allows null.
+    fn types(schema: &Value) -> (Vec<&str>, bool) {
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let nullable = types.contains(&"null");
+        (types.into_iter().filter(|name| *name != "null").collect(), nullable)
+    }
+
+    /// Checks that `value` has exactly the properties `schema` declares,
+    /// with the declared types.
+    fn check(document: &Value, schema: &Value, value: &Value, at: &str) -> Result<(), String> {
+        let schema = resolve(document, schema);
+        if let Some(variants) = schema["oneOf"].as_array() {
+            return match variants
+                .iter()
+                .find(|variant| check(document, variant, value, at).is_ok())
+            {
+                Some(_) => Ok(()),
+                None => Err(format!("{} = {} matches no variant", at, value)),
+            };
+        }
+        let (types, nullable) = types(schema);
+        if value.is_null() {
+            return match nullable {
+                true => Ok(()),
+                false => Err(format!("{} is null but not nullable", at)),
+            };
+        }
+        if let Some(variants) = schema["enum"].as_array() {
+            return match variants.contains(value) {
+                true => Ok(()),
+                false => Err(format!("{} = {} is not in the enum", at, value)),
+            };
+        }
+        let parts: Vec<&Value> = match schema["allOf"].as_array() {
+            Some(parts) => parts.iter().map(|part| resolve(document, part)).collect(),
+            None => vec![schema],
+        };
+        if parts.len() > 1 || parts[0].get("properties").is_some() {
+            let object = value
+                .as_object()
+                .ok_or_else(|| format!("{} is not an object", at))?;
+            let mut declared = BTreeSet::new();
+            let mut open = false;
+            for part in parts {
+                if part.get("properties").is_none() {
+                    check(document, part, value, at)?;
+                    continue;
+                }
+                open |= part["additionalProperties"].is_object();
+                for (name, property) in part["properties"].as_object().into_iter().flatten() {
+                    declared.insert(name.clone());
+                    if let Some(field) = object.get(name) {
+                        check(document, property, field, &format!("{}.{}", at, name))?;
+                    }
+                }
+                for required in part["required"].as_array().into_iter().flatten() {
+                    let required = required.as_str().unwrap();
+                    if !object.contains_key(required) {
+                        return Err(format!("{} lacks {}", at, required));
+                    }
+                }
+            }
+            let present: BTreeSet<String> = object.keys().cloned().collect();
+            if !open && present != declared {
+                return Err(format!(
+                    "properties of {} drifted: {:?} != {:?}",
+                    at, present, declared
+                ));
+            }
+            return Ok(());
+        }
+        let matches = match types.as_slice() {
+            ["string"] => value.is_string(),
+            ["integer"] => value.is_u64() || value.is_i64(),
+            ["number"] => value.is_number(),
+            ["boolean"] => value.is_boolean(),
+            ["array"] => {
+                let items = value
+                    .as_array()
+                    .ok_or_else(|| format!("{} is not an array", at))?;
+                for item in items {
+                    check(document, &schema["items"], item, &format!("{}[]", at))?;
+                }
+                true
+            }
+            other => return Err(format!("{} has unsupported schema type {:?}", at, other)),
+        };
+        match matches {
+            true => Ok(()),
+            false => Err(format!("{} = {} does not match {}", at, value, schema)),
+        }
+    }
+
+    fn check_sample(document: &Value, name: &str, sample: impl Serialize) {
+        let value = serde_json::to_value(sample).unwrap();
+        if let Err(drift) = check(document, &schema_ref(name), &value, name) {
+            panic!("{}", drift);
+        }
+    }
+
+    #[test]
+    fn test_every_route_is_documented() {
+        let document = openapi_document();
+        assert_eq!(documented_routes(&document), router_routes());
+    }
+
+    #[test]
+    fn test_schemas_match_the_types() {
+        let document = openapi_document();
+        let yen = YEN::new(3, 0, vec!['B', 'R'], "B/.R/...".to_string());
+        check_sample(&document, "YEN", &yen);
+        check_sample(
+            &document,
+            "ChooseRequest",
+            ChooseRequest {
+                yen: yen.clone(),
+                options: SearchOptions {
+                    seed: Some(7),
+                    max_nodes: Some(1_000),
+                    skill: Some(5),
+                },
+                game_id: Some("g-1".to_string()),
+            },
+        );
+        check_sample(
+            &document,
+            "MoveResponse",
+            MoveResponse {
+                api_version: "v1".to_string(),
+                bot_id: "smart_bot".to_string(),
+                coords: Coordinates::new(1, 0, 1),
+            },
+        );
+        check_sample(
+            &document,
+            "PieDecideResponse",
+            PieDecideResponse {
+                api_version: "v1".to_string(),
+                bot_id: "smart_bot".to_string(),
+                decision: PieChoice::Swap,
+            },
+        );
+        check_sample(
+            &document,
+            "PieOpeningResponse",
+            PieOpeningResponse {
+                api_version: "v1".to_string(),
+                bot_id: "smart_bot".to_string(),
+                coords: Coordinates::new(2, 0, 0),
+            },
+        );
+        check_sample(
+            &document,
+            "ChooseV2Request",
+            ChooseV2Request {
+                position: yen.clone(),
+                rules: GameRules { pie_rule: true },
+                limits: SearchLimits {
+                    max_nodes: Some(1_000),
+                    max_time_ms: Some(500),
+                },
+                seed: Some(7),
+                skill: Some(5),
+                options: RequestOptions {
+                    game_id: Some("g-1".to_string()),
+                    ..RequestOptions::default()
+                },
+            },
+        );
+        check_sample(
+            &document,
+            "ChooseV2Response",
+            ChooseV2Response {
+                api_version: "v2".to_string(),
+                bot_id: "smart_bot".to_string(),
+                movement: MoveRequest::Placement {
+                    coords: Coordinates::new(1, 0, 1),
+                },
+                evaluation: Some(Evaluation {
+                    score: 12,
+                    depth: 3,
+                    pv: vec![Coordinates::new(1, 0, 1)],
+                    nodes: 400,
+                }),
+                timing: Timing { elapsed_ms: 5 },
+                engine: EngineInfo {
+                    name: "gamey".to_string(),
+                    version: "0.1.0".to_string(),
+                },
+                warnings: vec!["Unknown option ignored: ponder".to_string()],
+            },
+        );
+        for code in ErrorCode::ALL {
+            check_sample(
+                &document,
+                "ErrorResponse",
+                ErrorResponse::error("failed", None, Some("smart_bot".to_string())).with_code(code),
+            );
+        }
+    }
+
+    // Only move selection is offered in v2, so every other versioned route
+    // must document that it serves v1 alone.
+    #[test]
+    fn test_versioned_routes_serve_only_v1() {
+        let document = openapi_document();
+        let mut versioned = 0;
+        for (path, item) in document["paths"].as_object().unwrap() {
+            for operation in item.as_object().unwrap().values() {
+                for parameter in operation["parameters"].as_array().into_iter().flatten() {
+                    if parameter["name"] == "api_version" {
+                        versioned += 1;
+                        assert_eq!(parameter["schema"]["pattern"], "^v1$", "{}", path);
+                    }
+                }
+            }
+        }
+        let v2: Vec<&String> = document["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .filter(|path| path.starts_with("/v2/"))
+            .collect();
+        assert_eq!(v2, ["/v2/ybot/choose/{bot_id}"]);
+        assert_eq!(
+            versioned,
+            router_routes()
+                .iter()
+                .filter(|(_, path)| path.starts_with("/{api_version}/"))
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_references_resolve() {
+        fn walk(document: &Value, value: &Value) {
+            match value {
+                Value::Object(object) => {
+                    if value.get("$ref").is_some() {
+                        resolve(document, value);
+                    }
+                    object.values().for_each(|value| walk(document, value));
+                }
+                Value::Array(items) => items.iter().for_each(|value| walk(document, value)),
+                _ => {}
+            }
+        }
+        let document = openapi_document();
+        walk(&document, &document);
+    }
+}
@@ -1,19 +1,30 @@
-use crate::{GameY, PieChoice, YEN, check_api_version, error::ErrorResponse, state::AppState};
+use crate::{
+    GameY, PieChoice, SearchOptions, YEN,
+    cache::{self, Lookup},
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable,
+    state::AppState,
+};
 use axum::{
     Json,
     extract::{Path, State},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 /// Path parameters for the pie-decide endpoint.
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
 pub struct PieDecideParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
     api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
     bot_id: String,
 }
 
 /// Response returned by the pie-decide endpoint.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct PieDecideResponse {
     pub api_version: String,
     pub bot_id: String,
@@ -25,6 +36,9 @@ pub struct PieDecideResponse {
 /// Given a board with exactly one stone placed by the opponent, the bot
 /// evaluates both futures (keep vs swap) and returns its choice.
 ///
+/// If the server has a [`ResponseCache`](crate::ResponseCache), decisions
+/// already taken by the bot in the position are answered from it.
+///
 /// # Route
 /// `POST /{api_version}/ybot/pie-decide/{bot_id}`
 ///
@@ -34,22 +48,27 @@ pub struct PieDecideResponse {
 ///
 /// # Response
 /// On success, returns a [`PieDecideResponse`] with the bot's decision.
+#[utoipa::path(
+    post, path = "/{api_version}/ybot/pie-decide/{bot_id}", summary = "Keep or swap the opponent's opening stone",
+    params(PieDecideParams),
+    request_body = YEN,
+    responses((status = 200, body = PieDecideResponse), ErrorResponse)
+)]
 #[axum::debug_handler]
 pub async fn pie_decide(
     State(state): State<AppState>,
     Path(params): Path<PieDecideParams>,
-    Json(yen): Json<YEN>,
-) -> Result<Json<PieDecideResponse>, Json<ErrorResponse>> {
-    check_api_version(&params.api_version)?;
-
+    JsonBody(yen): JsonBody<YEN>,
+) -> Result<Json<PieDecideResponse>, ErrorResponse> {
     let game_y = match GameY::try_from(yen) {
         Ok(game) => game,
         Err(err) => {
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 &format!("Invalid YEN format: {}", err),
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::from(&err)));
         }
     };
 
@@ -57,18 +76,42 @@ pub async fn pie_decide(
         Some(bot) => bot,
         None => {
             let available = state.bots().names().join(", ");
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 &format!(
                     "Bot not found: {}, available bots: [{}]",
                     params.bot_id, available
                 ),
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::BotNotFound));
         }
     };
 
-    let decision = bot.decide_pie(&game_y);
+    let options = SearchOptions::default();
+    let cache = match cache::lookup_pie_decision(&state, bot.as_ref(), &game_y, &options) {
+        Lookup::Hit(decision) => {
+            return Ok(Json(PieDecideResponse {
+                api_version: params.api_version,
+                bot_id: params.bot_id,
+                decision,
+            }));
+        }
+        Lookup::Miss(cache) => Some((cache, game_y.clone())),
+        Lookup::Uncached => None,
+    };
+    let decision = run_cancellable(move || bot.decide_pie(&game_y))
+        .await
+        .map_err(|err| {
+            ErrorResponse::error(
+                &format!("Search failed: {}", err),
+                Some(params.api_version.clone()),
+                Some(params.bot_id.clone()),
+            )
+        })?;
+    if let Some((cache, game)) = cache {
+        cache.insert_pie_decision(&params.bot_id, &game, &options, decision);
+    }
 
     Ok(Json(PieDecideResponse {
         api_version: params.api_version,
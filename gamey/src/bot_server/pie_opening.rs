@@ -1,19 +1,30 @@
-use crate::{Coordinates, GameY, YEN, check_api_version, error::ErrorResponse, state::AppState};
+use crate::{
+    Coordinates, GameY, SearchOptions, YEN,
+    cache::{self, Lookup},
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable,
+    state::AppState,
+};
 use axum::{
     Json,
     extract::{Path, State},
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 /// Path parameters for the pie-opening endpoint.
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
 pub struct PieOpeningParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
     api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
     bot_id: String,
 }
 
 /// Response returned by the pie-opening endpoint on success.
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct PieOpeningResponse {
     pub api_version: String,
     pub bot_id: String,
@@ -27,6 +38,9 @@ pub struct PieOpeningResponse {
 /// opponent would simply swap). This endpoint calls
 /// [`YBot::choose_pie_opening`] instead of [`YBot::choose_move`].
 ///
+/// If the server has a [`ResponseCache`](crate::ResponseCache), openings
+/// already chosen by the bot for the position are answered from it.
+///
 /// # Route
 /// `POST /{api_version}/ybot/pie-opening/{bot_id}`
 ///
@@ -35,22 +49,27 @@ pub struct PieOpeningResponse {
 ///
 /// # Response
 /// On success, returns a [`PieOpeningResponse`] with the chosen coordinates.
+#[utoipa::path(
+    post, path = "/{api_version}/ybot/pie-opening/{bot_id}", summary = "A balanced opening move for games with the Pie Rule",
+    params(PieOpeningParams),
+    request_body = YEN,
+    responses((status = 200, body = PieOpeningResponse), ErrorResponse)
+)]
 #[axum::debug_handler]
 pub async fn pie_opening(
     State(state): State<AppState>,
     Path(params): Path<PieOpeningParams>,
-    Json(yen): Json<YEN>,
-) -> Result<Json<PieOpeningResponse>, Json<ErrorResponse>> {
-    check_api_version(&params.api_version)?;
-
+    JsonBody(yen): JsonBody<YEN>,
+) -> Result<Json<PieOpeningResponse>, ErrorResponse> {
     let game_y = match GameY::try_from(yen) {
         Ok(game) => game,
         Err(err) => {
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 &format!("Invalid YEN format: {}", err),
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::from(&err)));
         }
     };
 
@@ -58,25 +77,53 @@ pub async fn pie_opening(
         Some(bot) => bot,
         None => {
             let available = state.bots().names().join(", ");
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 &format!(
                     "Bot not found: {}, available bots: [{}]",
                     params.bot_id, available
                 ),
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::BotNotFound));
         }
     };
 
-    let coords = match bot.choose_pie_opening(&game_y) {
-        Some(coords) => coords,
+    let options = SearchOptions::default();
+    let cache = match cache::lookup_pie_opening(&state, bot.as_ref(), &game_y, &options) {
+        Lookup::Hit(coords) => {
+            return Ok(Json(PieOpeningResponse {
+                api_version: params.api_version,
+                bot_id: params.bot_id,
+                coords,
+            }));
+        }
+        Lookup::Miss(cache) => Some((cache, game_y.clone())),
+        Lookup::Uncached => None,
+    };
+    let opening = run_cancellable(move || bot.choose_pie_opening(&game_y))
+        .await
+        .map_err(|err| {
+            ErrorResponse::error(
+                &format!("Search failed: {}", err),
+                Some(params.api_version.clone()),
+                Some(params.bot_id.clone()),
+            )
+        })?;
+    let coords = match opening {
+        Some(coords) => {
+            if let Some((cache, game)) = cache {
+                cache.insert_pie_opening(&params.bot_id, &game, &options, coords);
+            }
+            coords
+        }
         None => {
-            return Err(Json(ErrorResponse::error(
+            return Err(ErrorResponse::error(
                 "No valid moves available for the bot",
                 Some(params.api_version),
                 Some(params.bot_id),
-            )));
+            )
+            .with_code(ErrorCode::NoMoves));
         }
     };
 
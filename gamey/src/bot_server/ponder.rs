@@ -0,0 +1,220 @@
+use crate::{
+    Coordinates, GameY, YEN,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    search_token,
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+use std::sync::PoisonError;
+use utoipa::{IntoParams, ToSchema};
+
+/// Path parameters for starting a ponder search.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct PonderParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+}
+
+/// Path parameters for discarding a ponder search.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct DiscardPonderParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+    /// Identifier of the game.
+    game_id: String,
+}
+
+/// Request body of the ponder endpoint: the position after the bot's move
+/// and the game it belongs to.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PonderRequest {
+    #[serde(flatten)]
+    pub yen: YEN,
+    /// The game whose search session ponders.
+    pub game_id: String,
+}
+
+/// Response returned by the ponder endpoints.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct PonderResponse {
+    pub api_version: String,
+    pub bot_id: String,
+    pub game_id: String,
+    /// Whether a ponder search is now running for the game.
+    pub pondering: bool,
+    /// The reply the bot expects and is pondering on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub predicted: Option<Coordinates>,
+}
+
+/// Handler that starts pondering on the opponent's time.
+///
+/// Call it after each bot move, with the opponent to move. The bot guesses
+/// the reply and searches the resulting position in the background. The next
+/// choose request with the same `game_id` stops the ponder search and, if
+/// the guess was played, answers faster.
+///
+/// # Route
+/// `POST /{api_version}/ybot/ponder/{bot_id}`
+///
+/// # Request Body
+/// A JSON object in YEN format with an extra `game_id` field.
+///
+/// # Response
+/// On success, returns a [`PonderResponse`]. `pondering` is `false` if the
+/// bot does not ponder, there is nothing to ponder, or the server has no
+/// background search slot free (see [`SearchLimiter`](crate::SearchLimiter)).
+#[utoipa::path(
+    post, path = "/{api_version}/ybot/ponder/{bot_id}", summary = "Think on the opponent's time",
+    params(PonderParams),
+    request_body = PonderRequest,
+    responses((status = 200, body = PonderResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn ponder(
+    State(state): State<AppState>,
+    Path(params): Path<PonderParams>,
+    JsonBody(request): JsonBody<PonderRequest>,
+) -> Result<Json<PonderResponse>, ErrorResponse> {
+    let game_y = match GameY::try_from(request.yen) {
+        Ok(game) => game,
+        Err(err) => {
+            return Err(ErrorResponse::error(
+                &format!("Invalid YEN format: {}", err),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::from(&err)));
+        }
+    };
+
+    let bot = match state.bots().find(&params.bot_id) {
+        Some(bot) => bot,
+        None => {
+            let available = state.bots().names().join(", ");
+            return Err(ErrorResponse::error(
+                &format!(
+                    "Bot not found: {}, available bots: [{}]",
+                    params.bot_id, available
+                ),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::BotNotFound));
+        }
+    };
+
+    // Locking waits for a search of the same game, and starting a ponder
+    // stops the previous one, so neither runs on the async workers.
+    let session = state.sessions().session(&params.bot_id, &request.game_id);
+    let searches = state.searches();
+    // The ponder search outlives the request, but not the server.
+    let stop = search_token();
+    let predicted = tokio::task::spawn_blocking(move || {
+        let mut session = session.lock().unwrap_or_else(PoisonError::into_inner);
+        // The game's previous ponder search gives its slot back first.
+        session.stop_pondering();
+        let permit = searches.try_acquire_background()?;
+        session.set_ponder_slot(permit);
+        let predicted = stop.scope(|| bot.ponder(&game_y, &mut session));
+        session.release_ponder_slot();
+        predicted
+    })
+    .await
+    .map_err(|err| {
+        ErrorResponse::error(
+            &format!("Ponder failed: {}", err),
+            Some(params.api_version.clone()),
+            Some(params.bot_id.clone()),
+        )
+    })?;
+
+    Ok(Json(PonderResponse {
+        api_version: params.api_version,
+        bot_id: params.bot_id,
+        game_id: request.game_id,
+        pondering: predicted.is_some(),
+        predicted,
+    }))
+}
+
+/// Handler that stops and discards a ponder search, e.g. when the game ended
+/// or was abandoned.
+///
+/// # Route
+/// `DELETE /{api_version}/ybot/ponder/{bot_id}/{game_id}`
+///
+/// # Response
+/// A [`PonderResponse`] with `pondering` set to `false`.
+#[utoipa::path(
+    delete, path = "/{api_version}/ybot/ponder/{bot_id}/{game_id}", summary = "Discard a ponder search",
+    params(DiscardPonderParams),
+    responses((status = 200, body = PonderResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn discard_ponder(
+    State(state): State<AppState>,
+    Path(params): Path<DiscardPonderParams>,
+) -> Result<Json<PonderResponse>, ErrorResponse> {
+    if let Some(session) = state.sessions().get(&params.bot_id, &params.game_id) {
+        let discarded = tokio::task::spawn_blocking(move || {
+            session
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .discard_pondering();
+        });
+        discarded.await.map_err(|err| {
+            ErrorResponse::error(
+                &format!("Ponder failed: {}", err),
+                Some(params.api_version.clone()),
+                Some(params.bot_id.clone()),
+            )
+        })?;
+    }
+
+    Ok(Json(PonderResponse {
+        api_version: params.api_version,
+        bot_id: params.bot_id,
+        game_id: params.game_id,
+        pondering: false,
+        predicted: None,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ponder_request_reads_game_id() {
+        let json = r#"{"size":2,"turn":1,"players":["B","R"],"layout":"B/..","game_id":"g1"}"#;
+        let request: PonderRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(request.game_id, "g1");
+        assert_eq!(request.yen.size(), 2);
+    }
+
+    #[test]
+    fn test_ponder_response_omits_missing_prediction() {
+        let response = PonderResponse {
+            api_version: "v1".to_string(),
+            bot_id: "fast_bot".to_string(),
+            game_id: "g1".to_string(),
+            pondering: false,
+            predicted: None,
+        };
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("predicted"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Search sessions kept between the choose requests of one game.
+//!
+//! A client that sends a `game_id` with its choose requests gets the same
+//! [`SearchSession`] for every move of that game, so the bot reuses its
+//! transposition table instead of allocating a new one per request. Sessions
+//! are keyed by bot and game id and expire when unused for a while; an
+//! expiring session also stops its ponder search. The server drops expired
+//! sessions every [`SESSION_SWEEP_INTERVAL`] (see [`sweep_sessions`]).
+
+use crate::SearchSession;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Default time after which an unused session is dropped.
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Default number of sessions kept at once. Each session holds a
+/// transposition table, so this and [`DEFAULT_SESSION_TT_SIZE_MB`] bound the
+/// server's memory use.
+pub const DEFAULT_MAX_SESSIONS: usize = 16;
+
+/// Default cap on the transposition table of a session, in MB, whatever the
+/// bot's configuration asks for.
+pub const DEFAULT_SESSION_TT_SIZE_MB: usize = 8;
+
+/// Time between two sweeps of [`sweep_sessions`].
+pub const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A session shared between the requests of one game.
+pub type SharedSession = Arc<Mutex<SearchSession>>;
+
+struct StoredSession {
+    session: SharedSession,
+    last_used: Instant,
+}
+
+/// Search sessions of the games currently being played, keyed by bot id and
+/// game id.
+pub struct SessionStore {
+    ttl: Duration,
+    max_sessions: usize,
+    tt_size_mb: usize,
+    sessions: Mutex<HashMap<(String, String), StoredSession>>,
+}
+
+impl SessionStore {
+    /// Creates a store that drops sessions unused for `ttl` and keeps at
+    /// most `max_sessions` at once, evicting the least recently used one.
+    /// Their transposition tables are capped at
+    /// [`DEFAULT_SESSION_TT_SIZE_MB`].
+    pub fn new(ttl: Duration, max_sessions: usize) -> Self {
+        Self {
+            ttl,
+            max_sessions: max_sessions.max(1),
+            tt_size_mb: DEFAULT_SESSION_TT_SIZE_MB,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Caps the transposition table of each new session at `size_mb` MB.
+    pub fn with_tt_size_mb(mut self, size_mb: usize) -> Self {
+        self.tt_size_mb = size_mb.max(1);
+        self
+    }
+
+    /// Returns the session of `game_id` for `bot_id`, creating it if needed.
+    ///
+    /// Expired sessions are dropped on every call.
+    pub fn session(&self, bot_id: &str, game_id: &str) -> SharedSession {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        sessions.retain(|_, stored| now.duration_since(stored.last_used) < self.ttl);
+
+        let key = (bot_id.to_string(), game_id.to_string());
+        if !sessions.contains_key(&key) && sessions.len() >= self.max_sessions {
+            let oldest = sessions
+                .iter()
+                .min_by_key(|(_, stored)| stored.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+
+        let stored = sessions.entry(key).or_insert_with(|| StoredSession {
+            session: Arc::new(Mutex::new(
+                SearchSession::new().with_tt_limit_mb(self.tt_size_mb),
+            )),
+            last_used: now,
+        });
+        stored.last_used = now;
+        Arc::clone(&stored.session)
+    }
+
+    /// Returns the session of `game_id` for `bot_id` if one is stored and
+    /// has not expired.
+    pub fn get(&self, bot_id: &str, game_id: &str) -> Option<SharedSession> {
+        let sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        sessions
+            .get(&(bot_id.to_string(), game_id.to_string()))
+            .filter(|stored| stored.last_used.elapsed() < self.ttl)
+            .map(|stored| Arc::clone(&stored.session))
+    }
+
+    /// Drops the expired sessions, stopping their ponder searches, and
+    /// returns how many were dropped.
+    pub fn sweep(&self) -> usize {
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        let before = sessions.len();
+        sessions.retain(|_, stored| stored.last_used.elapsed() < self.ttl);
+        before - sessions.len()
+    }
+
+    /// Number of sessions currently stored, including expired ones that have
+    /// not been dropped yet.
+    pub fn len(&self) -> usize {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Returns `true` if no session is stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for SessionStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_TTL, DEFAULT_MAX_SESSIONS)
+    }
+}
+
+/// Sweeps `sessions` every `interval`, so that abandoned games give their
+/// tables and ponder searches back even if no new session is created.
+pub async fn sweep_sessions(sessions: Arc<SessionStore>, interval: Duration) {
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticks.tick().await;
+        sessions.sweep();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_game_gets_same_session() {
+        let store = SessionStore::default();
+        let first = store.session("fast_bot", "g1");
+        let second = store.session("fast_bot", "g1");
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(store.len(), 1);
+        assert!(Arc::ptr_eq(&first, &store.get("fast_bot", "g1").unwrap()));
+        assert!(store.get("fast_bot", "g2").is_none());
+    }
+
+    #[test]
+    fn test_sessions_are_per_bot_and_game() {
+        let store = SessionStore::default();
+        let a = store.session("fast_bot", "g1");
+        let b = store.session("smart_bot", "g1");
+        let c = store.session("fast_bot", "g2");
+        assert!(!Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_expired_sessions_are_dropped() {
+        let store = SessionStore::new(Duration::ZERO, DEFAULT_MAX_SESSIONS);
+        let first = store.session("fast_bot", "g1");
+        let second = store.session("fast_bot", "g1");
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_least_recently_used_session_is_evicted() {
+        let store = SessionStore::new(DEFAULT_SESSION_TTL, 2);
+        let g1 = store.session("fast_bot", "g1");
+        store.session("fast_bot", "g2");
+        store.session("fast_bot", "g1");
+        store.session("fast_bot", "g3");
+
+        assert_eq!(store.len(), 2);
+        assert!(Arc::ptr_eq(&g1, &store.session("fast_bot", "g1")));
+    }
+
+    #[test]
+    fn test_sweep_drops_only_expired_sessions() {
+        let store = SessionStore::new(Duration::from_millis(200), DEFAULT_MAX_SESSIONS);
+        store.session("fast_bot", "g1");
+        std::thread::sleep(Duration::from_millis(120));
+        store.session("fast_bot", "g2");
+        std::thread::sleep(Duration::from_millis(120));
+
+        assert_eq!(store.sweep(), 1);
+        assert_eq!(store.len(), 1);
+        assert!(store.get("fast_bot", "g2").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_swept_on_a_timer() {
+        let store = Arc::new(SessionStore::new(Duration::ZERO, DEFAULT_MAX_SESSIONS));
+        store.session("fast_bot", "g1");
+        let sweeper = tokio::spawn(sweep_sessions(Arc::clone(&store), Duration::from_millis(5)));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sweeper.abort();
+        assert!(store.is_empty());
+    }
+}
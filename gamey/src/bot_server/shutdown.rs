@@ -0,0 +1,168 @@
+//! Graceful shutdown of the bot server.
+//!
+//! On `SIGTERM` or `SIGINT` the server stops accepting connections and starts
+//! draining: `/health/ready` turns unready so that the orchestrator sends no
+//! more traffic, while the requests in flight go on. Searches still running
+//! when the shutdown deadline passes are cancelled through the server's
+//! [`StopToken`], of which every request's search token is a
+//! [`child`](StopToken::child); they return their best move so far and the
+//! server exits once the last response is sent.
+
+use crate::{StopToken, state::AppState};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+tokio::task_local! {
+    /// Server-wide token of the request being handled.
+    static SERVER_STOP: StopToken;
+}
+
+/// Default time in-flight searches get to finish once shutdown starts.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the server is in its life: since when it runs, whether it is
+/// draining, and the token that cancels all of its searches.
+pub struct Lifecycle {
+    started: Instant,
+    draining: watch::Sender<bool>,
+    stop: StopToken,
+}
+
+impl Lifecycle {
+    /// Creates the lifecycle of a server starting now.
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            draining: watch::Sender::new(false),
+            stop: StopToken::new(),
+        }
+    }
+
+    /// Time since the server started.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Returns `true` once shutdown has started.
+    pub fn is_draining(&self) -> bool {
+        *self.draining.borrow()
+    }
+
+    /// Starts the shutdown: the server reports itself unready from now on.
+    pub fn start_draining(&self) {
+        self.draining.send_replace(true);
+    }
+
+    /// Waits until shutdown has started.
+    pub async fn draining(&self) {
+        let mut draining = self.draining.subscribe();
+        let _ = draining.wait_for(|draining| *draining).await;
+    }
+
+    /// Stops every search started by a request, now and later.
+    pub fn cancel_searches(&self) {
+        self.stop.stop();
+    }
+
+    /// The server-wide token the searches of requests are children of.
+    pub fn stop_token(&self) -> &StopToken {
+        &self.stop
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware that lets the searches of a request be cancelled with the
+/// server's (see [`search_token`]).
+pub async fn watch_shutdown(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let stop = state.lifecycle().stop_token().clone();
+    SERVER_STOP.scope(stop, next.run(request)).await
+}
+
+/// A new token for a search started by the current request: a child of the
+/// server-wide token inside [`watch_shutdown`], an independent one outside.
+pub fn search_token() -> StopToken {
+    SERVER_STOP.try_with(StopToken::child).unwrap_or_default()
+}
+
+/// Completes when the process receives `SIGINT` (Ctrl+C) or, on Unix,
+/// `SIGTERM`.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = interrupt => {}
+        () = terminate => {}
+    }
+}
+
+/// Once `lifecycle` starts draining, waits `timeout` and cancels the
+/// searches still running.
+pub async fn cancel_after_deadline(lifecycle: Arc<Lifecycle>, timeout: Duration) {
+    lifecycle.draining().await;
+    tokio::time::sleep(timeout).await;
+    lifecycle.cancel_searches();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_searches_are_cancelled_after_the_deadline() {
+        let lifecycle = Arc::new(Lifecycle::new());
+        let search = lifecycle.stop_token().child();
+        let deadline = tokio::spawn(cancel_after_deadline(
+            Arc::clone(&lifecycle),
+            Duration::from_millis(10),
+        ));
+
+        tokio::task::yield_now().await;
+        assert!(!search.is_stopped());
+
+        lifecycle.start_draining();
+        assert!(lifecycle.is_draining());
+        deadline.await.unwrap();
+        assert!(search.is_stopped());
+    }
+
+    #[tokio::test]
+    async fn test_search_token_follows_the_server_token() {
+        assert!(!search_token().is_stopped());
+
+        let server = StopToken::new();
+        let search = SERVER_STOP
+            .scope(server.clone(), async { search_token() })
+            .await;
+        server.stop();
+        assert!(search.is_stopped());
+    }
+}
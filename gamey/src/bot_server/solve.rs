@@ -0,0 +1,142 @@
+use crate::{
+    DEFAULT_SOLVER_NODES, GameY, SolveResult, YEN,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable, solve,
+};
+use axum::{
+    Json,
+    extract::{Path, Query},
+};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Upper bound on `max_nodes`; larger requests are clamped to this value.
+pub const MAX_SOLVER_NODES: u64 = 10_000_000;
+
+/// Path parameters for the solve endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct SolveParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+}
+
+/// Query parameters for the solve endpoint.
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SolveQuery {
+    /// Node budget (defaults to [`DEFAULT_SOLVER_NODES`]).
+    max_nodes: Option<u64>,
+}
+
+/// Response returned by the solve endpoint on success.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct SolveResponse {
+    pub api_version: String,
+    #[serde(flatten)]
+    pub result: SolveResult,
+}
+
+/// Handler for the exact solver endpoint.
+///
+/// Proves whether the side to move wins or loses, and gives a winning move
+/// when it does. Positions too large for the node budget come back as
+/// `unknown`. Used to verify puzzle positions.
+///
+/// # Route
+/// `POST /{api_version}/solve?max_nodes=N`
+///
+/// # Request Body
+/// A JSON object in YEN format representing the position to solve.
+///
+/// # Response
+/// On success, returns a [`SolveResponse`]. On failure, returns an
+/// [`ErrorResponse`].
+#[utoipa::path(
+    post, path = "/{api_version}/solve", summary = "Exact win/loss for the side to move",
+    params(SolveParams, SolveQuery),
+    request_body = YEN,
+    responses((status = 200, body = SolveResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn solve_position(
+    Path(params): Path<SolveParams>,
+    Query(query): Query<SolveQuery>,
+    JsonBody(yen): JsonBody<YEN>,
+) -> Result<Json<SolveResponse>, ErrorResponse> {
+    let game_y = match GameY::try_from(yen) {
+        Ok(game) => game,
+        Err(err) => {
+            return Err(ErrorResponse::error(
+                &format!("Invalid YEN format: {}", err),
+                Some(params.api_version),
+                None,
+            )
+            .with_code(ErrorCode::from(&err)));
+        }
+    };
+    if game_y.check_game_over() {
+        return Err(ErrorResponse::error(
+            "The game is already over",
+            Some(params.api_version),
+            None,
+        )
+        .with_code(ErrorCode::GameOver));
+    }
+
+    let max_nodes = query
+        .max_nodes
+        .unwrap_or(DEFAULT_SOLVER_NODES)
+        .clamp(1, MAX_SOLVER_NODES);
+    let result = run_cancellable(move || solve(&game_y, max_nodes))
+        .await
+        .map_err(|err| {
+            ErrorResponse::error(
+                &format!("Search failed: {}", err),
+                Some(params.api_version.clone()),
+                None,
+            )
+        })?;
+    Ok(Json(SolveResponse {
+        api_version: params.api_version,
+        result,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Coordinates, SolveOutcome};
+
+    #[test]
+    fn test_solve_response_serialize() {
+        let resp = SolveResponse {
+            api_version: "v1".to_string(),
+            result: SolveResult {
+                outcome: SolveOutcome::Win,
+                proof_move: Some(Coordinates::new(1, 0, 0)),
+                nodes: 12,
+            },
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"outcome\":\"win\""));
+        assert!(json.contains("\"proof_move\":{"));
+        assert!(json.contains("\"nodes\":12"));
+    }
+
+    #[test]
+    fn test_solve_response_omits_proof_move_when_unknown() {
+        let resp = SolveResponse {
+            api_version: "v1".to_string(),
+            result: SolveResult {
+                outcome: SolveOutcome::Unknown,
+                proof_move: None,
+                nodes: 5,
+            },
+        };
+        let json = serde_json::to_string(&resp).unwrap();
+        assert!(json.contains("\"outcome\":\"unknown\""));
+        assert!(!json.contains("proof_move"));
+    }
+}
@@ -1,29 +1,166 @@
-use crate::YBotRegistry;
+use crate::{
+    DEFAULT_BODY_LIMIT, GameStore, Lifecycle, Metrics, ResponseCache, RunningSearches,
+    SearchLimiter, SessionStore, YBotRegistry,
+};
 use std::sync::Arc;
+use std::time::Duration;
 
 /// Shared application state for the bot server.
 ///
-/// This struct holds the bot registry and is shared across all request handlers
+/// This struct holds the bot registry, the per-game search sessions, the
+/// games hosted by the server and the limit on concurrent searches, and is
+/// shared across all request handlers
 /// via Axum's state extraction. It uses `Arc` internally to allow cheap cloning
 /// for concurrent request handling.
 #[derive(Clone)]
 pub struct AppState {
     /// The registry of available bots, wrapped in Arc for thread-safe sharing.
     bots: Arc<YBotRegistry>,
+    /// Search sessions of the games in progress.
+    sessions: Arc<SessionStore>,
+    /// Games hosted by the server.
+    games: Arc<GameStore>,
+    /// Slots for the searches running at once.
+    searches: Arc<SearchLimiter>,
+    /// Streamed searches in progress, so that clients can stop them.
+    streams: Arc<RunningSearches>,
+    /// Request and search metrics served at `/metrics`.
+    metrics: Arc<Metrics>,
+    /// Uptime and shutdown state of the server.
+    lifecycle: Arc<Lifecycle>,
+    /// Largest accepted request body, in bytes.
+    body_limit: usize,
+    /// Time after which a request is abandoned, if any.
+    request_timeout: Option<Duration>,
+    /// Moves already chosen, if caching is enabled.
+    cache: Option<Arc<ResponseCache>>,
+    /// Bearer token required by the `/admin` endpoints, if they are enabled.
+    admin_token: Option<Arc<str>>,
 }
 
 impl AppState {
-    /// Creates a new application state with the given bot registry.
+    /// Creates a new application state with the given bot registry, a
+    /// default session store, an empty game store, a default search
+    /// limiter, the default body limit, no request timeout, no response
+    /// cache and no admin token.
     pub fn new(bots: YBotRegistry) -> Self {
         Self {
             bots: Arc::new(bots),
+            sessions: Arc::new(SessionStore::default()),
+            games: Arc::new(GameStore::default()),
+            searches: Arc::new(SearchLimiter::default()),
+            streams: Arc::new(RunningSearches::default()),
+            metrics: Arc::new(Metrics::new()),
+            lifecycle: Arc::new(Lifecycle::new()),
+            body_limit: DEFAULT_BODY_LIMIT,
+            request_timeout: None,
+            cache: None,
+            admin_token: None,
         }
     }
 
+    /// Replaces the session store, e.g. to change its expiry time.
+    pub fn with_sessions(mut self, sessions: SessionStore) -> Self {
+        self.sessions = Arc::new(sessions);
+        self
+    }
+
+    /// Replaces the game store, e.g. to change how long idle games are kept.
+    pub fn with_games(mut self, games: GameStore) -> Self {
+        self.games = Arc::new(games);
+        self
+    }
+
+    /// Replaces the search limiter, e.g. to change how many searches may run
+    /// or wait at once.
+    pub fn with_search_limiter(mut self, searches: SearchLimiter) -> Self {
+        self.searches = Arc::new(searches);
+        self
+    }
+
+    /// Sets the largest accepted request body, in bytes.
+    pub fn with_body_limit(mut self, body_limit: usize) -> Self {
+        self.body_limit = body_limit;
+        self
+    }
+
+    /// Sets the time after which a request is abandoned; `None` lets
+    /// requests run as long as their searches take.
+    pub fn with_request_timeout(mut self, request_timeout: Option<Duration>) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Sets the cache of chosen moves; `None` searches every request.
+    pub fn with_cache(mut self, cache: Option<ResponseCache>) -> Self {
+        self.cache = cache.map(Arc::new);
+        self
+    }
+
+    /// Sets the bearer token required by the `/admin` endpoints; `None` or
+    /// an empty token refuses every admin request.
+    pub fn with_admin_token(mut self, admin_token: Option<String>) -> Self {
+        self.admin_token = admin_token
+            .filter(|token| !token.is_empty())
+            .map(Arc::from);
+        self
+    }
+
     /// Returns a clone of the Arc-wrapped bot registry.
     pub fn bots(&self) -> Arc<YBotRegistry> {
         Arc::clone(&self.bots)
     }
+
+    /// Returns a clone of the Arc-wrapped session store.
+    pub fn sessions(&self) -> Arc<SessionStore> {
+        Arc::clone(&self.sessions)
+    }
+
+    /// Returns a clone of the Arc-wrapped game store.
+    pub fn games(&self) -> Arc<GameStore> {
+        Arc::clone(&self.games)
+    }
+
+    /// Returns a clone of the Arc-wrapped search limiter.
+    pub fn searches(&self) -> Arc<SearchLimiter> {
+        Arc::clone(&self.searches)
+    }
+
+    /// Returns a clone of the Arc-wrapped registry of streamed searches.
+    pub fn streams(&self) -> Arc<RunningSearches> {
+        Arc::clone(&self.streams)
+    }
+
+    /// Returns a clone of the Arc-wrapped metrics.
+    pub fn metrics(&self) -> Arc<Metrics> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Returns a clone of the Arc-wrapped lifecycle of the server.
+    pub fn lifecycle(&self) -> Arc<Lifecycle> {
+        Arc::clone(&self.lifecycle)
+    }
+
+    /// Returns the largest accepted request body, in bytes.
+    pub fn body_limit(&self) -> usize {
+        self.body_limit
+    }
+
+    /// Returns the time after which a request is abandoned, if any.
+    pub fn request_timeout(&self) -> Option<Duration> {
+        self.request_timeout
+    }
+
+    /// Returns a clone of the Arc-wrapped response cache, if caching is
+    /// enabled.
+    pub fn cache(&self) -> Option<Arc<ResponseCache>> {
+        self.cache.clone()
+    }
+
+    /// Returns the bearer token required by the `/admin` endpoints, if any.
+    pub fn admin_token(&self) -> Option<&str> {
+        self.admin_token.as_deref()
+    }
 }
 
 #[cfg(test)]
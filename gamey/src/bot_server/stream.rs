@@ -0,0 +1,329 @@
+//! Live search progress over Server-Sent Events.
+//!
+//! A streamed search sends one event per completed depth of the bot's
+//! iterative deepening, then the chosen move. The search stops early, and
+//! still sends the best move found so far, when the client asks for it with
+//! the search id from the first event; it is cancelled outright when the
+//! client closes the stream.
+
+use crate::{
+    GameY, MoveResponse, SearchOptions, StopOnDrop, StopToken, YEN, search_token,
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    response::{
+        IntoResponse, Response,
+        sse::{Event, Sse},
+    },
+};
+use futures_core::Stream;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Path parameters for the streaming endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct StreamParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+}
+
+/// Path parameters for the stop endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct StopSearchParams {
+    /// API version; these endpoints serve only `v1`.
+    #[param(pattern = "^v1$")]
+    api_version: String,
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+    /// Identifier of the streamed search.
+    search_id: String,
+}
+
+/// Request body of the streaming endpoint: a YEN position plus the optional
+/// [`SearchOptions`] fields.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct StreamRequest {
+    #[serde(flatten)]
+    pub yen: YEN,
+    #[serde(flatten)]
+    pub options: SearchOptions,
+}
+
+/// Data of the `start` event, sent first on every stream.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SearchStarted {
+    pub api_version: String,
+    pub bot_id: String,
+    /// Id to stop the search with.
+    pub search_id: String,
+}
+
+/// Response returned by the stop endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct StopSearchResponse {
+    pub api_version: String,
+    pub bot_id: String,
+    pub search_id: String,
+    /// `false` if the search had already finished or never existed.
+    pub stopped: bool,
+}
+
+/// Stop tokens of the streamed searches in progress, keyed by search id.
+#[derive(Default)]
+pub struct RunningSearches {
+    searches: Mutex<HashMap<String, StopToken>>,
+}
+
+impl RunningSearches {
+    /// Registers a new search and returns its id and stop token, which the
+    /// server also stops on shutdown (see [`search_token`]).
+    pub fn start(&self) -> (String, StopToken) {
+        let mut searches = self.searches.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut rng = rand::rng();
+        let id = loop {
+            let id = format!("{:016x}", rng.random::<u64>());
+            if !searches.contains_key(&id) {
+                break id;
+            }
+        };
+        let token = search_token();
+        searches.insert(id.clone(), token.clone());
+        (id, token)
+    }
+
+    /// Stops the search `id`. Returns `false` if no such search is running.
+    pub fn stop(&self, id: &str) -> bool {
+        let searches = self.searches.lock().unwrap_or_else(PoisonError::into_inner);
+        searches.get(id).map(StopToken::stop).is_some()
+    }
+
+    /// Forgets the search `id` once it has finished.
+    pub fn finish(&self, id: &str) {
+        self.searches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(id);
+    }
+
+    /// Number of streamed searches in progress.
+    pub fn len(&self) -> usize {
+        self.searches
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns `true` if no streamed search is in progress.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The events of one streamed search. Dropping it, e.g. because the client
+/// went away, stops the search.
+pub struct SearchEvents {
+    events: mpsc::UnboundedReceiver<Event>,
+    _stop: StopOnDrop,
+}
+
+impl Stream for SearchEvents {
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx).map(|event| event.map(Ok))
+    }
+}
+
+fn json_event<T: Serialize>(name: &str, data: &T) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|_| Event::default().event(name))
+}
+
+/// Handler that runs a bot's search and streams its progress.
+///
+/// Used by the webapp's "bot is thinking" view and the analysis board.
+///
+/// # Route
+/// `POST /{api_version}/ybot/stream/{bot_id}`
+///
+/// # Request Body
+/// A [`StreamRequest`]: a YEN position, optionally with `seed` and
+/// `max_nodes`.
+///
+/// # Response
+/// A `text/event-stream` with these events, each carrying JSON data:
+/// - `start`: a [`SearchStarted`] with the id to stop the search with
+/// - `depth`: a [`SearchProgress`](crate::SearchProgress) for every completed depth
+/// - `move`: the final [`MoveResponse`], or `error` with an
+///   [`ErrorResponse`] if the bot found no move
+///
+/// Bots without iterative deepening, and moves found without it, only send
+/// `start` and `move`. Errors found before the search starts are returned
+/// as a plain [`ErrorResponse`], and a saturated server answers `503`.
+#[utoipa::path(
+    post, path = "/{api_version}/ybot/stream/{bot_id}", summary = "Stream search progress as Server-Sent Events",
+    params(StreamParams),
+    request_body = StreamRequest,
+    responses((status = 200, body = String, content_type = "text/event-stream"), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn stream_search(
+    State(state): State<AppState>,
+    Path(params): Path<StreamParams>,
+    JsonBody(request): JsonBody<StreamRequest>,
+) -> Result<Sse<SearchEvents>, Response> {
+    let game_y = match GameY::try_from(request.yen) {
+        Ok(game) => game,
+        Err(err) => {
+            return Err(ErrorResponse::error(
+                &format!("Invalid YEN format: {}", err),
+                Some(params.api_version),
+                Some(params.bot_id),
+            )
+            .with_code(ErrorCode::from(&err))
+            .into_response());
+        }
+    };
+    if game_y.check_game_over() {
+        return Err(ErrorResponse::error(
+            "The game is already over",
+            Some(params.api_version),
+            Some(params.bot_id),
+        )
+        .with_code(ErrorCode::GameOver)
+        .into_response());
+    }
+    let Some(bot) = state.bots().find(&params.bot_id) else {
+        let available = state.bots().names().join(", ");
+        return Err(ErrorResponse::error(
+            &format!(
+                "Bot not found: {}, available bots: [{}]",
+                params.bot_id, available
+            ),
+            Some(params.api_version),
+            Some(params.bot_id),
+        )
+        .with_code(ErrorCode::BotNotFound)
+        .into_response());
+    };
+
+    // The permit is held until the search ends, not just until the stream
+    // is opened, so streamed searches count against the same limit.
+    let permit = state
+        .searches()
+        .acquire()
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let streams = state.streams();
+    let (search_id, token) = streams.start();
+    let (events, receiver) = mpsc::unbounded_channel();
+    let _ = events.send(json_event(
+        "start",
+        &SearchStarted {
+            api_version: params.api_version.clone(),
+            bot_id: params.bot_id.clone(),
+            search_id: search_id.clone(),
+        },
+    ));
+
+    let stop = token.stop_on_drop();
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let chosen = token.scope(|| {
+            bot.choose_move_with_progress(&game_y, &request.options, &mut |progress| {
+                let _ = events.send(json_event("depth", progress));
+            })
+        });
+        streams.finish(&search_id);
+        let last = match chosen {
+            Some(coords) => json_event(
+                "move",
+                &MoveResponse {
+                    api_version: params.api_version,
+                    bot_id: params.bot_id,
+                    coords,
+                },
+            ),
+            None => json_event(
+                "error",
+                &ErrorResponse::error(
+                    "No valid moves available for the bot",
+                    Some(params.api_version),
+                    Some(params.bot_id),
+                )
+                .with_code(ErrorCode::NoMoves),
+            ),
+        };
+        let _ = events.send(last);
+    });
+
+    Ok(Sse::new(SearchEvents {
+        events: receiver,
+        _stop: stop,
+    }))
+}
+
+/// Handler that stops a streamed search early.
+///
+/// The stream then sends the best move found so far and ends.
+///
+/// # Route
+/// `DELETE /{api_version}/ybot/stream/{bot_id}/{search_id}`
+///
+/// # Response
+/// A [`StopSearchResponse`].
+#[utoipa::path(
+    delete, path = "/{api_version}/ybot/stream/{bot_id}/{search_id}", summary = "Stop a streamed search",
+    params(StopSearchParams),
+    responses((status = 200, body = StopSearchResponse), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn stop_search(
+    State(state): State<AppState>,
+    Path(params): Path<StopSearchParams>,
+) -> Result<Json<StopSearchResponse>, ErrorResponse> {
+    let stopped = state.streams().stop(&params.search_id);
+    Ok(Json(StopSearchResponse {
+        api_version: params.api_version,
+        bot_id: params.bot_id,
+        search_id: params.search_id,
+        stopped,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stopping_a_running_search_sets_its_token() {
+        let searches = RunningSearches::default();
+        let (id, token) = searches.start();
+        assert_eq!(searches.len(), 1);
+
+        assert!(searches.stop(&id));
+        assert!(token.is_stopped());
+
+        searches.finish(&id);
+        assert!(!searches.stop(&id));
+        assert!(searches.is_empty());
+    }
+}
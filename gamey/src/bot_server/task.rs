@@ -0,0 +1,144 @@
+//! Running bot searches off the async runtime.
+//!
+//! Searches are CPU-bound and can take seconds, so handlers run them on the
+//! blocking thread pool. Each search gets its own [`StopToken`](crate::StopToken), which is
+//! stopped when the handler's future is dropped, e.g. because the client
+//! disconnected or a timeout layer gave up on the request, or when the
+//! server cancels its searches on shutdown (see [`search_token`]). The
+//! search then returns within a few nodes instead of finishing for nobody.
+//!
+//! The statistics of the searches are attributed to the request in the
+//! server's metrics (see [`record_searches`]).
+//!
+//! [`limit_request_time`] is that timeout layer: it answers requests that
+//! outlive the configured request timeout with [`ErrorCode::Timeout`].
+
+use crate::{
+    error::{ErrorCode, ErrorResponse},
+    record_searches, search_token,
+    state::AppState,
+};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+use tokio::task::JoinError;
+
+/// Runs `search` on the blocking thread pool, inside the scope of a stop
+/// token that is stopped if the returned future is dropped before the
+/// search finishes, or if the server cancels its searches.
+///
+/// Returns an error only if `search` panicked.
+pub async fn run_cancellable<T, F>(search: F) -> Result<T, JoinError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    run_cancellable_for(None, search).await
+}
+
+/// Like [`run_cancellable`], but also stops the search once `time_limit`
+/// has passed. A stopped search returns its best result so far, which is
+/// awaited.
+pub async fn run_cancellable_for<T, F>(
+    time_limit: Option<Duration>,
+    search: F,
+) -> Result<T, JoinError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let stop = search_token();
+    let _cancel = stop.stop_on_drop();
+    let token = stop.clone();
+    let search = record_searches(search);
+    let mut handle = tokio::task::spawn_blocking(move || token.scope(search));
+    let Some(time_limit) = time_limit else {
+        return handle.await;
+    };
+    tokio::select! {
+        result = &mut handle => return result,
+        () = tokio::time::sleep(time_limit) => stop.stop(),
+    }
+    handle.await
+}
+
+/// Middleware that abandons requests running longer than the state's
+/// request timeout, which stops their searches.
+///
+/// Streamed searches only count the time until their stream opens.
+pub async fn limit_request_time(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(timeout) = state.request_timeout() else {
+        return next.run(request).await;
+    };
+    match tokio::time::timeout(timeout, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) => ErrorResponse::error(
+            &format!("Request timed out after {} ms", timeout.as_millis()),
+            None,
+            None,
+        )
+        .with_code(ErrorCode::Timeout)
+        .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StopToken;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_search_result_is_returned() {
+        let result = run_cancellable(|| StopToken::current().is_stopped()).await;
+        assert!(!result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_dropped_future_stops_search() {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&stopped);
+        let search = run_cancellable(move || {
+            let token = StopToken::current();
+            while !token.is_stopped() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        let timed_out = tokio::time::timeout(Duration::from_millis(20), search).await;
+        assert!(timed_out.is_err());
+
+        for _ in 0..500 {
+            if stopped.load(Ordering::SeqCst) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("search kept running after its request was dropped");
+    }
+
+    #[tokio::test]
+    async fn test_time_limit_stops_search_and_keeps_result() {
+        let result = run_cancellable_for(Some(Duration::from_millis(10)), || {
+            let token = StopToken::current();
+            let mut polls = 0;
+            while !token.is_stopped() {
+                std::thread::sleep(Duration::from_millis(1));
+                polls += 1;
+            }
+            polls
+        })
+        .await;
+        assert!(result.unwrap() > 0);
+    }
+}
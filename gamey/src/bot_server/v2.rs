@@ -0,0 +1,547 @@
+//! Version 2 of the HTTP API.
+//!
+//! Where v1 takes a bare YEN document per endpoint, v2 requests share one
+//! envelope: the `position`, the `rules` it is played under, search `limits`,
+//! a `seed` and free-form `options`. Responses carry, besides the move, the
+//! bot's evaluation, the time taken, the engine version and warnings about
+//! parts of the request that were not honoured, e.g. a seed sent to a bot
+//! that does not use one.
+//!
+//! Under the Pie Rule the bot plays a balanced opening on an empty board and
+//! decides whether to swap in reply to the opening stone, so that a client
+//! needs only one endpoint to play a whole game. After a swap the client
+//! sets [`RequestOptions::pie_swapped`], so the bot plays on rather than
+//! deciding again.
+//!
+//! Like v1 `choose`, requests without a `game_id` or a time limit are
+//! answered from the [`ResponseCache`](crate::ResponseCache) when it knows
+//! the position.
+
+use crate::{
+    ApiVersion, Coordinates, GameRules, GameY, MoveRequest, PieChoice, ResponseCache,
+    SearchOptions, SearchProgress, SharedSession, YBot, YEN,
+    cache::{self, Lookup},
+    error::{ErrorCode, ErrorResponse, JsonBody},
+    run_cancellable_for,
+    state::AppState,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::PoisonError;
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, ToSchema};
+
+/// Path parameters for the v2 choose endpoint.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Path)]
+pub struct ChooseV2Params {
+    /// Registry name of the bot, e.g. `smart_bot`.
+    bot_id: String,
+}
+
+/// Limits on the bot's search.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchLimits {
+    /// Stops the search after this many nodes, see
+    /// [`SearchOptions::max_nodes`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_nodes: Option<u64>,
+    /// Stops the search after this many ms; the bot then plays the best move
+    /// found so far.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_time_ms: Option<u64>,
+}
+
+/// Options of a v2 request.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+#[serde(default)]
+pub struct RequestOptions {
+    /// Identifies the game the position belongs to, so that the bot keeps
+    /// its search tables between moves, see
+    /// [`SessionStore`](crate::SessionStore).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+    /// Set once the opening stone of a Pie Rule game has been swapped, so
+    /// that the one-stone position is answered with a move rather than
+    /// another keep-or-swap decision.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pie_swapped: bool,
+    /// Options this server does not know; they are reported as warnings.
+    #[serde(flatten)]
+    pub unknown: Map<String, Value>,
+}
+
+/// Request envelope of the v2 API.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ChooseV2Request {
+    /// The position to move in.
+    pub position: YEN,
+    #[serde(default)]
+    pub rules: GameRules,
+    #[serde(default)]
+    pub limits: SearchLimits,
+    /// Seed for the bot's random choices, see [`SearchOptions::seed`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Skill level to play at, see [`SearchOptions::skill`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<u8>,
+    #[serde(default)]
+    pub options: RequestOptions,
+}
+
+/// The bot's judgement of the position after its search.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct Evaluation {
+    /// Search score for the side to move; values close to ±100 000 mean a
+    /// forced win or loss.
+    pub score: i32,
+    /// Deepest completed search depth.
+    pub depth: u32,
+    /// Expected continuation, starting with the move played.
+    pub pv: Vec<Coordinates>,
+    /// Nodes searched.
+    pub nodes: u64,
+}
+
+/// Time the server spent on a request.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub struct Timing {
+    /// Time from receiving the request to choosing the move.
+    pub elapsed_ms: u64,
+}
+
+/// The engine that answered a request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct EngineInfo {
+    /// Always `"gamey"`.
+    pub name: String,
+    /// Version of the `gamey` crate.
+    pub version: String,
+}
+
+impl EngineInfo {
+    fn current() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// Response of the v2 choose endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ChooseV2Response {
+    pub api_version: String,
+    pub bot_id: String,
+    /// The bot's move: a placement, or a swap under the Pie Rule.
+    #[serde(rename = "move")]
+    pub movement: MoveRequest,
+    /// Present when the move comes from a completed search iteration, also
+    /// one run in the game's session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evaluation: Option<Evaluation>,
+    pub timing: Timing,
+    pub engine: EngineInfo,
+    /// Parts of the request that were not honoured.
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// Handler for the v2 move selection endpoint.
+///
+/// # Route
+/// `POST /v2/ybot/choose/{bot_id}`
+///
+/// # Request Body
+/// A [`ChooseV2Request`], e.g.
+/// `{"position": {"size": 3, "turn": 0, "players": ["B","R"], "layout": "./../..."}, "rules": {"pie_rule": true}, "limits": {"max_time_ms": 500}}`.
+///
+/// # Response
+/// A [`ChooseV2Response`], or an [`ErrorResponse`] with the same codes as
+/// the v1 choose endpoint.
+#[utoipa::path(
+    post, path = "/v2/ybot/choose/{bot_id}", operation_id = "choose_v2",
+    summary = "Request a move with rules, limits and options",
+    params(ChooseV2Params),
+    request_body = ChooseV2Request,
+    responses((status = 200, body = ChooseV2Response), ErrorResponse)
+)]
+#[axum::debug_handler]
+pub async fn choose(
+    State(state): State<AppState>,
+    Path(params): Path<ChooseV2Params>,
+    JsonBody(request): JsonBody<ChooseV2Request>,
+) -> Result<Json<ChooseV2Response>, ErrorResponse> {
+    let started = Instant::now();
+    let ChooseV2Request {
+        position,
+        rules,
+        limits,
+        seed,
+        skill,
+        options: request_options,
+    } = request;
+    let error = |message: &str, code: ErrorCode| {
+        ErrorResponse::error(
+            message,
+            Some(ApiVersion::V2.to_string()),
+            Some(params.bot_id.clone()),
+        )
+        .with_code(code)
+    };
+
+    let game = GameY::try_from(position).map_err(|err| {
+        error(
+            &format!("Invalid YEN format: {}", err),
+            ErrorCode::from(&err),
+        )
+    })?;
+    if game.check_game_over() {
+        return Err(error("The game is already over", ErrorCode::GameOver));
+    }
+    let Some(bot) = state.bots().find(&params.bot_id) else {
+        let available = state.bots().names().join(", ");
+        return Err(error(
+            &format!(
+                "Bot not found: {}, available bots: [{}]",
+                params.bot_id, available
+            ),
+            ErrorCode::BotNotFound,
+        ));
+    };
+
+    let options = SearchOptions {
+        seed,
+        max_nodes: limits.max_nodes,
+        skill,
+    };
+    let mut phase = Phase::of(&game, rules, &request_options);
+    let mut warnings = warnings(&*bot, &options, &request_options);
+    let session = request_options
+        .game_id
+        .as_ref()
+        .map(|game_id| state.sessions().session(&params.bot_id, game_id));
+    let time_limit = limits.max_time_ms.map(Duration::from_millis);
+    // A time-limited search need not find the same move twice, and a
+    // session search should see every move of its game.
+    let cached = match (&session, time_limit) {
+        (None, None) => lookup_answer(&state, &*bot, &game, &options, &mut phase),
+        _ => Lookup::Uncached,
+    };
+    let cache = match cached {
+        Lookup::Hit(movement) => {
+            warnings.push("The move was served from the cache, without an evaluation".to_string());
+            return Ok(Json(ChooseV2Response {
+                api_version: ApiVersion::V2.to_string(),
+                bot_id: params.bot_id,
+                movement,
+                evaluation: None,
+                timing: Timing {
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                },
+                engine: EngineInfo::current(),
+                warnings,
+            }));
+        }
+        Lookup::Miss(cache) => Some((cache, game.clone(), options.clone())),
+        Lookup::Uncached => None,
+    };
+
+    let (movement, progress) = run_cancellable_for(time_limit, move || {
+        play(&*bot, &game, &options, phase, session)
+    })
+    .await
+    .map_err(|err| error(&format!("Search failed: {}", err), ErrorCode::Internal))?;
+    let Some(movement) = movement else {
+        return Err(error(
+            "No valid moves available for the bot",
+            ErrorCode::NoMoves,
+        ));
+    };
+    if let Some((cache, game, options)) = cache {
+        store_answer(&cache, &params.bot_id, &game, &options, phase, &movement);
+    }
+
+    let evaluation = progress
+        .filter(|last| {
+            movement
+                == MoveRequest::Placement {
+                    coords: last.best_move,
+                }
+        })
+        .map(|last| Evaluation {
+            score: last.score,
+            depth: last.depth,
+            pv: last.pv,
+            nodes: last.nodes,
+        });
+    if let Some(max_time_ms) = limits.max_time_ms
+        && started.elapsed() >= Duration::from_millis(max_time_ms)
+    {
+        warnings.push(format!(
+            "The search was stopped at the time limit of {} ms",
+            max_time_ms
+        ));
+    }
+
+    Ok(Json(ChooseV2Response {
+        api_version: ApiVersion::V2.to_string(),
+        bot_id: params.bot_id,
+        movement,
+        evaluation,
+        timing: Timing {
+            elapsed_ms: started.elapsed().as_millis() as u64,
+        },
+        engine: EngineInfo::current(),
+        warnings,
+    }))
+}
+
+/// Which decision the position asks of the bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// The first move of a Pie Rule game: a balanced opening.
+    PieOpening,
+    /// The reply to the opening stone of a Pie Rule game: swap or move.
+    PieReply,
+    /// Any other move.
+    Move,
+}
+
+impl Phase {
+    fn of(game: &GameY, rules: GameRules, options: &RequestOptions) -> Self {
+        match (rules.pie_rule, game.board_map().len()) {
+            (true, 0) => Phase::PieOpening,
+            (true, 1) if !options.pie_swapped => Phase::PieReply,
+            _ => Phase::Move,
+        }
+    }
+}
+
+/// Looks up the answer to `phase` in the response cache of `state`. A cached
+/// decision to keep the opening stone turns `phase` into the move after it.
+fn lookup_answer(
+    state: &AppState,
+    bot: &dyn YBot,
+    game: &GameY,
+    options: &SearchOptions,
+    phase: &mut Phase,
+) -> Lookup<MoveRequest> {
+    let placement = |coords| MoveRequest::Placement { coords };
+    match *phase {
+        Phase::PieOpening => cache::lookup_pie_opening(state, bot, game, options).map(placement),
+        Phase::PieReply => match cache::lookup_pie_decision(state, bot, game, options) {
+            Lookup::Hit(PieChoice::Keep) => {
+                *phase = Phase::Move;
+                lookup_answer(state, bot, game, options, phase)
+            }
+            decision => decision.map(|_| MoveRequest::Swap),
+        },
+        Phase::Move => cache::lookup(state, bot, game, options).map(placement),
+    }
+}
+
+/// Stores `movement`, the bot's answer to `phase`, in `cache`.
+fn store_answer(
+    cache: &ResponseCache,
+    bot_id: &str,
+    game: &GameY,
+    options: &SearchOptions,
+    phase: Phase,
+    movement: &MoveRequest,
+) {
+    match (phase, movement) {
+        (Phase::PieOpening, &MoveRequest::Placement { coords }) => {
+            cache.insert_pie_opening(bot_id, game, options, coords)
+        }
+        (Phase::PieReply, MoveRequest::Swap) => {
+            cache.insert_pie_decision(bot_id, game, options, PieChoice::Swap)
+        }
+        (Phase::PieReply, &MoveRequest::Placement { coords }) => {
+            cache.insert_pie_decision(bot_id, game, options, PieChoice::Keep);
+            cache.insert(bot_id, game, options, coords);
+        }
+        (Phase::Move, &MoveRequest::Placement { coords }) => {
+            cache.insert(bot_id, game, options, coords)
+        }
+        _ => {}
+    }
+}
+
+/// Chooses the bot's move in `game`, with the last completed iteration of
+/// its search if it reported any.
+fn play(
+    bot: &dyn YBot,
+    game: &GameY,
+    options: &SearchOptions,
+    phase: Phase,
+    session: Option<SharedSession>,
+) -> (Option<MoveRequest>, Option<SearchProgress>) {
+    if phase == Phase::PieOpening {
+        let opening = bot.choose_pie_opening_with_options(game, options);
+        return (
+            opening.map(|coords| MoveRequest::Placement { coords }),
+            None,
+        );
+    }
+    if phase == Phase::PieReply && bot.decide_pie_with_options(game, options) == PieChoice::Swap {
+        return (Some(MoveRequest::Swap), None);
+    }
+    let mut last = None;
+    let coords = match session {
+        Some(session) => {
+            let mut session = session.lock().unwrap_or_else(PoisonError::into_inner);
+            let coords = bot.choose_move_in_session(game, options, &mut session);
+            last = session.take_last_progress();
+            coords
+        }
+        None => bot.choose_move_with_progress(game, options, &mut |progress| {
+            last = Some(progress.clone());
+        }),
+    };
+    (coords.map(|coords| MoveRequest::Placement { coords }), last)
+}
+
+/// Lists the search settings and options of a request that `bot` will not
+/// honour.
+fn warnings(
+    bot: &dyn YBot,
+    search: &SearchOptions,
+    options: &RequestOptions,
+) -> Vec<String> {
+    let features = bot.metadata().features;
+    let mut warnings: Vec<String> = options
+        .unknown
+        .keys()
+        .map(|option| format!("Unknown option ignored: {}", option))
+        .collect();
+    if search.seed.is_some() && !features.seed {
+        warnings.push(format!("{} ignores the seed", bot.name()));
+    }
+    if search.max_nodes.is_some() && !features.node_limit {
+        warnings.push(format!("{} ignores max_nodes", bot.name()));
+    }
+    if search.skill.is_some() && !features.skill {
+        warnings.push(format!("{} ignores the skill", bot.name()));
+    }
+    if options.game_id.is_some() && !features.sessions {
+        warnings.push(format!(
+            "{} keeps no search tables between moves",
+            bot.name()
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FastBot, RandomBot};
+
+    fn request(json: Value) -> ChooseV2Request {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_envelope_defaults() {
+        let request = request(serde_json::json!({
+            "position": {"size": 3, "turn": 0, "players": ["B", "R"], "layout": "./../..."}
+        }));
+        assert_eq!(request.rules, GameRules::default());
+        assert_eq!(request.limits, SearchLimits::default());
+        assert_eq!(request.options, RequestOptions::default());
+    }
+
+    #[test]
+    fn test_unknown_envelope_fields_are_rejected() {
+        let result = serde_json::from_value::<ChooseV2Request>(serde_json::json!({
+            "position": {"size": 3, "turn": 0, "players": ["B", "R"], "layout": "./../..."},
+            "limit": {"max_nodes": 10}
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_ignored_settings_are_warned_about() {
+        let request = request(serde_json::json!({
+            "position": {"size": 3, "turn": 0, "players": ["B", "R"], "layout": "./../..."},
+            "limits": {"max_nodes": 10},
+            "skill": 5,
+            "options": {"ponder": true}
+        }));
+        let search = SearchOptions {
+            seed: request.seed,
+            max_nodes: request.limits.max_nodes,
+            skill: request.skill,
+        };
+        let warnings = warnings(&RandomBot, &search, &request.options);
+        assert_eq!(
+            warnings,
+            [
+                "Unknown option ignored: ponder",
+                "random_bot ignores max_nodes",
+                "random_bot ignores the skill"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minimax_bots_honour_every_search_setting() {
+        let search = SearchOptions {
+            seed: Some(1),
+            max_nodes: Some(10),
+            skill: Some(5),
+        };
+        assert!(warnings(&FastBot::new(), &search, &RequestOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_phase_follows_pie_rule_and_stones() {
+        let pie = GameRules { pie_rule: true };
+        let empty = GameY::new(3);
+        let options = RequestOptions::default();
+        assert_eq!(Phase::of(&empty, pie, &options), Phase::PieOpening);
+        assert_eq!(Phase::of(&empty, GameRules::default(), &options), Phase::Move);
+
+        let opened =
+            GameY::try_from(YEN::new(3, 1, vec!['B', 'R'], "B/../...".to_string())).unwrap();
+        assert_eq!(Phase::of(&opened, pie, &options), Phase::PieReply);
+    }
+
+    // Once the stone was swapped, the one-stone position asks for a move.
+    #[test]
+    fn test_swapped_opening_is_not_decided_again() {
+        let pie = GameRules { pie_rule: true };
+        let swapped =
+            GameY::try_from(YEN::new(3, 0, vec!['B', 'R'], "R/../...".to_string())).unwrap();
+        let options = RequestOptions {
+            pie_swapped: true,
+            ..RequestOptions::default()
+        };
+        assert_eq!(Phase::of(&swapped, pie, &options), Phase::Move);
+    }
+
+    #[test]
+    fn test_move_is_serialized_as_move() {
+        let response = ChooseV2Response {
+            api_version: "v2".to_string(),
+            bot_id: "random_bot".to_string(),
+            movement: MoveRequest::Swap,
+            evaluation: None,
+            timing: Timing { elapsed_ms: 1 },
+            engine: EngineInfo::current(),
+            warnings: Vec::new(),
+        };
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["move"]["type"], "swap");
+        assert_eq!(json["engine"]["name"], "gamey");
+        assert!(json.get("evaluation").is_none());
+    }
+}
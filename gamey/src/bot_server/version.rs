@@ -1,9 +1,56 @@
-use crate::error::ErrorResponse;
+//! Versions of the HTTP API.
+//!
+//! Every endpoint under `/{api_version}` serves [`ApiVersion::V1`]. Newer
+//! versions offer their own routes under a literal prefix, e.g.
+//! `/v2/ybot/choose/{bot_id}` (see [`v2`](crate::bot_server::v2)), which the
+//! router prefers over the `{api_version}` ones. A request naming any other
+//! version, or a version that lacks the endpoint, thus reaches a v1 route,
+//! where the [`accept_version`] layer rejects it with
+//! [`ErrorCode::UnsupportedVersion`]; handlers need not check the version.
 
-/// The currently supported API version.
+use crate::error::{ErrorCode, ErrorResponse};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The API version every endpoint serves.
 pub const SUPPORTED_VERSION: &str = "v1";
 
-/// Validates that the requested API version is supported.
+/// A version of the HTTP API.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    /// One endpoint per operation, taking bare YEN bodies.
+    V1,
+    /// Requests in a common envelope with rules, limits and options, and
+    /// responses with evaluation, timing and warnings.
+    V2,
+}
+
+impl ApiVersion {
+    /// Every version, oldest first.
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    /// The version as it appears in paths, e.g. `"v2"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+            ApiVersion::V2 => "v2",
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Validates that the requested API version is supported by every endpoint.
 ///
 /// # Arguments
 /// * `version` - The API version string from the request path
@@ -14,23 +61,48 @@ pub const SUPPORTED_VERSION: &str = "v1";
 ///
 /// # Example
 /// ```
+/// # #![allow(deprecated)]
 /// use gamey::check_api_version;
 ///
 /// assert!(check_api_version("v1").is_ok());
 /// assert!(check_api_version("v2").is_err());
 /// ```
+#[deprecated(note = "routes check their version with the `accept_version` layer")]
 pub fn check_api_version(version: &str) -> Result<(), ErrorResponse> {
-    if version != SUPPORTED_VERSION {
-        Err(ErrorResponse::error(
-            &format!(
-                "Unsupported API version: {}. Supported version is {}",
-                version, SUPPORTED_VERSION
-            ),
-            Some(version.to_string()),
-            None,
-        ))
+    check_version(version, ApiVersion::V1)
+}
+
+/// Validates that `version` names `served`, the version of the endpoint.
+fn check_version(version: &str, served: ApiVersion) -> Result<(), ErrorResponse> {
+    if version == served.as_str() {
+        return Ok(());
+    }
+    let message = if ApiVersion::ALL.iter().any(|known| known.as_str() == version) {
+        format!(
+            "Unsupported API version: {} does not offer this endpoint. Supported version is {}",
+            version, served
+        )
     } else {
-        Ok(())
+        format!(
+            "Unsupported API version: {}. Supported version is {}",
+            version, served
+        )
+    };
+    Err(ErrorResponse::error(&message, Some(version.to_string()), None)
+        .with_code(ErrorCode::UnsupportedVersion))
+}
+
+/// Middleware that lets requests through only if the first segment of their
+/// path names `served`.
+pub async fn accept_version(
+    State(served): State<ApiVersion>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let version = request.uri().path().split('/').nth(1).unwrap_or_default();
+    match check_version(version, served) {
+        Ok(()) => next.run(request).await,
+        Err(error) => error.into_response(),
     }
 }
 
@@ -39,29 +111,47 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_supported_version() {
+    #[allow(deprecated)]
+    fn test_check_api_version_accepts_only_v1() {
         assert!(check_api_version("v1").is_ok());
+        let err = check_api_version("v2").unwrap_err();
+        assert_eq!(err.code, ErrorCode::UnsupportedVersion);
+    }
+
+    #[test]
+    fn test_supported_version() {
+        assert!(check_version("v1", ApiVersion::V1).is_ok());
     }
 
     #[test]
     fn test_unsupported_version_v2() {
-        let result = check_api_version("v2");
+        let result = check_version("v2", ApiVersion::V1);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(err.message.contains("Unsupported API version"));
         assert!(err.message.contains("v2"));
         assert_eq!(err.api_version, Some("v2".to_string()));
+        assert_eq!(err.code, ErrorCode::UnsupportedVersion);
+    }
+
+    // v2 only offers some endpoints; the others stay v1-only.
+    #[test]
+    fn test_known_version_without_the_endpoint() {
+        let err = check_version("v2", ApiVersion::V1).unwrap_err();
+        assert!(err.message.contains("does not offer this endpoint"));
+        assert_eq!(err.code, ErrorCode::UnsupportedVersion);
+        assert!(check_version("v2", ApiVersion::V2).is_ok());
     }
 
     #[test]
     fn test_unsupported_version_empty() {
-        let result = check_api_version("");
+        let result = check_version("", ApiVersion::V1);
         assert!(result.is_err());
     }
 
     #[test]
     fn test_unsupported_version_random() {
-        let result = check_api_version("random_version");
+        let result = check_version("random_version", ApiVersion::V1);
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert_eq!(err.api_version, Some("random_version".to_string()));
@@ -16,6 +16,7 @@ use clap::{Parser, ValueEnum};
 use rustyline::DefaultEditor;
 use rustyline::error::ReadlineError;
 use std::fmt::Display;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 /// Command-line arguments for the GameY application.
@@ -39,9 +40,15 @@ pub struct CliArgs {
     #[arg(long, default_value_t = false)]
     pub botfirst: bool,
 
-    /// Port to run the server on (only used with --mode=server)
-    #[arg(short, long, default_value_t = 3000)]
-    pub port: u16,
+    /// Port to run the server on, overriding the configuration (only used
+    /// with --mode=server, default = 3000)
+    #[arg(short, long)]
+    pub port: Option<u16>,
+
+    /// TOML or JSON file with the server configuration (only used with
+    /// --mode=server); `GAMEY_*` environment variables override it
+    #[arg(long)]
+    pub config: Option<PathBuf>,
 }
 
 /// The game mode determining how the game is played.
@@ -76,8 +83,8 @@ pub fn run_cli_game() -> Result<()> {
     let mut rl = DefaultEditor::new()?;
     let bots_registry = YBotRegistry::new()
         .with_bot(Arc::new(RandomBot))
-        .with_bot(Arc::new(FastBot))
-        .with_bot(Arc::new(SmartBot));
+        .with_bot(Arc::new(FastBot::new()))
+        .with_bot(Arc::new(SmartBot::new()));
     let bot: Arc<dyn YBot> = match bots_registry.find(&args.bot) {
         Some(b) => b,
         None => {
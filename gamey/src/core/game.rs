@@ -151,6 +151,85 @@ impl GameY {
         Ok(())
     }
 
+    /// Plays `movement` after checking it against the rules of Y.
+    ///
+    /// Unlike [`add_move`](Self::add_move), which trusts its caller, this
+    /// rejects moves in a finished game, moves out of turn, cells outside the
+    /// board and swaps other than the Pie Rule reply to the opening stone.
+    /// The game is left unchanged when the move is rejected.
+    pub fn play(&mut self, movement: Movement) -> Result<()> {
+        if self.check_game_over() {
+            return Err(GameYError::GameOver { movement });
+        }
+        self.check_player_turn(&movement)?;
+        match &movement {
+            Movement::Placement { coords, .. } => {
+                if !coords.is_valid(self.board_size) {
+                    return Err(GameYError::InvalidCoordinates {
+                        coordinates: *coords,
+                        board_size: self.board_size,
+                    });
+                }
+            }
+            Movement::Action {
+                player,
+                action: GameAction::Swap,
+            } => {
+                if self.pie_stone(*player).is_none() {
+                    return Err(GameYError::SwapNotAllowed { player: *player });
+                }
+            }
+            Movement::Action { .. } => {}
+        }
+        self.add_move(movement)
+    }
+
+    /// Returns the opening stone `player` may take over with a Pie Rule
+    /// swap: the only stone on the board, owned by the opponent.
+    fn pie_stone(&self, player: PlayerId) -> Option<Coordinates> {
+        if self.board_map.len() != 1 {
+            return None;
+        }
+        self.board_map
+            .iter()
+            .find(|(_, (_, owner))| *owner != player)
+            .map(|(coords, _)| *coords)
+    }
+
+    /// Returns the chain of the winner's stones that connects all three
+    /// sides, in cell index order, or `None` if nobody has connected them
+    /// (e.g. the game is ongoing or was won by resignation).
+    pub fn winning_chain(&self) -> Option<Vec<Coordinates>> {
+        let GameStatus::Finished { winner } = self.status else {
+            return None;
+        };
+        let mut seen = std::collections::HashSet::new();
+        for (start, (_, owner)) in &self.board_map {
+            if *owner != winner || !seen.insert(*start) {
+                continue;
+            }
+            let mut chain = vec![*start];
+            let mut next = 0;
+            while next < chain.len() {
+                let current = chain[next];
+                next += 1;
+                for neighbor in self.get_neighbors(&current) {
+                    if self.cell_owner(&neighbor) == Some(winner) && seen.insert(neighbor) {
+                        chain.push(neighbor);
+                    }
+                }
+            }
+            let touches_all = chain.iter().any(Coordinates::touches_side_a)
+                && chain.iter().any(Coordinates::touches_side_b)
+                && chain.iter().any(Coordinates::touches_side_c);
+            if touches_all {
+                chain.sort_by_key(|coords| coords.to_index(self.board_size));
+                return Some(chain);
+            }
+        }
+        None
+    }
+
     /// Orchestrates the placement logic
     fn handle_placement(&mut self, player: PlayerId, coords: Coordinates) -> Result<()> {
         self.validate_placement(player, coords)?;
@@ -215,6 +294,13 @@ impl GameY {
                 };
             }
             GameAction::Swap => {
+                // Under the Pie Rule the swapping player takes over the
+                // opening stone and the opponent moves next.
+                if let Some(coords) = self.pie_stone(player)
+                    && let Some((_, owner)) = self.board_map.get_mut(&coords)
+                {
+                    *owner = player;
+                }
                 self.status = GameStatus::Ongoing {
                     next_player: other_player(player),
                 };
@@ -505,6 +591,15 @@ impl TryFrom<YEN> for GameY {
                 }
             }
         }
+        // The layout alone cannot tell whose turn it is; trust the YEN unless
+        // its turn names no player.
+        if let GameStatus::Ongoing { .. } = ygame.status
+            && game.turn() < 2
+        {
+            ygame.status = GameStatus::Ongoing {
+                next_player: PlayerId::new(game.turn()),
+            };
+        }
         Ok(ygame)
     }
 }
@@ -513,7 +608,7 @@ impl From<&GameY> for YEN {
     fn from(game: &GameY) -> Self {
         let size = game.board_size;
         let turn = match game.status {
-            GameStatus::Finished { winner } => other_player(winner).id() as u32,
+            GameStatus::Finished { winner } => other_player(winner).id(),
             GameStatus::Ongoing { next_player } => next_player.id(),
         };
         let mut layout = String::new();
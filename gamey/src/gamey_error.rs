@@ -123,6 +123,30 @@ pub enum GameYError {
         message: String,
     },
 
+    /// The server configuration could not be read or is invalid.
+    #[error("Invalid server configuration: {message}")]
+    ConfigError {
+        /// Description of the problem.
+        message: String,
+    },
+
+    /// The coordinates of a move do not name a cell of the board.
+    #[error("Coordinates {coordinates} are not on a board of size {board_size}")]
+    InvalidCoordinates {
+        /// The coordinates of the move.
+        coordinates: Coordinates,
+        /// The size of the board.
+        board_size: u32,
+    },
+
+    /// A swap was attempted other than as the Pie Rule reply to the opening
+    /// stone.
+    #[error("Player {player} can only swap in reply to the opponent's opening stone")]
+    SwapNotAllowed {
+        /// The player who attempted the swap.
+        player: PlayerId,
+    },
+
     /// Attempted to undo a move while the history is empty.
     #[error("Invalid attempt to undo a move with an empty history")]
     NoMoveToUndo,
@@ -17,27 +17,43 @@
 //!
 //! # Start the bot server on port 3000
 //! gamey --mode server --port 3000
+//!
+//! # Start the bot server with the settings of a configuration file
+//! gamey --mode server --config server.toml
 //! ```
 
 use clap::Parser;
-use gamey::{self, CliArgs, Mode, run_bot_server, run_cli_game};
+use gamey::{self, CliArgs, Mode, ServerConfig, init_logging, run_bot_server, run_cli_game};
 use tracing_subscriber::prelude::*;
 
 /// Main entry point for the GameY application.
 ///
 /// Parses command-line arguments and runs either the CLI game or the HTTP server
-/// depending on the selected mode.
+/// depending on the selected mode. The server reads its configuration from
+/// the `--config` file and the environment, and `--port` overrides its port.
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::registry().init();
     let args = CliArgs::parse();
 
     if args.mode == Mode::Server {
-        if let Err(e) = run_bot_server(args.port).await {
+        let config = match ServerConfig::load(args.config.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let config = match args.port {
+            Some(port) => config.with_port(port),
+            None => config,
+        };
+        init_logging(config.log_format);
+        if let Err(e) = run_bot_server(config).await {
             eprintln!("Error: {}", e);
             std::process::exit(1);
         }
     } else {
+        tracing_subscriber::registry().init();
         run_cli_game().expect("End CLI game");
     }
 }
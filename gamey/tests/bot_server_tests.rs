@@ -2,7 +2,7 @@ use axum::{
     body::Body,
     http::{Request, StatusCode},
 };
-use gamey::{YBotRegistry, YEN, PieChoice, create_default_state, create_router, state::AppState, RandomBot, MoveResponse, ErrorResponse, PieDecideResponse, PieOpeningResponse};
+use gamey::{YBotRegistry, YEN, PieChoice, create_default_state, create_router, state::AppState, RandomBot, MoveResponse, ErrorResponse, PieDecideResponse, PieOpeningResponse, AnalysisResponse, SolveResponse, SolveOutcome, ConnectionsResponse, Coordinates, MinimaxBot, MinimaxConfig, PonderResponse, SearchLimiter, ErrorCode, BotListResponse, BotResponse, Difficulty, ApplyResponse, ApplyStatus, GameStateResponse, SearchStarted, StopSearchResponse, ServerConfig, BotConfig, StatusResponse, BatchResponse, ResponseCache, ClearCacheResponse, ChooseV2Response, MoveRequest};
 use http_body_util::BodyExt;
 use std::sync::Arc;
 use tower::ServiceExt;
@@ -18,7 +18,7 @@ fn test_app_with_state(state: AppState) -> axum::Router {
 }
 
 // ============================================================================
-// Status endpoint tests
+// Status and health endpoint tests
 // ============================================================================
 
 #[tokio::test]
@@ -38,7 +38,281 @@ async fn test_status_endpoint_returns_ok() {
     assert_eq!(response.status(), StatusCode::OK);
 
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    assert_eq!(&body[..], b"OK");
+    let status: StatusResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(status.status, "ok");
+    assert_eq!(status.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(status.bots, ["fast_bot", "random_bot", "smart_bot"]);
+    assert!(!status.draining);
+}
+
+#[tokio::test]
+async fn test_health_probes_follow_draining() {
+    let state = create_default_state();
+    let app = test_app_with_state(state.clone());
+    let probe = |uri: &'static str| {
+        let app = app.clone();
+        async move {
+            app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+                .status()
+        }
+    };
+
+    assert_eq!(probe("/health/live").await, StatusCode::OK);
+    assert_eq!(probe("/health/ready").await, StatusCode::OK);
+
+    state.lifecycle().start_draining();
+    assert_eq!(probe("/health/live").await, StatusCode::OK);
+    assert_eq!(probe("/health/ready").await, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn test_cancelled_server_still_answers_searches_in_flight() {
+    let bot = MinimaxBot::new("slow", MinimaxConfig::new(60_000, 60_000));
+    let state = AppState::new(YBotRegistry::new().with_bot(Arc::new(bot)));
+    let request = test_app_with_state(state.clone()).oneshot(
+        Request::builder()
+            .method("POST")
+            .uri("/v1/ybot/choose/slow")
+            .header("content-type", "application/json")
+            .body(Body::from(
+                r#"{"size":9,"turn":0,"players":["B","R"],"layout":"./../.../..../...../....../......./......../........."}"#,
+            ))
+            .unwrap(),
+    );
+    let request = tokio::spawn(request);
+    while state.searches().running() == 0 {
+        tokio::task::yield_now().await;
+    }
+
+    state.lifecycle().start_draining();
+    state.lifecycle().cancel_searches();
+    let response = request.await.unwrap().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+// ============================================================================
+// Metrics endpoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_metrics_count_requests_searches_and_errors() {
+    let bot = MinimaxBot::new("quick", MinimaxConfig::new(0, 60_000).with_node_limit(2_000));
+    let app = test_app_with_state(AppState::new(YBotRegistry::new().with_bot(Arc::new(bot))));
+
+    for uri in ["/v1/ybot/choose/quick", "/v1/ybot/choose/missing"] {
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(uri)
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"size":7,"turn":0,"players":["B","R"],"layout":"./../.../..../...../....../......."}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+    }
+
+    let response = app
+        .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    assert!(text.contains(
+        r#"gamey_http_requests_total{endpoint="/{api_version}/ybot/choose/{bot_id}",bot="quick",status="200"} 1"#
+    ));
+    // Unknown bot ids are not used as labels.
+    assert!(text.contains(
+        r#"gamey_http_requests_total{endpoint="/{api_version}/ybot/choose/{bot_id}",bot="",status="404"} 1"#
+    ));
+    assert!(text.contains(r#"gamey_errors_total{code="BOT_NOT_FOUND"} 1"#));
+    assert!(text.contains(r#"gamey_search_depth_count{bot="quick"} 1"#));
+    assert!(text.contains("gamey_searches_running 0"));
+}
+
+// ============================================================================
+// OpenAPI document tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_openapi_document_is_served() {
+    let response = test_app()
+        .oneshot(Request::builder().uri("/openapi.json").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(document["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(document["paths"]["/{api_version}/ybot/choose/{bot_id}"]["post"].is_object());
+    assert!(document["components"]["schemas"]["YEN"].is_object());
+}
+
+// ============================================================================
+// Server configuration tests
+// ============================================================================
+
+const EMPTY_BOARD_7: &str =
+    r#"{"size":7,"turn":0,"players":["B","R"],"layout":"./../.../..../...../....../......."}"#;
+
+fn configured_app(config: ServerConfig) -> axum::Router {
+    create_router(config.state().unwrap())
+}
+
+fn choose_request(bot_id: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(format!("/v1/ybot/choose/{}", bot_id))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_configured_server_offers_only_configured_bots() {
+    let config = ServerConfig::from_toml(
+        r#"
+        [[bots]]
+        type = "minimax"
+        name = "quick"
+        max_nodes = 2000
+        "#,
+    )
+    .unwrap();
+    let app = configured_app(config);
+
+    let response = app
+        .clone()
+        .oneshot(choose_request("quick", EMPTY_BOARD_7))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(choose_request("random_bot", EMPTY_BOARD_7))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_configured_body_limit_rejects_large_requests() {
+    let config = ServerConfig {
+        bots: vec![BotConfig::Random],
+        body_limit_bytes: 16,
+        ..ServerConfig::default()
+    };
+    let response = configured_app(config)
+        .oneshot(choose_request("random_bot", EMPTY_BOARD_7))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error.code, ErrorCode::BodyTooLarge);
+}
+
+#[tokio::test]
+async fn test_configured_timeout_abandons_long_searches() {
+    let config = ServerConfig {
+        bots: vec![BotConfig::Minimax {
+            name: "slow".to_string(),
+            book: None,
+            config: MinimaxConfig::new(60_000, 60_000),
+        }],
+        request_timeout_ms: 50,
+        ..ServerConfig::default()
+    };
+    let response = configured_app(config)
+        .oneshot(choose_request(
+            "slow",
+            r#"{"size":9,"turn":0,"players":["B","R"],"layout":"./../.../..../...../....../......./......../........."}"#,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error.code, ErrorCode::Timeout);
+}
+
+// ============================================================================
+// Bot discovery endpoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_list_bots_returns_default_bots() {
+    let app = test_app();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/ybot")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let list: BotListResponse = serde_json::from_slice(&body).unwrap();
+    let names: Vec<&str> = list.bots.iter().map(|bot| bot.name.as_str()).collect();
+    assert_eq!(names, ["fast_bot", "random_bot", "smart_bot"]);
+}
+
+#[tokio::test]
+async fn test_bot_info_describes_smart_bot() {
+    let app = test_app();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/ybot/smart_bot")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let info: BotResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(info.bot.name, "smart_bot");
+    assert_eq!(info.bot.difficulty, Some(Difficulty::Hard));
+    assert!(info.bot.features.pie_rule);
+    assert!(info.bot.features.analysis);
+    assert_eq!(info.bot.time_budget.unwrap().max_time_ms, 3000);
+}
+
+#[tokio::test]
+async fn test_bot_info_with_unknown_bot() {
+    let app = test_app();
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/ybot/unknown_bot")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error_response.code, ErrorCode::BotNotFound);
 }
 
 // ============================================================================
@@ -103,6 +377,33 @@ async fn test_choose_endpoint_with_partially_filled_board() {
     assert_eq!(move_response.bot_id, "random_bot");
 }
 
+#[tokio::test]
+async fn test_choose_endpoint_with_seed_is_reproducible() {
+    let body = r#"{"size":4,"turn":0,"players":["B","R"],"layout":"./../.../....","seed":42,"max_nodes":500}"#;
+
+    let mut moves = Vec::new();
+    for bot_id in ["random_bot", "random_bot", "fast_bot", "fast_bot"] {
+        let response = test_app()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/v1/ybot/choose/{}", bot_id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let move_response: MoveResponse = serde_json::from_slice(&bytes).unwrap();
+        moves.push(move_response.coords);
+    }
+
+    assert_eq!(moves[0], moves[1], "seeded random_bot must repeat its move");
+    assert_eq!(moves[2], moves[3], "node-limited fast_bot must repeat its move");
+}
+
 // ============================================================================
 // Choose endpoint tests - Error cases
 // ============================================================================
@@ -117,7 +418,7 @@ async fn test_choose_endpoint_with_invalid_api_version() {
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v2/ybot/choose/random_bot") // v2 is not supported
+                .uri("/v3/ybot/choose/random_bot") // v3 does not exist
                 .header("content-type", "application/json")
                 .body(Body::from(serde_json::to_string(&yen).unwrap()))
                 .unwrap(),
@@ -125,13 +426,14 @@ async fn test_choose_endpoint_with_invalid_api_version() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), StatusCode::OK); // Axum returns 200 with error JSON
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
 
+    assert_eq!(error_response.code, ErrorCode::UnsupportedVersion);
     assert!(error_response.message.contains("Unsupported API version"));
-    assert_eq!(error_response.api_version, Some("v2".to_string()));
+    assert_eq!(error_response.api_version, Some("v3".to_string()));
 }
 
 #[tokio::test]
@@ -152,16 +454,70 @@ async fn test_choose_endpoint_with_unknown_bot() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
 
+    assert_eq!(error_response.code, ErrorCode::BotNotFound);
     assert!(error_response.message.contains("Bot not found"));
     assert!(error_response.message.contains("unknown_bot"));
     assert_eq!(error_response.bot_id, Some("unknown_bot".to_string()));
 }
 
+#[tokio::test]
+async fn test_choose_endpoint_with_invalid_layout() {
+    let app = test_app();
+
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "./..".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/choose/random_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(error_response.code, ErrorCode::InvalidYen);
+}
+
+#[tokio::test]
+async fn test_choose_endpoint_with_finished_game() {
+    let app = test_app();
+
+    // Size 1: the single stone touches all three sides.
+    let yen = YEN::new(1, 1, vec!['B', 'R'], "B".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/choose/random_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(error_response.code, ErrorCode::GameOver);
+}
+
 #[tokio::test]
 async fn test_choose_endpoint_with_invalid_json() {
     let app = test_app();
@@ -204,6 +560,34 @@ async fn test_choose_endpoint_with_missing_content_type() {
     assert!(response.status().is_client_error());
 }
 
+// Malformed bodies keep the structured error contract rather than Axum's
+// plain-text rejections.
+#[tokio::test]
+async fn test_broken_bodies_get_structured_errors() {
+    let requests = [
+        ("/v1/ybot/choose/random_bot", Some("application/json"), "{ invalid json }"),
+        ("/v1/ybot/choose/random_bot", Some("application/json"), r#"{"size":3}"#),
+        ("/v1/ybot/choose/random_bot", None, EMPTY_BOARD_7),
+        ("/v1/ybot/pie-decide/random_bot", Some("application/json"), "[]"),
+        ("/v1/games", Some("application/json"), r#"{"size":"big"}"#),
+    ];
+    for (uri, content_type, body) in requests {
+        let mut request = Request::builder().method("POST").uri(uri);
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        let response = test_app()
+            .oneshot(request.body(Body::from(body)).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{} {}", uri, body);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, ErrorCode::InvalidRequest);
+        assert!(!error.message.is_empty());
+    }
+}
+
 // ============================================================================
 // Custom state tests
 // ============================================================================
@@ -253,82 +637,145 @@ async fn test_choose_with_empty_bot_registry() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
 
+    assert_eq!(error_response.code, ErrorCode::BotNotFound);
     assert!(error_response.message.contains("Bot not found"));
 }
 
-// ============================================================================
-// Pie-decide endpoint tests
-// ============================================================================
-
+// Requests carrying the same game id reuse one search session.
 #[tokio::test]
-async fn test_pie_decide_with_valid_request() {
-    let app = test_app();
-
-    // Board with one Blue stone at (0,0) — Red (player 1/turn=1) decides
-    let yen = YEN::new(5, 1, vec!['B', 'R'], "B/../.../..../.....".to_string());
+async fn test_choose_with_game_id_reuses_session() {
+    // The solver is off so that both moves are searched with the session's
+    // tables instead of being proved outright.
+    let config = MinimaxConfig::new(10, 50)
+        .with_tt_size_mb(1)
+        .with_solver_max_empty(0);
+    let bot = MinimaxBot::new("quick", config);
+    let state = AppState::new(YBotRegistry::new().with_bot(Arc::new(bot)));
+
+    let body = r#"{"size":4,"turn":0,"players":["B","R"],"layout":"./../.../....","game_id":"g1"}"#;
+    for _ in 0..2 {
+        let response = test_app_with_state(state.clone())
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/v1/ybot/choose/quick")
+                    .header("content-type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    assert_eq!(state.sessions().len(), 1);
+    let session = state.sessions().session("quick", "g1");
+    assert_eq!(session.lock().unwrap().searches(), 2);
+}
 
-    let response = app
+#[tokio::test]
+async fn test_ponder_starts_and_discards_search() {
+    // The solver is off so that both moves are searched with the session's
+    // tables instead of being proved outright.
+    let config = MinimaxConfig::new(10, 50)
+        .with_tt_size_mb(1)
+        .with_solver_max_empty(0);
+    let bot = MinimaxBot::new("quick", config);
+    let state = AppState::new(YBotRegistry::new().with_bot(Arc::new(bot)));
+
+    let body = r#"{"size":4,"turn":1,"players":["B","R"],"layout":"./B./.../....","game_id":"g1"}"#;
+    let response = test_app_with_state(state.clone())
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v1/ybot/pie-decide/fast_bot")
+                .uri("/v1/ybot/ponder/quick")
                 .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .body(Body::from(body))
                 .unwrap(),
         )
         .await
         .unwrap();
-
     assert_eq!(response.status(), StatusCode::OK);
-
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    let pie_response: PieDecideResponse = serde_json::from_slice(&body).unwrap();
+    let started: PonderResponse = serde_json::from_slice(&body).unwrap();
+    assert!(started.pondering);
+    assert!(started.predicted.is_some());
 
-    assert_eq!(pie_response.api_version, "v1");
-    assert_eq!(pie_response.bot_id, "fast_bot");
-    assert!(pie_response.decision == PieChoice::Keep || pie_response.decision == PieChoice::Swap);
+    let session = state.sessions().get("quick", "g1").unwrap();
+    assert!(session.lock().unwrap().is_pondering());
+    assert_eq!(state.searches().running_background(), 1);
+
+    let response = test_app_with_state(state.clone())
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri("/v1/ybot/ponder/quick/g1")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let discarded: PonderResponse = serde_json::from_slice(&body).unwrap();
+    assert!(!discarded.pondering);
+    assert!(!session.lock().unwrap().is_pondering());
+    assert_eq!(state.searches().running_background(), 0);
 }
 
+// Pondering never waits for a search slot: a busy server answers at once
+// without starting a background search.
 #[tokio::test]
-async fn test_pie_decide_with_unknown_bot() {
-    let app = test_app();
-
-    let yen = YEN::new(3, 1, vec!['B', 'R'], "B/../...".to_string());
-
-    let response = app
+async fn test_ponder_is_refused_when_no_slot_is_free() {
+    let config = MinimaxConfig::new(10, 50).with_tt_size_mb(1);
+    let bot = MinimaxBot::new("quick", config);
+    let state = AppState::new(YBotRegistry::new().with_bot(Arc::new(bot)))
+        .with_search_limiter(SearchLimiter::new(1, 0));
+    let _running = state.searches().acquire().await.unwrap();
+
+    let body = r#"{"size":4,"turn":1,"players":["B","R"],"layout":"./B./.../....","game_id":"g1"}"#;
+    let response = test_app_with_state(state.clone())
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v1/ybot/pie-decide/nonexistent_bot")
+                .uri("/v1/ybot/ponder/quick")
                 .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .body(Body::from(body))
                 .unwrap(),
         )
         .await
         .unwrap();
-
+    assert_eq!(response.status(), StatusCode::OK);
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    let refused: PonderResponse = serde_json::from_slice(&body).unwrap();
+    assert!(!refused.pondering);
+    assert_eq!(refused.predicted, None);
 
-    assert!(error_response.message.contains("Bot not found"));
+    let session = state.sessions().session("quick", "g1");
+    assert!(!session.lock().unwrap().is_pondering());
+    assert_eq!(state.searches().running(), 1);
 }
 
+// A saturated server turns searches away with 503 but keeps answering
+// requests that do not search.
 #[tokio::test]
-async fn test_pie_decide_with_invalid_api_version() {
-    let app = test_app();
-
-    let yen = YEN::new(3, 1, vec!['B', 'R'], "B/../...".to_string());
+async fn test_saturated_server_answers_503_with_retry_hint() {
+    let state = create_default_state().with_search_limiter(SearchLimiter::new(1, 0));
+    let searches = state.searches();
+    let _running = searches.acquire().await.unwrap();
+    let app = test_app_with_state(state);
 
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
     let response = app
+        .clone()
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v99/ybot/pie-decide/fast_bot")
+                .uri("/v1/ybot/choose/random_bot")
                 .header("content-type", "application/json")
                 .body(Body::from(serde_json::to_string(&yen).unwrap()))
                 .unwrap(),
@@ -336,28 +783,40 @@ async fn test_pie_decide_with_invalid_api_version() {
         .await
         .unwrap();
 
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key("retry-after"));
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error_response.code, ErrorCode::Overloaded);
+    assert!(error_response.message.contains("retry"));
 
-    assert!(error_response.message.contains("Unsupported API version"));
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/status")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 // ============================================================================
-// Pie-opening endpoint tests
+// Analyze endpoint tests
 // ============================================================================
 
 #[tokio::test]
-async fn test_pie_opening_with_valid_request() {
+async fn test_analyze_returns_requested_number_of_candidates() {
     let app = test_app();
 
-    // Empty board — bot picks a pie-aware first move
-    let yen = YEN::new(5, 0, vec!['B', 'R'], "./../.../..../.....".to_string());
+    let yen = YEN::new(4, 0, vec!['B', 'R'], "./../.../....".to_string());
 
     let response = app
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v1/ybot/pie-opening/fast_bot")
+                .uri("/v1/ybot/analyze/fast_bot?multipv=2")
                 .header("content-type", "application/json")
                 .body(Body::from(serde_json::to_string(&yen).unwrap()))
                 .unwrap(),
@@ -368,14 +827,15 @@ async fn test_pie_opening_with_valid_request() {
     assert_eq!(response.status(), StatusCode::OK);
 
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    let pie_response: PieOpeningResponse = serde_json::from_slice(&body).unwrap();
+    let analysis: AnalysisResponse = serde_json::from_slice(&body).unwrap();
 
-    assert_eq!(pie_response.api_version, "v1");
-    assert_eq!(pie_response.bot_id, "fast_bot");
+    assert_eq!(analysis.bot_id, "fast_bot");
+    assert_eq!(analysis.candidates.len(), 2);
+    assert!(analysis.candidates[0].score >= analysis.candidates[1].score);
 }
 
 #[tokio::test]
-async fn test_pie_opening_with_unknown_bot() {
+async fn test_analyze_with_bot_without_analysis_support() {
     let app = test_app();
 
     let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
@@ -384,7 +844,7 @@ async fn test_pie_opening_with_unknown_bot() {
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v1/ybot/pie-opening/nonexistent_bot")
+                .uri("/v1/ybot/analyze/random_bot")
                 .header("content-type", "application/json")
                 .body(Body::from(serde_json::to_string(&yen).unwrap()))
                 .unwrap(),
@@ -395,33 +855,1016 @@ async fn test_pie_opening_with_unknown_bot() {
     let body = response.into_body().collect().await.unwrap().to_bytes();
     let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
 
-    assert!(error_response.message.contains("Bot not found"));
+    assert!(error_response.message.contains("does not support analysis"));
 }
 
-#[tokio::test]
-async fn test_pie_opening_with_invalid_api_version() {
-    let app = test_app();
+// ============================================================================
+// Batch endpoint tests
+// ============================================================================
 
-    let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
+#[tokio::test]
+async fn test_batch_answers_every_position_in_order() {
+    let bot = MinimaxBot::new("quick", MinimaxConfig::new(0, 60_000).with_node_limit(2_000));
+    let app = test_app_with_state(
+        AppState::new(YBotRegistry::new().with_bot(Arc::new(bot)))
+            .with_search_limiter(SearchLimiter::new(2, 0)),
+    );
+    let body = serde_json::json!({"positions": [
+        {"size": 3, "turn": 0, "players": ["B", "R"], "layout": "./../..."},
+        {"size": 3, "turn": 0, "players": ["B", "R"], "layout": "./X./..."},
+        {"size": 3, "turn": 1, "players": ["B", "R"], "layout": "B/../...", "max_nodes": 500},
+        {"size": 3},
+        {"size": 1, "turn": 0, "players": ["B", "R"], "layout": "B"},
+    ]});
 
     let response = app
         .oneshot(
             Request::builder()
                 .method("POST")
-                .uri("/v99/ybot/pie-opening/fast_bot")
+                .uri("/v1/ybot/choose-batch/quick")
                 .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .body(Body::from(body.to_string()))
                 .unwrap(),
         )
         .await
         .unwrap();
 
+    assert_eq!(response.status(), StatusCode::OK);
     let body = response.into_body().collect().await.unwrap().to_bytes();
-    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    let batch: BatchResponse = serde_json::from_slice(&body).unwrap();
+    let codes: Vec<Option<ErrorCode>> = batch
+        .results
+        .iter()
+        .map(|result| result.error.as_ref().map(|error| error.code))
+        .collect();
+    assert_eq!(
+        codes,
+        [
+            None,
+            Some(ErrorCode::InvalidYen),
+            None,
+            Some(ErrorCode::InvalidYen),
+            Some(ErrorCode::GameOver),
+        ]
+    );
+    assert!(batch.results[0].coords.is_some());
+    assert!(batch.results[2].coords.is_some());
+}
+
+#[tokio::test]
+async fn test_batch_with_unknown_bot_fails_whole_request() {
+    let response = test_app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/choose-batch/missing")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"positions":[]}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// ============================================================================
+// Response cache tests
+// ============================================================================
+
+const ADMIN_TOKEN: &str = "admin-secret";
+
+fn cached_app(bot: Arc<dyn gamey::YBot>) -> axum::Router {
+    test_app_with_state(
+        AppState::new(YBotRegistry::new().with_bot(bot))
+            .with_cache(Some(ResponseCache::new(16)))
+            .with_admin_token(Some(ADMIN_TOKEN.to_string())),
+    )
+}
+
+fn clear_cache_request(token: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder().method("DELETE").uri("/admin/cache");
+    if let Some(token) = token {
+        request = request.header("authorization", format!("Bearer {}", token));
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+fn pie_request(endpoint: &str, bot_id: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(format!("/v1/ybot/{}/{}", endpoint, bot_id))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+async fn metrics_text(app: &axum::Router) -> String {
+    let response = app
+        .clone()
+        .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn test_cache_answers_repeated_position_until_cleared() {
+    let bot = MinimaxBot::new("quick", MinimaxConfig::new(0, 60_000).with_node_limit(2_000));
+    let app = cached_app(Arc::new(bot));
+
+    let mut moves = Vec::new();
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(choose_request("quick", EMPTY_BOARD_7))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        moves.push(serde_json::from_slice::<MoveResponse>(&body).unwrap().coords);
+    }
+    assert_eq!(moves[0], moves[1]);
+
+    let text = metrics_text(&app).await;
+    assert!(text.contains(r#"gamey_cache_hits_total{bot="quick"} 1"#));
+    assert!(text.contains(r#"gamey_cache_misses_total{bot="quick"} 1"#));
+    // Only the first request searched.
+    assert!(text.contains(r#"gamey_search_depth_count{bot="quick"} 1"#));
+
+    let response = app
+        .clone()
+        .oneshot(clear_cache_request(Some(ADMIN_TOKEN)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let cleared: ClearCacheResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        cleared,
+        ClearCacheResponse {
+            enabled: true,
+            cleared: 1
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_cache_skips_random_moves_without_seed() {
+    let app = cached_app(Arc::new(RandomBot));
+    let seeded = EMPTY_BOARD_7.replace('}', r#","seed":7}"#);
+    for body in [EMPTY_BOARD_7, EMPTY_BOARD_7, seeded.as_str(), seeded.as_str()] {
+        let response = app
+            .clone()
+            .oneshot(choose_request("random_bot", body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let text = metrics_text(&app).await;
+    assert!(text.contains(r#"gamey_cache_hits_total{bot="random_bot"} 1"#));
+    assert!(text.contains(r#"gamey_cache_misses_total{bot="random_bot"} 1"#));
+}
+
+// A request that lowers the skill of a full-strength bot is as random as a
+// weak bot, so only its seeded form is cached.
+#[tokio::test]
+async fn test_cache_skips_weakened_moves_without_seed() {
+    let bot = MinimaxBot::new("quick", MinimaxConfig::new(0, 60_000).with_node_limit(500));
+    let app = cached_app(Arc::new(bot));
+    let weakened = EMPTY_BOARD_7.replace('}', r#","skill":3}"#);
+    let seeded = EMPTY_BOARD_7.replace('}', r#","skill":3,"seed":7}"#);
+    for body in [&weakened, &weakened, &seeded, &seeded] {
+        let response = app
+            .clone()
+            .oneshot(choose_request("quick", body))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let text = metrics_text(&app).await;
+    assert!(text.contains(r#"gamey_cache_hits_total{bot="quick"} 1"#));
+    assert!(text.contains(r#"gamey_cache_misses_total{bot="quick"} 1"#));
+}
+
+// The pie opening on the empty board is cached apart from the move chosen
+// there, and the decision on the reply board like any other answer.
+#[tokio::test]
+async fn test_cache_answers_repeated_pie_requests() {
+    let bot = MinimaxBot::new("quick", MinimaxConfig::new(0, 0).with_node_limit(2_000));
+    let app = cached_app(Arc::new(bot));
+    let opened = r#"{"size":7,"turn":1,"players":["B","R"],"layout":"./../.../..B./...../....../......."}"#;
+
+    let response = app
+        .clone()
+        .oneshot(choose_request("quick", EMPTY_BOARD_7))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let mut openings = Vec::new();
+    let mut decisions = Vec::new();
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(pie_request("pie-opening", "quick", EMPTY_BOARD_7))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        openings.push(serde_json::from_slice::<PieOpeningResponse>(&body).unwrap().coords);
+
+        let response = app
+            .clone()
+            .oneshot(pie_request("pie-decide", "quick", opened))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        decisions.push(serde_json::from_slice::<PieDecideResponse>(&body).unwrap().decision);
+    }
+    assert_eq!(openings[0], openings[1]);
+    assert_eq!(decisions[0], decisions[1]);
+
+    let text = metrics_text(&app).await;
+    assert!(text.contains(r#"gamey_cache_hits_total{bot="quick"} 2"#));
+    assert!(text.contains(r#"gamey_cache_misses_total{bot="quick"} 3"#));
+
+    let response = app
+        .clone()
+        .oneshot(clear_cache_request(Some(ADMIN_TOKEN)))
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let cleared: ClearCacheResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cleared.cleared, 3);
+}
+
+#[tokio::test]
+async fn test_clear_cache_requires_admin_token() {
+    let app = cached_app(Arc::new(RandomBot));
+    for token in [None, Some("wrong"), Some("")] {
+        let response = app.clone().oneshot(clear_cache_request(token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{:?}", token);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, ErrorCode::Unauthorized);
+    }
+
+    // Without a configured token the endpoint is closed to everyone.
+    let response = test_app()
+        .oneshot(clear_cache_request(Some(ADMIN_TOKEN)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_clear_cache_without_cache() {
+    let app = test_app_with_state(
+        create_default_state().with_admin_token(Some(ADMIN_TOKEN.to_string())),
+    );
+    let response = app
+        .oneshot(clear_cache_request(Some(ADMIN_TOKEN)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let cleared: ClearCacheResponse = serde_json::from_slice(&body).unwrap();
+    assert!(!cleared.enabled);
+}
+
+// ============================================================================
+// Streaming endpoint tests
+// ============================================================================
+
+fn stream_app(config: MinimaxConfig) -> axum::Router {
+    let bot = MinimaxBot::new("streamer", config);
+    test_app_with_state(AppState::new(YBotRegistry::new().with_bot(Arc::new(bot))))
+}
+
+fn stream_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/ybot/stream/streamer")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            r#"{"size":7,"turn":0,"players":["B","R"],"layout":"./../.../..../...../....../......."}"#,
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_stream_sends_depths_then_move() {
+    let app = stream_app(MinimaxConfig::new(0, 60_000).with_node_limit(5_000));
+    let response = app.oneshot(stream_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(body.to_vec()).unwrap();
+    let start = text.find("event: start").unwrap();
+    let depth = text.find("event: depth").unwrap();
+    let chosen = text.find("event: move").unwrap();
+    assert!(start < depth && depth < chosen);
+}
+
+#[tokio::test]
+async fn test_stopped_stream_still_sends_move() {
+    let app = stream_app(MinimaxConfig::new(60_000, 60_000));
+    let response = app.clone().oneshot(stream_request()).await.unwrap();
+    let mut body = response.into_body();
+
+    let first = body.frame().await.unwrap().unwrap().into_data().unwrap();
+    let first = String::from_utf8(first.to_vec()).unwrap();
+    let data = first
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .unwrap();
+    let started: SearchStarted = serde_json::from_str(data).unwrap();
+
+    let stop = app
+        .oneshot(
+            Request::builder()
+                .method("DELETE")
+                .uri(format!("/v1/ybot/stream/streamer/{}", started.search_id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let stop = stop.into_body().collect().await.unwrap().to_bytes();
+    let stop: StopSearchResponse = serde_json::from_slice(&stop).unwrap();
+    assert!(stop.stopped);
+
+    let rest = tokio::time::timeout(std::time::Duration::from_secs(10), body.collect())
+        .await
+        .expect("stopped search should end the stream")
+        .unwrap()
+        .to_bytes();
+    assert!(String::from_utf8(rest.to_vec()).unwrap().contains("event: move"));
+}
+
+// ============================================================================
+// Solve endpoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_solve_endpoint_proves_small_board() {
+    let app = test_app();
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/solve")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let solve_response: SolveResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(solve_response.result.outcome, SolveOutcome::Win);
+    assert!(solve_response.result.proof_move.is_some());
+}
+
+#[tokio::test]
+async fn test_solve_endpoint_reports_unknown_under_small_budget() {
+    let app = test_app();
+    let yen = YEN::new(6, 0, vec!['B', 'R'], "./../.../..../...../......".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/solve?max_nodes=10")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let solve_response: SolveResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(solve_response.result.outcome, SolveOutcome::Unknown);
+}
+
+// ============================================================================
+// Connections endpoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_connections_endpoint_lists_bridge() {
+    let app = test_app();
+    // Blue holds (2,1,1) and (0,2,2), bridged through (1,2,1) and (1,1,2).
+    let yen = YEN::new(5, 1, vec!['B', 'R'], "R/../.B./..../..B..".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/connections")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let connections: ConnectionsResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(connections.players.len(), 2);
+    let blue = &connections.players[0].connections;
+    assert_eq!(blue.bridges.len(), 1);
+    assert!(blue.bridges[0].stones.contains(&Coordinates::new(2, 1, 1)));
+    assert!(connections.players[1].connections.bridges.is_empty());
+}
+
+// ============================================================================
+// Apply endpoint tests
+// ============================================================================
+
+async fn post_apply(body: serde_json::Value) -> axum::response::Response {
+    test_app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/game/apply")
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_apply_winning_move_reports_winner_and_chain() {
+    // Red holds two bottom cells; the third connects all sides.
+    let response = post_apply(serde_json::json!({
+        "size": 3, "turn": 1, "players": ["B", "R"], "layout": "B/BB/RR.",
+        "move": {"type": "placement", "coords": {"x": 0, "y": 2, "z": 0}}
+    }))
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let applied: ApplyResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(applied.game.status, ApplyStatus::Finished);
+    assert_eq!(applied.game.winner, Some(1));
+    assert_eq!(applied.game.yen.layout(), "B/BB/RRR");
+    assert_eq!(applied.game.winning_chain.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_apply_swap_takes_over_opening_stone() {
+    let response = post_apply(serde_json::json!({
+        "size": 3, "turn": 1, "players": ["B", "R"], "layout": "./B./...",
+        "move": {"type": "swap"}
+    }))
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let applied: ApplyResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(applied.game.status, ApplyStatus::Ongoing);
+    assert_eq!(applied.game.next_player, Some(0));
+    assert_eq!(applied.game.yen.layout(), "./R./...");
+}
+
+#[tokio::test]
+async fn test_apply_on_occupied_cell_is_illegal() {
+    let response = post_apply(serde_json::json!({
+        "size": 3, "turn": 1, "players": ["B", "R"], "layout": "B/../...",
+        "move": {"type": "placement", "coords": {"x": 2, "y": 0, "z": 0}}
+    }))
+    .await;
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error_response.code, ErrorCode::IllegalMove);
+}
+
+// ============================================================================
+// Hosted game tests
+// ============================================================================
+
+async fn send_json(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> axum::response::Response {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    app.clone().oneshot(request.unwrap()).await.unwrap()
+}
+
+async fn read_game(response: axum::response::Response) -> GameStateResponse {
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_hosted_game_plays_bot_reply_until_deleted() {
+    let app = test_app();
+    let created = read_game(
+        send_json(
+            &app,
+            "POST",
+            "/v1/games",
+            Some(serde_json::json!({"size": 4, "bots": [null, "random_bot"]})),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(created.game.next_player, Some(0));
+    assert_eq!(created.bots, vec![None, Some("random_bot".to_string())]);
+
+    let uri = format!("/v1/games/{}", created.game_id);
+    let played = read_game(
+        send_json(
+            &app,
+            "POST",
+            &format!("{}/moves", uri),
+            Some(serde_json::json!({
+                "move": {"type": "placement", "coords": {"x": 1, "y": 1, "z": 1}}
+            })),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(played.history.len(), 2);
+    assert_eq!(played.history[1].player, 1);
+    assert_eq!(played.game.next_player, Some(0));
+
+    let fetched = read_game(send_json(&app, "GET", &uri, None).await).await;
+    assert_eq!(fetched.history, played.history);
+    assert_eq!(fetched.game.yen.layout(), played.game.yen.layout());
+
+    read_game(send_json(&app, "DELETE", &uri, None).await).await;
+    let response = send_json(&app, "GET", &uri, None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error_response.code, ErrorCode::GameNotFound);
+}
+
+// Moves that ask for no bot reply search nothing, so they are played even
+// while every search slot is taken.
+#[tokio::test]
+async fn test_hosted_game_moves_take_a_slot_only_for_the_bot_reply() {
+    let state = create_default_state().with_search_limiter(SearchLimiter::new(1, 0));
+    let _running = state.searches().acquire().await.unwrap();
+    let app = test_app_with_state(state);
+    let created = read_game(
+        send_json(
+            &app,
+            "POST",
+            "/v1/games",
+            Some(serde_json::json!({"size": 4, "bots": [null, "random_bot"]})),
+        )
+        .await,
+    )
+    .await;
+    let uri = format!("/v1/games/{}/moves", created.game_id);
+
+    let played = read_game(
+        send_json(
+            &app,
+            "POST",
+            &uri,
+            Some(serde_json::json!({
+                "move": {"type": "placement", "coords": {"x": 1, "y": 1, "z": 1}},
+                "bot_reply": false
+            })),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(played.history.len(), 1);
+
+    let response = send_json(&app, "POST", &uri, Some(serde_json::json!({}))).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key("retry-after"));
+}
+
+#[tokio::test]
+async fn test_hosted_game_rejects_swap_without_pie_rule() {
+    let app = test_app();
+    let created = read_game(
+        send_json(&app, "POST", "/v1/games", Some(serde_json::json!({"size": 3}))).await,
+    )
+    .await;
+    let uri = format!("/v1/games/{}/moves", created.game_id);
+    let opening = serde_json::json!({
+        "move": {"type": "placement", "coords": {"x": 1, "y": 1, "z": 0}}
+    });
+    read_game(send_json(&app, "POST", &uri, Some(opening)).await).await;
+
+    let response = send_json(
+        &app,
+        "POST",
+        &uri,
+        Some(serde_json::json!({"move": {"type": "swap"}})),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error_response.code, ErrorCode::IllegalMove);
+}
+
+#[tokio::test]
+async fn test_create_game_with_unknown_bot() {
+    let response = send_json(
+        &test_app(),
+        "POST",
+        "/v1/games",
+        Some(serde_json::json!({"size": 3, "bots": ["missing_bot"]})),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// ============================================================================
+// Pie-decide endpoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_pie_decide_with_valid_request() {
+    let app = test_app();
+
+    // Board with one Blue stone at (0,0) — Red (player 1/turn=1) decides
+    let yen = YEN::new(5, 1, vec!['B', 'R'], "B/../.../..../.....".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/pie-decide/fast_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let pie_response: PieDecideResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(pie_response.api_version, "v1");
+    assert_eq!(pie_response.bot_id, "fast_bot");
+    assert!(pie_response.decision == PieChoice::Keep || pie_response.decision == PieChoice::Swap);
+}
+
+#[tokio::test]
+async fn test_pie_decide_with_unknown_bot() {
+    let app = test_app();
+
+    let yen = YEN::new(3, 1, vec!['B', 'R'], "B/../...".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/pie-decide/nonexistent_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert!(error_response.message.contains("Bot not found"));
+}
+
+#[tokio::test]
+async fn test_pie_decide_with_invalid_api_version() {
+    let app = test_app();
+
+    let yen = YEN::new(3, 1, vec!['B', 'R'], "B/../...".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v99/ybot/pie-decide/fast_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
 
     assert!(error_response.message.contains("Unsupported API version"));
 }
 
+// ============================================================================
+// Pie-opening endpoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_pie_opening_with_valid_request() {
+    let app = test_app();
+
+    // Empty board — bot picks a pie-aware first move
+    let yen = YEN::new(5, 0, vec!['B', 'R'], "./../.../..../.....".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/pie-opening/fast_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let pie_response: PieOpeningResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(pie_response.api_version, "v1");
+    assert_eq!(pie_response.bot_id, "fast_bot");
+}
+
+#[tokio::test]
+async fn test_pie_opening_with_unknown_bot() {
+    let app = test_app();
+
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/ybot/pie-opening/nonexistent_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert!(error_response.message.contains("Bot not found"));
+}
+
+#[tokio::test]
+async fn test_pie_opening_with_invalid_api_version() {
+    let app = test_app();
+
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v99/ybot/pie-opening/fast_bot")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert!(error_response.message.contains("Unsupported API version"));
+}
+
+// ============================================================================
+// API v2 tests
+// ============================================================================
+
+/// Helper to POST a v2 choose request for `bot_id`
+async fn choose_v2(app: axum::Router, bot_id: &str, body: serde_json::Value) -> axum::response::Response {
+    app.oneshot(
+        Request::builder()
+            .method("POST")
+            .uri(format!("/v2/ybot/choose/{}", bot_id))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+    )
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_v2_choose_reports_move_evaluation_and_engine() {
+    let response = choose_v2(
+        test_app(),
+        "fast_bot",
+        serde_json::json!({
+            "position": {"size": 7, "turn": 0, "players": ["B", "R"], "layout": "./../.../..../...../....../......."},
+            "limits": {"max_nodes": 2000},
+            "options": {"ponder": true}
+        }),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let response: ChooseV2Response = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(response.api_version, "v2");
+    assert_eq!(response.bot_id, "fast_bot");
+    let MoveRequest::Placement { coords } = response.movement else {
+        panic!("Expected a placement, got {:?}", response.movement);
+    };
+    assert!(coords.is_valid(7));
+    let evaluation = response.evaluation.expect("minimax reports its evaluation");
+    assert_eq!(evaluation.pv.first(), Some(&coords));
+    assert!(evaluation.nodes > 0);
+    assert_eq!(response.engine.name, "gamey");
+    assert_eq!(response.warnings, ["Unknown option ignored: ponder"]);
+}
+
+#[tokio::test]
+async fn test_v2_choose_follows_the_pie_rule() {
+    let pie = |layout: &str, turn: u32| {
+        serde_json::json!({
+            "position": {"size": 3, "turn": turn, "players": ["B", "R"], "layout": layout},
+            "rules": {"pie_rule": true},
+            "seed": 3
+        })
+    };
+
+    // The random bot makes the opening move, drawn from the request seed.
+    let mut openings = Vec::new();
+    for _ in 0..2 {
+        let response = choose_v2(test_app(), "random_bot", pie("./../...", 0)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let response: ChooseV2Response = serde_json::from_slice(&body).unwrap();
+        assert!(matches!(response.movement, MoveRequest::Placement { .. }));
+        assert_eq!(response.evaluation, None);
+        assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+        openings.push(response.movement);
+    }
+    assert_eq!(openings[0], openings[1]);
+
+    // A strong opening next to the apex is swapped.
+    let response = choose_v2(test_app(), "fast_bot", pie("./B./...", 1)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let response: ChooseV2Response = serde_json::from_slice(&body).unwrap();
+    assert_eq!(response.movement, MoveRequest::Swap);
+
+    // After the swap the bot plays on instead of swapping back.
+    let mut swapped = pie("./R./...", 0);
+    swapped["options"] = serde_json::json!({"pie_swapped": true});
+    let response = choose_v2(test_app(), "fast_bot", swapped).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let response: ChooseV2Response = serde_json::from_slice(&body).unwrap();
+    assert!(matches!(response.movement, MoveRequest::Placement { .. }));
+}
+
+// Moves searched in a game session report their evaluation like any other.
+#[tokio::test]
+async fn test_v2_choose_in_session_reports_evaluation() {
+    let response = choose_v2(
+        test_app(),
+        "fast_bot",
+        serde_json::json!({
+            "position": {"size": 7, "turn": 0, "players": ["B", "R"], "layout": "./../.../..../...../....../......."},
+            "limits": {"max_nodes": 2000},
+            "options": {"game_id": "session-eval"}
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let response: ChooseV2Response = serde_json::from_slice(&body).unwrap();
+
+    let MoveRequest::Placement { coords } = response.movement else {
+        panic!("Expected a placement, got {:?}", response.movement);
+    };
+    let evaluation = response.evaluation.expect("the session search reports its evaluation");
+    assert_eq!(evaluation.pv.first(), Some(&coords));
+    assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+}
+
+#[tokio::test]
+async fn test_v2_choose_uses_the_response_cache() {
+    let bot = MinimaxBot::new("quick", MinimaxConfig::new(0, 0).with_node_limit(2_000));
+    let app = cached_app(Arc::new(bot));
+    let request = serde_json::json!({
+        "position": {"size": 7, "turn": 0, "players": ["B", "R"], "layout": "./../.../..../...../....../......."}
+    });
+
+    let mut responses = Vec::new();
+    for _ in 0..2 {
+        let response = choose_v2(app.clone(), "quick", request.clone()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        responses.push(serde_json::from_slice::<ChooseV2Response>(&body).unwrap());
+    }
+    assert_eq!(responses[0].movement, responses[1].movement);
+    assert!(responses[0].evaluation.is_some());
+    assert_eq!(responses[1].evaluation, None);
+    assert_eq!(
+        responses[1].warnings,
+        ["The move was served from the cache, without an evaluation"]
+    );
+
+    // v1 shares the entry, as the search options are the same.
+    let response = app
+        .clone()
+        .oneshot(choose_request("quick", EMPTY_BOARD_7))
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let MoveRequest::Placement { coords } = responses[0].movement else {
+        panic!("Expected a placement");
+    };
+    assert_eq!(serde_json::from_slice::<MoveResponse>(&body).unwrap().coords, coords);
+
+    let text = metrics_text(&app).await;
+    assert!(text.contains(r#"gamey_cache_hits_total{bot="quick"} 2"#));
+    assert!(text.contains(r#"gamey_cache_misses_total{bot="quick"} 1"#));
+}
+
+#[tokio::test]
+async fn test_v2_choose_rejects_unknown_fields() {
+    let response = choose_v2(
+        test_app(),
+        "random_bot",
+        serde_json::json!({
+            "position": {"size": 3, "turn": 0, "players": ["B", "R"], "layout": "./../..."},
+            "depth": 4
+        }),
+    )
+    .await;
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error: ErrorResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(error.code, ErrorCode::InvalidRequest);
+    assert!(error.message.contains("depth"), "{}", error.message);
+}
+
+#[tokio::test]
+async fn test_v1_only_endpoint_rejects_v2() {
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "./../...".to_string());
+
+    let response = test_app()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v2/solve")
+                .header("content-type", "application/json")
+                .body(Body::from(serde_json::to_string(&yen).unwrap()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let error_response: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(error_response.code, ErrorCode::UnsupportedVersion);
+    assert!(error_response.message.contains("v2 does not offer this endpoint"));
+    assert_eq!(error_response.api_version, Some("v2".to_string()));
+}
+
 // ============================================================================
 // Route not found tests
 // ============================================================================
@@ -278,7 +278,8 @@ fn test_cli_args_default_values() {
     assert_eq!(args.size, 7);
     assert_eq!(args.mode, Mode::Human);
     assert_eq!(args.bot, "random_bot");
-    assert_eq!(args.port, 3000);
+    assert_eq!(args.port, None);
+    assert_eq!(args.config, None);
 }
 
 #[test]
@@ -332,13 +333,13 @@ fn test_cli_args_custom_bot_short() {
 #[test]
 fn test_cli_args_custom_port() {
     let args = CliArgs::try_parse_from(["gamey", "--port", "8080"]).unwrap();
-    assert_eq!(args.port, 8080);
+    assert_eq!(args.port, Some(8080));
 }
 
 #[test]
 fn test_cli_args_custom_port_short() {
     let args = CliArgs::try_parse_from(["gamey", "-p", "9000"]).unwrap();
-    assert_eq!(args.port, 9000);
+    assert_eq!(args.port, Some(9000));
 }
 
 #[test]
@@ -358,7 +359,7 @@ fn test_cli_args_combined_options() {
     assert_eq!(args.size, 9);
     assert_eq!(args.mode, Mode::Computer);
     assert_eq!(args.bot, "advanced_bot");
-    assert_eq!(args.port, 5000);
+    assert_eq!(args.port, Some(5000));
 }
 
 #[test]
@@ -444,6 +444,153 @@ fn test_swap_after_opening_move() {
     assert!(!game.check_game_over());
 }
 
+#[test]
+fn test_swap_takes_over_opening_stone() {
+    let mut game = GameY::new(5);
+    let opening = Coordinates::new(2, 1, 1);
+
+    game.play(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: opening,
+    })
+    .unwrap();
+    game.play(Movement::Action {
+        player: PlayerId::new(1),
+        action: GameAction::Swap,
+    })
+    .unwrap();
+
+    assert_eq!(game.cell_owner(&opening), Some(PlayerId::new(1)));
+    assert_eq!(game.next_player(), Some(PlayerId::new(0)));
+}
+
+// ============================================================================
+// Rule Validation Tests
+// ============================================================================
+
+#[test]
+fn test_play_rejects_occupied_cell() {
+    let mut game = GameY::new(3);
+    let coords = Coordinates::new(1, 1, 0);
+    game.play(Movement::Placement {
+        player: PlayerId::new(0),
+        coords,
+    })
+    .unwrap();
+
+    let result = game.play(Movement::Placement {
+        player: PlayerId::new(1),
+        coords,
+    });
+
+    assert!(matches!(result, Err(GameYError::Occupied { .. })));
+    assert_eq!(game.next_player(), Some(PlayerId::new(1)));
+}
+
+#[test]
+fn test_play_rejects_move_out_of_turn() {
+    let mut game = GameY::new(3);
+
+    let result = game.play(Movement::Placement {
+        player: PlayerId::new(1),
+        coords: Coordinates::new(1, 1, 0),
+    });
+
+    assert!(matches!(result, Err(GameYError::InvalidPlayerTurn { .. })));
+    assert_eq!(game.available_cells().len(), 6);
+}
+
+#[test]
+fn test_play_rejects_cell_off_the_board() {
+    let mut game = GameY::new(3);
+
+    let result = game.play(Movement::Placement {
+        player: PlayerId::new(0),
+        coords: Coordinates::new(1, 1, 1),
+    });
+
+    assert!(matches!(result, Err(GameYError::InvalidCoordinates { .. })));
+}
+
+#[test]
+fn test_play_rejects_move_in_finished_game() {
+    let mut game = GameY::new(3);
+    game.play(Movement::Action {
+        player: PlayerId::new(0),
+        action: GameAction::Resign,
+    })
+    .unwrap();
+
+    let result = game.play(Movement::Placement {
+        player: PlayerId::new(1),
+        coords: Coordinates::new(1, 1, 0),
+    });
+
+    assert!(matches!(result, Err(GameYError::GameOver { .. })));
+}
+
+#[test]
+fn test_play_rejects_swap_after_second_stone() {
+    let mut game = GameY::new(4);
+    for (player, coords) in [(0, Coordinates::new(1, 1, 1)), (1, Coordinates::new(3, 0, 0))] {
+        game.play(Movement::Placement {
+            player: PlayerId::new(player),
+            coords,
+        })
+        .unwrap();
+    }
+
+    let result = game.play(Movement::Action {
+        player: PlayerId::new(0),
+        action: GameAction::Swap,
+    });
+
+    assert!(matches!(result, Err(GameYError::SwapNotAllowed { .. })));
+}
+
+#[test]
+fn test_winning_chain_lists_connecting_stones() {
+    let mut game = GameY::new(3);
+    let moves = [
+        (0, Coordinates::new(2, 0, 0)),
+        (1, Coordinates::new(0, 0, 2)),
+        (0, Coordinates::new(1, 1, 0)),
+        (1, Coordinates::new(0, 1, 1)),
+        (0, Coordinates::new(1, 0, 1)),
+        (1, Coordinates::new(0, 2, 0)),
+    ];
+    assert_eq!(game.winning_chain(), None);
+    for (player, coords) in moves {
+        game.play(Movement::Placement {
+            player: PlayerId::new(player),
+            coords,
+        })
+        .unwrap();
+    }
+
+    assert_eq!(
+        game.winning_chain(),
+        Some(vec![
+            Coordinates::new(0, 0, 2),
+            Coordinates::new(0, 1, 1),
+            Coordinates::new(0, 2, 0),
+        ])
+    );
+}
+
+#[test]
+fn test_resignation_has_no_winning_chain() {
+    let mut game = GameY::new(3);
+    game.play(Movement::Action {
+        player: PlayerId::new(0),
+        action: GameAction::Resign,
+    })
+    .unwrap();
+
+    assert!(game.check_game_over());
+    assert_eq!(game.winning_chain(), None);
+}
+
 // ============================================================================
 // YEN Serialization Tests
 // ============================================================================
@@ -577,6 +724,15 @@ fn test_yen_invalid_character() {
     }
 }
 
+#[test]
+fn test_yen_turn_decides_next_player() {
+    // Bottom-row stones are read last, so the layout alone would suggest
+    // that player 1 moves next.
+    let yen = YEN::new(3, 0, vec!['B', 'R'], "R/../B..".to_string());
+    let game = GameY::try_from(yen).unwrap();
+    assert_eq!(game.next_player(), Some(PlayerId::new(0)));
+}
+
 // ============================================================================
 // File Save/Load Tests
 // ============================================================================
@@ -125,7 +125,7 @@ async function callRustEngine(yen: YEN, botId: BotId): Promise<RustMoveResponse>
       }
 
       const body = axiosErr.response?.data as any;
-      if (body?.message?.toLowerCase().includes('no valid moves')) {
+      if (body?.code === 'NO_MOVES' || body?.message?.toLowerCase().includes('no valid moves')) {
         throw makeError('NO_MOVES_AVAILABLE', 'No legal moves are available for the given position.', 422);
       }
     }